@@ -3,7 +3,7 @@ import { PublicKey } from "@solana/web3.js";
 import { TOKEN_2022_PROGRAM_ID } from "@solana/spl-token";
 import { getGlobalFlags } from "../../cli.js";
 import { loadConfig } from "../../config.js";
-import { createContext } from "../../context.js";
+import { createContext, findCollateralVault, findRoleConfig } from "../../context.js";
 import {
   formatTxResult,
   formatSimulateResult,
@@ -17,10 +17,18 @@ import {
 export function registerBurn(parent: Command): void {
   parent
     .command("burn")
-    .description("Burn stablecoin tokens for fiat redemption")
+    .description("Burn the wallet's own stablecoin tokens for fiat redemption")
     .requiredOption("--amount <amount>", "Amount to burn (in smallest units)")
     .requiredOption("--mint <pubkey>", "Mint address")
     .requiredOption("--token-account <pubkey>", "Holder token account to burn from")
+    .option(
+      "--release-collateral",
+      "Release collateral worth the burned amount from the attested vault, at the oracle price when it has one"
+    )
+    .option(
+      "--issuer <pubkey>",
+      "Issuer authority to attribute the redemption to"
+    )
     .option(
       "--redemption-info <hex>",
       "Encrypted bank account info for fiat redemption (64 bytes hex)"
@@ -38,6 +46,17 @@ export function registerBurn(parent: Command): void {
         ctx.stablecoinProgram.programId
       );
 
+      const issuer = opts.issuer
+        ? PublicKey.findProgramAddressSync(
+            [Buffer.from("issuer"), parsePublicKey(opts.issuer).toBuffer()],
+            ctx.stablecoinProgram.programId
+          )[0]
+        : null;
+
+      const vault = opts.releaseCollateral
+        ? await findCollateralVault(ctx, mintConfigPda)
+        : { collateralVault: null, priceFeed: null };
+
       const params = {
         amount: parseAmount(opts.amount),
         redemptionInfo: parseOptionalHexBytes(opts.redemptionInfo, 64),
@@ -47,9 +66,13 @@ export function registerBurn(parent: Command): void {
         .burn(params)
         .accounts({
           holder: ctx.payer.publicKey,
+          burner: ctx.payer.publicKey,
           mintConfig: mintConfigPda,
+          roleConfig: await findRoleConfig(ctx, mintConfigPda),
           mint,
           holderTokenAccount,
+          issuer,
+          ...vault,
           tokenProgram: TOKEN_2022_PROGRAM_ID,
         });
 
@@ -21,6 +21,14 @@ export function registerInitVault(parent: Command): void {
       "Collateral type: fiat, government-bond, bank-deposit, other"
     )
     .option("--auditor <pubkey>", "Auditor public key")
+    .option(
+      "--audit-floor-bps <bps>",
+      "Share of the last audited amount withdrawals must preserve (bps)"
+    )
+    .option(
+      "--price-feed <pubkey>",
+      "Oracle price feed valuing non-fiat collateral (default: 1:1)"
+    )
     .action(async (opts, cmd) => {
       const flags = getGlobalFlags(cmd);
       const config = loadConfig(flags);
@@ -39,6 +47,9 @@ export function registerInitVault(parent: Command): void {
       const params = {
         collateralType: parseCollateralType(opts.collateralType),
         auditor: opts.auditor ? parsePublicKey(opts.auditor) : null,
+        auditFloorBps:
+          opts.auditFloorBps !== undefined ? parseInt(opts.auditFloorBps) : null,
+        priceFeed: opts.priceFeed ? parsePublicKey(opts.priceFeed) : null,
       };
 
       const builder = ctx.stablecoinProgram.methods
@@ -3,7 +3,7 @@ import { PublicKey } from "@solana/web3.js";
 import { TOKEN_2022_PROGRAM_ID } from "@solana/spl-token";
 import { getGlobalFlags } from "../../cli.js";
 import { loadConfig } from "../../config.js";
-import { createContext } from "../../context.js";
+import { createContext, findCollateralVault, roleConfigAddress } from "../../context.js";
 import {
   formatTxResult,
   formatSimulateResult,
@@ -50,9 +50,11 @@ export function registerMint(parent: Command): void {
         .accounts({
           issuerAuthority: ctx.payer.publicKey,
           mintConfig: mintConfigPda,
+          roleConfig: roleConfigAddress(ctx, mintConfigPda),
           issuer: issuerPda,
           mint,
           recipientTokenAccount,
+          ...(await findCollateralVault(ctx, mintConfigPda)),
           tokenProgram: TOKEN_2022_PROGRAM_ID,
         });
 
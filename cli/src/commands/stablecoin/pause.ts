@@ -2,7 +2,7 @@ import { Command } from "commander";
 import { PublicKey } from "@solana/web3.js";
 import { getGlobalFlags } from "../../cli.js";
 import { loadConfig } from "../../config.js";
-import { createContext } from "../../context.js";
+import { createContext, findRoleConfig } from "../../context.js";
 import {
   formatTxResult,
   formatSimulateResult,
@@ -28,6 +28,7 @@ export function registerPause(parent: Command): void {
       const builder = ctx.stablecoinProgram.methods[method]().accounts({
         authority: ctx.payer.publicKey,
         mintConfig: mintConfigPda,
+        roleConfig: await findRoleConfig(ctx, mintConfigPda),
       });
 
       if (flags.simulate) {
@@ -3,7 +3,7 @@ import { PublicKey } from "@solana/web3.js";
 import { TOKEN_2022_PROGRAM_ID } from "@solana/spl-token";
 import { getGlobalFlags } from "../../cli.js";
 import { loadConfig } from "../../config.js";
-import { createContext } from "../../context.js";
+import { createContext, findRoleConfig } from "../../context.js";
 import {
   formatTxResult,
   formatSimulateResult,
@@ -24,8 +24,10 @@ export function registerSeize(parent: Command): void {
     .requiredOption("--mint <pubkey>", "Mint address")
     .requiredOption("--source <pubkey>", "Frozen account to seize from")
     .requiredOption("--treasury <pubkey>", "Treasury account to receive seized tokens")
+    .option("--escrow <pubkey>", "Regulatory escrow account (when escrow_bps is configured)")
     .option("--amount <amount>", "Amount to seize (0 = entire balance)", "0")
     .option("--reason <hex>", "Reason for seizure (32 bytes hex)")
+    .option("--force", "Emergency seizure from an account that is not frozen", false)
     .action(async (opts, cmd) => {
       const flags = getGlobalFlags(cmd);
       const config = loadConfig(flags);
@@ -34,6 +36,7 @@ export function registerSeize(parent: Command): void {
       const mint = parsePublicKey(opts.mint);
       const source = parsePublicKey(opts.source);
       const treasury = parsePublicKey(opts.treasury);
+      const seizeEscrow = opts.escrow ? parsePublicKey(opts.escrow) : null;
 
       const [mintConfigPda] = PublicKey.findProgramAddressSync(
         [Buffer.from("mint_config")],
@@ -43,6 +46,7 @@ export function registerSeize(parent: Command): void {
       const params = {
         amount: parseAmount(opts.amount),
         reason: parseOptionalHexBytes(opts.reason, 32),
+        force: opts.force,
       };
 
       const builder = ctx.stablecoinProgram.methods
@@ -50,9 +54,11 @@ export function registerSeize(parent: Command): void {
         .accounts({
           authority: ctx.payer.publicKey,
           mintConfig: mintConfigPda,
+          roleConfig: await findRoleConfig(ctx, mintConfigPda),
           mint,
           source,
           treasury,
+          seizeEscrow,
           tokenProgram: TOKEN_2022_PROGRAM_ID,
         });
 
@@ -46,6 +46,52 @@ function makeStubIdl(name: string, programId: string) {
   };
 }
 
+/**
+ * Role config PDA for the given mint config, whether or not roles have been
+ * initialized. `mint` always takes it.
+ */
+export function roleConfigAddress(
+  ctx: MeridianContext,
+  mintConfig: PublicKey,
+): PublicKey {
+  const [roleConfigPda] = PublicKey.findProgramAddressSync(
+    [Buffer.from("role_config"), mintConfig.toBuffer()],
+    ctx.stablecoinProgram.programId
+  );
+  return roleConfigPda;
+}
+
+/**
+ * Role config PDA for the given mint config, or null when roles have not been
+ * initialized (the program then accepts only the master authority).
+ */
+export async function findRoleConfig(
+  ctx: MeridianContext,
+  mintConfig: PublicKey,
+): Promise<PublicKey | null> {
+  const roleConfigPda = roleConfigAddress(ctx, mintConfig);
+  const info = await ctx.connection.getAccountInfo(roleConfigPda);
+  return info ? roleConfigPda : null;
+}
+
+/**
+ * Collateral vault PDA for the given mint config and the oracle feed pricing
+ * its collateral (null when valued 1:1).
+ */
+export async function findCollateralVault(
+  ctx: MeridianContext,
+  mintConfig: PublicKey,
+): Promise<{ collateralVault: PublicKey; priceFeed: PublicKey | null }> {
+  const [collateralVault] = PublicKey.findProgramAddressSync(
+    [Buffer.from("collateral_vault"), mintConfig.toBuffer()],
+    ctx.stablecoinProgram.programId
+  );
+  const vault = await ctx.stablecoinProgram.account.collateralVault.fetch(
+    collateralVault
+  );
+  return { collateralVault, priceFeed: vault.priceFeed ?? null };
+}
+
 export function createContext(config: Config): MeridianContext {
   const connection = new Connection(config.rpcUrl, config.commitment);
   const payer = loadKeypair(config.wallet);
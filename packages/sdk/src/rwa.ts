@@ -98,6 +98,8 @@ export interface RegisterAssetParams {
   isin?: Uint8Array;
   jurisdiction: number;
   legalDocumentHash: Uint8Array;
+  /** Oracle feed for permissionless revaluation */
+  priceFeed?: PublicKey;
 }
 
 /**
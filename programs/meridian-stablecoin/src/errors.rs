@@ -98,4 +98,104 @@ pub enum MeridianError {
     /// Invalid role: caller does not have the required role
     #[msg("Caller does not have the required role")]
     InvalidRole,
+
+    /// Withdrawal would drop collateral below the audited floor
+    #[msg("Withdrawal would drop collateral below the audited floor: submit a fresh audit")]
+    AuditFloorViolation,
+
+    /// Invalid basis points value
+    #[msg("Invalid basis points: must not exceed 10000")]
+    InvalidBps,
+
+    /// Seize escrow account missing or mismatched
+    #[msg("Seize escrow not configured or does not match config")]
+    SeizeEscrowNotConfigured,
+
+    /// Account layout is already current
+    #[msg("Account is already at the current version")]
+    AlreadyMigrated,
+
+    /// Vault collateral is held on-chain; bookkeeping updates are disabled
+    #[msg("Vault is token-backed: use deposit/withdraw_backed_collateral")]
+    VaultIsBacked,
+
+    /// Vault has no backing token account configured
+    #[msg("Vault is not token-backed or backing account does not match")]
+    VaultNotBacked,
+
+    /// Issuer's attributed collateral does not cover its outstanding mint
+    #[msg("Issuer's attributed collateral does not cover its outstanding mint")]
+    IssuerUndercollateralized,
+
+    /// Issuer minted again before its minimum interval elapsed
+    #[msg("Issuer must wait out its minimum mint interval")]
+    MintTooFrequent,
+
+    /// Signer has already signed off on this proposal
+    #[msg("Signer has already approved this proposal")]
+    AlreadyApproved,
+
+    /// Proposal has not reached the approval threshold
+    #[msg("Proposal has not reached the approval threshold")]
+    ApprovalThresholdNotMet,
+
+    /// Proposal is for a different action or target
+    #[msg("Proposal does not match the requested action or target")]
+    ProposalMismatch,
+
+    /// Token is not on the settlement allowlist
+    #[msg("Token is not an approved settlement token")]
+    UnapprovedToken,
+
+    /// Settlement allowlist already holds `MintConfig::MAX_SETTLEMENT_TOKENS`
+    #[msg("Settlement token allowlist is full")]
+    SettlementAllowlistFull,
+
+    /// Token is already on the settlement allowlist
+    #[msg("Token is already an approved settlement token")]
+    SettlementTokenAlreadyApproved,
+
+    /// Thaw batch is empty or longer than `MintConfig::MAX_THAW_BATCH_SIZE`
+    #[msg("Thaw batch must hold between 1 and 20 token accounts")]
+    InvalidThawBatch,
+
+    /// Token account owner has no active whitelist entry in the transfer hook
+    #[msg("Token account owner is not actively whitelisted")]
+    NotWhitelisted,
+
+    /// A multisig is attached: the action must go through its proposals
+    #[msg("Action requires multisig approval: use propose/approve/execute")]
+    MultisigRequired,
+
+    /// No multisig is attached to the mint config, or a different one is
+    #[msg("Multisig is not attached to this mint config")]
+    MultisigNotConfigured,
+
+    /// A multisig is already attached to the mint config
+    #[msg("A multisig is already attached")]
+    MultisigAlreadyConfigured,
+
+    /// Empty, oversized or duplicated signer set, or a threshold outside 1..=signers
+    #[msg("Invalid multisig signers or threshold")]
+    InvalidMultisigConfig,
+
+    /// Signer is not in the multisig's signer set
+    #[msg("Signer is not a multisig signer")]
+    NotMultisigSigner,
+
+    /// Price feed account missing or not the one attached to the vault
+    #[msg("Price feed does not match the vault's collateral feed")]
+    PriceFeedMismatch,
+
+    /// Collateral feed is past its staleness bound
+    #[msg("Collateral price feed is stale")]
+    StaleCollateralPrice,
+
+    /// Collateral feed is inactive, halted or has no price
+    #[msg("Collateral price feed is not usable")]
+    InvalidCollateralPrice,
+
+    /// Issuer attributions would add up to more than the mint's collateral
+    #[msg("Attributed collateral would exceed the mint's total collateral")]
+    AttributionExceedsCollateral,
 }
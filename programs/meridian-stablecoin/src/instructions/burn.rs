@@ -2,6 +2,9 @@ use anchor_lang::prelude::*;
 use anchor_spl::token_2022::{self, Burn, Token2022};
 use anchor_spl::token_interface::{Mint, TokenAccount};
 
+use oracle::PriceFeed;
+
+use crate::instructions::collateral::collateral_price;
 use crate::state::*;
 use crate::errors::MeridianError;
 
@@ -10,6 +13,10 @@ pub struct BurnStablecoin<'info> {
     #[account(mut)]
     pub holder: Signer<'info>,
 
+    /// The holder itself for a self-redemption; otherwise the master
+    /// authority or burner role, co-signing on the holder's behalf
+    pub burner: Signer<'info>,
+
     #[account(
         mut,
         seeds = [MintConfig::SEED_PREFIX],
@@ -18,6 +25,14 @@ pub struct BurnStablecoin<'info> {
     )]
     pub mint_config: Account<'info, MintConfig>,
 
+    /// Role assignments; without it only the master authority may burn on a
+    /// holder's behalf
+    #[account(
+        seeds = [RoleConfig::SEED_PREFIX, mint_config.key().as_ref()],
+        bump = role_config.bump,
+    )]
+    pub role_config: Option<Account<'info, RoleConfig>>,
+
     #[account(
         mut,
         constraint = mint.key() == mint_config.mint @ MeridianError::InvalidMint
@@ -32,6 +47,30 @@ pub struct BurnStablecoin<'info> {
     )]
     pub holder_token_account: InterfaceAccount<'info, TokenAccount>,
 
+    /// Issuer the redemption is attributed to; its outstanding mint and burn
+    /// limit are charged, and collateral released comes out of its attribution
+    #[account(
+        mut,
+        seeds = [Issuer::SEED_PREFIX, issuer.authority.as_ref()],
+        bump = issuer.bump,
+        constraint = issuer.mint_config == mint_config.key() @ MeridianError::InvalidIssuer
+    )]
+    pub issuer: Option<Account<'info, Issuer>>,
+
+    /// Attested collateral vault; when provided, collateral worth the burned
+    /// amount at the current price is released. Omitted on SSS-1 deployments
+    /// without one.
+    #[account(
+        mut,
+        seeds = [CollateralVault::SEED_PREFIX, mint_config.key().as_ref()],
+        bump = collateral_vault.bump,
+        constraint = collateral_vault.is_active() @ MeridianError::VaultInactive
+    )]
+    pub collateral_vault: Option<Account<'info, CollateralVault>>,
+
+    /// Required when releasing from a vault with a price feed
+    pub price_feed: Option<Box<Account<'info, PriceFeed>>>,
+
     pub token_program: Program<'info, Token2022>,
 }
 
@@ -44,6 +83,18 @@ pub struct BurnParams {
 
 pub fn handler(ctx: Context<BurnStablecoin>, params: BurnParams) -> Result<()> {
     let clock = Clock::get()?;
+    // Holders redeem their own tokens freely; the role gates third-party burns
+    let burner = ctx.accounts.burner.key();
+    require!(
+        burner == ctx.accounts.holder.key()
+            || RoleConfig::permits(
+                ctx.accounts.role_config.as_deref(),
+                |roles| roles.burner,
+                &ctx.accounts.mint_config.authority,
+                &burner,
+            ),
+        MeridianError::InvalidRole
+    );
     let mint_config = &mut ctx.accounts.mint_config;
 
     // Validate burning
@@ -72,12 +123,68 @@ pub fn handler(ctx: Context<BurnStablecoin>, params: BurnParams) -> Result<()> {
     mint_config.total_supply = mint_config.total_supply.saturating_sub(params.amount);
     mint_config.updated_at = clock.unix_timestamp;
 
+    // Release collateral worth the redeemed amount at the current price
+    let mut price = None;
+    let collateral_released = if let Some(vault) = ctx.accounts.collateral_vault.as_mut() {
+        // Token-backed vaults follow their real balance, not reported figures
+        require!(!vault.is_backed(), MeridianError::VaultIsBacked);
+        price = collateral_price(vault, ctx.accounts.price_feed.as_deref(), clock.unix_timestamp)?;
+        let released = CollateralVault::units_for_value(params.amount, price)
+            .ok_or(MeridianError::MathOverflow)?;
+        require!(
+            vault.total_collateral >= released
+                && mint_config.total_collateral >= released,
+            MeridianError::InsufficientCollateral
+        );
+        let new_collateral = mint_config.total_collateral - released;
+        let new_value = CollateralVault::market_value(new_collateral, price)
+            .ok_or(MeridianError::MathOverflow)?;
+        require!(
+            new_value >= mint_config.total_supply,
+            MeridianError::InsufficientCollateral
+        );
+        vault.total_collateral -= released;
+        mint_config.total_collateral = new_collateral;
+        released
+    } else {
+        0
+    };
+
+    if let Some(issuer) = ctx.accounts.issuer.as_mut() {
+        issuer.maybe_reset_daily(clock.unix_timestamp);
+        require!(
+            issuer.can_burn(params.amount),
+            MeridianError::DailyLimitExceeded
+        );
+        require!(
+            issuer.outstanding() >= params.amount,
+            MeridianError::InsufficientSupply
+        );
+        issuer.record_burn(params.amount);
+        let unattributed = collateral_released.min(issuer.attributed_collateral);
+        issuer.attributed_collateral -= unattributed;
+        mint_config.attributed_collateral =
+            mint_config.attributed_collateral.saturating_sub(unattributed);
+    }
+    // Released collateral can't leave issuer attributions uncovered
+    if collateral_released > 0 {
+        require!(
+            mint_config.attributed_collateral <= mint_config.total_collateral,
+            MeridianError::AttributionExceedsCollateral
+        );
+    }
+
+    let collateral_value = CollateralVault::market_value(mint_config.total_collateral, price)
+        .ok_or(MeridianError::MathOverflow)?;
+
     emit!(StablecoinBurned {
         mint: ctx.accounts.mint.key(),
         holder: ctx.accounts.holder.key(),
         amount: params.amount,
         redemption_info: params.redemption_info,
         total_supply: mint_config.total_supply,
+        collateral_released,
+        collateral_ratio: mint_config.collateral_ratio_with(collateral_value),
         timestamp: clock.unix_timestamp,
     });
 
@@ -91,5 +198,8 @@ pub struct StablecoinBurned {
     pub amount: u64,
     pub redemption_info: [u8; 64],
     pub total_supply: u64,
+    /// Collateral units released alongside the burn (0 without a vault)
+    pub collateral_released: u64,
+    pub collateral_ratio: u64,
     pub timestamp: i64,
 }
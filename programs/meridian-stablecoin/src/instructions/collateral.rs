@@ -1,4 +1,6 @@
 use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked};
+use oracle::PriceFeed;
 
 use crate::state::*;
 use crate::errors::MeridianError;
@@ -34,30 +36,63 @@ pub struct InitializeVault<'info> {
 pub struct InitializeVaultParams {
     pub collateral_type: CollateralType,
     pub auditor: Option<Pubkey>,
+    /// Share of the last audited amount that withdrawals must preserve (bps, default 0 = off)
+    pub audit_floor_bps: Option<u16>,
+    /// Oracle feed pricing non-fiat collateral (`None` = valued 1:1)
+    pub price_feed: Option<Pubkey>,
 }
 
 pub fn initialize_vault_handler(
     ctx: Context<InitializeVault>,
     params: InitializeVaultParams,
+) -> Result<()> {
+    require!(ctx.accounts.mint_config.multisig.is_none(), MeridianError::MultisigRequired);
+
+    init_vault(
+        &mut ctx.accounts.collateral_vault,
+        ctx.accounts.mint_config.key(),
+        ctx.accounts.authority.key(),
+        params,
+        ctx.bumps.collateral_vault,
+    )
+}
+
+/// Set up a freshly created collateral vault. Callers have already
+/// authorized `authority`, which becomes the vault authority.
+pub(crate) fn init_vault(
+    vault: &mut Account<CollateralVault>,
+    mint_config: Pubkey,
+    authority: Pubkey,
+    params: InitializeVaultParams,
+    bump: u8,
 ) -> Result<()> {
     let clock = Clock::get()?;
-    let vault = &mut ctx.accounts.collateral_vault;
 
-    vault.mint_config = ctx.accounts.mint_config.key();
+    let audit_floor_bps = params.audit_floor_bps.unwrap_or(0);
+    require!(audit_floor_bps <= 10000, MeridianError::InvalidBps);
+
+    vault.mint_config = mint_config;
     vault.total_collateral = 0;
-    vault.authority = ctx.accounts.authority.key();
+    vault.authority = authority;
     vault.auditor = params.auditor;
     vault.last_audit_hash = [0u8; 32];
     vault.last_audit_at = clock.unix_timestamp;
     vault.collateral_type = params.collateral_type;
     vault.status = VaultStatus::Active;
     vault.created_at = clock.unix_timestamp;
-    vault.bump = ctx.bumps.collateral_vault;
+    vault.bump = bump;
+    vault.version = CollateralVault::CURRENT_VERSION;
+    vault.last_audited_amount = 0;
+    vault.audit_floor_bps = audit_floor_bps;
+    vault.backing_token_account = None;
+    vault.backing_mint = None;
+    vault.price_feed = params.price_feed;
 
     emit!(VaultInitialized {
         vault: vault.key(),
-        mint_config: ctx.accounts.mint_config.key(),
+        mint_config,
         collateral_type: params.collateral_type,
+        price_feed: params.price_feed,
         timestamp: clock.unix_timestamp,
     });
 
@@ -85,6 +120,9 @@ pub struct UpdateCollateral<'info> {
         constraint = collateral_vault.is_active() @ MeridianError::VaultInactive
     )]
     pub collateral_vault: Account<'info, CollateralVault>,
+
+    /// Required when the vault has a price feed
+    pub price_feed: Option<Box<Account<'info, PriceFeed>>>,
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize)]
@@ -105,25 +143,22 @@ pub fn update_collateral_handler(
     let vault = &mut ctx.accounts.collateral_vault;
     let mint_config = &mut ctx.accounts.mint_config;
 
+    // Token-backed vaults follow their real balance, not reported figures
+    require!(!vault.is_backed(), MeridianError::VaultIsBacked);
+    let price = collateral_price(vault, ctx.accounts.price_feed.as_deref(), clock.unix_timestamp)?;
+
     if params.is_deposit {
         vault.total_collateral = vault.total_collateral.saturating_add(params.amount);
         mint_config.total_collateral = mint_config.total_collateral.saturating_add(params.amount);
     } else {
-        require!(
-            vault.can_withdraw(params.amount),
-            MeridianError::InsufficientCollateral
-        );
-        // Ensure we maintain at least 100% collateralization
-        let new_collateral = mint_config.total_collateral.saturating_sub(params.amount);
-        require!(
-            new_collateral >= mint_config.total_supply,
-            MeridianError::CollateralRatioViolation
-        );
+        check_withdrawal(vault, mint_config, params.amount, price, clock.unix_timestamp)?;
         vault.total_collateral = vault.total_collateral.saturating_sub(params.amount);
-        mint_config.total_collateral = new_collateral;
+        mint_config.total_collateral = mint_config.total_collateral.saturating_sub(params.amount);
     }
 
     mint_config.updated_at = clock.unix_timestamp;
+    let collateral_value = CollateralVault::market_value(mint_config.total_collateral, price)
+        .ok_or(MeridianError::MathOverflow)?;
 
     emit!(CollateralUpdated {
         vault: vault.key(),
@@ -131,7 +166,8 @@ pub fn update_collateral_handler(
         is_deposit: params.is_deposit,
         proof_hash: params.proof_hash,
         new_total: vault.total_collateral,
-        collateral_ratio: mint_config.calculate_collateral_ratio(),
+        collateral_value,
+        collateral_ratio: mint_config.collateral_ratio_with(collateral_value),
         timestamp: clock.unix_timestamp,
     });
 
@@ -158,6 +194,9 @@ pub struct SubmitAudit<'info> {
         bump = collateral_vault.bump,
     )]
     pub collateral_vault: Account<'info, CollateralVault>,
+
+    /// Required when the vault has a price feed
+    pub price_feed: Option<Box<Account<'info, PriceFeed>>>,
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize)]
@@ -172,24 +211,375 @@ pub fn submit_audit_handler(ctx: Context<SubmitAudit>, params: SubmitAuditParams
     let clock = Clock::get()?;
     let vault = &mut ctx.accounts.collateral_vault;
     let mint_config = &mut ctx.accounts.mint_config;
+    let price = collateral_price(vault, ctx.accounts.price_feed.as_deref(), clock.unix_timestamp)?;
 
-    // Update vault with audited values
-    vault.total_collateral = params.verified_amount;
+    // Update vault with audited values (a backed vault's total is its on-chain balance)
+    if !vault.is_backed() {
+        vault.total_collateral = params.verified_amount;
+        mint_config.total_collateral = params.verified_amount;
+    }
+    vault.last_audited_amount = params.verified_amount;
     vault.last_audit_hash = params.audit_hash;
     vault.last_audit_at = clock.unix_timestamp;
     vault.status = VaultStatus::Active;
 
     // Update mint config
-    mint_config.total_collateral = params.verified_amount;
     mint_config.last_audit = clock.unix_timestamp;
     mint_config.updated_at = clock.unix_timestamp;
+    let collateral_value = CollateralVault::market_value(mint_config.total_collateral, price)
+        .ok_or(MeridianError::MathOverflow)?;
 
     emit!(AuditSubmitted {
         vault: vault.key(),
         auditor: ctx.accounts.auditor.key(),
         verified_amount: params.verified_amount,
         audit_hash: params.audit_hash,
-        collateral_ratio: mint_config.calculate_collateral_ratio(),
+        collateral_value,
+        collateral_ratio: mint_config.collateral_ratio_with(collateral_value),
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct UpdateVaultConfig<'info> {
+    #[account(
+        constraint = authority.key() == mint_config.authority @ MeridianError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [MintConfig::SEED_PREFIX],
+        bump = mint_config.bump,
+    )]
+    pub mint_config: Account<'info, MintConfig>,
+
+    #[account(
+        mut,
+        seeds = [CollateralVault::SEED_PREFIX, mint_config.key().as_ref()],
+        bump = collateral_vault.bump,
+    )]
+    pub collateral_vault: Account<'info, CollateralVault>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct UpdateVaultConfigParams {
+    /// New audit floor in basis points (0 disables the guardrail)
+    pub audit_floor_bps: Option<u16>,
+}
+
+pub fn update_vault_config_handler(
+    ctx: Context<UpdateVaultConfig>,
+    params: UpdateVaultConfigParams,
+) -> Result<()> {
+    let clock = Clock::get()?;
+    let vault = &mut ctx.accounts.collateral_vault;
+
+    if let Some(audit_floor_bps) = params.audit_floor_bps {
+        require!(audit_floor_bps <= 10000, MeridianError::InvalidBps);
+        vault.audit_floor_bps = audit_floor_bps;
+    }
+
+    emit!(VaultConfigUpdated {
+        vault: vault.key(),
+        audit_floor_bps: vault.audit_floor_bps,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// `(price, exponent)` from the vault's price feed, or `None` when the vault
+/// has no feed and its collateral counts 1:1. The feed must be the one
+/// attached to the vault, active, unhalted and fresh.
+pub(crate) fn collateral_price(
+    vault: &CollateralVault,
+    price_feed: Option<&Account<PriceFeed>>,
+    now: i64,
+) -> Result<Option<(u64, i8)>> {
+    require!(
+        vault.price_feed == price_feed.map(|feed| feed.key()),
+        MeridianError::PriceFeedMismatch
+    );
+    let Some(feed) = price_feed else {
+        return Ok(None);
+    };
+    require!(!feed.is_stale(now), MeridianError::StaleCollateralPrice);
+    require!(
+        feed.is_active && !feed.is_halted && feed.current_price > 0,
+        MeridianError::InvalidCollateralPrice
+    );
+    Ok(Some((feed.current_price, feed.price_exponent)))
+}
+
+/// Guardrails shared by attested and token-backed withdrawals
+fn check_withdrawal(
+    vault: &Account<CollateralVault>,
+    mint_config: &MintConfig,
+    amount: u64,
+    price: Option<(u64, i8)>,
+    now: i64,
+) -> Result<()> {
+    require!(
+        vault.can_withdraw(amount),
+        MeridianError::InsufficientCollateral
+    );
+    // Ensure the market value left behind still covers 100% of supply
+    let new_collateral = mint_config.total_collateral.saturating_sub(amount);
+    require!(
+        mint_config.attributed_collateral <= new_collateral,
+        MeridianError::AttributionExceedsCollateral
+    );
+    let new_value = CollateralVault::market_value(new_collateral, price)
+        .ok_or(MeridianError::MathOverflow)?;
+    require!(
+        mint_config.meets_min_ratio(new_value),
+        MeridianError::CollateralRatioViolation
+    );
+    let new_vault_total = vault.total_collateral.saturating_sub(amount);
+    // Don't let reserves an auditor just verified walk out before the next audit
+    if vault.audit_floor_bps > 0 {
+        require!(
+            new_vault_total >= vault.audit_floor(),
+            MeridianError::AuditFloorViolation
+        );
+    }
+    if new_vault_total < vault.last_audited_amount {
+        emit!(CollateralBelowAudited {
+            vault: vault.key(),
+            last_audited_amount: vault.last_audited_amount,
+            new_total: new_vault_total,
+            last_audit_at: vault.last_audit_at,
+            timestamp: now,
+        });
+    }
+    Ok(())
+}
+
+/// Set both collateral totals from the backing token account's balance
+fn sync_backed_total(vault: &mut CollateralVault, mint_config: &mut MintConfig, balance: u64) {
+    mint_config.total_collateral = mint_config
+        .total_collateral
+        .saturating_sub(vault.total_collateral)
+        .saturating_add(balance);
+    vault.total_collateral = balance;
+}
+
+#[derive(Accounts)]
+pub struct EnableBackedCollateral<'info> {
+    #[account(
+        constraint = authority.key() == mint_config.authority @ MeridianError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [MintConfig::SEED_PREFIX],
+        bump = mint_config.bump,
+    )]
+    pub mint_config: Account<'info, MintConfig>,
+
+    #[account(
+        mut,
+        seeds = [CollateralVault::SEED_PREFIX, mint_config.key().as_ref()],
+        bump = collateral_vault.bump,
+        constraint = !collateral_vault.is_backed() @ MeridianError::VaultIsBacked,
+    )]
+    pub collateral_vault: Account<'info, CollateralVault>,
+
+    #[account(
+        constraint = mint_config.is_settlement_token(&backing_mint.key()) @ MeridianError::UnapprovedToken
+    )]
+    pub backing_mint: InterfaceAccount<'info, Mint>,
+
+    /// Must be owned by the vault PDA so only this program can move it
+    #[account(
+        token::mint = backing_mint,
+        token::authority = collateral_vault,
+    )]
+    pub backing_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// Required when the vault has a price feed
+    pub price_feed: Option<Box<Account<'info, PriceFeed>>>,
+}
+
+/// Switch the vault to token-backed mode. The recorded collateral is replaced
+/// by the backing account's balance, whose market value must still cover supply.
+pub fn enable_backed_collateral_handler(ctx: Context<EnableBackedCollateral>) -> Result<()> {
+    let clock = Clock::get()?;
+    let vault = &mut ctx.accounts.collateral_vault;
+    let mint_config = &mut ctx.accounts.mint_config;
+    let balance = ctx.accounts.backing_token_account.amount;
+    let price = collateral_price(vault, ctx.accounts.price_feed.as_deref(), clock.unix_timestamp)?;
+
+    vault.backing_token_account = Some(ctx.accounts.backing_token_account.key());
+    vault.backing_mint = Some(ctx.accounts.backing_mint.key());
+    sync_backed_total(vault, mint_config, balance);
+    let collateral_value = CollateralVault::market_value(mint_config.total_collateral, price)
+        .ok_or(MeridianError::MathOverflow)?;
+    require!(
+        mint_config.meets_min_ratio(collateral_value),
+        MeridianError::CollateralRatioViolation
+    );
+    mint_config.updated_at = clock.unix_timestamp;
+
+    emit!(BackedCollateralEnabled {
+        vault: vault.key(),
+        backing_mint: ctx.accounts.backing_mint.key(),
+        backing_token_account: ctx.accounts.backing_token_account.key(),
+        balance,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct MoveBackedCollateral<'info> {
+    #[account(
+        constraint = authority.key() == collateral_vault.authority @ MeridianError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [MintConfig::SEED_PREFIX],
+        bump = mint_config.bump,
+    )]
+    pub mint_config: Account<'info, MintConfig>,
+
+    #[account(
+        mut,
+        seeds = [CollateralVault::SEED_PREFIX, mint_config.key().as_ref()],
+        bump = collateral_vault.bump,
+        constraint = collateral_vault.is_active() @ MeridianError::VaultInactive
+    )]
+    pub collateral_vault: Account<'info, CollateralVault>,
+
+    #[account(
+        constraint = Some(backing_mint.key()) == collateral_vault.backing_mint @ MeridianError::VaultNotBacked
+    )]
+    pub backing_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        constraint = Some(backing_token_account.key()) == collateral_vault.backing_token_account @ MeridianError::VaultNotBacked
+    )]
+    pub backing_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// Source on deposit, destination on withdrawal
+    #[account(
+        mut,
+        token::mint = backing_mint,
+    )]
+    pub counterparty_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// Required when the vault has a price feed
+    pub price_feed: Option<Box<Account<'info, PriceFeed>>>,
+}
+
+/// Deposit tokenized collateral into the vault's backing account. The backing
+/// mint must still be an approved settlement token; withdrawals are not
+/// checked so a delisted token can always be unwound.
+pub fn deposit_backed_collateral_handler(
+    ctx: Context<MoveBackedCollateral>,
+    amount: u64,
+) -> Result<()> {
+    require!(amount > 0, MeridianError::InvalidAmount);
+    require!(
+        ctx.accounts
+            .mint_config
+            .is_settlement_token(&ctx.accounts.backing_mint.key()),
+        MeridianError::UnapprovedToken
+    );
+
+    token_interface::transfer_checked(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.counterparty_token_account.to_account_info(),
+                mint: ctx.accounts.backing_mint.to_account_info(),
+                to: ctx.accounts.backing_token_account.to_account_info(),
+                authority: ctx.accounts.authority.to_account_info(),
+            },
+        ),
+        amount,
+        ctx.accounts.backing_mint.decimals,
+    )?;
+
+    settle_backed_move(ctx, amount, true)
+}
+
+/// Withdraw tokenized collateral from the vault's backing account, subject to
+/// the same ratio and audit-floor guardrails as attested withdrawals
+pub fn withdraw_backed_collateral_handler(
+    ctx: Context<MoveBackedCollateral>,
+    amount: u64,
+) -> Result<()> {
+    let clock = Clock::get()?;
+    require!(amount > 0, MeridianError::InvalidAmount);
+    let price = collateral_price(
+        &ctx.accounts.collateral_vault,
+        ctx.accounts.price_feed.as_deref(),
+        clock.unix_timestamp,
+    )?;
+    check_withdrawal(
+        &ctx.accounts.collateral_vault,
+        &ctx.accounts.mint_config,
+        amount,
+        price,
+        clock.unix_timestamp,
+    )?;
+
+    let mint_config_key = ctx.accounts.mint_config.key();
+    let vault_seeds = &[
+        CollateralVault::SEED_PREFIX,
+        mint_config_key.as_ref(),
+        &[ctx.accounts.collateral_vault.bump],
+    ];
+    let signer_seeds = &[&vault_seeds[..]];
+
+    token_interface::transfer_checked(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.backing_token_account.to_account_info(),
+                mint: ctx.accounts.backing_mint.to_account_info(),
+                to: ctx.accounts.counterparty_token_account.to_account_info(),
+                authority: ctx.accounts.collateral_vault.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        amount,
+        ctx.accounts.backing_mint.decimals,
+    )?;
+
+    settle_backed_move(ctx, amount, false)
+}
+
+fn settle_backed_move(ctx: Context<MoveBackedCollateral>, amount: u64, is_deposit: bool) -> Result<()> {
+    let clock = Clock::get()?;
+    ctx.accounts.backing_token_account.reload()?;
+    let balance = ctx.accounts.backing_token_account.amount;
+
+    let vault = &mut ctx.accounts.collateral_vault;
+    let mint_config = &mut ctx.accounts.mint_config;
+    let price = collateral_price(vault, ctx.accounts.price_feed.as_deref(), clock.unix_timestamp)?;
+    sync_backed_total(vault, mint_config, balance);
+    mint_config.updated_at = clock.unix_timestamp;
+    let collateral_value = CollateralVault::market_value(mint_config.total_collateral, price)
+        .ok_or(MeridianError::MathOverflow)?;
+
+    emit!(BackedCollateralUpdated {
+        vault: vault.key(),
+        amount,
+        is_deposit,
+        vault_balance: balance,
+        collateral_value,
+        collateral_ratio: mint_config.collateral_ratio_with(collateral_value),
         timestamp: clock.unix_timestamp,
     });
 
@@ -201,6 +591,7 @@ pub struct VaultInitialized {
     pub vault: Pubkey,
     pub mint_config: Pubkey,
     pub collateral_type: CollateralType,
+    pub price_feed: Option<Pubkey>,
     pub timestamp: i64,
 }
 
@@ -211,16 +602,57 @@ pub struct CollateralUpdated {
     pub is_deposit: bool,
     pub proof_hash: [u8; 32],
     pub new_total: u64,
+    /// Market value of total collateral (units × oracle price, or 1:1)
+    pub collateral_value: u64,
     pub collateral_ratio: u64,
     pub timestamp: i64,
 }
 
+#[event]
+pub struct CollateralBelowAudited {
+    pub vault: Pubkey,
+    pub last_audited_amount: u64,
+    pub new_total: u64,
+    pub last_audit_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BackedCollateralEnabled {
+    pub vault: Pubkey,
+    pub backing_mint: Pubkey,
+    pub backing_token_account: Pubkey,
+    pub balance: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BackedCollateralUpdated {
+    pub vault: Pubkey,
+    pub amount: u64,
+    pub is_deposit: bool,
+    pub vault_balance: u64,
+    /// Market value of total collateral (units × oracle price, or 1:1)
+    pub collateral_value: u64,
+    pub collateral_ratio: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct VaultConfigUpdated {
+    pub vault: Pubkey,
+    pub audit_floor_bps: u16,
+    pub timestamp: i64,
+}
+
 #[event]
 pub struct AuditSubmitted {
     pub vault: Pubkey,
     pub auditor: Pubkey,
     pub verified_amount: u64,
     pub audit_hash: [u8; 32],
+    /// Market value of total collateral (units × oracle price, or 1:1)
+    pub collateral_value: u64,
     pub collateral_ratio: u64,
     pub timestamp: i64,
 }
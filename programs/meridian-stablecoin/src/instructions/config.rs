@@ -0,0 +1,353 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+
+use crate::state::*;
+use crate::errors::MeridianError;
+
+#[derive(Accounts)]
+pub struct UpdateMintConfig<'info> {
+    #[account(
+        constraint = authority.key() == mint_config.authority @ MeridianError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    /// Config changes go through the multisig once one is attached
+    #[account(
+        mut,
+        seeds = [MintConfig::SEED_PREFIX],
+        bump = mint_config.bump,
+        constraint = mint_config.multisig.is_none() @ MeridianError::MultisigRequired
+    )]
+    pub mint_config: Account<'info, MintConfig>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateMintConfig<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// CHECK: may not deserialize under the current layout; discriminator and
+    /// authority are verified in the handler
+    #[account(
+        mut,
+        owner = crate::ID,
+        seeds = [MintConfig::SEED_PREFIX],
+        bump,
+    )]
+    pub mint_config: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateIssuer<'info> {
+    #[account(
+        mut,
+        constraint = authority.key() == mint_config.authority @ MeridianError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [MintConfig::SEED_PREFIX],
+        bump = mint_config.bump,
+    )]
+    pub mint_config: Account<'info, MintConfig>,
+
+    /// CHECK: may not deserialize under the current layout; discriminator and
+    /// mint config are verified in the handler
+    #[account(mut, owner = crate::ID)]
+    pub issuer: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateCollateralVault<'info> {
+    #[account(
+        mut,
+        constraint = authority.key() == mint_config.authority @ MeridianError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [MintConfig::SEED_PREFIX],
+        bump = mint_config.bump,
+    )]
+    pub mint_config: Account<'info, MintConfig>,
+
+    /// CHECK: may not deserialize under the current layout; discriminator is
+    /// verified in the handler
+    #[account(
+        mut,
+        owner = crate::ID,
+        seeds = [CollateralVault::SEED_PREFIX, mint_config.key().as_ref()],
+        bump,
+    )]
+    pub collateral_vault: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct UpdateMintConfigParams {
+    /// Regulatory escrow token account for seized funds
+    pub seize_escrow: Option<Pubkey>,
+    /// Share of each seizure routed to the escrow (basis points)
+    pub escrow_bps: Option<u16>,
+    /// Require each issuer's attributed collateral to cover its outstanding mint
+    pub require_issuer_attribution: Option<bool>,
+}
+
+pub fn update_mint_config_handler(
+    ctx: Context<UpdateMintConfig>,
+    params: UpdateMintConfigParams,
+) -> Result<()> {
+    let clock = Clock::get()?;
+    let mint_config = &mut ctx.accounts.mint_config;
+
+    if let Some(seize_escrow) = params.seize_escrow {
+        mint_config.seize_escrow = Some(seize_escrow);
+    }
+    if let Some(escrow_bps) = params.escrow_bps {
+        require!(escrow_bps <= 10000, MeridianError::InvalidBps);
+        mint_config.escrow_bps = escrow_bps;
+    }
+    if let Some(required) = params.require_issuer_attribution {
+        mint_config.require_issuer_attribution = required;
+    }
+    require!(
+        mint_config.escrow_bps == 0 || mint_config.seize_escrow.is_some(),
+        MeridianError::SeizeEscrowNotConfigured
+    );
+
+    mint_config.updated_at = clock.unix_timestamp;
+
+    emit!(MintConfigUpdated {
+        mint: mint_config.mint,
+        authority: ctx.accounts.authority.key(),
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Add a mint to the settlement allowlist. The first entry turns the
+/// allowlist on: from then on only listed mints are accepted.
+pub fn add_settlement_token_handler(ctx: Context<UpdateMintConfig>, token: Pubkey) -> Result<()> {
+    let clock = Clock::get()?;
+    let mint_config = &mut ctx.accounts.mint_config;
+
+    require!(
+        !mint_config.settlement_tokens.contains(&token),
+        MeridianError::SettlementTokenAlreadyApproved
+    );
+    require!(
+        mint_config.settlement_tokens.len() < MintConfig::MAX_SETTLEMENT_TOKENS,
+        MeridianError::SettlementAllowlistFull
+    );
+    mint_config.settlement_tokens.push(token);
+    mint_config.updated_at = clock.unix_timestamp;
+
+    emit!(SettlementTokenAdded {
+        mint: mint_config.mint,
+        token,
+        authority: ctx.accounts.authority.key(),
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Remove a mint from the settlement allowlist. Removing the last entry
+/// turns the allowlist off.
+pub fn remove_settlement_token_handler(ctx: Context<UpdateMintConfig>, token: Pubkey) -> Result<()> {
+    let clock = Clock::get()?;
+    let mint_config = &mut ctx.accounts.mint_config;
+
+    let index = mint_config
+        .settlement_tokens
+        .iter()
+        .position(|listed| *listed == token)
+        .ok_or(MeridianError::UnapprovedToken)?;
+    mint_config.settlement_tokens.remove(index);
+    mint_config.updated_at = clock.unix_timestamp;
+
+    emit!(SettlementTokenRemoved {
+        mint: mint_config.mint,
+        token,
+        authority: ctx.accounts.authority.key(),
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Read the `index`th leading `Pubkey` field of a raw account after checking
+/// its discriminator, without deserializing the rest
+fn read_key(info: &AccountInfo, discriminator: &[u8], index: usize) -> Result<Pubkey> {
+    let data = info.try_borrow_data()?;
+    let start = 8 + 32 * index;
+    require!(
+        data.len() >= start + 32 && &data[..8] == discriminator,
+        ErrorCode::AccountDiscriminatorMismatch
+    );
+    Ok(Pubkey::new_from_array(data[start..start + 32].try_into().unwrap()))
+}
+
+/// Grow an account to `space`, with `payer` topping up any rent shortfall.
+/// The new bytes are zero-filled.
+fn grow_account<'info>(
+    info: &AccountInfo<'info>,
+    space: usize,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+) -> Result<()> {
+    if info.data_len() >= space {
+        return Ok(());
+    }
+
+    let shortfall = Rent::get()?.minimum_balance(space).saturating_sub(info.lamports());
+    if shortfall > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                system_program.to_account_info(),
+                system_program::Transfer {
+                    from: payer.to_account_info(),
+                    to: info.clone(),
+                },
+            ),
+            shortfall,
+        )?;
+    }
+    info.resize(space)?;
+    Ok(())
+}
+
+pub fn migrate_mint_config_handler(ctx: Context<MigrateMintConfig>) -> Result<()> {
+    let clock = Clock::get()?;
+    let info = ctx.accounts.mint_config.to_account_info();
+
+    let authority = read_key(&info, MintConfig::DISCRIMINATOR, 0)?;
+    require_keys_eq!(authority, ctx.accounts.authority.key(), MeridianError::Unauthorized);
+
+    grow_account(
+        &info,
+        8 + MintConfig::INIT_SPACE,
+        &ctx.accounts.authority,
+        &ctx.accounts.system_program,
+    )?;
+
+    let mut mint_config = MintConfig::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+    let from_version = mint_config.version;
+    require!(from_version < MintConfig::CURRENT_VERSION, MeridianError::AlreadyMigrated);
+
+    // Nothing to backfill: zero/None is the default for every appended field
+    mint_config.version = MintConfig::CURRENT_VERSION;
+    mint_config.updated_at = clock.unix_timestamp;
+    mint_config.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+
+    emit!(AccountMigrated {
+        mint: mint_config.mint,
+        from_version,
+        to_version: MintConfig::CURRENT_VERSION,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+pub fn migrate_issuer_handler(ctx: Context<MigrateIssuer>) -> Result<()> {
+    let clock = Clock::get()?;
+    let info = ctx.accounts.issuer.to_account_info();
+
+    // An issuer leads with its own authority, then its mint config
+    let mint_config = read_key(&info, Issuer::DISCRIMINATOR, 1)?;
+    require_keys_eq!(mint_config, ctx.accounts.mint_config.key(), MeridianError::InvalidIssuer);
+
+    grow_account(
+        &info,
+        8 + Issuer::INIT_SPACE,
+        &ctx.accounts.authority,
+        &ctx.accounts.system_program,
+    )?;
+
+    let mut issuer = Issuer::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+    let from_version = issuer.version;
+    require!(from_version < Issuer::CURRENT_VERSION, MeridianError::AlreadyMigrated);
+
+    // Nothing to backfill: zero is the default for every appended field
+    issuer.version = Issuer::CURRENT_VERSION;
+    issuer.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+
+    emit!(AccountMigrated {
+        mint: ctx.accounts.mint_config.mint,
+        from_version,
+        to_version: Issuer::CURRENT_VERSION,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+pub fn migrate_collateral_vault_handler(ctx: Context<MigrateCollateralVault>) -> Result<()> {
+    let clock = Clock::get()?;
+    let info = ctx.accounts.collateral_vault.to_account_info();
+
+    // The PDA seeds already tie the vault to this mint config
+    read_key(&info, CollateralVault::DISCRIMINATOR, 0)?;
+
+    grow_account(
+        &info,
+        8 + CollateralVault::INIT_SPACE,
+        &ctx.accounts.authority,
+        &ctx.accounts.system_program,
+    )?;
+
+    let mut vault = CollateralVault::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+    let from_version = vault.version;
+    require!(from_version < CollateralVault::CURRENT_VERSION, MeridianError::AlreadyMigrated);
+
+    // Nothing to backfill: zero/None is the default for every appended field
+    vault.version = CollateralVault::CURRENT_VERSION;
+    vault.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+
+    emit!(AccountMigrated {
+        mint: ctx.accounts.mint_config.mint,
+        from_version,
+        to_version: CollateralVault::CURRENT_VERSION,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+#[event]
+pub struct AccountMigrated {
+    pub mint: Pubkey,
+    pub from_version: u8,
+    pub to_version: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SettlementTokenAdded {
+    pub mint: Pubkey,
+    pub token: Pubkey,
+    pub authority: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SettlementTokenRemoved {
+    pub mint: Pubkey,
+    pub token: Pubkey,
+    pub authority: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MintConfigUpdated {
+    pub mint: Pubkey,
+    pub authority: Pubkey,
+    pub timestamp: i64,
+}
@@ -0,0 +1,78 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_2022::{self, FreezeAccount, Token2022};
+use anchor_spl::token_interface::{Mint, TokenAccount};
+
+use crate::errors::MeridianError;
+use crate::state::*;
+
+/// Re-freeze a single token account, e.g. when a holder's KYC lapses
+#[derive(Accounts)]
+pub struct FreezeTokenAccount<'info> {
+    /// Holder of the blacklister role
+    pub blacklister: Signer<'info>,
+
+    #[account(
+        seeds = [MintConfig::SEED_PREFIX],
+        bump = mint_config.bump,
+    )]
+    pub mint_config: Account<'info, MintConfig>,
+
+    #[account(
+        seeds = [RoleConfig::SEED_PREFIX, mint_config.key().as_ref()],
+        bump = role_config.bump,
+        constraint = role_config.blacklister == Some(blacklister.key()) @ MeridianError::InvalidRole
+    )]
+    pub role_config: Account<'info, RoleConfig>,
+
+    #[account(
+        constraint = mint.key() == mint_config.mint @ MeridianError::InvalidMint
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+    )]
+    pub target: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token2022>,
+}
+
+pub fn freeze_account_handler(ctx: Context<FreezeTokenAccount>) -> Result<()> {
+    let clock = Clock::get()?;
+    let accounts = &ctx.accounts;
+    let mint_config = &accounts.mint_config;
+
+    require!(mint_config.multisig.is_none(), MeridianError::MultisigRequired);
+
+    let seeds = &[MintConfig::SEED_PREFIX, &[mint_config.bump]];
+    let signer_seeds = &[&seeds[..]];
+    token_2022::freeze_account(CpiContext::new_with_signer(
+        accounts.token_program.to_account_info(),
+        FreezeAccount {
+            account: accounts.target.to_account_info(),
+            mint: accounts.mint.to_account_info(),
+            authority: mint_config.to_account_info(),
+        },
+        signer_seeds,
+    ))?;
+
+    emit!(AccountFrozen {
+        mint: accounts.mint.key(),
+        account: accounts.target.key(),
+        owner: accounts.target.owner,
+        blacklister: accounts.blacklister.key(),
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+#[event]
+pub struct AccountFrozen {
+    pub mint: Pubkey,
+    pub account: Pubkey,
+    pub owner: Pubkey,
+    pub blacklister: Pubkey,
+    pub timestamp: i64,
+}
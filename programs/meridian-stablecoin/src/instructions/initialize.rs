@@ -5,6 +5,7 @@ use anchor_spl::token_interface::Mint;
 use crate::state::*;
 
 #[derive(Accounts)]
+#[instruction(params: InitializeParams)]
 pub struct Initialize<'info> {
     #[account(mut)]
     pub authority: Signer<'info>,
@@ -24,7 +25,7 @@ pub struct Initialize<'info> {
     #[account(
         init,
         payer = authority,
-        mint::decimals = 2, // Stablecoin uses 2 decimal places
+        mint::decimals = params.decimals,
         mint::authority = mint_config,
         mint::freeze_authority = mint_config,
         extensions::transfer_hook::authority = mint_config,
@@ -102,6 +103,13 @@ pub fn handler(ctx: Context<Initialize>, params: InitializeParams) -> Result<()>
     mint_config.default_account_frozen = default_frozen;
     mint_config.decimals = params.decimals;
     mint_config.treasury = params.treasury;
+    mint_config.seize_escrow = None;
+    mint_config.escrow_bps = 0;
+    mint_config.require_issuer_attribution = false;
+    mint_config.attributed_collateral = 0;
+    mint_config.settlement_tokens = Vec::new();
+    mint_config.multisig = None;
+    mint_config.version = MintConfig::CURRENT_VERSION;
 
     emit!(MintInitialized {
         mint: ctx.accounts.mint.key(),
@@ -55,6 +55,10 @@ pub fn register_handler(ctx: Context<RegisterIssuer>, params: RegisterIssuerPara
     issuer.is_active = true;
     issuer.registered_at = clock.unix_timestamp;
     issuer.bump = ctx.bumps.issuer;
+    issuer.version = Issuer::CURRENT_VERSION;
+    issuer.attributed_collateral = 0;
+    issuer.min_mint_interval = 0;
+    issuer.last_mint_time = 0;
 
     emit!(IssuerRegistered {
         issuer: params.issuer_authority,
@@ -75,6 +79,7 @@ pub struct UpdateIssuer<'info> {
     pub authority: Signer<'info>,
 
     #[account(
+        mut,
         seeds = [MintConfig::SEED_PREFIX],
         bump = mint_config.bump,
     )]
@@ -94,6 +99,10 @@ pub struct UpdateIssuerParams {
     pub daily_mint_limit: Option<u64>,
     pub daily_burn_limit: Option<u64>,
     pub is_active: Option<bool>,
+    /// Collateral attributed to the issuer's outstanding mint
+    pub attributed_collateral: Option<u64>,
+    /// Minimum seconds between mints (0 = no limit)
+    pub min_mint_interval: Option<i64>,
 }
 
 pub fn update_handler(ctx: Context<UpdateIssuer>, params: UpdateIssuerParams) -> Result<()> {
@@ -109,12 +118,32 @@ pub fn update_handler(ctx: Context<UpdateIssuer>, params: UpdateIssuerParams) ->
     if let Some(active) = params.is_active {
         issuer.is_active = active;
     }
+    if let Some(attributed) = params.attributed_collateral {
+        // Attributions across all issuers can't claim more than the mint holds
+        let mint_config = &mut ctx.accounts.mint_config;
+        let total = mint_config
+            .attributed_collateral
+            .saturating_sub(issuer.attributed_collateral)
+            .saturating_add(attributed);
+        require!(
+            total <= mint_config.total_collateral,
+            MeridianError::AttributionExceedsCollateral
+        );
+        mint_config.attributed_collateral = total;
+        issuer.attributed_collateral = attributed;
+    }
+    if let Some(interval) = params.min_mint_interval {
+        require!(interval >= 0, MeridianError::InvalidAmount);
+        issuer.min_mint_interval = interval;
+    }
 
     emit!(IssuerUpdated {
         issuer: issuer.authority,
         daily_mint_limit: issuer.daily_mint_limit,
         daily_burn_limit: issuer.daily_burn_limit,
         is_active: issuer.is_active,
+        attributed_collateral: issuer.attributed_collateral,
+        min_mint_interval: issuer.min_mint_interval,
         timestamp: clock.unix_timestamp,
     });
 
@@ -136,5 +165,7 @@ pub struct IssuerUpdated {
     pub daily_mint_limit: u64,
     pub daily_burn_limit: u64,
     pub is_active: bool,
+    pub attributed_collateral: u64,
+    pub min_mint_interval: i64,
     pub timestamp: i64,
 }
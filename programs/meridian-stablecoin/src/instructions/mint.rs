@@ -1,7 +1,10 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token_2022::{self, MintTo, Token2022};
+use anchor_spl::token_2022::{self, FreezeAccount, MintTo, ThawAccount, Token2022};
 use anchor_spl::token_interface::{Mint, TokenAccount};
 
+use oracle::PriceFeed;
+
+use crate::instructions::collateral::collateral_price;
 use crate::state::*;
 use crate::errors::MeridianError;
 
@@ -18,6 +21,15 @@ pub struct MintStablecoin<'info> {
     )]
     pub mint_config: Account<'info, MintConfig>,
 
+    /// CHECK: role config PDA, read through `RoleConfig::load`. Always
+    /// passed, so an assigned minter can't be sidestepped by omitting it;
+    /// empty until `initialize_roles`.
+    #[account(
+        seeds = [RoleConfig::SEED_PREFIX, mint_config.key().as_ref()],
+        bump,
+    )]
+    pub role_config: UncheckedAccount<'info>,
+
     #[account(
         mut,
         seeds = [Issuer::SEED_PREFIX, issuer_authority.key().as_ref()],
@@ -40,6 +52,16 @@ pub struct MintStablecoin<'info> {
     )]
     pub recipient_token_account: InterfaceAccount<'info, TokenAccount>,
 
+    /// Collateral vault; mints are checked against its market value
+    #[account(
+        seeds = [CollateralVault::SEED_PREFIX, mint_config.key().as_ref()],
+        bump = collateral_vault.bump,
+    )]
+    pub collateral_vault: Box<Account<'info, CollateralVault>>,
+
+    /// Required when the vault has a price feed
+    pub price_feed: Option<Box<Account<'info, PriceFeed>>>,
+
     pub token_program: Program<'info, Token2022>,
 }
 
@@ -51,22 +73,83 @@ pub struct MintParams {
 }
 
 pub fn handler(ctx: Context<MintStablecoin>, params: MintParams) -> Result<()> {
+    let accounts = ctx.accounts;
+    // Registered issuers mint as they did before roles until a minter is
+    // assigned; then only the minter and the master authority may
+    let roles = RoleConfig::load(&accounts.role_config)?;
+    require!(
+        roles.as_ref().and_then(|roles| roles.minter).is_none()
+            || RoleConfig::permits(
+                roles.as_ref(),
+                |roles| roles.minter,
+                &accounts.mint_config.authority,
+                &accounts.issuer_authority.key(),
+            ),
+        MeridianError::InvalidRole
+    );
+    let collateral_value = collateral_value(
+        &accounts.mint_config,
+        &accounts.collateral_vault,
+        accounts.price_feed.as_deref(),
+    )?;
+    mint_checked(
+        &mut accounts.mint_config,
+        &mut accounts.issuer,
+        &accounts.mint,
+        &accounts.recipient_token_account,
+        &accounts.token_program,
+        collateral_value,
+        &params,
+    )
+}
+
+/// Market value of the collateral backing `mint_config`, priced through the
+/// vault's feed (1:1 without one)
+fn collateral_value(
+    mint_config: &MintConfig,
+    vault: &CollateralVault,
+    price_feed: Option<&Account<PriceFeed>>,
+) -> Result<u64> {
+    let price = collateral_price(vault, price_feed, Clock::get()?.unix_timestamp)?;
+    CollateralVault::market_value(mint_config.total_collateral, price)
+        .ok_or_else(|| MeridianError::MathOverflow.into())
+}
+
+/// Mint to a recipient after the issuer limit and collateral checks shared
+/// by `mint` and `mint_and_thaw`
+fn mint_checked<'info>(
+    mint_config: &mut Account<'info, MintConfig>,
+    issuer: &mut Account<'info, Issuer>,
+    mint: &InterfaceAccount<'info, Mint>,
+    recipient: &InterfaceAccount<'info, TokenAccount>,
+    token_program: &Program<'info, Token2022>,
+    collateral_value: u64,
+    params: &MintParams,
+) -> Result<()> {
     let clock = Clock::get()?;
-    let mint_config = &mut ctx.accounts.mint_config;
-    let issuer = &mut ctx.accounts.issuer;
 
     // Reset daily limits if needed
     issuer.maybe_reset_daily(clock.unix_timestamp);
 
     // Validate minting
     require!(
-        mint_config.can_mint(params.amount),
+        mint_config.can_mint(params.amount, collateral_value),
         MeridianError::InsufficientCollateral
     );
     require!(
         issuer.can_mint(params.amount),
         MeridianError::DailyLimitExceeded
     );
+    require!(
+        issuer.mint_interval_elapsed(clock.unix_timestamp),
+        MeridianError::MintTooFrequent
+    );
+    if mint_config.require_issuer_attribution {
+        require!(
+            issuer.attribution_covers(params.amount),
+            MeridianError::IssuerUndercollateralized
+        );
+    }
 
     // Create signer seeds for mint_config PDA
     let seeds = &[
@@ -77,12 +160,12 @@ pub fn handler(ctx: Context<MintStablecoin>, params: MintParams) -> Result<()> {
 
     // Mint tokens
     let cpi_accounts = MintTo {
-        mint: ctx.accounts.mint.to_account_info(),
-        to: ctx.accounts.recipient_token_account.to_account_info(),
+        mint: mint.to_account_info(),
+        to: recipient.to_account_info(),
         authority: mint_config.to_account_info(),
     };
     let cpi_ctx = CpiContext::new_with_signer(
-        ctx.accounts.token_program.to_account_info(),
+        token_program.to_account_info(),
         cpi_accounts,
         signer_seeds,
     );
@@ -91,21 +174,168 @@ pub fn handler(ctx: Context<MintStablecoin>, params: MintParams) -> Result<()> {
     // Update state
     mint_config.total_supply = mint_config.total_supply.saturating_add(params.amount);
     mint_config.updated_at = clock.unix_timestamp;
-    issuer.record_mint(params.amount);
+    issuer.record_mint(params.amount, clock.unix_timestamp);
 
     emit!(StablecoinMinted {
-        mint: ctx.accounts.mint.key(),
-        issuer: ctx.accounts.issuer_authority.key(),
-        recipient: ctx.accounts.recipient_token_account.key(),
+        mint: mint.key(),
+        issuer: issuer.authority,
+        recipient: recipient.key(),
         amount: params.amount,
         reference: params.reference,
         total_supply: mint_config.total_supply,
+        issuer_outstanding: issuer.outstanding(),
+        issuer_attributed_collateral: issuer.attributed_collateral,
+        issuer_collateral_ratio_bps: issuer.collateral_ratio_bps(),
         timestamp: clock.unix_timestamp,
     });
 
     Ok(())
 }
 
+/// Mint into an account that starts frozen (SSS-2 `default_account_frozen`):
+/// thaw, mint, and optionally re-freeze in one instruction
+#[derive(Accounts)]
+pub struct MintAndThaw<'info> {
+    /// Issuer authority holding the minter role
+    #[account(mut)]
+    pub issuer_authority: Signer<'info>,
+
+    /// Holder of the blacklister role, co-signing the thaw
+    pub blacklister: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [MintConfig::SEED_PREFIX],
+        bump = mint_config.bump,
+        constraint = !mint_config.is_paused @ MeridianError::MintPaused
+    )]
+    pub mint_config: Account<'info, MintConfig>,
+
+    #[account(
+        seeds = [RoleConfig::SEED_PREFIX, mint_config.key().as_ref()],
+        bump = role_config.bump,
+        constraint = role_config.minter == Some(issuer_authority.key()) @ MeridianError::InvalidRole,
+        constraint = role_config.blacklister == Some(blacklister.key()) @ MeridianError::InvalidRole
+    )]
+    pub role_config: Account<'info, RoleConfig>,
+
+    #[account(
+        mut,
+        seeds = [Issuer::SEED_PREFIX, issuer_authority.key().as_ref()],
+        bump = issuer.bump,
+        constraint = issuer.is_active @ MeridianError::IssuerInactive,
+        constraint = issuer.mint_config == mint_config.key() @ MeridianError::InvalidIssuer
+    )]
+    pub issuer: Account<'info, Issuer>,
+
+    #[account(
+        mut,
+        constraint = mint.key() == mint_config.mint @ MeridianError::InvalidMint
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+    )]
+    pub recipient_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// Collateral vault; mints are checked against its market value
+    #[account(
+        seeds = [CollateralVault::SEED_PREFIX, mint_config.key().as_ref()],
+        bump = collateral_vault.bump,
+    )]
+    pub collateral_vault: Box<Account<'info, CollateralVault>>,
+
+    /// Required when the vault has a price feed
+    pub price_feed: Option<Box<Account<'info, PriceFeed>>>,
+
+    pub token_program: Program<'info, Token2022>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct MintAndThawParams {
+    pub amount: u64,
+    /// Bank transfer reference for audit trail
+    pub reference: [u8; 32],
+    /// Freeze the recipient again after minting (KYC still pending)
+    pub refreeze: bool,
+}
+
+pub fn mint_and_thaw_handler(ctx: Context<MintAndThaw>, params: MintAndThawParams) -> Result<()> {
+    let clock = Clock::get()?;
+    let accounts = ctx.accounts;
+    let bump = accounts.mint_config.bump;
+    let seeds = &[MintConfig::SEED_PREFIX, &[bump]];
+    let signer_seeds = &[&seeds[..]];
+
+    let was_frozen = accounts.recipient_token_account.is_frozen();
+    // Freezing an account that wasn't frozen is a plain freeze, so it's
+    // gated like `freeze_account`
+    if params.refreeze && !was_frozen {
+        require!(accounts.mint_config.multisig.is_none(), MeridianError::MultisigRequired);
+    }
+    if was_frozen {
+        token_2022::thaw_account(CpiContext::new_with_signer(
+            accounts.token_program.to_account_info(),
+            ThawAccount {
+                account: accounts.recipient_token_account.to_account_info(),
+                mint: accounts.mint.to_account_info(),
+                authority: accounts.mint_config.to_account_info(),
+            },
+            signer_seeds,
+        ))?;
+    }
+
+    let collateral_value = collateral_value(
+        &accounts.mint_config,
+        &accounts.collateral_vault,
+        accounts.price_feed.as_deref(),
+    )?;
+    mint_checked(
+        &mut accounts.mint_config,
+        &mut accounts.issuer,
+        &accounts.mint,
+        &accounts.recipient_token_account,
+        &accounts.token_program,
+        collateral_value,
+        &MintParams { amount: params.amount, reference: params.reference },
+    )?;
+
+    if params.refreeze {
+        token_2022::freeze_account(CpiContext::new_with_signer(
+            accounts.token_program.to_account_info(),
+            FreezeAccount {
+                account: accounts.recipient_token_account.to_account_info(),
+                mint: accounts.mint.to_account_info(),
+                authority: accounts.mint_config.to_account_info(),
+            },
+            signer_seeds,
+        ))?;
+    }
+
+    emit!(FrozenRecipientMinted {
+        recipient: accounts.recipient_token_account.key(),
+        minter: accounts.issuer_authority.key(),
+        blacklister: accounts.blacklister.key(),
+        was_frozen,
+        refrozen: params.refreeze,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+#[event]
+pub struct FrozenRecipientMinted {
+    pub recipient: Pubkey,
+    pub minter: Pubkey,
+    pub blacklister: Pubkey,
+    pub was_frozen: bool,
+    pub refrozen: bool,
+    pub timestamp: i64,
+}
+
 #[event]
 pub struct StablecoinMinted {
     pub mint: Pubkey,
@@ -114,5 +344,8 @@ pub struct StablecoinMinted {
     pub amount: u64,
     pub reference: [u8; 32],
     pub total_supply: u64,
+    pub issuer_outstanding: u64,
+    pub issuer_attributed_collateral: u64,
+    pub issuer_collateral_ratio_bps: u64,
     pub timestamp: i64,
 }
@@ -9,6 +9,10 @@ pub mod issuer;
 pub mod collateral;
 pub mod seize;
 pub mod roles;
+pub mod config;
+pub mod thaw;
+pub mod freeze;
+pub mod multisig;
 
 pub use initialize::*;
 pub use mint::*;
@@ -19,3 +23,7 @@ pub use issuer::*;
 pub use collateral::*;
 pub use seize::*;
 pub use roles::*;
+pub use config::*;
+pub use thaw::*;
+pub use freeze::*;
+pub use multisig::*;
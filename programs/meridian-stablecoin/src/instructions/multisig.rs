@@ -0,0 +1,538 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_2022::{self, FreezeAccount, Token2022};
+use anchor_spl::token_interface::{Mint, TokenAccount};
+
+use crate::errors::MeridianError;
+use crate::instructions::collateral::*;
+use crate::instructions::pause::*;
+use crate::instructions::roles::*;
+use crate::instructions::seize::*;
+use crate::state::*;
+
+#[derive(Accounts)]
+pub struct ConfigureMultisig<'info> {
+    /// Master authority handing pause, role, config, vault, seize and freeze
+    /// actions to the multisig
+    #[account(
+        mut,
+        constraint = authority.key() == mint_config.authority @ MeridianError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [MintConfig::SEED_PREFIX],
+        bump = mint_config.bump,
+        constraint = mint_config.multisig.is_none() @ MeridianError::MultisigAlreadyConfigured
+    )]
+    pub mint_config: Account<'info, MintConfig>,
+
+    /// Reused when a disabled multisig is configured again
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + MultisigConfig::INIT_SPACE,
+        seeds = [MultisigConfig::SEED_PREFIX, mint_config.key().as_ref()],
+        bump
+    )]
+    pub multisig: Account<'info, MultisigConfig>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ProposeMultisigAction<'info> {
+    /// Multisig signer opening the proposal (pays rent, counts as the first approval)
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+
+    #[account(
+        seeds = [MintConfig::SEED_PREFIX],
+        bump = mint_config.bump,
+        constraint = mint_config.multisig == Some(multisig.key()) @ MeridianError::MultisigNotConfigured
+    )]
+    pub mint_config: Account<'info, MintConfig>,
+
+    #[account(
+        mut,
+        seeds = [MultisigConfig::SEED_PREFIX, mint_config.key().as_ref()],
+        bump = multisig.bump,
+        constraint = multisig.is_signer(&proposer.key()) @ MeridianError::NotMultisigSigner
+    )]
+    pub multisig: Account<'info, MultisigConfig>,
+
+    #[account(
+        init,
+        payer = proposer,
+        space = 8 + MultisigProposal::INIT_SPACE,
+        seeds = [
+            MultisigProposal::SEED_PREFIX,
+            multisig.key().as_ref(),
+            &multisig.proposal_count.to_le_bytes(),
+        ],
+        bump
+    )]
+    pub proposal: Account<'info, MultisigProposal>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveMultisigAction<'info> {
+    pub approver: Signer<'info>,
+
+    #[account(
+        seeds = [MintConfig::SEED_PREFIX],
+        bump = mint_config.bump,
+        constraint = mint_config.multisig == Some(multisig.key()) @ MeridianError::MultisigNotConfigured
+    )]
+    pub mint_config: Account<'info, MintConfig>,
+
+    #[account(
+        seeds = [MultisigConfig::SEED_PREFIX, mint_config.key().as_ref()],
+        bump = multisig.bump,
+        constraint = multisig.is_signer(&approver.key()) @ MeridianError::NotMultisigSigner
+    )]
+    pub multisig: Account<'info, MultisigConfig>,
+
+    #[account(
+        mut,
+        seeds = [MultisigProposal::SEED_PREFIX, multisig.key().as_ref(), &proposal.index.to_le_bytes()],
+        bump = proposal.bump,
+    )]
+    pub proposal: Account<'info, MultisigProposal>,
+}
+
+#[derive(Accounts)]
+pub struct CancelMultisigAction<'info> {
+    /// Signer who opened the proposal (receives the rent back)
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+
+    #[account(
+        seeds = [MultisigConfig::SEED_PREFIX, multisig.mint_config.as_ref()],
+        bump = multisig.bump,
+    )]
+    pub multisig: Account<'info, MultisigConfig>,
+
+    #[account(
+        mut,
+        close = proposer,
+        seeds = [MultisigProposal::SEED_PREFIX, multisig.key().as_ref(), &proposal.index.to_le_bytes()],
+        bump = proposal.bump,
+        has_one = proposer @ MeridianError::Unauthorized,
+    )]
+    pub proposal: Account<'info, MultisigProposal>,
+}
+
+/// Accounts shared by every multisig execution. The proposal is closed back
+/// to its proposer once executed.
+#[derive(Accounts)]
+pub struct MultisigExecution<'info> {
+    /// Multisig signer executing the proposal
+    pub executor: Signer<'info>,
+
+    #[account(
+        constraint = multisig.is_signer(&executor.key()) @ MeridianError::NotMultisigSigner
+    )]
+    pub multisig: Account<'info, MultisigConfig>,
+
+    #[account(
+        mut,
+        close = proposer,
+        seeds = [MultisigProposal::SEED_PREFIX, multisig.key().as_ref(), &proposal.index.to_le_bytes()],
+        bump = proposal.bump,
+    )]
+    pub proposal: Account<'info, MultisigProposal>,
+
+    /// CHECK: rent refund destination, must be the original proposer
+    #[account(mut, address = proposal.proposer)]
+    pub proposer: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteMultisigAction<'info> {
+    pub execution: MultisigExecution<'info>,
+
+    #[account(
+        mut,
+        seeds = [MintConfig::SEED_PREFIX],
+        bump = mint_config.bump,
+    )]
+    pub mint_config: Account<'info, MintConfig>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteMultisigUpdateRoles<'info> {
+    pub execution: MultisigExecution<'info>,
+
+    #[account(
+        seeds = [MintConfig::SEED_PREFIX],
+        bump = mint_config.bump,
+    )]
+    pub mint_config: Account<'info, MintConfig>,
+
+    #[account(
+        mut,
+        seeds = [RoleConfig::SEED_PREFIX, mint_config.key().as_ref()],
+        bump = role_config.bump,
+    )]
+    pub role_config: Account<'info, RoleConfig>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteMultisigInitializeVault<'info> {
+    pub execution: MultisigExecution<'info>,
+
+    /// Pays the vault rent
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        seeds = [MintConfig::SEED_PREFIX],
+        bump = mint_config.bump,
+    )]
+    pub mint_config: Account<'info, MintConfig>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + CollateralVault::INIT_SPACE,
+        seeds = [CollateralVault::SEED_PREFIX, mint_config.key().as_ref()],
+        bump
+    )]
+    pub collateral_vault: Account<'info, CollateralVault>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteMultisigSeize<'info> {
+    pub execution: MultisigExecution<'info>,
+
+    /// Seize accounts; `authority` must be the executor
+    pub seize: Seize<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteMultisigFreeze<'info> {
+    pub execution: MultisigExecution<'info>,
+
+    #[account(
+        seeds = [MintConfig::SEED_PREFIX],
+        bump = mint_config.bump,
+    )]
+    pub mint_config: Account<'info, MintConfig>,
+
+    #[account(
+        constraint = mint.key() == mint_config.mint @ MeridianError::InvalidMint
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// Token account to freeze; must be the proposal's target
+    #[account(
+        mut,
+        token::mint = mint,
+    )]
+    pub target: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token2022>,
+}
+
+pub fn configure_multisig_handler(
+    ctx: Context<ConfigureMultisig>,
+    signers: Vec<Pubkey>,
+    threshold: u8,
+) -> Result<()> {
+    let clock = Clock::get()?;
+
+    require!(
+        !signers.is_empty() && signers.len() <= MultisigConfig::MAX_SIGNERS,
+        MeridianError::InvalidMultisigConfig
+    );
+    require!(
+        threshold > 0 && threshold as usize <= signers.len(),
+        MeridianError::InvalidMultisigConfig
+    );
+    for (i, signer) in signers.iter().enumerate() {
+        require!(!signers[..i].contains(signer), MeridianError::InvalidMultisigConfig);
+    }
+
+    let multisig = &mut ctx.accounts.multisig;
+    multisig.mint_config = ctx.accounts.mint_config.key();
+    multisig.signers = signers;
+    multisig.threshold = threshold;
+    multisig.config_generation = multisig.config_generation.saturating_add(1);
+    multisig.bump = ctx.bumps.multisig;
+
+    let mint_config = &mut ctx.accounts.mint_config;
+    mint_config.multisig = Some(multisig.key());
+    mint_config.updated_at = clock.unix_timestamp;
+
+    emit!(MultisigConfigured {
+        mint_config: mint_config.key(),
+        multisig: multisig.key(),
+        signers: multisig.signers.clone(),
+        threshold,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+pub fn propose_multisig_handler(
+    ctx: Context<ProposeMultisigAction>,
+    action: MultisigAction,
+) -> Result<()> {
+    let clock = Clock::get()?;
+    let multisig = &mut ctx.accounts.multisig;
+
+    let proposal = &mut ctx.accounts.proposal;
+    proposal.multisig = multisig.key();
+    proposal.index = multisig.proposal_count;
+    proposal.action = action;
+    proposal.proposer = ctx.accounts.proposer.key();
+    proposal.approvals = vec![ctx.accounts.proposer.key()];
+    proposal.created_at = clock.unix_timestamp;
+    proposal.bump = ctx.bumps.proposal;
+    proposal.config_generation = multisig.config_generation;
+
+    multisig.proposal_count += 1;
+
+    emit!(ProposalCreated {
+        proposal: proposal.key(),
+        multisig: multisig.key(),
+        index: proposal.index,
+        action,
+        proposer: proposal.proposer,
+        threshold: multisig.threshold,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+pub fn approve_multisig_handler(ctx: Context<ApproveMultisigAction>) -> Result<()> {
+    let clock = Clock::get()?;
+    let approver = ctx.accounts.approver.key();
+    let multisig = &ctx.accounts.multisig;
+    let proposal = &mut ctx.accounts.proposal;
+
+    require!(!proposal.approvals.contains(&approver), MeridianError::AlreadyApproved);
+    proposal.approvals.push(approver);
+
+    emit!(ProposalApproved {
+        proposal: proposal.key(),
+        approver,
+        approvals: multisig.approval_count(proposal),
+        threshold: multisig.threshold,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Withdraw a pending proposal (proposer only), refunding its rent
+pub fn cancel_multisig_handler(ctx: Context<CancelMultisigAction>) -> Result<()> {
+    let clock = Clock::get()?;
+
+    emit!(ProposalCancelled {
+        proposal: ctx.accounts.proposal.key(),
+        multisig: ctx.accounts.multisig.key(),
+        cancelled_by: ctx.accounts.proposer.key(),
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Check that `execution` carries an approved proposal for the multisig
+/// attached to `mint_config`, and return its action. A zero threshold is
+/// never treated as approval, whatever the stored config says.
+fn approved_action(execution: &MultisigExecution, mint_config: &Account<MintConfig>) -> Result<MultisigAction> {
+    let multisig = &execution.multisig;
+    require!(
+        mint_config.multisig == Some(multisig.key())
+            && multisig.mint_config == mint_config.key(),
+        MeridianError::MultisigNotConfigured
+    );
+    require!(multisig.threshold >= 1, MeridianError::InvalidMultisigConfig);
+    require!(
+        multisig.approval_count(&execution.proposal) >= multisig.threshold,
+        MeridianError::ApprovalThresholdNotMet
+    );
+    Ok(execution.proposal.action)
+}
+
+fn emit_executed(execution: &MultisigExecution) -> Result<()> {
+    let clock = Clock::get()?;
+
+    emit!(ProposalExecuted {
+        proposal: execution.proposal.key(),
+        multisig: execution.multisig.key(),
+        action: execution.proposal.action,
+        executed_by: execution.executor.key(),
+        approvals: execution.multisig.approval_count(&execution.proposal),
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Execute an approved pause, unpause or disable proposal
+pub fn execute_multisig_action_handler(ctx: Context<ExecuteMultisigAction>) -> Result<()> {
+    let accounts = ctx.accounts;
+    let executor = accounts.execution.executor.key();
+
+    match approved_action(&accounts.execution, &accounts.mint_config)? {
+        MultisigAction::Pause => set_paused(&mut accounts.mint_config, true, executor)?,
+        MultisigAction::Unpause => set_paused(&mut accounts.mint_config, false, executor)?,
+        MultisigAction::Disable => {
+            accounts.mint_config.multisig = None;
+            accounts.mint_config.updated_at = Clock::get()?.unix_timestamp;
+        }
+        _ => return err!(MeridianError::ProposalMismatch),
+    }
+
+    emit_executed(&accounts.execution)
+}
+
+/// Execute an approved role update
+pub fn execute_multisig_update_roles_handler(ctx: Context<ExecuteMultisigUpdateRoles>) -> Result<()> {
+    let accounts = ctx.accounts;
+
+    let MultisigAction::UpdateRoles { minter, burner, blacklister, pauser, seizer } =
+        approved_action(&accounts.execution, &accounts.mint_config)?
+    else {
+        return err!(MeridianError::ProposalMismatch);
+    };
+
+    apply_roles(
+        &mut accounts.role_config,
+        UpdateRolesParams { minter, burner, blacklister, pauser, seizer },
+    );
+
+    emit!(RolesUpdated {
+        mint_config: accounts.mint_config.key(),
+        updated_by: accounts.execution.executor.key(),
+    });
+
+    emit_executed(&accounts.execution)
+}
+
+/// Execute an approved collateral vault creation. The vault authority is the
+/// master authority, as with `initialize_vault`.
+pub fn execute_multisig_initialize_vault_handler(
+    ctx: Context<ExecuteMultisigInitializeVault>,
+) -> Result<()> {
+    let accounts = ctx.accounts;
+
+    let MultisigAction::InitializeVault { collateral_type, auditor, audit_floor_bps, price_feed } =
+        approved_action(&accounts.execution, &accounts.mint_config)?
+    else {
+        return err!(MeridianError::ProposalMismatch);
+    };
+
+    init_vault(
+        &mut accounts.collateral_vault,
+        accounts.mint_config.key(),
+        accounts.mint_config.authority,
+        InitializeVaultParams { collateral_type, auditor, audit_floor_bps, price_feed },
+        ctx.bumps.collateral_vault,
+    )?;
+
+    emit_executed(&accounts.execution)
+}
+
+/// Execute an approved seizure against the proposal's source account
+pub fn execute_multisig_seize_handler(ctx: Context<ExecuteMultisigSeize>) -> Result<()> {
+    let accounts = &ctx.accounts;
+
+    require_keys_eq!(
+        accounts.seize.authority.key(),
+        accounts.execution.executor.key(),
+        MeridianError::Unauthorized
+    );
+    let MultisigAction::Seize { source, amount, reason, force } =
+        approved_action(&accounts.execution, &accounts.seize.mint_config)?
+    else {
+        return err!(MeridianError::ProposalMismatch);
+    };
+    require_keys_eq!(accounts.seize.source.key(), source, MeridianError::ProposalMismatch);
+
+    seize_tokens(&accounts.seize, amount, reason, force)?;
+
+    emit_executed(&accounts.execution)
+}
+
+/// Execute an approved freeze against the proposal's target account
+pub fn execute_multisig_freeze_handler(ctx: Context<ExecuteMultisigFreeze>) -> Result<()> {
+    let accounts = &ctx.accounts;
+    let mint_config = &accounts.mint_config;
+
+    let MultisigAction::Freeze { target } = approved_action(&accounts.execution, mint_config)? else {
+        return err!(MeridianError::ProposalMismatch);
+    };
+    require_keys_eq!(accounts.target.key(), target, MeridianError::ProposalMismatch);
+
+    let seeds = &[MintConfig::SEED_PREFIX, &[mint_config.bump]];
+    let signer_seeds = &[&seeds[..]];
+    token_2022::freeze_account(CpiContext::new_with_signer(
+        accounts.token_program.to_account_info(),
+        FreezeAccount {
+            account: accounts.target.to_account_info(),
+            mint: accounts.mint.to_account_info(),
+            authority: mint_config.to_account_info(),
+        },
+        signer_seeds,
+    ))?;
+
+    emit_executed(&accounts.execution)
+}
+
+#[event]
+pub struct MultisigConfigured {
+    pub mint_config: Pubkey,
+    pub multisig: Pubkey,
+    pub signers: Vec<Pubkey>,
+    pub threshold: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ProposalCreated {
+    pub proposal: Pubkey,
+    pub multisig: Pubkey,
+    pub index: u64,
+    pub action: MultisigAction,
+    pub proposer: Pubkey,
+    pub threshold: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ProposalApproved {
+    pub proposal: Pubkey,
+    pub approver: Pubkey,
+    pub approvals: u8,
+    pub threshold: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ProposalCancelled {
+    pub proposal: Pubkey,
+    pub multisig: Pubkey,
+    pub cancelled_by: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ProposalExecuted {
+    pub proposal: Pubkey,
+    pub multisig: Pubkey,
+    pub action: MultisigAction,
+    pub executed_by: Pubkey,
+    pub approvals: u8,
+    pub timestamp: i64,
+}
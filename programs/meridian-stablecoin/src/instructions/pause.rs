@@ -5,9 +5,7 @@ use crate::errors::MeridianError;
 
 #[derive(Accounts)]
 pub struct PauseMint<'info> {
-    #[account(
-        constraint = authority.key() == mint_config.authority @ MeridianError::Unauthorized
-    )]
+    /// Master authority or pauser role
     pub authority: Signer<'info>,
 
     #[account(
@@ -16,40 +14,67 @@ pub struct PauseMint<'info> {
         bump = mint_config.bump,
     )]
     pub mint_config: Account<'info, MintConfig>,
-}
-
-pub fn pause_handler(ctx: Context<PauseMint>) -> Result<()> {
-    let clock = Clock::get()?;
-    let mint_config = &mut ctx.accounts.mint_config;
-
-    require!(!mint_config.is_paused, MeridianError::AlreadyPaused);
-
-    mint_config.is_paused = true;
-    mint_config.updated_at = clock.unix_timestamp;
 
-    emit!(MintPaused {
-        mint: mint_config.mint,
-        authority: ctx.accounts.authority.key(),
-        timestamp: clock.unix_timestamp,
-    });
+    /// Role assignments; without it only the master authority may pause
+    #[account(
+        seeds = [RoleConfig::SEED_PREFIX, mint_config.key().as_ref()],
+        bump = role_config.bump,
+    )]
+    pub role_config: Option<Account<'info, RoleConfig>>,
+}
 
+fn require_pauser(accounts: &PauseMint) -> Result<()> {
+    require!(accounts.mint_config.multisig.is_none(), MeridianError::MultisigRequired);
+    require!(
+        RoleConfig::permits(
+            accounts.role_config.as_deref(),
+            |roles| roles.pauser,
+            &accounts.mint_config.authority,
+            &accounts.authority.key(),
+        ),
+        MeridianError::InvalidRole
+    );
     Ok(())
 }
 
+pub fn pause_handler(ctx: Context<PauseMint>) -> Result<()> {
+    require_pauser(ctx.accounts)?;
+    let authority = ctx.accounts.authority.key();
+    set_paused(&mut ctx.accounts.mint_config, true, authority)
+}
+
 pub fn unpause_handler(ctx: Context<PauseMint>) -> Result<()> {
+    require_pauser(ctx.accounts)?;
+    let authority = ctx.accounts.authority.key();
+    set_paused(&mut ctx.accounts.mint_config, false, authority)
+}
+
+/// Pause or unpause minting. Callers have already authorized `authority`.
+pub(crate) fn set_paused(mint_config: &mut MintConfig, paused: bool, authority: Pubkey) -> Result<()> {
     let clock = Clock::get()?;
-    let mint_config = &mut ctx.accounts.mint_config;
 
-    require!(mint_config.is_paused, MeridianError::NotPaused);
+    if paused {
+        require!(!mint_config.is_paused, MeridianError::AlreadyPaused);
+    } else {
+        require!(mint_config.is_paused, MeridianError::NotPaused);
+    }
 
-    mint_config.is_paused = false;
+    mint_config.is_paused = paused;
     mint_config.updated_at = clock.unix_timestamp;
 
-    emit!(MintUnpaused {
-        mint: mint_config.mint,
-        authority: ctx.accounts.authority.key(),
-        timestamp: clock.unix_timestamp,
-    });
+    if paused {
+        emit!(MintPaused {
+            mint: mint_config.mint,
+            authority,
+            timestamp: clock.unix_timestamp,
+        });
+    } else {
+        emit!(MintUnpaused {
+            mint: mint_config.mint,
+            authority,
+            timestamp: clock.unix_timestamp,
+        });
+    }
 
     Ok(())
 }
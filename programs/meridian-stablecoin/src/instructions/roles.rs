@@ -79,8 +79,20 @@ pub fn update_roles_handler(
     ctx: Context<UpdateRoles>,
     params: UpdateRolesParams,
 ) -> Result<()> {
-    let role_config = &mut ctx.accounts.role_config;
+    require!(ctx.accounts.mint_config.multisig.is_none(), MeridianError::MultisigRequired);
+
+    apply_roles(&mut ctx.accounts.role_config, params);
 
+    emit!(RolesUpdated {
+        mint_config: ctx.accounts.mint_config.key(),
+        updated_by: ctx.accounts.authority.key(),
+    });
+
+    Ok(())
+}
+
+/// Write the roles set in `params`. Callers have already authorized the update.
+pub(crate) fn apply_roles(role_config: &mut RoleConfig, params: UpdateRolesParams) {
     if let Some(minter) = params.minter {
         role_config.minter = minter;
     }
@@ -96,13 +108,6 @@ pub fn update_roles_handler(
     if let Some(seizer) = params.seizer {
         role_config.seizer = seizer;
     }
-
-    emit!(RolesUpdated {
-        mint_config: ctx.accounts.mint_config.key(),
-        updated_by: ctx.accounts.authority.key(),
-    });
-
-    Ok(())
 }
 
 #[event]
@@ -1,5 +1,5 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token_2022::{self, TransferChecked, Token2022};
+use anchor_spl::token_2022::{self, FreezeAccount, ThawAccount, TransferChecked, Token2022};
 use anchor_spl::token_interface::{Mint, TokenAccount};
 
 use crate::errors::MeridianError;
@@ -19,6 +19,14 @@ pub struct Seize<'info> {
     )]
     pub mint_config: Account<'info, MintConfig>,
 
+    /// Role assignments; without it only the master authority may seize.
+    /// Not consulted when seizing through `execute_multisig_seize`.
+    #[account(
+        seeds = [RoleConfig::SEED_PREFIX, mint_config.key().as_ref()],
+        bump = role_config.bump,
+    )]
+    pub role_config: Option<Account<'info, RoleConfig>>,
+
     #[account(
         constraint = mint.key() == mint_config.mint @ MeridianError::InvalidMint
     )]
@@ -39,6 +47,14 @@ pub struct Seize<'info> {
     )]
     pub treasury: InterfaceAccount<'info, TokenAccount>,
 
+    /// Regulatory escrow (required when `mint_config.escrow_bps` is set)
+    #[account(
+        mut,
+        token::mint = mint,
+        constraint = Some(seize_escrow.key()) == mint_config.seize_escrow @ MeridianError::SeizeEscrowNotConfigured,
+    )]
+    pub seize_escrow: Option<InterfaceAccount<'info, TokenAccount>>,
+
     pub token_program: Program<'info, Token2022>,
 }
 
@@ -48,27 +64,56 @@ pub struct SeizeParams {
     pub amount: u64,
     /// Reason for seizure (audit trail)
     pub reason: [u8; 32],
+    /// Emergency seizure from an account that is not frozen
+    pub force: bool,
 }
 
 pub fn seize_handler(ctx: Context<Seize>, params: SeizeParams) -> Result<()> {
-    let clock = Clock::get()?;
     let mint_config = &ctx.accounts.mint_config;
 
-    // Verify caller is authorized (master authority check)
+    // Verify caller is the master authority or holds the seizer role
     require!(
-        ctx.accounts.authority.key() == mint_config.authority,
-        MeridianError::Unauthorized
+        RoleConfig::permits(
+            ctx.accounts.role_config.as_deref(),
+            |roles| roles.seizer,
+            &mint_config.authority,
+            &ctx.accounts.authority.key(),
+        ),
+        MeridianError::InvalidRole
     );
+    require!(mint_config.multisig.is_none(), MeridianError::MultisigRequired);
+
+    seize_tokens(ctx.accounts, params.amount, params.reason, params.force)
+}
+
+/// Move `amount` (0 = entire balance) out of the source account, split
+/// between treasury and escrow. Callers have already authorized the seizure.
+/// The source must be frozen unless `force` is set; a frozen source is
+/// thawed for the transfers and frozen again, since Token-2022 refuses
+/// transfers out of frozen accounts even for the permanent delegate.
+pub(crate) fn seize_tokens(
+    accounts: &Seize,
+    amount: u64,
+    reason: [u8; 32],
+    force: bool,
+) -> Result<()> {
+    let clock = Clock::get()?;
+    let mint_config = &accounts.mint_config;
+
+    let was_frozen = accounts.source.is_frozen();
+    require!(was_frozen || force, MeridianError::AccountNotFrozen);
 
     // Determine seize amount
-    let seize_amount = if params.amount == 0 {
-        ctx.accounts.source.amount
+    let seize_amount = if amount == 0 {
+        accounts.source.amount
     } else {
-        params.amount
+        amount
     };
 
     require!(seize_amount > 0, MeridianError::InvalidAmount);
 
+    let (treasury_amount, escrow_amount) = mint_config.split_seizure(seize_amount);
+
     // Transfer using permanent delegate authority (mint_config PDA)
     let seeds = &[
         MintConfig::SEED_PREFIX,
@@ -76,26 +121,78 @@ pub fn seize_handler(ctx: Context<Seize>, params: SeizeParams) -> Result<()> {
     ];
     let signer_seeds = &[&seeds[..]];
 
-    let cpi_accounts = TransferChecked {
-        from: ctx.accounts.source.to_account_info(),
-        mint: ctx.accounts.mint.to_account_info(),
-        to: ctx.accounts.treasury.to_account_info(),
-        authority: ctx.accounts.mint_config.to_account_info(),
+    if was_frozen {
+        token_2022::thaw_account(CpiContext::new_with_signer(
+            accounts.token_program.to_account_info(),
+            ThawAccount {
+                account: accounts.source.to_account_info(),
+                mint: accounts.mint.to_account_info(),
+                authority: accounts.mint_config.to_account_info(),
+            },
+            signer_seeds,
+        ))?;
+    }
+
+    if treasury_amount > 0 {
+        let cpi_accounts = TransferChecked {
+            from: accounts.source.to_account_info(),
+            mint: accounts.mint.to_account_info(),
+            to: accounts.treasury.to_account_info(),
+            authority: accounts.mint_config.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            accounts.token_program.to_account_info(),
+            cpi_accounts,
+            signer_seeds,
+        );
+        token_2022::transfer_checked(cpi_ctx, treasury_amount, mint_config.decimals)?;
+    }
+
+    let escrow_key = if escrow_amount > 0 {
+        let escrow = accounts
+            .seize_escrow
+            .as_ref()
+            .ok_or(MeridianError::SeizeEscrowNotConfigured)?;
+        let cpi_accounts = TransferChecked {
+            from: accounts.source.to_account_info(),
+            mint: accounts.mint.to_account_info(),
+            to: escrow.to_account_info(),
+            authority: accounts.mint_config.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            accounts.token_program.to_account_info(),
+            cpi_accounts,
+            signer_seeds,
+        );
+        token_2022::transfer_checked(cpi_ctx, escrow_amount, mint_config.decimals)?;
+        Some(escrow.key())
+    } else {
+        None
     };
-    let cpi_ctx = CpiContext::new_with_signer(
-        ctx.accounts.token_program.to_account_info(),
-        cpi_accounts,
-        signer_seeds,
-    );
-    token_2022::transfer_checked(cpi_ctx, seize_amount, mint_config.decimals)?;
+
+    if was_frozen {
+        token_2022::freeze_account(CpiContext::new_with_signer(
+            accounts.token_program.to_account_info(),
+            FreezeAccount {
+                account: accounts.source.to_account_info(),
+                mint: accounts.mint.to_account_info(),
+                authority: accounts.mint_config.to_account_info(),
+            },
+            signer_seeds,
+        ))?;
+    }
 
     emit!(TokensSeized {
-        mint: ctx.accounts.mint.key(),
-        from: ctx.accounts.source.key(),
-        to: ctx.accounts.treasury.key(),
+        mint: accounts.mint.key(),
+        from: accounts.source.key(),
+        to: accounts.treasury.key(),
         amount: seize_amount,
-        reason: params.reason,
-        seized_by: ctx.accounts.authority.key(),
+        treasury_amount,
+        escrow: escrow_key,
+        escrow_amount,
+        reason,
+        seized_by: accounts.authority.key(),
+        forced: !was_frozen,
         timestamp: clock.unix_timestamp,
     });
 
@@ -108,7 +205,12 @@ pub struct TokensSeized {
     pub from: Pubkey,
     pub to: Pubkey,
     pub amount: u64,
+    pub treasury_amount: u64,
+    pub escrow: Option<Pubkey>,
+    pub escrow_amount: u64,
     pub reason: [u8; 32],
     pub seized_by: Pubkey,
+    /// Seized from an unfrozen account under `SeizeParams::force`
+    pub forced: bool,
     pub timestamp: i64,
 }
@@ -0,0 +1,198 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_2022::{self, ThawAccount, Token2022};
+use anchor_spl::token_interface::{Mint, TokenAccount};
+
+use crate::errors::MeridianError;
+use crate::state::*;
+
+/// Seed of the transfer hook's per-wallet `WhitelistEntry` PDA
+const WHITELIST_SEED: &[u8] = b"whitelist";
+
+/// Offset of `WhitelistEntry::is_active`: discriminator, wallet, registry,
+/// kyc_level, jurisdiction, kyc_hash
+const WHITELIST_IS_ACTIVE_OFFSET: usize = 8 + 32 + 32 + 1 + 1 + 32;
+
+/// Thaw a single token account once its owner clears KYC (SSS-2
+/// `default_account_frozen`)
+#[derive(Accounts)]
+pub struct ThawTokenAccount<'info> {
+    /// Holder of the blacklister role
+    pub blacklister: Signer<'info>,
+
+    #[account(
+        seeds = [MintConfig::SEED_PREFIX],
+        bump = mint_config.bump,
+    )]
+    pub mint_config: Account<'info, MintConfig>,
+
+    #[account(
+        seeds = [RoleConfig::SEED_PREFIX, mint_config.key().as_ref()],
+        bump = role_config.bump,
+        constraint = role_config.blacklister == Some(blacklister.key()) @ MeridianError::InvalidRole
+    )]
+    pub role_config: Account<'info, RoleConfig>,
+
+    #[account(
+        constraint = mint.key() == mint_config.mint @ MeridianError::InvalidMint
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+    )]
+    pub target: InterfaceAccount<'info, TokenAccount>,
+
+    /// The owner's transfer-hook whitelist entry. When provided, the thaw
+    /// only goes ahead if the entry is active.
+    /// CHECK: PDA, owner and `is_active` are verified in the handler
+    pub whitelist_entry: Option<UncheckedAccount<'info>>,
+
+    pub token_program: Program<'info, Token2022>,
+}
+
+pub fn thaw_account_handler(ctx: Context<ThawTokenAccount>) -> Result<()> {
+    let clock = Clock::get()?;
+    let accounts = &ctx.accounts;
+    let owner = accounts.target.owner;
+
+    if let Some(entry) = &accounts.whitelist_entry {
+        require_whitelisted(entry, &accounts.mint_config.transfer_hook_program, &owner)?;
+    }
+
+    let seeds = &[MintConfig::SEED_PREFIX, &[accounts.mint_config.bump]];
+    let signer_seeds = &[&seeds[..]];
+    token_2022::thaw_account(CpiContext::new_with_signer(
+        accounts.token_program.to_account_info(),
+        ThawAccount {
+            account: accounts.target.to_account_info(),
+            mint: accounts.mint.to_account_info(),
+            authority: accounts.mint_config.to_account_info(),
+        },
+        signer_seeds,
+    ))?;
+
+    emit!(AccountThawed {
+        mint: accounts.mint.key(),
+        account: accounts.target.key(),
+        owner,
+        blacklister: accounts.blacklister.key(),
+        whitelist_checked: accounts.whitelist_entry.is_some(),
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Check that `entry` is the transfer hook's active whitelist entry for `wallet`
+fn require_whitelisted(entry: &AccountInfo, hook_program: &Pubkey, wallet: &Pubkey) -> Result<()> {
+    let (expected, _) = Pubkey::find_program_address(&[WHITELIST_SEED, wallet.as_ref()], hook_program);
+    require_keys_eq!(entry.key(), expected, MeridianError::NotWhitelisted);
+    require_keys_eq!(*entry.owner, *hook_program, MeridianError::NotWhitelisted);
+    let data = entry.try_borrow_data()?;
+    require!(
+        data.get(WHITELIST_IS_ACTIVE_OFFSET) == Some(&1),
+        MeridianError::NotWhitelisted
+    );
+    Ok(())
+}
+
+/// Thaw many frozen accounts at once after a mass-freeze incident.
+/// `remaining_accounts` holds the (writable) token accounts to thaw.
+#[derive(Accounts)]
+pub struct ThawAccountsBatch<'info> {
+    /// Holder of the blacklister role
+    pub blacklister: Signer<'info>,
+
+    #[account(
+        seeds = [MintConfig::SEED_PREFIX],
+        bump = mint_config.bump,
+    )]
+    pub mint_config: Account<'info, MintConfig>,
+
+    #[account(
+        seeds = [RoleConfig::SEED_PREFIX, mint_config.key().as_ref()],
+        bump = role_config.bump,
+        constraint = role_config.blacklister == Some(blacklister.key()) @ MeridianError::InvalidRole
+    )]
+    pub role_config: Account<'info, RoleConfig>,
+
+    #[account(
+        constraint = mint.key() == mint_config.mint @ MeridianError::InvalidMint
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Program<'info, Token2022>,
+}
+
+pub fn thaw_accounts_batch_handler<'info>(
+    ctx: Context<'_, '_, 'info, 'info, ThawAccountsBatch<'info>>,
+) -> Result<()> {
+    let clock = Clock::get()?;
+    let accounts = &ctx.accounts;
+    require!(
+        !ctx.remaining_accounts.is_empty()
+            && ctx.remaining_accounts.len() <= MintConfig::MAX_THAW_BATCH_SIZE,
+        MeridianError::InvalidThawBatch
+    );
+
+    let seeds = &[MintConfig::SEED_PREFIX, &[accounts.mint_config.bump]];
+    let signer_seeds = &[&seeds[..]];
+    let mut thawed = Vec::new();
+    let mut skipped = Vec::new();
+
+    for info in ctx.remaining_accounts {
+        // Skip anything that isn't a frozen account of this mint instead of
+        // letting one bad entry fail the whole batch
+        let thawable = info.is_writable
+            && InterfaceAccount::<TokenAccount>::try_from(info)
+                .is_ok_and(|account| account.mint == accounts.mint.key() && account.is_frozen());
+        if !thawable {
+            skipped.push(info.key());
+            continue;
+        }
+
+        token_2022::thaw_account(CpiContext::new_with_signer(
+            accounts.token_program.to_account_info(),
+            ThawAccount {
+                account: info.clone(),
+                mint: accounts.mint.to_account_info(),
+                authority: accounts.mint_config.to_account_info(),
+            },
+            signer_seeds,
+        ))?;
+        thawed.push(info.key());
+    }
+
+    emit!(AccountsBatchThawed {
+        mint: accounts.mint.key(),
+        blacklister: accounts.blacklister.key(),
+        thawed,
+        skipped,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+#[event]
+pub struct AccountThawed {
+    pub mint: Pubkey,
+    pub account: Pubkey,
+    pub owner: Pubkey,
+    pub blacklister: Pubkey,
+    /// Whether the owner's whitelist entry was verified before thawing
+    pub whitelist_checked: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AccountsBatchThawed {
+    pub mint: Pubkey,
+    pub blacklister: Pubkey,
+    /// Accounts thawed by this batch
+    pub thawed: Vec<Pubkey>,
+    /// Accounts left untouched: already thawed, wrong mint, or not a token account
+    pub skipped: Vec<Pubkey>,
+    pub timestamp: i64,
+}
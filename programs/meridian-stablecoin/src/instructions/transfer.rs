@@ -76,8 +76,7 @@ pub fn handler(ctx: Context<TransferStablecoin>, params: TransferParams) -> Resu
         cpi_accounts,
     );
 
-    // Decimals = 2 for stablecoin
-    token_2022::transfer_checked(cpi_ctx, params.amount, 2)?;
+    token_2022::transfer_checked(cpi_ctx, params.amount, ctx.accounts.mint.decimals)?;
 
     emit!(StablecoinTransferred {
         mint: ctx.accounts.mint.key(),
@@ -37,6 +37,11 @@ pub mod meridian_stablecoin {
         instructions::mint::handler(ctx, params)
     }
 
+    /// Thaw a frozen recipient, mint, and optionally re-freeze (minter + blacklister)
+    pub fn mint_and_thaw(ctx: Context<MintAndThaw>, params: MintAndThawParams) -> Result<()> {
+        instructions::mint::mint_and_thaw_handler(ctx, params)
+    }
+
     /// Burn stablecoin tokens for fiat redemption
     pub fn burn(ctx: Context<BurnStablecoin>, params: BurnParams) -> Result<()> {
         instructions::burn::handler(ctx, params)
@@ -91,16 +96,63 @@ pub mod meridian_stablecoin {
         instructions::collateral::submit_audit_handler(ctx, params)
     }
 
+    /// Update collateral vault guardrails
+    pub fn update_vault_config(
+        ctx: Context<UpdateVaultConfig>,
+        params: UpdateVaultConfigParams,
+    ) -> Result<()> {
+        instructions::collateral::update_vault_config_handler(ctx, params)
+    }
+
+    /// Switch the collateral vault to token-backed mode
+    pub fn enable_backed_collateral(ctx: Context<EnableBackedCollateral>) -> Result<()> {
+        instructions::collateral::enable_backed_collateral_handler(ctx)
+    }
+
+    /// Deposit tokenized collateral into a token-backed vault
+    pub fn deposit_backed_collateral(ctx: Context<MoveBackedCollateral>, amount: u64) -> Result<()> {
+        instructions::collateral::deposit_backed_collateral_handler(ctx, amount)
+    }
+
+    /// Withdraw tokenized collateral from a token-backed vault
+    pub fn withdraw_backed_collateral(
+        ctx: Context<MoveBackedCollateral>,
+        amount: u64,
+    ) -> Result<()> {
+        instructions::collateral::withdraw_backed_collateral_handler(ctx, amount)
+    }
+
     // =========================================================================
     // SSS-2 Compliance Instructions
     // =========================================================================
 
     /// Seize tokens from a frozen account via permanent delegate (SSS-2 only)
+    /// Unfrozen accounts fail with `AccountNotFrozen` unless `force` is set.
     /// Fails gracefully if permanent delegate was not enabled during initialization.
     pub fn seize(ctx: Context<Seize>, params: SeizeParams) -> Result<()> {
         instructions::seize::seize_handler(ctx, params)
     }
 
+    /// Thaw the token accounts in `remaining_accounts` (blacklister only).
+    /// Accounts that cannot be thawed are skipped rather than failing the batch.
+    pub fn thaw_accounts_batch<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ThawAccountsBatch<'info>>,
+    ) -> Result<()> {
+        instructions::thaw::thaw_accounts_batch_handler(ctx)
+    }
+
+    /// Thaw one token account after KYC, optionally checking the owner's
+    /// whitelist entry (blacklister only)
+    pub fn thaw_account(ctx: Context<ThawTokenAccount>) -> Result<()> {
+        instructions::thaw::thaw_account_handler(ctx)
+    }
+
+    /// Freeze one token account (blacklister only). Goes through
+    /// propose/approve/`execute_multisig_freeze` once a multisig is attached.
+    pub fn freeze_account(ctx: Context<FreezeTokenAccount>) -> Result<()> {
+        instructions::freeze::freeze_account_handler(ctx)
+    }
+
     // =========================================================================
     // Role Management
     // =========================================================================
@@ -114,4 +166,97 @@ pub mod meridian_stablecoin {
     pub fn update_roles(ctx: Context<UpdateRoles>, params: UpdateRolesParams) -> Result<()> {
         instructions::roles::update_roles_handler(ctx, params)
     }
+
+    /// Update mint configuration (master authority, without a multisig)
+    pub fn update_mint_config(
+        ctx: Context<UpdateMintConfig>,
+        params: UpdateMintConfigParams,
+    ) -> Result<()> {
+        instructions::config::update_mint_config_handler(ctx, params)
+    }
+
+    /// Approve a mint as settlement/collateral token (master authority, without a multisig)
+    pub fn add_settlement_token(ctx: Context<UpdateMintConfig>, token: Pubkey) -> Result<()> {
+        instructions::config::add_settlement_token_handler(ctx, token)
+    }
+
+    /// Withdraw a mint's settlement/collateral approval (master authority, without a multisig)
+    pub fn remove_settlement_token(ctx: Context<UpdateMintConfig>, token: Pubkey) -> Result<()> {
+        instructions::config::remove_settlement_token_handler(ctx, token)
+    }
+
+    // =========================================================================
+    // Multisig Authority
+    // =========================================================================
+
+    /// Attach an M-of-N multisig (master authority only). Pause, role, config,
+    /// vault, seize and freeze actions then go through propose/approve/execute.
+    pub fn configure_multisig(
+        ctx: Context<ConfigureMultisig>,
+        signers: Vec<Pubkey>,
+        threshold: u8,
+    ) -> Result<()> {
+        instructions::multisig::configure_multisig_handler(ctx, signers, threshold)
+    }
+
+    /// Propose a multisig action (multisig signer)
+    pub fn propose_multisig_action(
+        ctx: Context<ProposeMultisigAction>,
+        action: state::MultisigAction,
+    ) -> Result<()> {
+        instructions::multisig::propose_multisig_handler(ctx, action)
+    }
+
+    /// Approve a pending multisig proposal (multisig signer)
+    pub fn approve_multisig_action(ctx: Context<ApproveMultisigAction>) -> Result<()> {
+        instructions::multisig::approve_multisig_handler(ctx)
+    }
+
+    /// Withdraw a pending multisig proposal (proposer only)
+    pub fn cancel_multisig_action(ctx: Context<CancelMultisigAction>) -> Result<()> {
+        instructions::multisig::cancel_multisig_handler(ctx)
+    }
+
+    /// Execute an approved pause, unpause or disable proposal
+    pub fn execute_multisig_action(ctx: Context<ExecuteMultisigAction>) -> Result<()> {
+        instructions::multisig::execute_multisig_action_handler(ctx)
+    }
+
+    /// Execute an approved role update proposal
+    pub fn execute_multisig_update_roles(ctx: Context<ExecuteMultisigUpdateRoles>) -> Result<()> {
+        instructions::multisig::execute_multisig_update_roles_handler(ctx)
+    }
+
+    /// Execute an approved collateral vault proposal
+    pub fn execute_multisig_initialize_vault(
+        ctx: Context<ExecuteMultisigInitializeVault>,
+    ) -> Result<()> {
+        instructions::multisig::execute_multisig_initialize_vault_handler(ctx)
+    }
+
+    /// Execute an approved seize proposal
+    pub fn execute_multisig_seize(ctx: Context<ExecuteMultisigSeize>) -> Result<()> {
+        instructions::multisig::execute_multisig_seize_handler(ctx)
+    }
+
+    /// Execute an approved freeze proposal
+    pub fn execute_multisig_freeze(ctx: Context<ExecuteMultisigFreeze>) -> Result<()> {
+        instructions::multisig::execute_multisig_freeze_handler(ctx)
+    }
+
+    /// Upgrade a mint config created under an older layout (master authority only)
+    pub fn migrate_mint_config(ctx: Context<MigrateMintConfig>) -> Result<()> {
+        instructions::config::migrate_mint_config_handler(ctx)
+    }
+
+    /// Upgrade an issuer created under an older layout (master authority only)
+    pub fn migrate_issuer(ctx: Context<MigrateIssuer>) -> Result<()> {
+        instructions::config::migrate_issuer_handler(ctx)
+    }
+
+    /// Upgrade the collateral vault created under an older layout (master
+    /// authority only)
+    pub fn migrate_collateral_vault(ctx: Context<MigrateCollateralVault>) -> Result<()> {
+        instructions::config::migrate_collateral_vault_handler(ctx)
+    }
 }
@@ -33,6 +33,44 @@ pub struct CollateralVault {
 
     /// Bump seed
     pub bump: u8,
+
+    // =========================================================================
+    // Versioning
+    // =========================================================================
+
+    /// Account layout version (see `CollateralVault::CURRENT_VERSION`)
+    pub version: u8,
+
+    // =========================================================================
+    // Audit guardrail
+    // =========================================================================
+
+    /// Collateral amount verified by the most recent audit
+    pub last_audited_amount: u64,
+
+    /// Minimum share of the last audited amount that must remain after a
+    /// withdrawal, in basis points (0 = guardrail disabled)
+    pub audit_floor_bps: u16,
+
+    // =========================================================================
+    // Backed mode
+    // =========================================================================
+
+    /// Token account (owned by this vault PDA) holding tokenized collateral.
+    /// When set, `total_collateral` tracks its balance instead of attestations.
+    pub backing_token_account: Option<Pubkey>,
+
+    /// Mint of the tokenized collateral (e.g. a JGB token)
+    pub backing_mint: Option<Pubkey>,
+
+    // =========================================================================
+    // Oracle valuation
+    // =========================================================================
+
+    /// Oracle `PriceFeed` quoting one collateral unit in stablecoin units.
+    /// When set, ratio checks use `total_collateral × price` instead of
+    /// counting collateral 1:1.
+    pub price_feed: Option<Pubkey>,
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
@@ -62,6 +100,13 @@ pub enum VaultStatus {
 impl CollateralVault {
     pub const SEED_PREFIX: &'static [u8] = b"collateral_vault";
 
+    /// Current account layout version. `migrate_collateral_vault` upgrades
+    /// older accounts:
+    /// - 0: created before versioning; fields appended after `bump` read as zero
+    /// - 1: adds `version`, the audit guardrail (zero = disabled), backed mode
+    ///   and oracle valuation (None = attested, counted 1:1)
+    pub const CURRENT_VERSION: u8 = 1;
+
     pub fn is_active(&self) -> bool {
         matches!(self.status, VaultStatus::Active)
     }
@@ -69,4 +114,48 @@ impl CollateralVault {
     pub fn can_withdraw(&self, amount: u64) -> bool {
         self.is_active() && self.total_collateral >= amount
     }
+
+    /// Collateral is held on-chain rather than attested
+    pub fn is_backed(&self) -> bool {
+        self.backing_token_account.is_some()
+    }
+
+    /// Minimum collateral that must remain after a withdrawal, based on the last audit.
+    /// Rounds up so a fractional subunit never lowers the floor.
+    pub fn audit_floor(&self) -> u64 {
+        (self.last_audited_amount as u128 * self.audit_floor_bps as u128).div_ceil(10000) as u64
+    }
+
+    /// Market value of `units` of collateral in stablecoin units, rounded
+    /// down. `price` is the feed's `(price, exponent)`; `None` counts units
+    /// 1:1, as for fiat held in trust. `None` on overflow.
+    pub fn market_value(units: u64, price: Option<(u64, i8)>) -> Option<u64> {
+        let Some((price, exponent)) = price else {
+            return Some(units);
+        };
+        let value = units as u128 * price as u128;
+        let scaled = if exponent >= 0 {
+            value.checked_mul(10u128.checked_pow(exponent as u32)?)?
+        } else {
+            value / 10u128.checked_pow(exponent.unsigned_abs() as u32)?
+        };
+        u64::try_from(scaled).ok()
+    }
+
+    /// Collateral units worth `value` stablecoin units at `price`, rounded
+    /// down so a release never takes more than its value. The inverse of
+    /// `market_value`; `None` on overflow or a zero price.
+    pub fn units_for_value(value: u64, price: Option<(u64, i8)>) -> Option<u64> {
+        let Some((price, exponent)) = price else {
+            return Some(value);
+        };
+        let units = if exponent >= 0 {
+            let unit_value = (price as u128).checked_mul(10u128.checked_pow(exponent as u32)?)?;
+            (value as u128).checked_div(unit_value)?
+        } else {
+            let scaled = (value as u128).checked_mul(10u128.checked_pow(exponent.unsigned_abs() as u32)?)?;
+            scaled.checked_div(price as u128)?
+        };
+        u64::try_from(units).ok()
+    }
 }
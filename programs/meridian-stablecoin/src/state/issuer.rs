@@ -42,6 +42,18 @@ pub struct Issuer {
 
     /// Bump seed
     pub bump: u8,
+
+    /// Account layout version (see `Issuer::CURRENT_VERSION`)
+    pub version: u8,
+
+    /// Collateral attributed to this issuer's outstanding mint
+    pub attributed_collateral: u64,
+
+    /// Minimum seconds between mints (0 = no limit)
+    pub min_mint_interval: i64,
+
+    /// Timestamp of the last mint (0 = never minted)
+    pub last_mint_time: i64,
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
@@ -58,6 +70,12 @@ pub enum IssuerType {
 
 impl Issuer {
     pub const SEED_PREFIX: &'static [u8] = b"issuer";
+
+    /// Current account layout version. `migrate_issuer` upgrades older accounts:
+    /// - 0: created before versioning; fields appended after `bump` read as zero
+    /// - 1: adds `version`, collateral attribution (zero = nothing attributed)
+    ///   and the minimum mint interval (zero = no limit)
+    pub const CURRENT_VERSION: u8 = 1;
     pub const SECONDS_PER_DAY: i64 = 86400;
 
     /// Reset daily limits if new day
@@ -91,10 +109,38 @@ impl Issuer {
         self.daily_burned.saturating_add(amount) <= self.daily_burn_limit
     }
 
+    /// Check the minimum interval since the last mint has elapsed
+    pub fn mint_interval_elapsed(&self, current_time: i64) -> bool {
+        self.min_mint_interval == 0
+            || self.last_mint_time == 0
+            || current_time - self.last_mint_time >= self.min_mint_interval
+    }
+
+    /// Tokens minted by this issuer and not yet burned against it
+    pub fn outstanding(&self) -> u64 {
+        self.total_minted.saturating_sub(self.total_burned)
+    }
+
+    /// Check if attributed collateral covers outstanding mint plus `amount`
+    pub fn attribution_covers(&self, amount: u64) -> bool {
+        self.attributed_collateral >= self.outstanding().saturating_add(amount)
+    }
+
+    /// Attributed collateral over outstanding mint, in basis points
+    pub fn collateral_ratio_bps(&self) -> u64 {
+        let outstanding = self.outstanding();
+        if outstanding == 0 {
+            return 10000; // 100% if nothing outstanding
+        }
+        ((self.attributed_collateral as u128 * 10000) / outstanding as u128)
+            .min(u64::MAX as u128) as u64
+    }
+
     /// Record mint
-    pub fn record_mint(&mut self, amount: u64) {
+    pub fn record_mint(&mut self, amount: u64, current_time: i64) {
         self.daily_minted = self.daily_minted.saturating_add(amount);
         self.total_minted = self.total_minted.saturating_add(amount);
+        self.last_mint_time = current_time;
     }
 
     /// Record burn
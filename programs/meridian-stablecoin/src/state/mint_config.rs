@@ -48,6 +48,28 @@ pub struct RoleConfig {
 
 impl RoleConfig {
     pub const SEED_PREFIX: &'static [u8] = b"role_config";
+
+    /// Whether `signer` may act in a role: the master authority always, and
+    /// the role holder once assigned. Without a role config, or with the role
+    /// unassigned, only the master authority qualifies.
+    pub fn permits(
+        role_config: Option<&RoleConfig>,
+        role: impl Fn(&RoleConfig) -> Option<Pubkey>,
+        master_authority: &Pubkey,
+        signer: &Pubkey,
+    ) -> bool {
+        signer == master_authority || role_config.and_then(role) == Some(*signer)
+    }
+
+    /// Read the role config PDA at `info`, or `None` before `initialize_roles`
+    /// has created it. Callers check `info` is the PDA.
+    pub fn load(info: &AccountInfo) -> Result<Option<RoleConfig>> {
+        if info.data_is_empty() {
+            return Ok(None);
+        }
+        require_keys_eq!(*info.owner, crate::ID, ErrorCode::AccountOwnedByWrongProgram);
+        Ok(Some(RoleConfig::try_deserialize(&mut &info.try_borrow_data()?[..])?))
+    }
 }
 
 /// Configuration for the stablecoin mint
@@ -115,23 +137,103 @@ pub struct MintConfig {
 
     /// Treasury account for seized tokens
     pub treasury: Option<Pubkey>,
+
+    // =========================================================================
+    // Seize routing
+    // =========================================================================
+
+    /// Regulatory escrow account receiving a share of seized tokens
+    pub seize_escrow: Option<Pubkey>,
+
+    /// Share of each seizure routed to `seize_escrow` (basis points, 0 = all to treasury)
+    pub escrow_bps: u16,
+
+    // =========================================================================
+    // Versioning
+    // =========================================================================
+
+    /// Account layout version (see `MintConfig::CURRENT_VERSION`)
+    pub version: u8,
+
+    // =========================================================================
+    // Issuer collateral attribution
+    // =========================================================================
+
+    /// Require each issuer's attributed collateral to cover its outstanding mint
+    pub require_issuer_attribution: bool,
+
+    /// Sum of `attributed_collateral` across issuers, kept within `total_collateral`
+    pub attributed_collateral: u64,
+
+    // =========================================================================
+    // Settlement allowlist
+    // =========================================================================
+
+    /// Mints accepted as tokenized collateral (empty = any mint, the default)
+    #[max_len(8)]
+    pub settlement_tokens: Vec<Pubkey>,
+
+    // =========================================================================
+    // Multisig authority
+    // =========================================================================
+
+    /// Multisig that must approve pause, role, config, vault, seize and freeze
+    /// actions (None = master authority acts alone, the default)
+    pub multisig: Option<Pubkey>,
 }
 
 impl MintConfig {
     pub const SEED_PREFIX: &'static [u8] = b"mint_config";
 
-    /// Calculate current collateral ratio
+    /// Current account layout version. `migrate_mint_config` upgrades older configs:
+    /// - 0: created before versioning; fields appended since read as zero
+    /// - 1: adds `version`; all appended fields default to zero/None
+    /// - 2: adds per-issuer collateral attribution (disabled by default) and
+    ///   its running total
+    /// - 3: adds the settlement token allowlist (empty = unrestricted)
+    /// - 4: adds the optional multisig authority (None = single authority)
+    pub const CURRENT_VERSION: u8 = 4;
+
+    /// Maximum number of allowlisted settlement tokens
+    pub const MAX_SETTLEMENT_TOKENS: usize = 8;
+
+    /// Maximum number of token accounts thawed by one `thaw_accounts_batch`
+    pub const MAX_THAW_BATCH_SIZE: usize = 20;
+
+    /// Collateral ratio a withdrawal must leave behind, in basis points
+    pub const MIN_COLLATERAL_RATIO_BPS: u64 = 10000;
+
+    /// Rounding tolerance on the minimum ratio, in basis points. Deliberately
+    /// zero: the ratio rounds down, so collateral exactly equal to supply reads
+    /// 10000 and passes, while any shortfall, even one subunit, reads 9999 or
+    /// less and fails. A non-zero tolerance would admit real shortfalls.
+    pub const COLLATERAL_RATIO_TOLERANCE_BPS: u64 = 0;
+
+    /// Calculate current collateral ratio. Rounds down, so backing is never overstated.
     pub fn calculate_collateral_ratio(&self) -> u64 {
+        self.collateral_ratio_with(self.total_collateral)
+    }
+
+    /// Collateral ratio if total collateral were `collateral`, rounding down
+    pub fn collateral_ratio_with(&self, collateral: u64) -> u64 {
         if self.total_supply == 0 {
             return 10000; // 100% if no supply
         }
-        ((self.total_collateral as u128 * 10000) / self.total_supply as u128) as u64
+        ((collateral as u128 * 10000) / self.total_supply as u128).min(u64::MAX as u128) as u64
+    }
+
+    /// Whether `collateral` keeps supply at the minimum ratio, checked at the
+    /// same basis-point precision as `calculate_collateral_ratio`
+    pub fn meets_min_ratio(&self, collateral: u64) -> bool {
+        self.collateral_ratio_with(collateral) + Self::COLLATERAL_RATIO_TOLERANCE_BPS
+            >= Self::MIN_COLLATERAL_RATIO_BPS
     }
 
-    /// Check if minting is allowed
-    pub fn can_mint(&self, amount: u64) -> bool {
+    /// Check if minting is allowed against collateral worth `collateral_value`
+    /// (see `CollateralVault::market_value`)
+    pub fn can_mint(&self, amount: u64, collateral_value: u64) -> bool {
         !self.is_paused &&
-        self.total_collateral >= self.total_supply.saturating_add(amount)
+        collateral_value >= self.total_supply.saturating_add(amount)
     }
 
     /// Check if burning is allowed
@@ -139,6 +241,20 @@ impl MintConfig {
         !self.is_paused && self.total_supply >= amount
     }
 
+    /// Split a seized amount into (treasury, escrow) portions. The escrow share
+    /// rounds down and the treasury takes the remainder, so the parts always sum
+    /// to `amount` even at single-subunit granularity.
+    pub fn split_seizure(&self, amount: u64) -> (u64, u64) {
+        let escrow = ((amount as u128 * self.escrow_bps as u128) / 10000) as u64;
+        (amount - escrow, escrow)
+    }
+
+    /// Whether `mint` may be used as settlement or collateral. An empty
+    /// allowlist accepts any mint.
+    pub fn is_settlement_token(&self, mint: &Pubkey) -> bool {
+        self.settlement_tokens.is_empty() || self.settlement_tokens.contains(mint)
+    }
+
     /// Check if SSS-2 compliance features are enabled
     pub fn is_compliant(&self) -> bool {
         matches!(self.preset, StablecoinPreset::Sss2) ||
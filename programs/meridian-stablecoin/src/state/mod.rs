@@ -1,7 +1,9 @@
 pub mod mint_config;
 pub mod collateral_vault;
 pub mod issuer;
+pub mod multisig;
 
 pub use mint_config::{MintConfig, StablecoinPreset, RoleConfig};
 pub use collateral_vault::*;
 pub use issuer::*;
+pub use multisig::*;
@@ -0,0 +1,125 @@
+use anchor_lang::prelude::*;
+
+use super::CollateralType;
+
+/// M-of-N signer set that replaces the master authority for high-impact
+/// actions once attached to `MintConfig::multisig`
+#[account]
+#[derive(InitSpace)]
+pub struct MultisigConfig {
+    /// Associated mint config
+    pub mint_config: Pubkey,
+
+    /// Keys allowed to propose, approve and execute
+    #[max_len(10)]
+    pub signers: Vec<Pubkey>,
+
+    /// Approvals required to execute a proposal
+    pub threshold: u8,
+
+    /// Proposals opened so far; the next proposal's index
+    pub proposal_count: u64,
+
+    /// Bumped each time the signer set is configured. Proposals opened under
+    /// an earlier generation are void, so re-enabling a multisig starts from a
+    /// clean slate even within the same second.
+    pub config_generation: u64,
+
+    /// Bump seed
+    pub bump: u8,
+}
+
+impl MultisigConfig {
+    pub const SEED_PREFIX: &'static [u8] = b"multisig";
+
+    /// Maximum number of multisig signers
+    pub const MAX_SIGNERS: usize = 10;
+
+    /// Check if `key` is a multisig signer
+    pub fn is_signer(&self, key: &Pubkey) -> bool {
+        self.signers.contains(key)
+    }
+
+    /// Approvals from current signers on a proposal opened under the current
+    /// signer set
+    pub fn approval_count(&self, proposal: &MultisigProposal) -> u8 {
+        if proposal.config_generation != self.config_generation {
+            return 0;
+        }
+        proposal
+            .approvals
+            .iter()
+            .filter(|key| self.is_signer(key))
+            .count() as u8
+    }
+}
+
+/// High-impact action awaiting multisig approval
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum MultisigAction {
+    /// Pause minting and burning
+    Pause,
+    /// Resume minting and burning
+    Unpause,
+    /// Reassign roles (`None` leaves a role unchanged)
+    UpdateRoles {
+        minter: Option<Option<Pubkey>>,
+        burner: Option<Option<Pubkey>>,
+        blacklister: Option<Option<Pubkey>>,
+        pauser: Option<Option<Pubkey>>,
+        seizer: Option<Option<Pubkey>>,
+    },
+    /// Create the collateral vault
+    InitializeVault {
+        collateral_type: CollateralType,
+        auditor: Option<Pubkey>,
+        audit_floor_bps: Option<u16>,
+        price_feed: Option<Pubkey>,
+    },
+    /// Seize from `source` (0 = entire balance)
+    Seize {
+        source: Pubkey,
+        amount: u64,
+        reason: [u8; 32],
+        force: bool,
+    },
+    /// Freeze the `target` token account
+    Freeze { target: Pubkey },
+    /// Detach the multisig, returning to single-authority mode
+    Disable,
+}
+
+/// A proposed `MultisigAction`, executed once `MultisigConfig::threshold`
+/// signers have approved it
+#[account]
+#[derive(InitSpace)]
+pub struct MultisigProposal {
+    /// Multisig the proposal belongs to
+    pub multisig: Pubkey,
+
+    /// Sequence number under the multisig (PDA seed)
+    pub index: u64,
+
+    /// Action to perform
+    pub action: MultisigAction,
+
+    /// Signer who opened the proposal (receives the rent back)
+    pub proposer: Pubkey,
+
+    /// Signers who have approved, proposer included
+    #[max_len(10)]
+    pub approvals: Vec<Pubkey>,
+
+    /// Creation timestamp
+    pub created_at: i64,
+
+    /// Bump seed
+    pub bump: u8,
+
+    /// `MultisigConfig::config_generation` the proposal was opened under
+    pub config_generation: u64,
+}
+
+impl MultisigProposal {
+    pub const SEED_PREFIX: &'static [u8] = b"multisig_proposal";
+}
@@ -10,6 +10,7 @@
 //! - Volatility regime detection
 
 use anchor_lang::prelude::*;
+use anchor_lang::system_program;
 
 declare_id!("BXm2LiGwT2AR267gqd1yNbckytysBD8LCaov5zTGUDpw");
 
@@ -19,6 +20,13 @@ pub const MAX_PRICE_SAMPLES: usize = 100;
 /// Maximum funding rate samples
 pub const MAX_FUNDING_SAMPLES: usize = 24;
 
+/// Maximum observations accepted by one `update_price_multi`
+pub const MAX_PRICE_OBSERVATIONS: usize = 16;
+
+/// Observations further than this many standard deviations from the
+/// weighted median are dropped before aggregation
+pub const MAX_OUTLIER_STD_DEVS: u64 = 3;
+
 #[error_code]
 pub enum OracleError {
     #[msg("Unauthorized oracle update")]
@@ -32,6 +40,27 @@ pub enum OracleError {
 
     #[msg("Insufficient samples for calculation")]
     InsufficientSamples,
+
+    #[msg("Account is already at the current version")]
+    AlreadyMigrated,
+
+    #[msg("Invalid feed configuration")]
+    InvalidConfig,
+
+    #[msg("Too many price observations")]
+    TooManyObservations,
+
+    #[msg("Duplicate price source")]
+    DuplicateSource,
+
+    #[msg("Price feed is halted by its circuit breaker")]
+    CircuitBreakerTripped,
+
+    #[msg("Price feed is not halted")]
+    FeedNotHalted,
+
+    #[msg("Feed is closed")]
+    FeedClosed,
 }
 
 #[program]
@@ -60,11 +89,80 @@ pub mod oracle {
         feed.is_active = true;
         feed.created_at = clock.unix_timestamp;
         feed.bump = ctx.bumps.price_feed;
+        feed.price_exponent = params.price_exponent.unwrap_or(PriceFeed::DEFAULT_PRICE_EXPONENT);
+        feed.version = PriceFeed::CURRENT_VERSION;
+        feed.min_event_move_bps = 0;
+        feed.event_heartbeat_seconds = 0;
+        feed.last_event_price = 0;
+        feed.last_event_time = 0;
+        feed.min_twap_samples = 0;
+        feed.max_staleness_seconds = 0;
+        feed.max_move_bps = 0;
+        feed.is_halted = false;
+
+        Ok(())
+    }
+
+    /// Upgrade a price feed created under an older layout to
+    /// `PriceFeed::CURRENT_VERSION` (feed authority only)
+    pub fn migrate_price_feed(ctx: Context<MigratePriceFeed>) -> Result<()> {
+        let clock = Clock::get()?;
+        let info = ctx.accounts.price_feed.to_account_info();
+
+        let data = info.try_borrow_data()?;
+        require!(
+            data.len() >= 8 + 32 && data[..8] == *PriceFeed::DISCRIMINATOR,
+            ErrorCode::AccountDiscriminatorMismatch
+        );
+        let authority = Pubkey::new_from_array(data[8..40].try_into().unwrap());
+        require_keys_eq!(authority, ctx.accounts.authority.key(), OracleError::Unauthorized);
+        // Feeds created before `price_exponent` end right after `bump`
+        let missing_exponent = data.len() <= PriceFeed::PRE_EXPONENT_SPACE;
+        drop(data);
+
+        if info.data_len() < PriceFeed::SPACE {
+            let rent = Rent::get()?.minimum_balance(PriceFeed::SPACE);
+            let shortfall = rent.saturating_sub(info.lamports());
+            if shortfall > 0 {
+                system_program::transfer(
+                    CpiContext::new(
+                        ctx.accounts.system_program.to_account_info(),
+                        system_program::Transfer {
+                            from: ctx.accounts.authority.to_account_info(),
+                            to: info.clone(),
+                        },
+                    ),
+                    shortfall,
+                )?;
+            }
+            info.resize(PriceFeed::SPACE)?;
+        }
+
+        let mut feed = PriceFeed::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+        let from_version = feed.version;
+        require!(from_version < PriceFeed::CURRENT_VERSION, OracleError::AlreadyMigrated);
+
+        // 0 -> 1: a zero exponent on a pre-exponent feed would mean whole units;
+        // those feeds were always scaled by 1e6
+        if missing_exponent {
+            feed.price_exponent = PriceFeed::DEFAULT_PRICE_EXPONENT;
+        }
+        feed.version = PriceFeed::CURRENT_VERSION;
+        feed.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+
+        emit!(AccountMigrated {
+            account: info.key(),
+            from_version,
+            to_version: PriceFeed::CURRENT_VERSION,
+            timestamp: clock.unix_timestamp,
+        });
 
         Ok(())
     }
 
-    /// Update price feed with new observation
+    /// Update price feed with new observation. A move past `max_move_bps`
+    /// halts the feed instead of applying; the halt is committed, so the
+    /// transaction succeeds and later updates fail until `resume_feed`.
     pub fn update_price(
         ctx: Context<UpdatePrice>,
         price: u64,
@@ -74,41 +172,192 @@ pub mod oracle {
         let feed = &mut ctx.accounts.price_feed;
 
         require!(price > 0, OracleError::InvalidPrice);
+        require!(!feed.is_halted, OracleError::CircuitBreakerTripped);
+
+        if let Some(move_bps) = feed.circuit_breaker_move(price) {
+            feed.is_halted = true;
+            emit!(FeedHalted {
+                feed: feed.key(),
+                last_price: feed.current_price,
+                rejected_price: price,
+                move_bps,
+                timestamp: clock.unix_timestamp,
+            });
+            return Ok(());
+        }
 
-        // Update current price
-        let old_price = feed.current_price;
-        feed.current_price = price;
-        feed.confidence = confidence;
-        feed.last_update_time = clock.unix_timestamp;
+        if feed.record_price(price, confidence, clock.unix_timestamp) {
+            emit!(PriceUpdated {
+                feed: feed.key(),
+                price,
+                confidence,
+                exponent: feed.price_exponent,
+                twap: feed.twap_value,
+                timestamp: clock.unix_timestamp,
+            });
+        }
 
-        // Check if we should add a new sample
-        let time_since_last_sample = clock.unix_timestamp - feed.last_sample_time;
-        if time_since_last_sample >= feed.sample_interval_seconds as i64 {
-            feed.add_sample(price, clock.unix_timestamp);
+        Ok(())
+    }
+
+    /// Update price feed from several sources at once. The stored price is
+    /// the confidence-weighted median of the observations left after
+    /// outliers are dropped.
+    pub fn update_price_multi(
+        ctx: Context<UpdatePrice>,
+        samples: Vec<PriceObservation>,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        let feed = &mut ctx.accounts.price_feed;
+
+        require!(!feed.is_halted, OracleError::CircuitBreakerTripped);
+        require!(!samples.is_empty(), OracleError::InsufficientSamples);
+        require!(
+            samples.len() <= MAX_PRICE_OBSERVATIONS,
+            OracleError::TooManyObservations
+        );
+        for (i, sample) in samples.iter().enumerate() {
+            require!(sample.price > 0, OracleError::InvalidPrice);
+            require!(
+                samples[..i].iter().all(|s| s.source != sample.source),
+                OracleError::DuplicateSource
+            );
         }
 
-        // Update EMA (exponential moving average)
-        if old_price > 0 {
-            // EMA with alpha = 0.1 (scaled by 1000)
-            let alpha: u128 = 100;
-            let one_minus_alpha: u128 = 900;
-            feed.ema_value = ((alpha * price as u128 + one_minus_alpha * feed.ema_value as u128)
-                / 1000) as u64;
-        } else {
-            feed.ema_value = price;
+        let aggregate = PriceObservation::aggregate(&samples);
+
+        if let Some(move_bps) = feed.circuit_breaker_move(aggregate.price) {
+            feed.is_halted = true;
+            emit!(FeedHalted {
+                feed: feed.key(),
+                last_price: feed.current_price,
+                rejected_price: aggregate.price,
+                move_bps,
+                timestamp: clock.unix_timestamp,
+            });
+            return Ok(());
         }
 
-        emit!(PriceUpdated {
+        if feed.record_price(aggregate.price, aggregate.confidence, clock.unix_timestamp) {
+            emit!(PriceUpdated {
+                feed: feed.key(),
+                price: aggregate.price,
+                confidence: aggregate.confidence,
+                exponent: feed.price_exponent,
+                twap: feed.twap_value,
+                timestamp: clock.unix_timestamp,
+            });
+        }
+
+        emit!(PriceAggregated {
             feed: feed.key(),
-            price,
-            confidence,
-            twap: feed.twap_value,
+            price: aggregate.price,
+            confidence: aggregate.confidence,
+            source_count: samples.len() as u8,
+            rejected_sources: aggregate.rejected,
             timestamp: clock.unix_timestamp,
         });
 
         Ok(())
     }
 
+    /// Update price feed settings (feed authority only)
+    pub fn update_price_feed_config(
+        ctx: Context<UpdatePrice>,
+        params: UpdatePriceFeedConfigParams,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        let feed = &mut ctx.accounts.price_feed;
+
+        if let Some(min_move_bps) = params.min_event_move_bps {
+            require!(min_move_bps <= 10000, OracleError::InvalidConfig);
+            feed.min_event_move_bps = min_move_bps;
+        }
+        if let Some(heartbeat_seconds) = params.event_heartbeat_seconds {
+            feed.event_heartbeat_seconds = heartbeat_seconds;
+        }
+        if let Some(min_samples) = params.min_twap_samples {
+            require!(min_samples as usize <= MAX_PRICE_SAMPLES, OracleError::InvalidConfig);
+            feed.min_twap_samples = min_samples;
+        }
+        if let Some(max_staleness) = params.max_staleness_seconds {
+            require!(max_staleness >= 0, OracleError::InvalidConfig);
+            feed.max_staleness_seconds = max_staleness;
+        }
+        if let Some(max_move_bps) = params.max_move_bps {
+            feed.max_move_bps = max_move_bps;
+        }
+
+        emit!(PriceFeedConfigUpdated {
+            feed: feed.key(),
+            min_event_move_bps: feed.min_event_move_bps,
+            event_heartbeat_seconds: feed.event_heartbeat_seconds,
+            min_twap_samples: feed.min_twap_samples,
+            max_staleness_seconds: feed.max_staleness_seconds,
+            max_move_bps: feed.max_move_bps,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Clear a circuit-breaker halt after manual review (feed authority only).
+    /// The next update is checked against the last accepted price.
+    pub fn resume_feed(ctx: Context<UpdatePrice>) -> Result<()> {
+        let clock = Clock::get()?;
+        let feed = &mut ctx.accounts.price_feed;
+
+        require!(feed.is_halted, OracleError::FeedNotHalted);
+        feed.is_halted = false;
+
+        emit!(FeedResumed {
+            feed: feed.key(),
+            authority: ctx.accounts.authority.key(),
+            price: feed.current_price,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Close a price feed. The account stays behind as an inactive tombstone
+    /// that rejects updates and reads, so the permissionless initializer can't
+    /// recreate the symbol's feed under a new authority. Nothing on-chain
+    /// tracks which volatility indexes or markets read the feed: close its
+    /// `VolatilityIndex` first and repoint any market using it.
+    pub fn close_price_feed(ctx: Context<ClosePriceFeed>) -> Result<()> {
+        let clock = Clock::get()?;
+        let feed = &mut ctx.accounts.price_feed;
+
+        feed.is_active = false;
+
+        emit!(FeedClosed {
+            feed: feed.key(),
+            authority: ctx.accounts.authority.key(),
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Read the current price with its scaling exponent
+    pub fn get_price(ctx: Context<ReadPriceFeed>) -> Result<PriceData> {
+        Ok(ctx.accounts.price_feed.price_data())
+    }
+
+    /// Read the current price, failing with `StalePriceFeed` if the feed has
+    /// not updated within its staleness bound. Programs that CPI for a price
+    /// should call this rather than `get_price`; programs that load the feed
+    /// account directly should use `PriceFeed::get_checked_price`.
+    pub fn read_price(ctx: Context<ReadPriceFeed>) -> Result<PriceData> {
+        let clock = Clock::get()?;
+        let feed = &ctx.accounts.price_feed;
+
+        feed.get_checked_price(clock.unix_timestamp)?;
+
+        Ok(feed.price_data())
+    }
+
     /// Initialize volatility index for an asset
     pub fn initialize_volatility_index(
         ctx: Context<InitializeVolatilityIndex>,
@@ -127,6 +376,7 @@ pub mod oracle {
         vol_index.observation_count = 0;
         vol_index.last_update = clock.unix_timestamp;
         vol_index.bump = ctx.bumps.volatility_index;
+        vol_index.is_closed = false;
 
         Ok(())
     }
@@ -146,17 +396,7 @@ pub mod oracle {
         vol_index.last_update = clock.unix_timestamp;
 
         // Detect volatility regime
-        vol_index.regime = if realized_vol < 500 {
-            VolatilityRegime::VeryLow
-        } else if realized_vol < 1500 {
-            VolatilityRegime::Low
-        } else if realized_vol < 3000 {
-            VolatilityRegime::Normal
-        } else if realized_vol < 5000 {
-            VolatilityRegime::High
-        } else {
-            VolatilityRegime::Extreme
-        };
+        vol_index.regime = VolatilityRegime::from_realized(realized_vol);
 
         // Mean reversion signal (implied - realized)
         vol_index.mean_reversion_signal = implied_vol as i64 - realized_vol as i64;
@@ -172,6 +412,54 @@ pub mod oracle {
         Ok(())
     }
 
+    /// Recompute realized volatility from the linked price feed's samples
+    /// (permissionless; the result depends only on on-chain data)
+    pub fn compute_realized_volatility(ctx: Context<ComputeRealizedVolatility>) -> Result<()> {
+        let clock = Clock::get()?;
+        let feed = &ctx.accounts.price_feed;
+        let vol_index = &mut ctx.accounts.volatility_index;
+
+        require!(
+            feed.sample_count >= VolatilityIndex::MIN_SAMPLES,
+            OracleError::InsufficientSamples
+        );
+        let realized_vol = feed
+            .realized_volatility_bps()
+            .ok_or(OracleError::InsufficientSamples)?;
+
+        vol_index.realized_volatility = realized_vol;
+        vol_index.observation_count += 1;
+        vol_index.last_update = clock.unix_timestamp;
+        vol_index.regime = VolatilityRegime::from_realized(realized_vol);
+        vol_index.mean_reversion_signal =
+            vol_index.implied_volatility as i64 - realized_vol as i64;
+
+        emit!(VolatilityUpdated {
+            index: vol_index.key(),
+            realized: realized_vol,
+            implied: vol_index.implied_volatility,
+            regime: vol_index.regime,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Close a volatility index, leaving a tombstone that rejects updates so
+    /// the symbol's index can't be re-initialized
+    pub fn close_volatility_index(ctx: Context<CloseVolatilityIndex>) -> Result<()> {
+        let clock = Clock::get()?;
+        ctx.accounts.volatility_index.is_closed = true;
+
+        emit!(FeedClosed {
+            feed: ctx.accounts.volatility_index.key(),
+            authority: ctx.accounts.authority.key(),
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
     /// Initialize funding rate feed
     pub fn initialize_funding_feed(
         ctx: Context<InitializeFundingFeed>,
@@ -187,6 +475,8 @@ pub mod oracle {
         feed.sample_count = 0;
         feed.last_update = clock.unix_timestamp;
         feed.bump = ctx.bumps.funding_feed;
+        feed.twap_rate = 0;
+        feed.is_closed = false;
 
         Ok(())
     }
@@ -206,12 +496,29 @@ pub mod oracle {
             feed: feed.key(),
             rate,
             aggregated: feed.aggregated_rate,
+            twap: feed.twap_rate,
             source,
             timestamp: clock.unix_timestamp,
         });
 
         Ok(())
     }
+
+    /// Close a funding feed, leaving a tombstone that rejects updates so the
+    /// symbol's feed can't be re-initialized. Markets reading it refuse a
+    /// closed feed, so repoint them first.
+    pub fn close_funding_feed(ctx: Context<CloseFundingFeed>) -> Result<()> {
+        let clock = Clock::get()?;
+        ctx.accounts.funding_feed.is_closed = true;
+
+        emit!(FeedClosed {
+            feed: ctx.accounts.funding_feed.key(),
+            authority: ctx.accounts.authority.key(),
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
 }
 
 // State structures
@@ -265,14 +572,201 @@ pub struct PriceFeed {
 
     /// Bump seed
     pub bump: u8,
+
+    /// Decimal exponent of `current_price`, `confidence`, `twap_value` and
+    /// `ema_value` (real value = price × 10^exponent, e.g. -6 for 1e6 scaling)
+    pub price_exponent: i8,
+
+    /// Account layout version (see `PriceFeed::CURRENT_VERSION`)
+    pub version: u8,
+
+    // =========================================================================
+    // Event compaction
+    // =========================================================================
+
+    /// Only emit `PriceUpdated` when price moves at least this far from the
+    /// last emitted price, in basis points (0 = emit on every update)
+    pub min_event_move_bps: u16,
+
+    /// With compaction on, still emit at least this often (0 = no heartbeat)
+    pub event_heartbeat_seconds: u32,
+
+    /// Price carried by the last emitted `PriceUpdated`
+    pub last_event_price: u64,
+
+    /// Timestamp of the last emitted `PriceUpdated`
+    pub last_event_time: i64,
+
+    // =========================================================================
+    // TWAP maturity
+    // =========================================================================
+
+    /// Samples required before `twap_value` is considered valid
+    /// (0 = `DEFAULT_MIN_TWAP_SAMPLES`)
+    pub min_twap_samples: u32,
+
+    // =========================================================================
+    // Staleness
+    // =========================================================================
+
+    /// Seconds without an update before the feed is stale
+    /// (0 = `DEFAULT_MAX_STALENESS`)
+    pub max_staleness_seconds: i64,
+
+    // =========================================================================
+    // Circuit breaker
+    // =========================================================================
+
+    /// Largest accepted move from `current_price` in one update, in basis
+    /// points (0 = no limit)
+    pub max_move_bps: u16,
+
+    /// Set when an update exceeded `max_move_bps`; updates are refused until
+    /// `resume_feed`
+    pub is_halted: bool,
 }
 
 impl PriceFeed {
     pub const SEED_PREFIX: &'static [u8] = b"price_feed";
-    pub const MAX_STALENESS: i64 = 300; // 5 minutes
+    pub const DEFAULT_MAX_STALENESS: i64 = 300; // 5 minutes
+    pub const DEFAULT_PRICE_EXPONENT: i8 = -6;
+    /// Layout up to and including `bump`, before `price_exponent` was added
+    pub const PRE_EXPONENT_SPACE: usize =
+        8 + 32 + 32 + 1 + 8 + 8 + 4 + 4 + (16 * MAX_PRICE_SAMPLES) + 4 + 8 + 8 + 8 + 8 + 1 + 8 + 1;
+    pub const SPACE: usize = Self::PRE_EXPONENT_SPACE + 1 + 1 + 2 + 4 + 8 + 8 + 4 + 8 + 2 + 1;
+    /// Below two samples `twap_value` is just the current price
+    pub const DEFAULT_MIN_TWAP_SAMPLES: u32 = 2;
+
+    pub const SECONDS_PER_YEAR: i64 = 365 * 86_400;
+
+    /// Current account layout version. `migrate_price_feed` upgrades older feeds:
+    /// - 0: created before versioning; feeds that also predate `price_exponent`
+    ///   get it backfilled to `DEFAULT_PRICE_EXPONENT`
+    /// - 1: adds `version`
+    /// - 2: adds event compaction (disabled by default)
+    /// - 3: adds the TWAP sample minimum (zero = default minimum)
+    /// - 4: adds the per-feed staleness bound (zero = default bound)
+    /// - 5: adds the circuit breaker (disabled by default)
+    pub const CURRENT_VERSION: u8 = 5;
+
+    /// Staleness bound in seconds, falling back to `DEFAULT_MAX_STALENESS`
+    pub fn max_staleness(&self) -> i64 {
+        if self.max_staleness_seconds == 0 {
+            Self::DEFAULT_MAX_STALENESS
+        } else {
+            self.max_staleness_seconds
+        }
+    }
 
     pub fn is_stale(&self, current_time: i64) -> bool {
-        current_time - self.last_update_time > Self::MAX_STALENESS
+        current_time - self.last_update_time > self.max_staleness()
+    }
+
+    /// Current price, or `StalePriceFeed` if the feed is past its staleness
+    /// bound. Consumers should read prices through this rather than
+    /// `current_price`.
+    pub fn get_checked_price(&self, current_time: i64) -> Result<u64> {
+        require!(self.is_active, OracleError::FeedClosed);
+        require!(!self.is_halted, OracleError::CircuitBreakerTripped);
+        require!(!self.is_stale(current_time), OracleError::StalePriceFeed);
+        require!(self.current_price > 0, OracleError::InvalidPrice);
+        Ok(self.current_price)
+    }
+
+    /// Snapshot returned by `get_price` and `read_price`
+    pub fn price_data(&self) -> PriceData {
+        PriceData {
+            price: self.current_price,
+            confidence: self.confidence,
+            exponent: self.price_exponent,
+            twap: self.twap_value,
+            twap_valid: self.is_twap_valid(),
+            ema: self.ema_value,
+            last_update_time: self.last_update_time,
+        }
+    }
+
+    /// Whether enough samples back `twap_value` for consumers to rely on it
+    pub fn is_twap_valid(&self) -> bool {
+        let min_samples = if self.min_twap_samples == 0 {
+            Self::DEFAULT_MIN_TWAP_SAMPLES
+        } else {
+            self.min_twap_samples
+        };
+        self.sample_count >= min_samples
+    }
+
+    /// Whether an update to `price` should emit `PriceUpdated`. State is
+    /// written on every update regardless; this only thins the event log.
+    pub fn should_emit_event(&self, price: u64, current_time: i64) -> bool {
+        if self.min_event_move_bps == 0 || self.last_event_price == 0 {
+            return true;
+        }
+        if self.event_heartbeat_seconds > 0
+            && current_time - self.last_event_time >= self.event_heartbeat_seconds as i64
+        {
+            return true;
+        }
+        let moved = price.abs_diff(self.last_event_price) as u128 * 10000;
+        moved >= self.last_event_price as u128 * self.min_event_move_bps as u128
+    }
+
+    /// Move from `current_price` to `price` in basis points, if it exceeds
+    /// `max_move_bps`. The first price is never checked.
+    pub fn circuit_breaker_move(&self, price: u64) -> Option<u64> {
+        if self.max_move_bps == 0 || self.current_price == 0 {
+            return None;
+        }
+        let move_bps = price.abs_diff(self.current_price) as u128 * 10000 / self.current_price as u128;
+        (move_bps > self.max_move_bps as u128).then_some(move_bps.min(u64::MAX as u128) as u64)
+    }
+
+    /// Current price rescaled to `exponent` (e.g. -6 for 1e6 fixed point)
+    pub fn price_at_exponent(&self, exponent: i8) -> Option<u64> {
+        Self::rescale(self.current_price, self.price_exponent, exponent)
+    }
+
+    /// Convert a value between decimal exponents, `None` on overflow
+    pub fn rescale(value: u64, from_exponent: i8, to_exponent: i8) -> Option<u64> {
+        let shift = from_exponent as i32 - to_exponent as i32;
+        if shift >= 0 {
+            value.checked_mul(10u64.checked_pow(shift as u32)?)
+        } else {
+            Some(value / 10u64.checked_pow((-shift) as u32)?)
+        }
+    }
+
+    /// Store a new price and roll it into the samples and EMA. Returns
+    /// whether the update should emit `PriceUpdated`.
+    pub fn record_price(&mut self, price: u64, confidence: u64, current_time: i64) -> bool {
+        let old_price = self.current_price;
+        self.current_price = price;
+        self.confidence = confidence;
+        self.last_update_time = current_time;
+
+        // Check if we should add a new sample
+        let time_since_last_sample = current_time - self.last_sample_time;
+        if time_since_last_sample >= self.sample_interval_seconds as i64 {
+            self.add_sample(price, current_time);
+        }
+
+        // Update EMA (exponential moving average)
+        if old_price > 0 {
+            // EMA with alpha = 0.1 (scaled by 1000)
+            let alpha: u128 = 100;
+            let one_minus_alpha: u128 = 900;
+            self.ema_value = ((alpha * price as u128 + one_minus_alpha * self.ema_value as u128)
+                / 1000) as u64;
+        } else {
+            self.ema_value = price;
+        }
+
+        if !self.should_emit_event(price, current_time) {
+            return false;
+        }
+        self.last_event_price = price;
+        self.last_event_time = current_time;
+        true
     }
 
     pub fn add_sample(&mut self, price: u64, timestamp: i64) {
@@ -286,6 +780,34 @@ impl PriceFeed {
         self.calculate_twap();
     }
 
+    /// Annualized realized volatility in basis points: the sum of squared
+    /// log returns across the sample buffer, scaled by the share of a year
+    /// the samples span. `None` if the samples span no time.
+    pub fn realized_volatility_bps(&self) -> Option<u64> {
+        let count = (self.sample_count as usize).min(MAX_PRICE_SAMPLES);
+        if count < 2 {
+            return None;
+        }
+        let oldest = (self.sample_index as usize + MAX_PRICE_SAMPLES - count) % MAX_PRICE_SAMPLES;
+        let sample = |i: usize| &self.samples[(oldest + i) % MAX_PRICE_SAMPLES];
+
+        let mut sum_squared_returns: u128 = 0;
+        for i in 1..count {
+            let r = ln_ratio(sample(i).price, sample(i - 1).price).unsigned_abs();
+            sum_squared_returns = sum_squared_returns.saturating_add(r.saturating_mul(r));
+        }
+
+        let elapsed = sample(count - 1).timestamp - sample(0).timestamp;
+        if elapsed <= 0 {
+            return None;
+        }
+        let annual_variance = sum_squared_returns
+            .saturating_mul(Self::SECONDS_PER_YEAR as u128)
+            / elapsed as u128;
+        let volatility = isqrt(annual_variance);
+        Some((volatility.saturating_mul(10_000) / LN_SCALE as u128).min(u64::MAX as u128) as u64)
+    }
+
     fn calculate_twap(&mut self) {
         if self.sample_count < 2 {
             self.twap_value = self.current_price;
@@ -313,12 +835,178 @@ impl PriceFeed {
     }
 }
 
+/// Price snapshot returned by `get_price` and `read_price`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct PriceData {
+    pub price: u64,
+    pub confidence: u64,
+    pub exponent: i8,
+    pub twap: u64,
+    /// False until the feed has `min_twap_samples` samples
+    pub twap_valid: bool,
+    pub ema: u64,
+    pub last_update_time: i64,
+}
+
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
 pub struct PriceSample {
     pub price: u64,
     pub timestamp: i64,
 }
 
+/// One source's reading passed to `update_price_multi`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct PriceObservation {
+    pub price: u64,
+    /// Confidence interval in price units (0 is treated as 1)
+    pub confidence: u64,
+    pub source: PriceSource,
+}
+
+/// Result of aggregating a set of `PriceObservation`s
+pub struct PriceAggregate {
+    pub price: u64,
+    pub confidence: u64,
+    /// Bitmask of dropped observations, by index
+    pub rejected: u16,
+}
+
+impl PriceObservation {
+    /// Scale for inverse-variance weights so tight confidences stay integral
+    const WEIGHT_SCALE: u128 = 1_000_000_000_000_000_000_000_000;
+    /// MAD × 1.4826 estimates the standard deviation of normal data
+    const MAD_TO_STD_DEV_BPS: u128 = 14_826;
+
+    fn weight(&self) -> u128 {
+        let confidence = self.confidence.max(1) as u128;
+        (Self::WEIGHT_SCALE / confidence.saturating_mul(confidence)).max(1)
+    }
+
+    /// Price at which half the total weight lies on either side
+    fn weighted_median(samples: &[&PriceObservation]) -> u64 {
+        let mut sorted = samples.to_vec();
+        sorted.sort_by_key(|s| s.price);
+        let total: u128 = sorted.iter().map(|s| s.weight()).sum();
+        let mut cumulative: u128 = 0;
+        for sample in &sorted {
+            cumulative += sample.weight();
+            if cumulative * 2 >= total {
+                return sample.price;
+            }
+        }
+        sorted[sorted.len() - 1].price
+    }
+
+    /// Drop observations more than `MAX_OUTLIER_STD_DEVS` from the weighted
+    /// median, then take the weighted median of the rest. Dispersion is the
+    /// median absolute deviation so a single bad print cannot widen it,
+    /// floored at the median source's own confidence so agreeing sources
+    /// don't reject each other over noise. The aggregate confidence is the
+    /// combined inverse-variance confidence, or the spread of the surviving
+    /// sources around the aggregate if they disagree by more than that.
+    pub fn aggregate(samples: &[PriceObservation]) -> PriceAggregate {
+        let all: Vec<&PriceObservation> = samples.iter().collect();
+        let median = Self::weighted_median(&all);
+
+        let mut deviations: Vec<u64> = samples.iter().map(|s| s.price.abs_diff(median)).collect();
+        deviations.sort_unstable();
+        let mad = deviations[deviations.len() / 2] as u128;
+        let median_confidence = samples
+            .iter()
+            .filter(|s| s.price == median)
+            .map(|s| s.confidence)
+            .min()
+            .unwrap_or(0) as u128;
+        let std_dev = (mad * Self::MAD_TO_STD_DEV_BPS / 10_000)
+            .max(median_confidence)
+            .max(1);
+        let cutoff = std_dev * MAX_OUTLIER_STD_DEVS as u128;
+
+        let mut rejected: u16 = 0;
+        let mut accepted = Vec::with_capacity(samples.len());
+        for (i, sample) in samples.iter().enumerate() {
+            if sample.price.abs_diff(median) as u128 > cutoff {
+                rejected |= 1 << i;
+            } else {
+                accepted.push(sample);
+            }
+        }
+
+        let price = Self::weighted_median(&accepted);
+        let total_weight: u128 = accepted.iter().map(|s| s.weight()).sum();
+        // 1/σ² = Σ 1/σᵢ²
+        let combined = isqrt(Self::WEIGHT_SCALE / total_weight);
+        let spread = isqrt(
+            accepted
+                .iter()
+                .map(|s| {
+                    let d = s.price.abs_diff(price) as u128;
+                    (d * d).saturating_mul(s.weight()) / total_weight
+                })
+                .fold(0u128, |acc, v| acc.saturating_add(v)),
+        );
+
+        PriceAggregate {
+            price,
+            confidence: combined.max(spread).min(u64::MAX as u128) as u64,
+            rejected,
+        }
+    }
+}
+
+/// Fixed-point scale of `ln_ratio`
+const LN_SCALE: i128 = 1_000_000_000_000;
+
+/// ln(numerator / denominator) scaled by `LN_SCALE`, via the atanh series
+/// ln(x) = 2·(z + z³/3 + z⁵/5 + …) with z = (x − 1)/(x + 1). Converges fast
+/// for the moves between consecutive samples; ratios beyond ~10× are
+/// slightly understated.
+fn ln_ratio(numerator: u64, denominator: u64) -> i128 {
+    let (n, d) = (numerator as i128, denominator as i128);
+    if n + d == 0 {
+        return 0;
+    }
+    let z = (n - d) * LN_SCALE / (n + d);
+    let z_squared = z * z / LN_SCALE;
+    let mut power = z;
+    let mut sum = z;
+    for k in 1..32 {
+        power = power * z_squared / LN_SCALE;
+        if power == 0 {
+            break;
+        }
+        sum += power / (2 * k + 1);
+    }
+    2 * sum
+}
+
+/// Integer square root (floor)
+fn isqrt(value: u128) -> u128 {
+    if value < 2 {
+        return value;
+    }
+    let mut x = value;
+    let mut y = x / 2;
+    while y < x {
+        x = y;
+        y = (x + value / x) / 2;
+    }
+    x
+}
+
+/// Tag identifying where a `PriceObservation` came from
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PriceSource {
+    #[default]
+    Internal,
+    Pyth,
+    Switchboard,
+    Chainlink,
+    Exchange,
+    Appraiser,
+    Custodian,
+}
+
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
 pub enum AssetType {
     /// Fiat currency (e.g., JPY, USD, EUR)
@@ -345,10 +1033,15 @@ pub struct VolatilityIndex {
     pub observation_count: u32,
     pub last_update: i64,
     pub bump: u8,
+    /// Set by `close_volatility_index`; the account is kept as a tombstone.
+    /// Older indexes read it as false from the space a short symbol leaves.
+    pub is_closed: bool,
 }
 
 impl VolatilityIndex {
     pub const SEED_PREFIX: &'static [u8] = b"volatility_index";
+    /// Price samples required before `compute_realized_volatility`
+    pub const MIN_SAMPLES: u32 = 20;
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
@@ -360,6 +1053,23 @@ pub enum VolatilityRegime {
     Extreme,
 }
 
+impl VolatilityRegime {
+    /// Regime for an annualized realized volatility in basis points
+    pub fn from_realized(realized_vol: u64) -> Self {
+        if realized_vol < 500 {
+            Self::VeryLow
+        } else if realized_vol < 1500 {
+            Self::Low
+        } else if realized_vol < 3000 {
+            Self::Normal
+        } else if realized_vol < 5000 {
+            Self::High
+        } else {
+            Self::Extreme
+        }
+    }
+}
+
 #[account]
 pub struct FundingFeed {
     pub authority: Pubkey,
@@ -371,10 +1081,29 @@ pub struct FundingFeed {
     pub sample_count: u32,
     pub last_update: i64,
     pub bump: u8,
+    /// Average of the outlier-filtered samples weighted by how long each was
+    /// the latest rate. Fits in the space originally over-allocated for
+    /// samples, so older feeds read it as zero until their next update.
+    pub twap_rate: i64,
+    /// Set by `close_funding_feed`; the account is kept as a tombstone. Fits
+    /// in the same over-allocated space, so older feeds read it as false.
+    pub is_closed: bool,
 }
 
 impl FundingFeed {
     pub const SEED_PREFIX: &'static [u8] = b"funding_feed";
+    pub const MAX_STALENESS: i64 = 3600; // 1 hour
+    /// Samples further than this multiple of the median absolute deviation
+    /// from the median are left out of `aggregated_rate` and `twap_rate`
+    pub const OUTLIER_MAD_MULTIPLE: i64 = 3;
+
+    pub fn is_stale(&self, current_time: i64) -> bool {
+        self.is_stale_after(current_time, Self::MAX_STALENESS)
+    }
+
+    pub fn is_stale_after(&self, current_time: i64, max_staleness: i64) -> bool {
+        current_time - self.last_update > max_staleness
+    }
 
     pub fn add_sample(&mut self, rate: i64, source: FundingSource, timestamp: i64) {
         let idx = (self.sample_index as usize) % MAX_FUNDING_SAMPLES;
@@ -388,10 +1117,48 @@ impl FundingFeed {
         self.current_rate = rate;
         self.last_update = timestamp;
 
-        // Calculate aggregated rate (average of recent samples)
+        self.aggregate();
+    }
+
+    /// Recompute `aggregated_rate` and `twap_rate` over recent samples,
+    /// discarding outliers first
+    fn aggregate(&mut self) {
         let count = (self.sample_count as usize).min(MAX_FUNDING_SAMPLES);
-        let sum: i64 = self.samples[..count].iter().map(|s| s.rate).sum();
-        self.aggregated_rate = sum / count as i64;
+        let oldest = (self.sample_index as usize + MAX_FUNDING_SAMPLES - count) % MAX_FUNDING_SAMPLES;
+        let samples: Vec<FundingSample> = (0..count)
+            .map(|i| self.samples[(oldest + i) % MAX_FUNDING_SAMPLES])
+            .collect();
+
+        let mut rates: Vec<i64> = samples.iter().map(|s| s.rate).collect();
+        rates.sort_unstable();
+        let median = rates[count / 2];
+        let mut deviations: Vec<u64> = rates.iter().map(|r| r.abs_diff(median)).collect();
+        deviations.sort_unstable();
+        let cutoff = deviations[count / 2].max(1) * Self::OUTLIER_MAD_MULTIPLE as u64;
+        let kept: Vec<&FundingSample> = samples
+            .iter()
+            .filter(|s| s.rate.abs_diff(median) <= cutoff)
+            .collect();
+
+        // The median itself always survives the filter
+        let sum: i128 = kept.iter().map(|s| s.rate as i128).sum();
+        self.aggregated_rate = (sum / kept.len() as i128) as i64;
+
+        // Each rate weighted by the time until the next kept sample
+        let mut total_weighted_rate: i128 = 0;
+        let mut total_time: i64 = 0;
+        for pair in kept.windows(2) {
+            let time_delta = pair[1].timestamp - pair[0].timestamp;
+            if time_delta > 0 {
+                total_weighted_rate += pair[0].rate as i128 * time_delta as i128;
+                total_time += time_delta;
+            }
+        }
+        self.twap_rate = if total_time > 0 {
+            (total_weighted_rate / total_time as i128) as i64
+        } else {
+            self.aggregated_rate
+        };
     }
 }
 
@@ -424,7 +1191,7 @@ pub struct InitializePriceFeed<'info> {
     #[account(
         init,
         payer = authority,
-        space = 8 + 32 + 32 + 1 + 8 + 8 + 4 + 4 + (16 * MAX_PRICE_SAMPLES) + 4 + 8 + 8 + 8 + 8 + 1 + 8 + 1,
+        space = PriceFeed::SPACE,
         seeds = [PriceFeed::SEED_PREFIX, params.asset_symbol.as_bytes()],
         bump
     )]
@@ -438,6 +1205,19 @@ pub struct InitializePriceFeedParams {
     pub asset_symbol: String,
     pub asset_type: AssetType,
     pub sample_interval_seconds: u32,
+    /// Price exponent (defaults to -6)
+    pub price_exponent: Option<i8>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct UpdatePriceFeedConfigParams {
+    pub min_event_move_bps: Option<u16>,
+    pub event_heartbeat_seconds: Option<u32>,
+    pub min_twap_samples: Option<u32>,
+    /// Seconds without an update before the feed is stale (0 = default)
+    pub max_staleness_seconds: Option<i64>,
+    /// Largest single-update move in basis points (0 = no limit)
+    pub max_move_bps: Option<u16>,
 }
 
 #[derive(Accounts)]
@@ -447,7 +1227,41 @@ pub struct UpdatePrice<'info> {
     )]
     pub authority: Signer<'info>,
 
+    #[account(
+        mut,
+        constraint = price_feed.is_active @ OracleError::FeedClosed
+    )]
+    pub price_feed: Account<'info, PriceFeed>,
+}
+
+#[derive(Accounts)]
+pub struct MigratePriceFeed<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// CHECK: may not deserialize under the current layout; discriminator and
+    /// authority are verified in the handler
+    #[account(mut, owner = crate::ID)]
+    pub price_feed: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClosePriceFeed<'info> {
     #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = authority.key() == price_feed.authority @ OracleError::Unauthorized,
+        constraint = price_feed.is_active @ OracleError::FeedClosed
+    )]
+    pub price_feed: Account<'info, PriceFeed>,
+}
+
+#[derive(Accounts)]
+pub struct ReadPriceFeed<'info> {
     pub price_feed: Account<'info, PriceFeed>,
 }
 
@@ -462,7 +1276,7 @@ pub struct InitializeVolatilityIndex<'info> {
     #[account(
         init,
         payer = authority,
-        space = 8 + 32 + 32 + 32 + 8 + 8 + 1 + 8 + 4 + 8 + 1,
+        space = 8 + 32 + 32 + 32 + 8 + 8 + 1 + 8 + 4 + 8 + 1 + 1,
         seeds = [VolatilityIndex::SEED_PREFIX, asset_symbol.as_bytes()],
         bump
     )]
@@ -478,7 +1292,35 @@ pub struct UpdateVolatility<'info> {
     )]
     pub authority: Signer<'info>,
 
+    #[account(
+        mut,
+        constraint = !volatility_index.is_closed @ OracleError::FeedClosed
+    )]
+    pub volatility_index: Account<'info, VolatilityIndex>,
+}
+
+#[derive(Accounts)]
+pub struct ComputeRealizedVolatility<'info> {
+    pub price_feed: Box<Account<'info, PriceFeed>>,
+
+    #[account(
+        mut,
+        constraint = volatility_index.price_feed == price_feed.key() @ OracleError::InvalidConfig,
+        constraint = !volatility_index.is_closed @ OracleError::FeedClosed
+    )]
+    pub volatility_index: Account<'info, VolatilityIndex>,
+}
+
+#[derive(Accounts)]
+pub struct CloseVolatilityIndex<'info> {
     #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = authority.key() == volatility_index.authority @ OracleError::Unauthorized,
+        constraint = !volatility_index.is_closed @ OracleError::FeedClosed
+    )]
     pub volatility_index: Account<'info, VolatilityIndex>,
 }
 
@@ -507,7 +1349,23 @@ pub struct UpdateFundingRate<'info> {
     )]
     pub authority: Signer<'info>,
 
+    #[account(
+        mut,
+        constraint = !funding_feed.is_closed @ OracleError::FeedClosed
+    )]
+    pub funding_feed: Account<'info, FundingFeed>,
+}
+
+#[derive(Accounts)]
+pub struct CloseFundingFeed<'info> {
     #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = authority.key() == funding_feed.authority @ OracleError::Unauthorized,
+        constraint = !funding_feed.is_closed @ OracleError::FeedClosed
+    )]
     pub funding_feed: Account<'info, FundingFeed>,
 }
 
@@ -518,10 +1376,66 @@ pub struct PriceUpdated {
     pub feed: Pubkey,
     pub price: u64,
     pub confidence: u64,
+    pub exponent: i8,
     pub twap: u64,
     pub timestamp: i64,
 }
 
+#[event]
+pub struct PriceAggregated {
+    pub feed: Pubkey,
+    pub price: u64,
+    pub confidence: u64,
+    pub source_count: u8,
+    /// Bitmask of observations dropped as outliers, by index
+    pub rejected_sources: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PriceFeedConfigUpdated {
+    pub feed: Pubkey,
+    pub min_event_move_bps: u16,
+    pub event_heartbeat_seconds: u32,
+    pub min_twap_samples: u32,
+    pub max_staleness_seconds: i64,
+    pub max_move_bps: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FeedHalted {
+    pub feed: Pubkey,
+    pub last_price: u64,
+    pub rejected_price: u64,
+    pub move_bps: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FeedResumed {
+    pub feed: Pubkey,
+    pub authority: Pubkey,
+    pub price: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AccountMigrated {
+    pub account: Pubkey,
+    pub from_version: u8,
+    pub to_version: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FeedClosed {
+    /// Closed `PriceFeed`, `VolatilityIndex` or `FundingFeed`
+    pub feed: Pubkey,
+    pub authority: Pubkey,
+    pub timestamp: i64,
+}
+
 #[event]
 pub struct VolatilityUpdated {
     pub index: Pubkey,
@@ -536,6 +1450,7 @@ pub struct FundingRateUpdated {
     pub feed: Pubkey,
     pub rate: i64,
     pub aggregated: i64,
+    pub twap: i64,
     pub source: FundingSource,
     pub timestamp: i64,
 }
@@ -10,7 +10,10 @@
 //! - IP and royalties
 
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Token, TokenAccount, Mint, MintTo, Burn};
+use anchor_lang::system_program;
+use anchor_spl::token::{self, Token, TokenAccount, Mint, MintTo, Burn, Transfer};
+use oracle::PriceFeed;
+use zk_verifier::state::ComplianceAttestation;
 
 declare_id!("BMej5CMvLs8xN3TGj7o9HKV2px6gyycS26y5ZJSBjL5D");
 
@@ -33,6 +36,96 @@ pub enum RwaError {
 
     #[msg("Dividend not available")]
     DividendNotAvailable,
+
+    #[msg("Record-date snapshot required to claim this dividend")]
+    SnapshotRequired,
+
+    #[msg("Snapshots open on the record date")]
+    SnapshotNotOpen,
+
+    #[msg("Holding has changed since the record date")]
+    HoldingChangedSinceRecord,
+
+    #[msg("Dividend batch is empty, too large, or misaligned with remaining accounts")]
+    InvalidDividendBatch,
+
+    #[msg("Dividend account does not match the expected PDA")]
+    InvalidDividendAccount,
+
+    #[msg("Math overflow")]
+    MathOverflow,
+
+    #[msg("Asset is not real estate")]
+    NotRealEstate,
+
+    #[msg("Property is not active")]
+    PropertyNotActive,
+
+    #[msg("Valuation is aggregated from property sub-units")]
+    ValuationAggregated,
+
+    #[msg("Holding period before the record date not met")]
+    HoldingPeriodNotMet,
+
+    #[msg("Invalid amount")]
+    InvalidAmount,
+
+    #[msg("Account is already at the current version")]
+    AlreadyMigrated,
+
+    #[msg("Redemption fee treasury not configured or does not match")]
+    FeeTreasuryNotConfigured,
+
+    #[msg("Record date must not follow the payment date, and payment must fall within the announce window")]
+    InvalidDividendSchedule,
+
+    #[msg("Dividend total does not match amount per token times supply")]
+    DividendAmountMismatch,
+
+    #[msg("Dividend claim is past its clawback window")]
+    ClawbackWindowClosed,
+
+    #[msg("Asset is not a commodity")]
+    NotCommodity,
+
+    #[msg("Unit decimals or unit symbol out of range")]
+    InvalidCommodityUnits,
+
+    #[msg("Price feed is not configured for this asset or does not match")]
+    PriceFeedMismatch,
+
+    #[msg("Oracle price feed is too stale to value the asset")]
+    StaleValuation,
+
+    #[msg("Oracle price is invalid or inactive")]
+    InvalidPrice,
+
+    #[msg("Token mint does not match the dividend payment token")]
+    PaymentTokenMismatch,
+
+    #[msg("Recipient must differ from the sender")]
+    InvalidRecipient,
+
+    #[msg("Asset status transition is not allowed")]
+    InvalidStatusTransition,
+
+    #[msg("Dividend can only be cancelled before its payment date and any claim")]
+    DividendNotCancellable,
+
+    #[msg("Escrow holds nothing beyond what holders are still owed")]
+    NoDividendDust,
+
+    #[msg("Dividend is past its claim window")]
+    DividendClaimWindowClosed,
+
+    #[msg("Dividend escrow must be supplied once the dividend is funded")]
+    DividendEscrowRequired,
+
+    #[msg("Dividend already claimed")]
+    DividendAlreadyClaimed,
+
+    #[msg("Dividend claim has already been clawed back")]
+    ClaimAlreadyClawedBack,
 }
 
 #[program]
@@ -66,6 +159,20 @@ pub mod rwa_registry {
         asset.last_audit = clock.unix_timestamp;
         asset.created_at = clock.unix_timestamp;
         asset.bump = ctx.bumps.asset;
+        asset.require_dividend_snapshot = false;
+        asset.min_holding_seconds = 0;
+        asset.property_count = 0;
+        asset.active_property_count = 0;
+        asset.version = RwaAsset::CURRENT_VERSION;
+        asset.redemption_fee_bps = 0;
+        asset.fee_treasury = None;
+        asset.max_dividend_window_seconds = 0;
+        asset.valuation_authority = None;
+        asset.clawback_window_seconds = 0;
+        asset.unit_quantity = 0;
+        asset.unit_decimals = 0;
+        asset.unit_symbol = String::new();
+        asset.price_feed = params.price_feed;
 
         emit!(AssetRegistered {
             asset: asset.key(),
@@ -144,6 +251,7 @@ pub mod rwa_registry {
         proof.acquired_at = clock.unix_timestamp;
         proof.is_active = true;
         proof.bump = ctx.bumps.ownership_proof;
+        proof.amount_changed_at = clock.unix_timestamp;
 
         emit!(TokensMinted {
             asset: asset.key(),
@@ -156,6 +264,153 @@ pub mod rwa_registry {
         Ok(())
     }
 
+    /// Redeem asset tokens. The redemption fee is sent to the fee treasury and
+    /// the rest is burned; the issuer settles the underlying off-chain. Holders
+    /// with a live Institutional KYC attestation have the fee waived. Delisted
+    /// assets stay redeemable unless `block_delisted_redemptions` is set.
+    pub fn redeem_tokens(ctx: Context<RedeemTokens>, amount: u64) -> Result<()> {
+        let clock = Clock::get()?;
+        let asset = &mut ctx.accounts.asset;
+
+        require!(asset.accepts_redemptions(), RwaError::AssetNotActive);
+        require!(!asset.is_frozen, RwaError::AssetFrozen);
+        require!(amount > 0, RwaError::InvalidAmount);
+        let proof = &ctx.accounts.ownership_proof;
+        require!(
+            proof.is_active && amount <= proof.amount,
+            RwaError::InvalidOwnershipProof
+        );
+
+        let fee_waived = asset.redemption_fee_bps > 0
+            && ctx.accounts.kyc_attestation.as_ref().is_some_and(|attestation| {
+                RwaAsset::waives_redemption_fee(attestation, clock.unix_timestamp)
+            });
+        let fee = if fee_waived { 0 } else { asset.redemption_fee(amount) };
+        let burned = amount - fee;
+
+        if fee > 0 {
+            let treasury = ctx.accounts.fee_treasury.as_ref().ok_or(RwaError::FeeTreasuryNotConfigured)?;
+            token::transfer(
+                CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: ctx.accounts.holder_token.to_account_info(),
+                        to: treasury.to_account_info(),
+                        authority: ctx.accounts.holder.to_account_info(),
+                    },
+                ),
+                fee,
+            )?;
+        }
+
+        token::burn(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Burn {
+                    mint: ctx.accounts.token_mint.to_account_info(),
+                    from: ctx.accounts.holder_token.to_account_info(),
+                    authority: ctx.accounts.holder.to_account_info(),
+                },
+            ),
+            burned,
+        )?;
+
+        asset.total_supply = asset.total_supply.saturating_sub(burned);
+
+        let proof = &mut ctx.accounts.ownership_proof;
+        proof.amount -= amount;
+        proof.is_active = proof.amount > 0;
+        proof.amount_changed_at = clock.unix_timestamp;
+
+        emit!(TokensRedeemed {
+            asset: asset.key(),
+            holder: ctx.accounts.holder.key(),
+            amount,
+            fee,
+            fee_waived,
+            burned,
+            total_supply: asset.total_supply,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Move recorded ownership, and the backing tokens with it, to another
+    /// wallet after a peer-to-peer trade. Only active, unfrozen assets move.
+    /// The sender's cost basis moves pro rata with the amount, and the
+    /// recipient's acquisition time becomes the amount-weighted average.
+    pub fn transfer_ownership(ctx: Context<TransferOwnership>, amount: u64) -> Result<()> {
+        let clock = Clock::get()?;
+        require!(
+            matches!(ctx.accounts.asset.status, AssetStatus::Active),
+            RwaError::AssetNotActive
+        );
+        require!(!ctx.accounts.asset.is_frozen, RwaError::AssetFrozen);
+        require!(amount > 0, RwaError::InvalidAmount);
+
+        let sender_proof = &mut ctx.accounts.sender_proof;
+        require!(
+            sender_proof.is_active && amount <= sender_proof.amount,
+            RwaError::InvalidOwnershipProof
+        );
+
+        let moved_cost = (sender_proof.acquisition_price as u128 * amount as u128
+            / sender_proof.amount as u128) as u64;
+        sender_proof.acquisition_price -= moved_cost;
+        sender_proof.amount -= amount;
+        sender_proof.is_active = sender_proof.amount > 0;
+        sender_proof.amount_changed_at = clock.unix_timestamp;
+
+        let recipient_proof = &mut ctx.accounts.recipient_proof;
+        if !recipient_proof.is_active {
+            recipient_proof.asset = ctx.accounts.asset.key();
+            recipient_proof.owner = ctx.accounts.recipient.key();
+            recipient_proof.amount = 0;
+            recipient_proof.acquisition_price = 0;
+            recipient_proof.bump = ctx.bumps.recipient_proof;
+        }
+        let held = recipient_proof.amount as i128;
+        let total = held + amount as i128;
+        recipient_proof.acquired_at = ((recipient_proof.acquired_at as i128 * held
+            + clock.unix_timestamp as i128 * amount as i128)
+            / total) as i64;
+        recipient_proof.amount = recipient_proof
+            .amount
+            .checked_add(amount)
+            .ok_or(RwaError::MathOverflow)?;
+        recipient_proof.acquisition_price = recipient_proof
+            .acquisition_price
+            .checked_add(moved_cost)
+            .ok_or(RwaError::MathOverflow)?;
+        recipient_proof.is_active = true;
+        recipient_proof.amount_changed_at = clock.unix_timestamp;
+
+        // Move the backing tokens with the recorded ownership
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.sender_token.to_account_info(),
+                    to: ctx.accounts.recipient_token.to_account_info(),
+                    authority: ctx.accounts.sender.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        emit!(OwnershipTransferred {
+            asset: ctx.accounts.asset.key(),
+            from: ctx.accounts.sender.key(),
+            to: ctx.accounts.recipient.key(),
+            amount,
+            acquisition_price: moved_cost,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
     /// Update asset valuation
     pub fn update_valuation(
         ctx: Context<UpdateValuation>,
@@ -165,6 +420,8 @@ pub mod rwa_registry {
         let clock = Clock::get()?;
         let asset = &mut ctx.accounts.asset;
 
+        require!(asset.active_property_count == 0, RwaError::ValuationAggregated);
+
         let old_valuation = asset.valuation;
         asset.valuation = new_valuation;
         asset.last_audit = clock.unix_timestamp;
@@ -174,6 +431,112 @@ pub mod rwa_registry {
             old_valuation,
             new_valuation,
             proof_hash: valuation_proof_hash,
+            updated_by: ctx.accounts.authority.key(),
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Set the physical holding behind a commodity asset and the price feed
+    /// that values it (asset authority only). The feed must quote one unit in
+    /// the asset's valuation currency.
+    pub fn set_commodity_units(
+        ctx: Context<SetCommodityUnits>,
+        params: CommodityUnitsParams,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        let asset = &mut ctx.accounts.asset;
+
+        require!(asset.asset_type == RwaAssetType::Commodity, RwaError::NotCommodity);
+        require!(
+            params.unit_decimals <= RwaAsset::MAX_UNIT_DECIMALS
+                && params.unit_symbol.len() <= RwaAsset::MAX_UNIT_SYMBOL_LEN,
+            RwaError::InvalidCommodityUnits
+        );
+
+        asset.unit_quantity = params.unit_quantity;
+        asset.unit_decimals = params.unit_decimals;
+        asset.unit_symbol = params.unit_symbol;
+        asset.price_feed = params.price_feed;
+
+        emit!(CommodityUnitsSet {
+            asset: asset.key(),
+            unit_quantity: asset.unit_quantity,
+            unit_decimals: asset.unit_decimals,
+            unit_symbol: asset.unit_symbol.clone(),
+            price_feed: asset.price_feed,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Revalue an asset from its configured feed: commodities as
+    /// `unit_quantity × spot price`, other assets as `total_supply × price per
+    /// token`. Permissionless: anyone may crank it. A feed whose confidence
+    /// band is wider than `max_valuation_confidence_bps` leaves the valuation
+    /// unchanged and emits `ValuationRejectedLowConfidence`.
+    pub fn update_valuation_from_oracle(ctx: Context<UpdateValuationFromOracle>) -> Result<()> {
+        let clock = Clock::get()?;
+        let feed = &ctx.accounts.price_feed;
+        let asset = &mut ctx.accounts.asset;
+
+        require!(asset.active_property_count == 0, RwaError::ValuationAggregated);
+        require!(!feed.is_stale(clock.unix_timestamp), RwaError::StaleValuation);
+        require!(
+            feed.is_active && !feed.is_halted && feed.current_price > 0,
+            RwaError::InvalidPrice
+        );
+
+        // Leave the valuation untouched rather than failing, so the rejection
+        // is visible to indexers
+        if asset.exceeds_valuation_confidence(feed.current_price, feed.confidence) {
+            emit!(ValuationRejectedLowConfidence {
+                asset: asset.key(),
+                price_feed: feed.key(),
+                price: feed.current_price,
+                confidence: feed.confidence,
+                max_confidence_bps: asset.max_valuation_confidence_bps,
+                timestamp: clock.unix_timestamp,
+            });
+            return Ok(());
+        }
+
+        let new_valuation = asset
+            .oracle_valuation(feed.current_price, feed.price_exponent)
+            .ok_or(RwaError::MathOverflow)?;
+
+        let old_valuation = asset.valuation;
+        asset.valuation = new_valuation;
+        asset.last_audit = clock.unix_timestamp;
+
+        emit!(ValuationUpdated {
+            asset: asset.key(),
+            old_valuation,
+            new_valuation,
+            proof_hash: feed.key().to_bytes(),
+            updated_by: ctx.accounts.cranker.key(),
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Delegate valuation updates to an independent appraiser (asset authority
+    /// only). `None` leaves valuations to the asset authority alone.
+    pub fn set_valuation_authority(
+        ctx: Context<SetValuationAuthority>,
+        valuation_authority: Option<Pubkey>,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        let asset = &mut ctx.accounts.asset;
+
+        asset.valuation_authority = valuation_authority;
+
+        emit!(ValuationAuthoritySet {
+            asset: asset.key(),
+            valuation_authority,
             timestamp: clock.unix_timestamp,
         });
 
@@ -186,22 +549,28 @@ pub mod rwa_registry {
         params: DividendParams,
     ) -> Result<()> {
         let clock = Clock::get()?;
+        Dividend::validate(&ctx.accounts.asset, &params, clock.unix_timestamp)?;
         let dividend = &mut ctx.accounts.dividend;
 
-        dividend.asset = ctx.accounts.asset.key();
-        dividend.amount_per_token = params.amount_per_token;
-        dividend.total_amount = params.total_amount;
-        dividend.payment_token = params.payment_token;
-        dividend.record_date = params.record_date;
-        dividend.payment_date = params.payment_date;
-        dividend.status = if params.payment_date <= clock.unix_timestamp {
-            DividendStatus::Payable
-        } else {
-            DividendStatus::Announced
-        };
-        dividend.claimed_amount = 0;
-        dividend.created_at = clock.unix_timestamp;
-        dividend.bump = ctx.bumps.dividend;
+        dividend.set_inner(Dividend::new(
+            ctx.accounts.asset.key(),
+            &params,
+            clock.unix_timestamp,
+            ctx.bumps.dividend,
+        ));
+        dividend.escrow = ctx.accounts.escrow.key();
+
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.authority_payment.to_account_info(),
+                    to: ctx.accounts.escrow.to_account_info(),
+                    authority: ctx.accounts.authority.to_account_info(),
+                },
+            ),
+            params.total_amount,
+        )?;
 
         emit!(DividendAnnounced {
             asset: ctx.accounts.asset.key(),
@@ -210,6 +579,169 @@ pub mod rwa_registry {
             record_date: params.record_date,
             timestamp: clock.unix_timestamp,
         });
+        emit!(DividendFunded {
+            dividend: dividend.key(),
+            escrow: ctx.accounts.escrow.key(),
+            amount: params.total_amount,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Create and fund the payment escrow of a dividend announced through
+    /// `distribute_dividends_batch` (asset authority only)
+    pub fn fund_dividend(ctx: Context<FundDividend>) -> Result<()> {
+        let clock = Clock::get()?;
+        let dividend = &mut ctx.accounts.dividend;
+        dividend.escrow = ctx.accounts.escrow.key();
+
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.authority_payment.to_account_info(),
+                    to: ctx.accounts.escrow.to_account_info(),
+                    authority: ctx.accounts.authority.to_account_info(),
+                },
+            ),
+            dividend.total_amount,
+        )?;
+
+        emit!(DividendFunded {
+            dividend: dividend.key(),
+            escrow: ctx.accounts.escrow.key(),
+            amount: dividend.total_amount,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Announce dividends for several assets in one transaction.
+    /// `remaining_accounts` holds an `[asset, dividend]` pair per entry in
+    /// `params`; any failure rolls back the whole batch. Each dividend is
+    /// claimable once funded with `fund_dividend`.
+    pub fn distribute_dividends_batch<'info>(
+        ctx: Context<'_, '_, 'info, 'info, DistributeDividendsBatch<'info>>,
+        params: Vec<DividendParams>,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        require!(
+            !params.is_empty()
+                && params.len() <= Dividend::MAX_BATCH_SIZE
+                && ctx.remaining_accounts.len() == params.len() * 2,
+            RwaError::InvalidDividendBatch
+        );
+
+        let authority = &ctx.accounts.authority;
+        let space = 8 + Dividend::INIT_SPACE;
+        let lamports = Rent::get()?.minimum_balance(space);
+        let mut total_amount: u64 = 0;
+
+        for (entry, accounts) in params.iter().zip(ctx.remaining_accounts.chunks(2)) {
+            let asset_info = &accounts[0];
+            let dividend_info = &accounts[1];
+
+            let asset: Account<RwaAsset> = Account::try_from(asset_info)?;
+            require!(asset.authority == authority.key(), RwaError::Unauthorized);
+            Dividend::validate(&asset, entry, clock.unix_timestamp)?;
+
+            let record_date = entry.record_date.to_le_bytes();
+            let (expected, bump) = Pubkey::find_program_address(
+                &[Dividend::SEED_PREFIX, asset_info.key.as_ref(), &record_date],
+                ctx.program_id,
+            );
+            require_keys_eq!(dividend_info.key(), expected, RwaError::InvalidDividendAccount);
+
+            let signer_seeds: &[&[u8]] = &[
+                Dividend::SEED_PREFIX,
+                asset_info.key.as_ref(),
+                &record_date,
+                &[bump],
+            ];
+            system_program::create_account(
+                CpiContext::new_with_signer(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::CreateAccount {
+                        from: authority.to_account_info(),
+                        to: dividend_info.clone(),
+                    },
+                    &[signer_seeds],
+                ),
+                lamports,
+                space as u64,
+                ctx.program_id,
+            )?;
+
+            let dividend = Dividend::new(asset_info.key(), entry, clock.unix_timestamp, bump);
+            dividend.try_serialize(&mut &mut dividend_info.try_borrow_mut_data()?[..])?;
+
+            total_amount = total_amount
+                .checked_add(entry.total_amount)
+                .ok_or(RwaError::MathOverflow)?;
+
+            emit!(DividendAnnounced {
+                asset: asset_info.key(),
+                dividend: dividend_info.key(),
+                amount_per_token: entry.amount_per_token,
+                record_date: entry.record_date,
+                timestamp: clock.unix_timestamp,
+            });
+        }
+
+        emit!(DividendBatchDistributed {
+            authority: authority.key(),
+            dividend_count: params.len() as u8,
+            total_amount,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Snapshot a holder's record-date balance for a dividend (permissionless
+    /// crank). Runs on or after the record date, against a holding unchanged
+    /// since then, so tokens moved after the record date are never counted
+    /// twice. Claims on assets with `require_dividend_snapshot` are paid
+    /// against this balance.
+    pub fn snapshot_dividend_holder(ctx: Context<SnapshotDividendHolder>) -> Result<()> {
+        let clock = Clock::get()?;
+        let dividend = &ctx.accounts.dividend;
+        let ownership = &ctx.accounts.ownership_proof;
+
+        require!(
+            clock.unix_timestamp >= dividend.record_date,
+            RwaError::SnapshotNotOpen
+        );
+        require!(
+            ownership.amount_changed_at <= dividend.record_date,
+            RwaError::HoldingChangedSinceRecord
+        );
+        require!(ownership.is_active, RwaError::InvalidOwnershipProof);
+
+        // Same capture guard claim_dividend applies to live holdings
+        let min_holding = ctx.accounts.asset.min_holding_seconds;
+        if min_holding > 0 {
+            require!(
+                ownership.acquired_at.saturating_add(min_holding) <= dividend.record_date,
+                RwaError::HoldingPeriodNotMet
+            );
+        }
+
+        let snapshot = &mut ctx.accounts.snapshot;
+        snapshot.dividend = dividend.key();
+        snapshot.owner = ownership.owner;
+        snapshot.amount = ownership.amount;
+        snapshot.captured_at = clock.unix_timestamp;
+        snapshot.bump = ctx.bumps.snapshot;
+
+        emit!(DividendSnapshotRecorded {
+            dividend: dividend.key(),
+            owner: ownership.owner,
+            amount: ownership.amount,
+            timestamp: clock.unix_timestamp,
+        });
 
         Ok(())
     }
@@ -219,21 +751,102 @@ pub mod rwa_registry {
         let clock = Clock::get()?;
         let dividend = &mut ctx.accounts.dividend;
         let ownership = &ctx.accounts.ownership_proof;
+        let receipt = &ctx.accounts.claim;
+
+        // An existing receipt only reopens once its claim is clawed back
+        require!(
+            receipt.owner == Pubkey::default() || receipt.clawed_back,
+            RwaError::DividendAlreadyClaimed
+        );
 
+        // Announced dividends become payable on their payment date
+        if matches!(dividend.status, DividendStatus::Announced)
+            && clock.unix_timestamp >= dividend.payment_date
+        {
+            dividend.status = DividendStatus::Payable;
+        }
         require!(
             matches!(dividend.status, DividendStatus::Payable),
             RwaError::DividendNotAvailable
         );
-        require!(ownership.is_active, RwaError::InvalidOwnershipProof);
+        require!(
+            !dividend.claim_window_closed(clock.unix_timestamp),
+            RwaError::DividendClaimWindowClosed
+        );
+
+        // Eligible balance: the record-date snapshot when required (the holder
+        // may have sold since), else live holdings
+        let eligible_amount = if ctx.accounts.asset.require_dividend_snapshot {
+            ctx.accounts
+                .snapshot
+                .as_ref()
+                .ok_or(RwaError::SnapshotRequired)?
+                .amount
+        } else {
+            require!(ownership.is_active, RwaError::InvalidOwnershipProof);
+            // Live holdings only stand in for the record-date balance while
+            // unchanged since then; otherwise tokens moved after claiming
+            // would be paid again to the next wallet. Snapshot instead.
+            require!(
+                ownership.amount_changed_at <= dividend.record_date,
+                RwaError::HoldingChangedSinceRecord
+            );
+
+            // Guard against dividend capture: holdings must predate the record date
+            let min_holding = ctx.accounts.asset.min_holding_seconds;
+            if min_holding > 0 {
+                require!(
+                    ownership.acquired_at.saturating_add(min_holding) <= dividend.record_date,
+                    RwaError::HoldingPeriodNotMet
+                );
+            }
+            ownership.amount
+        };
 
-        // Calculate claimable amount
-        let claimable = (ownership.amount as u128 * dividend.amount_per_token as u128
+        // Calculate the entitlement; a clawed-back amount the holder still has
+        // is netted first, and the rest is paid up to what is left in escrow
+        let entitled = (eligible_amount as u128 * dividend.amount_per_token as u128
             / 1_000_000) as u64;
+        let netted = entitled.min(receipt.owed);
+        let owed = receipt.owed - netted;
+        let claimable = (entitled - netted)
+            .min(dividend.total_amount.saturating_sub(dividend.claimed_amount));
 
         dividend.claimed_amount = dividend.claimed_amount.checked_add(claimable).unwrap();
+        if dividend.claimed_amount >= dividend.total_amount {
+            dividend.status = DividendStatus::Completed;
+        }
+
+        ctx.accounts.claim.set_inner(DividendClaim {
+            dividend: dividend.key(),
+            owner: ctx.accounts.owner.key(),
+            amount: netted + claimable,
+            claimed_at: clock.unix_timestamp,
+            bump: ctx.bumps.claim,
+            owed,
+            clawed_back: false,
+        });
 
-        // Transfer dividend (implementation would use SPL token transfer)
-        // For now, just emit event
+        let asset_key = ctx.accounts.asset.key();
+        let record_date = dividend.record_date.to_le_bytes();
+        let dividend_seeds: &[&[u8]] = &[
+            Dividend::SEED_PREFIX,
+            asset_key.as_ref(),
+            &record_date,
+            &[dividend.bump],
+        ];
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.escrow.to_account_info(),
+                    to: ctx.accounts.owner_payment.to_account_info(),
+                    authority: dividend.to_account_info(),
+                },
+                &[dividend_seeds],
+            ),
+            claimable,
+        )?;
 
         emit!(DividendClaimed {
             dividend: dividend.key(),
@@ -245,55 +858,471 @@ pub mod rwa_registry {
         Ok(())
     }
 
-    /// Freeze asset (regulatory action)
-    pub fn freeze_asset(ctx: Context<FreezeAsset>) -> Result<()> {
+    /// Reverse an erroneous claim within the asset's clawback window (asset
+    /// authority only). The holder keeps the paid tokens, so the receipt
+    /// records the clawed-back amount as owed and reopens; the holder's
+    /// corrected claim is netted against it before anything more is paid.
+    pub fn clawback_dividend_claim(
+        ctx: Context<ClawbackDividendClaim>,
+        justification_hash: [u8; 32],
+    ) -> Result<()> {
         let clock = Clock::get()?;
-        let asset = &mut ctx.accounts.asset;
+        let window = ctx.accounts.asset.clawback_window_seconds;
+        let claim = &mut ctx.accounts.claim;
 
-        asset.is_frozen = true;
+        require!(!claim.clawed_back, RwaError::ClaimAlreadyClawedBack);
+        require!(
+            window > 0 && clock.unix_timestamp <= claim.claimed_at.saturating_add(window),
+            RwaError::ClawbackWindowClosed
+        );
 
-        emit!(AssetFrozen {
-            asset: asset.key(),
-            authority: ctx.accounts.authority.key(),
+        let amount = claim.amount;
+        claim.owed = claim.owed.checked_add(amount).unwrap();
+        claim.amount = 0;
+        claim.clawed_back = true;
+
+        // Reopen the dividend for the corrected claim
+        let dividend = &mut ctx.accounts.dividend;
+        if matches!(dividend.status, DividendStatus::Completed) {
+            dividend.status = DividendStatus::Payable;
+        }
+
+        emit!(DividendClawedBack {
+            dividend: dividend.key(),
+            owner: claim.owner,
+            amount,
+            justification_hash,
             timestamp: clock.unix_timestamp,
         });
 
         Ok(())
     }
 
-    /// Unfreeze asset
-    pub fn unfreeze_asset(ctx: Context<FreezeAsset>) -> Result<()> {
+    /// Cancel an announced dividend before its payment date and refund the
+    /// escrow to the issuer (asset authority only). `escrow` may only be
+    /// omitted for a batch-announced dividend that was never funded.
+    pub fn cancel_dividend(ctx: Context<CancelDividend>) -> Result<()> {
         let clock = Clock::get()?;
-        let asset = &mut ctx.accounts.asset;
+        let dividend = &mut ctx.accounts.dividend;
 
-        asset.is_frozen = false;
+        require!(
+            matches!(dividend.status, DividendStatus::Announced)
+                && clock.unix_timestamp < dividend.payment_date
+                && dividend.claimed_amount == 0,
+            RwaError::DividendNotCancellable
+        );
+        require!(
+            dividend.escrow == Pubkey::default() || ctx.accounts.escrow.is_some(),
+            RwaError::DividendEscrowRequired
+        );
+        dividend.status = DividendStatus::Cancelled;
+
+        let refunded = match ctx.accounts.escrow.as_ref() {
+            Some(escrow) => {
+                let asset_key = ctx.accounts.asset.key();
+                let record_date = dividend.record_date.to_le_bytes();
+                let dividend_seeds: &[&[u8]] = &[
+                    Dividend::SEED_PREFIX,
+                    asset_key.as_ref(),
+                    &record_date,
+                    &[dividend.bump],
+                ];
+                token::transfer(
+                    CpiContext::new_with_signer(
+                        ctx.accounts.token_program.to_account_info(),
+                        Transfer {
+                            from: escrow.to_account_info(),
+                            to: ctx.accounts.authority_payment.to_account_info(),
+                            authority: dividend.to_account_info(),
+                        },
+                        &[dividend_seeds],
+                    ),
+                    escrow.amount,
+                )?;
+                escrow.amount
+            }
+            None => 0,
+        };
 
-        emit!(AssetUnfrozen {
-            asset: asset.key(),
-            authority: ctx.accounts.authority.key(),
+        emit!(DividendCancelled {
+            asset: ctx.accounts.asset.key(),
+            dividend: dividend.key(),
+            refunded,
             timestamp: clock.unix_timestamp,
         });
 
         Ok(())
     }
-}
 
-// State structures
-
-#[account]
-#[derive(InitSpace)]
-pub struct RwaAsset {
-    /// Asset authority (issuer)
-    pub authority: Pubkey,
+    /// Sweep what a dividend's escrow holds beyond the holders' entitlement
+    /// (rounding remainder or over-funding) back to the issuer (asset
+    /// authority only). Open once the dividend is completed or cancelled, or
+    /// its claim window has closed; in the latter two nothing is owed any
+    /// more and the whole balance is swept.
+    pub fn reclaim_dividend_dust(ctx: Context<ReclaimDividendDust>) -> Result<()> {
+        let clock = Clock::get()?;
+        let dividend = &ctx.accounts.dividend;
+
+        let owed = match dividend.status {
+            DividendStatus::Cancelled => 0,
+            _ if dividend.claim_window_closed(clock.unix_timestamp) => 0,
+            DividendStatus::Completed => {
+                dividend.total_amount.saturating_sub(dividend.claimed_amount)
+            }
+            _ => return err!(RwaError::DividendNotAvailable),
+        };
+        let dust = ctx.accounts.escrow.amount.saturating_sub(owed);
+        require!(dust > 0, RwaError::NoDividendDust);
+
+        let asset_key = ctx.accounts.asset.key();
+        let record_date = dividend.record_date.to_le_bytes();
+        let dividend_seeds: &[&[u8]] = &[
+            Dividend::SEED_PREFIX,
+            asset_key.as_ref(),
+            &record_date,
+            &[dividend.bump],
+        ];
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.escrow.to_account_info(),
+                    to: ctx.accounts.authority_payment.to_account_info(),
+                    authority: dividend.to_account_info(),
+                },
+                &[dividend_seeds],
+            ),
+            dust,
+        )?;
 
-    /// Custodian holding the underlying asset
-    pub custodian: Pubkey,
+        emit!(DividendDustReclaimed {
+            dividend: dividend.key(),
+            amount: dust,
+            timestamp: clock.unix_timestamp,
+        });
 
-    /// Type of asset
-    pub asset_type: RwaAssetType,
+        Ok(())
+    }
 
-    /// Token mint representing ownership
-    pub token_mint: Pubkey,
+    /// Update per-asset configuration
+    pub fn update_asset_config(
+        ctx: Context<UpdateAssetConfig>,
+        params: UpdateAssetConfigParams,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        let asset = &mut ctx.accounts.asset;
+
+        if let Some(require_snapshot) = params.require_dividend_snapshot {
+            asset.require_dividend_snapshot = require_snapshot;
+        }
+        if let Some(min_holding_seconds) = params.min_holding_seconds {
+            require!(min_holding_seconds >= 0, RwaError::InvalidAmount);
+            asset.min_holding_seconds = min_holding_seconds;
+        }
+        if let Some(fee_bps) = params.redemption_fee_bps {
+            require!(fee_bps <= 10000, RwaError::InvalidAmount);
+            asset.redemption_fee_bps = fee_bps;
+        }
+        if let Some(fee_treasury) = params.fee_treasury {
+            asset.fee_treasury = Some(fee_treasury);
+        }
+        if let Some(window_seconds) = params.max_dividend_window_seconds {
+            require!(window_seconds >= 0, RwaError::InvalidAmount);
+            asset.max_dividend_window_seconds = window_seconds;
+        }
+        if let Some(window_seconds) = params.clawback_window_seconds {
+            require!(window_seconds >= 0, RwaError::InvalidAmount);
+            asset.clawback_window_seconds = window_seconds;
+        }
+        if let Some(max_confidence_bps) = params.max_valuation_confidence_bps {
+            require!(max_confidence_bps <= 10000, RwaError::InvalidAmount);
+            asset.max_valuation_confidence_bps = max_confidence_bps;
+        }
+        if let Some(block_delisted_redemptions) = params.block_delisted_redemptions {
+            asset.block_delisted_redemptions = block_delisted_redemptions;
+        }
+        require!(
+            asset.redemption_fee_bps == 0 || asset.fee_treasury.is_some(),
+            RwaError::FeeTreasuryNotConfigured
+        );
+
+        emit!(AssetConfigUpdated {
+            asset: asset.key(),
+            require_dividend_snapshot: asset.require_dividend_snapshot,
+            min_holding_seconds: asset.min_holding_seconds,
+            redemption_fee_bps: asset.redemption_fee_bps,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Add a property sub-unit to a real-estate asset. Once an asset has
+    /// properties, its valuation is the sum of its active properties.
+    pub fn add_property(
+        ctx: Context<AddProperty>,
+        valuation: u64,
+        address_hash: [u8; 32],
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        let asset = &mut ctx.accounts.asset;
+        let property = &mut ctx.accounts.property;
+
+        require!(
+            matches!(asset.asset_type, RwaAssetType::RealEstate),
+            RwaError::NotRealEstate
+        );
+
+        let old_valuation = asset.valuation;
+        // The first property replaces the lump valuation
+        let base = if asset.active_property_count == 0 { 0 } else { asset.valuation };
+        asset.valuation = base.checked_add(valuation).ok_or(RwaError::MathOverflow)?;
+
+        property.asset = asset.key();
+        property.property_id = asset.property_count;
+        property.valuation = valuation;
+        property.address_hash = address_hash;
+        property.status = PropertyStatus::Active;
+        property.sale_price = 0;
+        property.created_at = clock.unix_timestamp;
+        property.updated_at = clock.unix_timestamp;
+        property.bump = ctx.bumps.property;
+
+        asset.property_count = asset.property_count.checked_add(1).ok_or(RwaError::MathOverflow)?;
+        asset.active_property_count += 1;
+
+        emit!(PropertyAdded {
+            asset: asset.key(),
+            property: property.key(),
+            property_id: property.property_id,
+            valuation,
+            address_hash,
+            old_asset_valuation: old_valuation,
+            new_asset_valuation: asset.valuation,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Revalue an active property sub-unit
+    pub fn revalue_property(
+        ctx: Context<UpdateProperty>,
+        new_valuation: u64,
+        valuation_proof_hash: [u8; 32],
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        let asset = &mut ctx.accounts.asset;
+        let property = &mut ctx.accounts.property;
+
+        require!(
+            matches!(property.status, PropertyStatus::Active),
+            RwaError::PropertyNotActive
+        );
+
+        let old_valuation = property.valuation;
+        asset.valuation = asset
+            .valuation
+            .checked_sub(old_valuation)
+            .and_then(|v| v.checked_add(new_valuation))
+            .ok_or(RwaError::MathOverflow)?;
+        asset.last_audit = clock.unix_timestamp;
+
+        property.valuation = new_valuation;
+        property.updated_at = clock.unix_timestamp;
+
+        emit!(PropertyRevalued {
+            asset: asset.key(),
+            property: property.key(),
+            old_valuation,
+            new_valuation,
+            proof_hash: valuation_proof_hash,
+            new_asset_valuation: asset.valuation,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Mark a property as sold, removing its share of the asset valuation
+    pub fn mark_property_sold(ctx: Context<UpdateProperty>, sale_price: u64) -> Result<()> {
+        let clock = Clock::get()?;
+        let asset = &mut ctx.accounts.asset;
+        let property = &mut ctx.accounts.property;
+
+        require!(
+            matches!(property.status, PropertyStatus::Active),
+            RwaError::PropertyNotActive
+        );
+
+        asset.valuation = asset
+            .valuation
+            .checked_sub(property.valuation)
+            .ok_or(RwaError::MathOverflow)?;
+        asset.active_property_count -= 1;
+
+        property.status = PropertyStatus::Sold;
+        property.sale_price = sale_price;
+        property.updated_at = clock.unix_timestamp;
+
+        emit!(PropertySold {
+            asset: asset.key(),
+            property: property.key(),
+            book_valuation: property.valuation,
+            sale_price,
+            new_asset_valuation: asset.valuation,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Freeze asset (regulatory action)
+    pub fn freeze_asset(ctx: Context<FreezeAsset>) -> Result<()> {
+        let clock = Clock::get()?;
+        let asset = &mut ctx.accounts.asset;
+
+        asset.is_frozen = true;
+
+        emit!(AssetFrozen {
+            asset: asset.key(),
+            authority: ctx.accounts.authority.key(),
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Unfreeze asset
+    pub fn unfreeze_asset(ctx: Context<FreezeAsset>) -> Result<()> {
+        let clock = Clock::get()?;
+        let asset = &mut ctx.accounts.asset;
+
+        asset.is_frozen = false;
+
+        emit!(AssetUnfrozen {
+            asset: asset.key(),
+            authority: ctx.accounts.authority.key(),
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Move an asset through its lifecycle (asset authority only):
+    /// Active ⇄ Suspended, and Active or Suspended → Delisted, which is final.
+    /// Activation from Pending goes through `verify_custody`.
+    pub fn set_asset_status(ctx: Context<SetAssetStatus>, new_status: AssetStatus) -> Result<()> {
+        let clock = Clock::get()?;
+        let asset = &mut ctx.accounts.asset;
+
+        let old_status = asset.status;
+        require!(
+            old_status.can_transition_to(new_status),
+            RwaError::InvalidStatusTransition
+        );
+        asset.status = new_status;
+
+        emit!(AssetStatusChanged {
+            asset: asset.key(),
+            old_status,
+            new_status,
+            authority: ctx.accounts.authority.key(),
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Upgrade an asset created under an older layout to `RwaAsset::CURRENT_VERSION`
+    /// (asset authority only). Grows the account, zero-filling appended fields.
+    pub fn migrate_asset(ctx: Context<MigrateAsset>) -> Result<()> {
+        let clock = Clock::get()?;
+        let info = ctx.accounts.asset.to_account_info();
+
+        let authority = read_account_authority(&info, RwaAsset::DISCRIMINATOR)?;
+        require_keys_eq!(authority, ctx.accounts.authority.key(), RwaError::Unauthorized);
+
+        grow_account(
+            &info,
+            8 + RwaAsset::INIT_SPACE,
+            &ctx.accounts.authority,
+            &ctx.accounts.system_program,
+        )?;
+
+        let mut asset = RwaAsset::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+        let from_version = asset.version;
+        require!(from_version < RwaAsset::CURRENT_VERSION, RwaError::AlreadyMigrated);
+
+        // Nothing to backfill yet: zero is the default for every appended field
+        asset.version = RwaAsset::CURRENT_VERSION;
+        asset.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+
+        emit!(AccountMigrated {
+            account: info.key(),
+            from_version,
+            to_version: RwaAsset::CURRENT_VERSION,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+/// Read the authority (first field) of a raw account after checking its discriminator
+fn read_account_authority(info: &AccountInfo, discriminator: &[u8]) -> Result<Pubkey> {
+    let data = info.try_borrow_data()?;
+    require!(
+        data.len() >= 8 + 32 && &data[..8] == discriminator,
+        ErrorCode::AccountDiscriminatorMismatch
+    );
+    Ok(Pubkey::new_from_array(data[8..40].try_into().unwrap()))
+}
+
+/// Grow an account to `new_len`, topping up rent from `payer`
+fn grow_account<'info>(
+    info: &AccountInfo<'info>,
+    new_len: usize,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+) -> Result<()> {
+    if info.data_len() >= new_len {
+        return Ok(());
+    }
+
+    let shortfall = Rent::get()?.minimum_balance(new_len).saturating_sub(info.lamports());
+    if shortfall > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                system_program.to_account_info(),
+                system_program::Transfer {
+                    from: payer.to_account_info(),
+                    to: info.clone(),
+                },
+            ),
+            shortfall,
+        )?;
+    }
+    info.resize(new_len)?;
+    Ok(())
+}
+
+// State structures
+
+#[account]
+#[derive(InitSpace)]
+pub struct RwaAsset {
+    /// Asset authority (issuer)
+    pub authority: Pubkey,
+
+    /// Custodian holding the underlying asset
+    pub custodian: Pubkey,
+
+    /// Type of asset
+    pub asset_type: RwaAssetType,
+
+    /// Token mint representing ownership
+    pub token_mint: Pubkey,
 
     /// Total token supply
     pub total_supply: u64,
@@ -338,10 +1367,202 @@ pub struct RwaAsset {
 
     /// Bump seed
     pub bump: u8,
+
+    // =========================================================================
+    // Dividend policy
+    // =========================================================================
+
+    /// Pay dividends against record-date snapshots instead of live holdings
+    pub require_dividend_snapshot: bool,
+
+    // =========================================================================
+    // Property sub-units (real estate)
+    // =========================================================================
+
+    /// Properties ever added (seeds the next `PropertySubUnit` PDA)
+    pub property_count: u32,
+
+    /// Properties not yet sold; when non-zero, `valuation` is their sum
+    pub active_property_count: u32,
+
+    // =========================================================================
+    // Dividend eligibility
+    // =========================================================================
+
+    /// Minimum time holdings must predate a dividend's record date (0 = off)
+    pub min_holding_seconds: i64,
+
+    // =========================================================================
+    // Versioning
+    // =========================================================================
+
+    /// Account layout version (see `RwaAsset::CURRENT_VERSION`)
+    pub version: u8,
+
+    // =========================================================================
+    // Redemption
+    // =========================================================================
+
+    /// Fee on redemptions in basis points (0 = no fee)
+    pub redemption_fee_bps: u16,
+
+    /// Asset authority's token account receiving redemption fees
+    pub fee_treasury: Option<Pubkey>,
+
+    // =========================================================================
+    // Dividend schedule
+    // =========================================================================
+
+    /// Maximum time from announcement to payment date, in seconds
+    /// (0 = `Dividend::DEFAULT_MAX_WINDOW_SECONDS`)
+    pub max_dividend_window_seconds: i64,
+
+    // =========================================================================
+    // Valuation
+    // =========================================================================
+
+    /// Appraiser allowed to update the valuation alongside the asset
+    /// authority (None = asset authority only)
+    pub valuation_authority: Option<Pubkey>,
+
+    // =========================================================================
+    // Dividend clawback
+    // =========================================================================
+
+    /// How long after a claim the asset authority may reverse it, in seconds
+    /// (0 = claims are final immediately)
+    pub clawback_window_seconds: i64,
+
+    // =========================================================================
+    // Commodity units
+    // =========================================================================
+
+    /// Physical quantity held, scaled by 10^`unit_decimals` (e.g. troy ounces)
+    pub unit_quantity: u64,
+
+    /// Decimal places of `unit_quantity`
+    pub unit_decimals: u8,
+
+    /// Unit of `unit_quantity` (e.g. "XAU-OZ")
+    #[max_len(10)]
+    pub unit_symbol: String,
+
+    /// Oracle feed pricing one unit in `valuation_currency`
+    /// (None = valuation is set manually)
+    pub price_feed: Option<Pubkey>,
+
+    // =========================================================================
+    // Valuation confidence
+    // =========================================================================
+
+    /// Widest oracle confidence band, as bps of price, accepted by
+    /// `update_valuation_from_oracle` (0 = no limit)
+    pub max_valuation_confidence_bps: u16,
+
+    // =========================================================================
+    // Delisting
+    // =========================================================================
+
+    /// Refuse redemptions once the asset is delisted (false = holders may
+    /// still redeem after delisting)
+    pub block_delisted_redemptions: bool,
 }
 
 impl RwaAsset {
     pub const SEED_PREFIX: &'static [u8] = b"asset";
+
+    /// Current account layout version. `migrate_asset` upgrades older accounts:
+    /// - 0: created before versioning; fields appended after `bump` read as zero
+    /// - 1: adds `version`; all appended fields default to zero/false
+    /// - 2: adds the redemption fee (zero = no fee)
+    /// - 3: adds the dividend announce window (zero = default window)
+    /// - 4: adds the valuation authority (None = asset authority only)
+    /// - 5: adds the dividend clawback window (zero = no clawback)
+    /// - 6: adds commodity units and price feed (None = manual valuation)
+    /// - 7: adds the valuation confidence limit (zero = no limit)
+    /// - 8: adds the delisted-redemption block (false = still redeemable)
+    pub const CURRENT_VERSION: u8 = 8;
+
+    pub const MAX_UNIT_DECIMALS: u8 = 9;
+    /// Decimals of every asset token mint
+    pub const TOKEN_DECIMALS: u8 = 6;
+    pub const MAX_UNIT_SYMBOL_LEN: usize = 10;
+
+    /// zk-verifier KYC level that qualifies for the redemption fee waiver
+    pub const INSTITUTIONAL_KYC_LEVEL: u8 = 4;
+
+    /// The asset authority, or the delegated valuation authority if set
+    pub fn can_update_valuation(&self, signer: &Pubkey) -> bool {
+        *signer == self.authority || self.valuation_authority == Some(*signer)
+    }
+
+    /// `unit_quantity × price` in the smallest unit of `valuation_currency`,
+    /// rounded down. `None` on overflow.
+    pub fn commodity_valuation(&self, price: u64, price_exponent: i8) -> Option<u64> {
+        self.scale_valuation(self.unit_quantity, self.unit_decimals, price, price_exponent)
+    }
+
+    /// Valuation from a feed quoting one asset token
+    pub fn supply_valuation(&self, price: u64, price_exponent: i8) -> Option<u64> {
+        self.scale_valuation(self.total_supply, Self::TOKEN_DECIMALS, price, price_exponent)
+    }
+
+    /// Valuation for `update_valuation_from_oracle`: commodities price their
+    /// units, everything else prices its token supply
+    pub fn oracle_valuation(&self, price: u64, price_exponent: i8) -> Option<u64> {
+        if self.asset_type == RwaAssetType::Commodity {
+            self.commodity_valuation(price, price_exponent)
+        } else {
+            self.supply_valuation(price, price_exponent)
+        }
+    }
+
+    /// `quantity × price` in the smallest unit of the valuation currency
+    fn scale_valuation(
+        &self,
+        quantity: u64,
+        quantity_decimals: u8,
+        price: u64,
+        price_exponent: i8,
+    ) -> Option<u64> {
+        let value = quantity as u128 * price as u128;
+        let shift = price_exponent as i32 - quantity_decimals as i32
+            + self.valuation_currency.decimals() as i32;
+        let scaled = if shift >= 0 {
+            value.checked_mul(10u128.checked_pow(shift as u32)?)?
+        } else {
+            value / 10u128.checked_pow((-shift) as u32)?
+        };
+        u64::try_from(scaled).ok()
+    }
+
+    /// Whether a feed's confidence band is wider than `max_valuation_confidence_bps`
+    /// of its price
+    pub fn exceeds_valuation_confidence(&self, price: u64, confidence: u64) -> bool {
+        self.max_valuation_confidence_bps != 0
+            && confidence as u128 * 10000
+                > price as u128 * self.max_valuation_confidence_bps as u128
+    }
+
+    /// Active assets, and delisted ones unless `block_delisted_redemptions`
+    pub fn accepts_redemptions(&self) -> bool {
+        match self.status {
+            AssetStatus::Active => true,
+            AssetStatus::Delisted => !self.block_delisted_redemptions,
+            AssetStatus::Pending | AssetStatus::Suspended => false,
+        }
+    }
+
+    pub fn redemption_fee(&self, amount: u64) -> u64 {
+        ((amount as u128 * self.redemption_fee_bps as u128) / 10000) as u64
+    }
+
+    /// A valid, unexpired attestation proving Institutional KYC
+    pub fn waives_redemption_fee(attestation: &ComplianceAttestation, current_time: i64) -> bool {
+        attestation.is_valid
+            && attestation.expires_at > current_time
+            && attestation.required_kyc_level >= Self::INSTITUTIONAL_KYC_LEVEL
+    }
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
@@ -370,6 +1591,16 @@ pub enum Currency {
     Sgd,
 }
 
+impl Currency {
+    /// Decimal places of the currency's smallest unit
+    pub fn decimals(&self) -> u8 {
+        match self {
+            Currency::Jpy => 0,
+            Currency::Usd | Currency::Eur | Currency::Sgd => 2,
+        }
+    }
+}
+
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
 pub enum Jurisdiction {
     Japan,
@@ -392,6 +1623,18 @@ pub enum AssetStatus {
     Delisted,
 }
 
+impl AssetStatus {
+    /// Transitions allowed through `set_asset_status`
+    pub fn can_transition_to(self, next: AssetStatus) -> bool {
+        matches!(
+            (self, next),
+            (AssetStatus::Active, AssetStatus::Suspended)
+                | (AssetStatus::Suspended, AssetStatus::Active)
+                | (AssetStatus::Active | AssetStatus::Suspended, AssetStatus::Delisted)
+        )
+    }
+}
+
 #[account]
 #[derive(InitSpace)]
 pub struct OwnershipProof {
@@ -415,6 +1658,9 @@ pub struct OwnershipProof {
 
     /// Bump seed
     pub bump: u8,
+
+    /// Last time `amount` changed (issue, transfer or redemption)
+    pub amount_changed_at: i64,
 }
 
 impl OwnershipProof {
@@ -453,48 +1699,205 @@ pub struct Dividend {
 
     /// Bump seed
     pub bump: u8,
+
+    /// Payment escrow, default until the dividend is funded
+    pub escrow: Pubkey,
 }
 
 impl Dividend {
     pub const SEED_PREFIX: &'static [u8] = b"dividend";
-}
+    pub const ESCROW_SEED_PREFIX: &'static [u8] = b"dividend_escrow";
+    /// Maximum dividends per `distribute_dividends_batch` call (compute budget)
+    pub const MAX_BATCH_SIZE: usize = 8;
+    /// Announce-to-payment window when the asset doesn't configure one
+    pub const DEFAULT_MAX_WINDOW_SECONDS: i64 = 365 * 86400;
+    /// Allowed drift between `total_amount` and `amount_per_token` x supply
+    pub const AMOUNT_TOLERANCE_BPS: u64 = 100;
+    /// How long after the payment date holders may claim
+    pub const CLAIM_WINDOW_SECONDS: i64 = 365 * 86400;
+
+    /// Unclaimed entitlements lapse once the claim window has passed
+    pub fn claim_window_closed(&self, now: i64) -> bool {
+        now > self.payment_date.saturating_add(Self::CLAIM_WINDOW_SECONDS)
+    }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
-pub enum DividendStatus {
-    Announced,
-    Payable,
-    Completed,
-    Cancelled,
+    /// Reject schedules with payment before record or beyond the asset's
+    /// announce window, and totals that don't match the per-token amount
+    /// over the current supply.
+    pub fn validate(asset: &RwaAsset, params: &DividendParams, now: i64) -> Result<()> {
+        let window = match asset.max_dividend_window_seconds {
+            0 => Self::DEFAULT_MAX_WINDOW_SECONDS,
+            seconds => seconds,
+        };
+        require!(
+            params.record_date <= params.payment_date
+                && params.payment_date <= now.saturating_add(window),
+            RwaError::InvalidDividendSchedule
+        );
+
+        let expected = (asset.total_supply as u128 * params.amount_per_token as u128 / 1_000_000)
+            .min(u64::MAX as u128) as u64;
+        let tolerance = (expected as u128 * Self::AMOUNT_TOLERANCE_BPS as u128 / 10000).max(1) as u64;
+        require!(
+            params.total_amount.abs_diff(expected) <= tolerance,
+            RwaError::DividendAmountMismatch
+        );
+        Ok(())
+    }
+
+    pub fn new(asset: Pubkey, params: &DividendParams, now: i64, bump: u8) -> Self {
+        Self {
+            asset,
+            amount_per_token: params.amount_per_token,
+            total_amount: params.total_amount,
+            payment_token: params.payment_token,
+            record_date: params.record_date,
+            payment_date: params.payment_date,
+            status: if params.payment_date <= now {
+                DividendStatus::Payable
+            } else {
+                DividendStatus::Announced
+            },
+            claimed_amount: 0,
+            created_at: now,
+            bump,
+            escrow: Pubkey::default(),
+        }
+    }
 }
 
-// Account contexts
+/// Holder balance captured at a dividend's record date
+#[account]
+#[derive(InitSpace)]
+pub struct DividendSnapshot {
+    /// Associated dividend
+    pub dividend: Pubkey,
 
-#[derive(Accounts)]
-#[instruction(params: RegisterAssetParams)]
-pub struct RegisterAsset<'info> {
-    #[account(mut)]
-    pub authority: Signer<'info>,
+    /// Holder
+    pub owner: Pubkey,
 
-    #[account(
-        init,
-        payer = authority,
-        space = 8 + RwaAsset::INIT_SPACE,
-        seeds = [RwaAsset::SEED_PREFIX, params.symbol.as_bytes()],
-        bump
-    )]
-    pub asset: Account<'info, RwaAsset>,
+    /// Token amount held on the record date
+    pub amount: u64,
 
-    #[account(
-        init,
-        payer = authority,
-        mint::decimals = 6,
-        mint::authority = asset,
-    )]
-    pub token_mint: Account<'info, Mint>,
+    /// Snapshot timestamp
+    pub captured_at: i64,
 
-    pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>,
-    pub rent: Sysvar<'info, Rent>,
+    /// Bump seed
+    pub bump: u8,
+}
+
+impl DividendSnapshot {
+    pub const SEED_PREFIX: &'static [u8] = b"dividend_snapshot";
+}
+
+/// Receipt for a holder's dividend claim; one per holder per dividend
+#[account]
+#[derive(InitSpace)]
+pub struct DividendClaim {
+    /// Associated dividend
+    pub dividend: Pubkey,
+
+    /// Holder
+    pub owner: Pubkey,
+
+    /// Amount credited by the claim
+    pub amount: u64,
+
+    /// Claim timestamp
+    pub claimed_at: i64,
+
+    /// Bump seed
+    pub bump: u8,
+
+    /// Clawed-back amount the holder still has, netted against a re-claim
+    pub owed: u64,
+
+    /// Set by a clawback until the holder claims again
+    pub clawed_back: bool,
+}
+
+impl DividendClaim {
+    pub const SEED_PREFIX: &'static [u8] = b"dividend_claim";
+}
+
+/// Individual property within a real-estate asset
+#[account]
+#[derive(InitSpace)]
+pub struct PropertySubUnit {
+    /// Parent asset
+    pub asset: Pubkey,
+
+    /// Sequence number within the asset
+    pub property_id: u32,
+
+    /// Current valuation (in the asset's valuation_currency)
+    pub valuation: u64,
+
+    /// Hash of the property address / land registry record
+    pub address_hash: [u8; 32],
+
+    /// Property status
+    pub status: PropertyStatus,
+
+    /// Sale price (set when sold)
+    pub sale_price: u64,
+
+    /// Creation timestamp
+    pub created_at: i64,
+
+    /// Last updated timestamp
+    pub updated_at: i64,
+
+    /// Bump seed
+    pub bump: u8,
+}
+
+impl PropertySubUnit {
+    pub const SEED_PREFIX: &'static [u8] = b"property";
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum PropertyStatus {
+    Active,
+    Sold,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
+pub enum DividendStatus {
+    Announced,
+    Payable,
+    Completed,
+    Cancelled,
+}
+
+// Account contexts
+
+#[derive(Accounts)]
+#[instruction(params: RegisterAssetParams)]
+pub struct RegisterAsset<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + RwaAsset::INIT_SPACE,
+        seeds = [RwaAsset::SEED_PREFIX, params.symbol.as_bytes()],
+        bump
+    )]
+    pub asset: Account<'info, RwaAsset>,
+
+    #[account(
+        init,
+        payer = authority,
+        mint::decimals = RwaAsset::TOKEN_DECIMALS,
+        mint::authority = asset,
+    )]
+    pub token_mint: Account<'info, Mint>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize)]
@@ -508,6 +1911,8 @@ pub struct RegisterAssetParams {
     pub isin: Option<[u8; 12]>,
     pub jurisdiction: Jurisdiction,
     pub legal_document_hash: [u8; 32],
+    /// Feed for `update_valuation_from_oracle`; None = manual valuation
+    pub price_feed: Option<Pubkey>,
 }
 
 #[derive(Accounts)]
@@ -558,8 +1963,148 @@ pub struct MintTokens<'info> {
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+pub struct RedeemTokens<'info> {
+    pub holder: Signer<'info>,
+
+    #[account(mut)]
+    pub asset: Account<'info, RwaAsset>,
+
+    #[account(
+        mut,
+        constraint = token_mint.key() == asset.token_mint
+    )]
+    pub token_mint: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        token::mint = token_mint,
+        token::authority = holder,
+    )]
+    pub holder_token: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [OwnershipProof::SEED_PREFIX, asset.key().as_ref(), holder.key().as_ref()],
+        bump = ownership_proof.bump
+    )]
+    pub ownership_proof: Account<'info, OwnershipProof>,
+
+    /// Required unless the fee is zero or waived
+    #[account(
+        mut,
+        token::mint = token_mint,
+        constraint = Some(fee_treasury.key()) == asset.fee_treasury @ RwaError::FeeTreasuryNotConfigured,
+    )]
+    pub fee_treasury: Option<Account<'info, TokenAccount>>,
+
+    /// Holder's zk-verifier attestation, for the Institutional fee waiver
+    #[account(
+        seeds = [ComplianceAttestation::SEED_PREFIX, holder.key().as_ref()],
+        bump = kyc_attestation.bump,
+        seeds::program = zk_verifier::ID,
+    )]
+    pub kyc_attestation: Option<Account<'info, ComplianceAttestation>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct TransferOwnership<'info> {
+    #[account(mut)]
+    pub sender: Signer<'info>,
+
+    /// CHECK: Only used as the recipient proof's seed
+    #[account(constraint = recipient.key() != sender.key() @ RwaError::InvalidRecipient)]
+    pub recipient: UncheckedAccount<'info>,
+
+    pub asset: Account<'info, RwaAsset>,
+
+    #[account(
+        mut,
+        seeds = [OwnershipProof::SEED_PREFIX, asset.key().as_ref(), sender.key().as_ref()],
+        bump = sender_proof.bump
+    )]
+    pub sender_proof: Account<'info, OwnershipProof>,
+
+    #[account(
+        init_if_needed,
+        payer = sender,
+        space = 8 + OwnershipProof::INIT_SPACE,
+        seeds = [OwnershipProof::SEED_PREFIX, asset.key().as_ref(), recipient.key().as_ref()],
+        bump
+    )]
+    pub recipient_proof: Account<'info, OwnershipProof>,
+
+    #[account(
+        constraint = token_mint.key() == asset.token_mint
+    )]
+    pub token_mint: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        token::mint = token_mint,
+        token::authority = sender,
+    )]
+    pub sender_token: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = token_mint,
+        token::authority = recipient,
+    )]
+    pub recipient_token: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
 #[derive(Accounts)]
 pub struct UpdateValuation<'info> {
+    /// Asset authority or valuation authority
+    #[account(
+        constraint = asset.can_update_valuation(&authority.key()) @ RwaError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    #[account(mut)]
+    pub asset: Account<'info, RwaAsset>,
+}
+
+#[derive(Accounts)]
+pub struct SetCommodityUnits<'info> {
+    #[account(
+        constraint = authority.key() == asset.authority @ RwaError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    #[account(mut)]
+    pub asset: Account<'info, RwaAsset>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct CommodityUnitsParams {
+    pub unit_quantity: u64,
+    pub unit_decimals: u8,
+    pub unit_symbol: String,
+    pub price_feed: Option<Pubkey>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateValuationFromOracle<'info> {
+    pub cranker: Signer<'info>,
+
+    #[account(mut)]
+    pub asset: Account<'info, RwaAsset>,
+
+    #[account(
+        constraint = asset.price_feed == Some(price_feed.key()) @ RwaError::PriceFeedMismatch
+    )]
+    pub price_feed: Account<'info, PriceFeed>,
+}
+
+#[derive(Accounts)]
+pub struct SetValuationAuthority<'info> {
     #[account(
         constraint = authority.key() == asset.authority @ RwaError::Unauthorized
     )]
@@ -569,6 +2114,47 @@ pub struct UpdateValuation<'info> {
     pub asset: Account<'info, RwaAsset>,
 }
 
+#[derive(Accounts)]
+pub struct AddProperty<'info> {
+    #[account(
+        mut,
+        constraint = authority.key() == asset.authority @ RwaError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    #[account(mut)]
+    pub asset: Account<'info, RwaAsset>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + PropertySubUnit::INIT_SPACE,
+        seeds = [PropertySubUnit::SEED_PREFIX, asset.key().as_ref(), &asset.property_count.to_le_bytes()],
+        bump
+    )]
+    pub property: Account<'info, PropertySubUnit>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateProperty<'info> {
+    #[account(
+        constraint = authority.key() == asset.authority @ RwaError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    #[account(mut)]
+    pub asset: Account<'info, RwaAsset>,
+
+    #[account(
+        mut,
+        seeds = [PropertySubUnit::SEED_PREFIX, asset.key().as_ref(), &property.property_id.to_le_bytes()],
+        bump = property.bump
+    )]
+    pub property: Account<'info, PropertySubUnit>,
+}
+
 #[derive(Accounts)]
 #[instruction(params: DividendParams)]
 pub struct DistributeDividend<'info> {
@@ -589,6 +2175,84 @@ pub struct DistributeDividend<'info> {
     )]
     pub dividend: Account<'info, Dividend>,
 
+    #[account(
+        constraint = payment_mint.key() == params.payment_token @ RwaError::PaymentTokenMismatch
+    )]
+    pub payment_mint: Account<'info, Mint>,
+
+    /// Source of the dividend funds
+    #[account(
+        mut,
+        token::mint = payment_mint,
+        token::authority = authority,
+    )]
+    pub authority_payment: Account<'info, TokenAccount>,
+
+    /// Holds `total_amount` until claimed; owned by the dividend PDA
+    #[account(
+        init,
+        payer = authority,
+        seeds = [Dividend::ESCROW_SEED_PREFIX, dividend.key().as_ref()],
+        bump,
+        token::mint = payment_mint,
+        token::authority = dividend,
+    )]
+    pub escrow: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FundDividend<'info> {
+    #[account(
+        mut,
+        constraint = authority.key() == asset.authority @ RwaError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    pub asset: Account<'info, RwaAsset>,
+
+    #[account(
+        mut,
+        seeds = [Dividend::SEED_PREFIX, asset.key().as_ref(), &dividend.record_date.to_le_bytes()],
+        bump = dividend.bump
+    )]
+    pub dividend: Account<'info, Dividend>,
+
+    #[account(
+        constraint = payment_mint.key() == dividend.payment_token @ RwaError::PaymentTokenMismatch
+    )]
+    pub payment_mint: Account<'info, Mint>,
+
+    /// Source of the dividend funds
+    #[account(
+        mut,
+        token::mint = payment_mint,
+        token::authority = authority,
+    )]
+    pub authority_payment: Account<'info, TokenAccount>,
+
+    /// Holds `total_amount` until claimed; owned by the dividend PDA
+    #[account(
+        init,
+        payer = authority,
+        seeds = [Dividend::ESCROW_SEED_PREFIX, dividend.key().as_ref()],
+        bump,
+        token::mint = payment_mint,
+        token::authority = dividend,
+    )]
+    pub escrow: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DistributeDividendsBatch<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
     pub system_program: Program<'info, System>,
 }
 
@@ -603,6 +2267,7 @@ pub struct DividendParams {
 
 #[derive(Accounts)]
 pub struct ClaimDividend<'info> {
+    #[account(mut)]
     pub owner: Signer<'info>,
 
     pub asset: Account<'info, RwaAsset>,
@@ -619,6 +2284,200 @@ pub struct ClaimDividend<'info> {
         bump = dividend.bump
     )]
     pub dividend: Account<'info, Dividend>,
+
+    /// Record-date snapshot (required when `asset.require_dividend_snapshot`)
+    #[account(
+        seeds = [DividendSnapshot::SEED_PREFIX, dividend.key().as_ref(), owner.key().as_ref()],
+        bump = snapshot.bump
+    )]
+    pub snapshot: Option<Account<'info, DividendSnapshot>>,
+
+    /// Claim receipt, reused when a clawback reopened it
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + DividendClaim::INIT_SPACE,
+        seeds = [DividendClaim::SEED_PREFIX, dividend.key().as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub claim: Account<'info, DividendClaim>,
+
+    #[account(
+        mut,
+        seeds = [Dividend::ESCROW_SEED_PREFIX, dividend.key().as_ref()],
+        bump,
+    )]
+    pub escrow: Account<'info, TokenAccount>,
+
+    /// Receives the payment
+    #[account(
+        mut,
+        token::mint = dividend.payment_token,
+        token::authority = owner,
+    )]
+    pub owner_payment: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelDividend<'info> {
+    #[account(
+        constraint = authority.key() == asset.authority @ RwaError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    pub asset: Account<'info, RwaAsset>,
+
+    #[account(
+        mut,
+        seeds = [Dividend::SEED_PREFIX, asset.key().as_ref(), &dividend.record_date.to_le_bytes()],
+        bump = dividend.bump
+    )]
+    pub dividend: Account<'info, Dividend>,
+
+    /// Payment escrow (absent only for a batch-announced dividend never funded)
+    #[account(
+        mut,
+        seeds = [Dividend::ESCROW_SEED_PREFIX, dividend.key().as_ref()],
+        bump,
+    )]
+    pub escrow: Option<Account<'info, TokenAccount>>,
+
+    /// Receives the refund
+    #[account(
+        mut,
+        token::mint = dividend.payment_token,
+        token::authority = authority,
+    )]
+    pub authority_payment: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ReclaimDividendDust<'info> {
+    #[account(
+        constraint = authority.key() == asset.authority @ RwaError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    pub asset: Account<'info, RwaAsset>,
+
+    #[account(
+        seeds = [Dividend::SEED_PREFIX, asset.key().as_ref(), &dividend.record_date.to_le_bytes()],
+        bump = dividend.bump
+    )]
+    pub dividend: Account<'info, Dividend>,
+
+    #[account(
+        mut,
+        seeds = [Dividend::ESCROW_SEED_PREFIX, dividend.key().as_ref()],
+        bump,
+    )]
+    pub escrow: Account<'info, TokenAccount>,
+
+    /// Receives the swept remainder
+    #[account(
+        mut,
+        token::mint = dividend.payment_token,
+        token::authority = authority,
+    )]
+    pub authority_payment: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ClawbackDividendClaim<'info> {
+    #[account(
+        constraint = authority.key() == asset.authority @ RwaError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    pub asset: Account<'info, RwaAsset>,
+
+    #[account(
+        mut,
+        seeds = [Dividend::SEED_PREFIX, asset.key().as_ref(), &dividend.record_date.to_le_bytes()],
+        bump = dividend.bump
+    )]
+    pub dividend: Account<'info, Dividend>,
+
+    #[account(
+        mut,
+        seeds = [DividendClaim::SEED_PREFIX, dividend.key().as_ref(), claim.owner.as_ref()],
+        bump = claim.bump
+    )]
+    pub claim: Account<'info, DividendClaim>,
+}
+
+#[derive(Accounts)]
+pub struct SnapshotDividendHolder<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub asset: Account<'info, RwaAsset>,
+
+    #[account(
+        seeds = [Dividend::SEED_PREFIX, asset.key().as_ref(), &dividend.record_date.to_le_bytes()],
+        bump = dividend.bump
+    )]
+    pub dividend: Account<'info, Dividend>,
+
+    #[account(
+        seeds = [OwnershipProof::SEED_PREFIX, asset.key().as_ref(), ownership_proof.owner.as_ref()],
+        bump = ownership_proof.bump
+    )]
+    pub ownership_proof: Account<'info, OwnershipProof>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + DividendSnapshot::INIT_SPACE,
+        seeds = [DividendSnapshot::SEED_PREFIX, dividend.key().as_ref(), ownership_proof.owner.as_ref()],
+        bump
+    )]
+    pub snapshot: Account<'info, DividendSnapshot>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateAssetConfig<'info> {
+    #[account(
+        constraint = authority.key() == asset.authority @ RwaError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    #[account(mut)]
+    pub asset: Account<'info, RwaAsset>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct UpdateAssetConfigParams {
+    pub require_dividend_snapshot: Option<bool>,
+    pub min_holding_seconds: Option<i64>,
+    pub redemption_fee_bps: Option<u16>,
+    pub fee_treasury: Option<Pubkey>,
+    pub max_dividend_window_seconds: Option<i64>,
+    pub clawback_window_seconds: Option<i64>,
+    pub max_valuation_confidence_bps: Option<u16>,
+    pub block_delisted_redemptions: Option<bool>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateAsset<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// CHECK: may not deserialize under the current layout; discriminator and
+    /// authority are verified in the handler
+    #[account(mut, owner = crate::ID)]
+    pub asset: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
@@ -632,6 +2491,17 @@ pub struct FreezeAsset<'info> {
     pub asset: Account<'info, RwaAsset>,
 }
 
+#[derive(Accounts)]
+pub struct SetAssetStatus<'info> {
+    #[account(
+        constraint = authority.key() == asset.authority @ RwaError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    #[account(mut)]
+    pub asset: Account<'info, RwaAsset>,
+}
+
 // Events
 
 #[event]
@@ -665,7 +2535,70 @@ pub struct ValuationUpdated {
     pub asset: Pubkey,
     pub old_valuation: u64,
     pub new_valuation: u64,
+    /// Appraisal proof hash, or the price feed address for oracle updates
+    pub proof_hash: [u8; 32],
+    /// Signer of the update (any cranker for oracle updates)
+    pub updated_by: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ValuationRejectedLowConfidence {
+    pub asset: Pubkey,
+    pub price_feed: Pubkey,
+    pub price: u64,
+    pub confidence: u64,
+    pub max_confidence_bps: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CommodityUnitsSet {
+    pub asset: Pubkey,
+    pub unit_quantity: u64,
+    pub unit_decimals: u8,
+    pub unit_symbol: String,
+    pub price_feed: Option<Pubkey>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ValuationAuthoritySet {
+    pub asset: Pubkey,
+    pub valuation_authority: Option<Pubkey>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PropertyAdded {
+    pub asset: Pubkey,
+    pub property: Pubkey,
+    pub property_id: u32,
+    pub valuation: u64,
+    pub address_hash: [u8; 32],
+    pub old_asset_valuation: u64,
+    pub new_asset_valuation: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PropertyRevalued {
+    pub asset: Pubkey,
+    pub property: Pubkey,
+    pub old_valuation: u64,
+    pub new_valuation: u64,
     pub proof_hash: [u8; 32],
+    pub new_asset_valuation: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PropertySold {
+    pub asset: Pubkey,
+    pub property: Pubkey,
+    pub book_valuation: u64,
+    pub sale_price: u64,
+    pub new_asset_valuation: u64,
     pub timestamp: i64,
 }
 
@@ -678,6 +2611,22 @@ pub struct DividendAnnounced {
     pub timestamp: i64,
 }
 
+#[event]
+pub struct DividendFunded {
+    pub dividend: Pubkey,
+    pub escrow: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DividendBatchDistributed {
+    pub authority: Pubkey,
+    pub dividend_count: u8,
+    pub total_amount: u64,
+    pub timestamp: i64,
+}
+
 #[event]
 pub struct DividendClaimed {
     pub dividend: Pubkey,
@@ -686,6 +2635,87 @@ pub struct DividendClaimed {
     pub timestamp: i64,
 }
 
+#[event]
+pub struct DividendCancelled {
+    pub asset: Pubkey,
+    pub dividend: Pubkey,
+    pub refunded: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DividendDustReclaimed {
+    pub dividend: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DividendClawedBack {
+    pub dividend: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub justification_hash: [u8; 32],
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DividendSnapshotRecorded {
+    pub dividend: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AssetConfigUpdated {
+    pub asset: Pubkey,
+    pub require_dividend_snapshot: bool,
+    pub min_holding_seconds: i64,
+    pub redemption_fee_bps: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TokensRedeemed {
+    pub asset: Pubkey,
+    pub holder: Pubkey,
+    pub amount: u64,
+    pub fee: u64,
+    pub fee_waived: bool,
+    pub burned: u64,
+    pub total_supply: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct OwnershipTransferred {
+    pub asset: Pubkey,
+    pub from: Pubkey,
+    pub to: Pubkey,
+    pub amount: u64,
+    /// Cost basis moved with the amount
+    pub acquisition_price: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AccountMigrated {
+    pub account: Pubkey,
+    pub from_version: u8,
+    pub to_version: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AssetStatusChanged {
+    pub asset: Pubkey,
+    pub old_status: AssetStatus,
+    pub new_status: AssetStatus,
+    pub authority: Pubkey,
+    pub timestamp: i64,
+}
+
 #[event]
 pub struct AssetFrozen {
     pub asset: Pubkey,
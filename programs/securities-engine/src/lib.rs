@@ -18,7 +18,10 @@
 //! - AI agent compatible
 
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Token, TokenAccount, Transfer, Mint, MintTo, Burn};
+use anchor_lang::system_program;
+use anchor_spl::token::{self, CloseAccount, Token, TokenAccount, Transfer, Mint, MintTo, Burn};
+use anchor_spl::token_interface::{self, TokenInterface, TransferChecked};
+use oracle::{FundingFeed, PriceFeed};
 
 pub mod state;
 use state::*;
@@ -59,6 +62,156 @@ pub enum SecuritiesError {
 
     #[msg("Math overflow")]
     MathOverflow,
+
+    #[msg("Unauthorized")]
+    Unauthorized,
+
+    #[msg("LP governance is not enabled for this market")]
+    GovernanceDisabled,
+
+    #[msg("Fee outside the governed range")]
+    FeeOutOfRange,
+
+    #[msg("Voting period has ended")]
+    VotingClosed,
+
+    #[msg("Voting period or timelock has not elapsed")]
+    TimelockActive,
+
+    #[msg("Proposal did not pass")]
+    ProposalNotPassed,
+
+    #[msg("Oracle feed does not match the market oracle")]
+    OracleMismatch,
+
+    #[msg("Oracle price feed is stale")]
+    StalePriceFeed,
+
+    #[msg("Oracle price is invalid or inactive")]
+    InvalidOraclePrice,
+
+    #[msg("Position does not belong to this market")]
+    InvalidPosition,
+
+    #[msg("Auto-deleveraging is not enabled for this market")]
+    AdlDisabled,
+
+    #[msg("Position has no bad debt")]
+    NoBadDebt,
+
+    #[msg("Insurance fund can cover the bad debt")]
+    InsuranceFundSufficient,
+
+    #[msg("Invalid auto-deleveraging counterparty")]
+    InvalidAdlCounterparty,
+
+    #[msg("Counterparties must be ordered from most to least profitable")]
+    AdlOrderViolation,
+
+    #[msg("Counterparties cannot absorb the bankrupt position")]
+    InsufficientAdlCounterparties,
+
+    #[msg("Oracle confidence band is too wide")]
+    PriceTooUncertain,
+
+    #[msg("Account is already at the current version")]
+    AlreadyMigrated,
+
+    #[msg("Slippage tolerance must not exceed 10000 bps")]
+    InvalidSlippage,
+
+    #[msg("Market is outside its trading hours")]
+    MarketClosedHours,
+
+    #[msg("Trading hours must be seconds within a day")]
+    InvalidTradingSchedule,
+
+    #[msg("Auto-pause requires a non-zero deviation threshold")]
+    InvalidDeviationThreshold,
+
+    #[msg("Invalid swap route")]
+    InvalidRoute,
+
+    #[msg("Market insurance fund account is required")]
+    InsuranceFundRequired,
+
+    #[msg("Mint does not match the market quote mint")]
+    QuoteMintMismatch,
+
+    #[msg("Variance swap terms are out of range or mis-scaled")]
+    InvalidVarianceTerms,
+
+    #[msg("Funding already applied to this position at the current time")]
+    FundingAlreadyApplied,
+
+    #[msg("Invalid order: resting limit orders need a price, a size and GTC or GTD")]
+    InvalidOrder,
+
+    #[msg("Pool price does not cross the order's limit price")]
+    OrderNotMatchable,
+
+    #[msg("Pool price moved since the crank observed it")]
+    MatchPriceMoved,
+
+    #[msg("Fill-or-kill order cannot be filled in full at its limit price")]
+    FillOrKillFailed,
+
+    #[msg("First deposit must earn more than the locked minimum liquidity")]
+    InitialLiquidityTooSmall,
+
+    #[msg("Insurance fund balance is too low for this withdrawal")]
+    InsufficientInsuranceFund,
+
+    #[msg("No insurance withdrawal is pending")]
+    NoPendingWithdrawal,
+
+    #[msg("Market status transition is not allowed")]
+    InvalidStatusTransition,
+
+    #[msg("Reduce-only order would open or increase a position")]
+    ReduceOnlyViolation,
+
+    #[msg("Position is already open")]
+    PositionAlreadyOpen,
+
+    #[msg("Variance observation must wait out the observation interval and precede settlement")]
+    InvalidVarianceObservation,
+
+    #[msg("Variance swap has not reached its settlement date")]
+    VarianceSwapNotMatured,
+
+    #[msg("Variance swap is already settled")]
+    VarianceSwapAlreadySettled,
+
+    #[msg("Variance swap needs at least two observations to settle")]
+    InsufficientVarianceObservations,
+
+    #[msg("Insurance fund cannot cover the variance swap payout")]
+    SettlementUnderfunded,
+
+    #[msg("Position has no take-profit or stop-loss set")]
+    NoTriggerSet,
+
+    #[msg("Price has not crossed the position's take-profit or stop-loss")]
+    TriggerNotReached,
+
+    #[msg("Swap would move the pool price too far from the oracle")]
+    PriceDeviationExceeded,
+
+    #[msg("Amount exceeds accrued protocol fees")]
+    InsufficientProtocolFees,
+
+    #[msg("Protocol is in emergency shutdown")]
+    ProtocolShutdown,
+
+    #[msg("Protocol is not in emergency shutdown")]
+    ProtocolNotShutdown,
+
+    #[msg("Shutdown settlement price already set for this market")]
+    SettlementPriceAlreadySet,
+
+    #[msg("Shutdown settlement price not set for this market")]
+    SettlementPriceNotSet,
 }
 
 #[program]
@@ -94,6 +247,23 @@ pub mod securities_engine {
         market.is_active = true;
         market.created_at = clock.unix_timestamp;
         market.bump = ctx.bumps.market;
+        market.lp_governance_enabled = false;
+        market.min_governed_fee_bps = 0;
+        market.max_governed_fee_bps = 0;
+        market.fee_proposal_count = 0;
+        market.min_liquidity_to_trade = 0;
+        market.adl_enabled = false;
+        market.liquidation_bounty_bps = 0;
+        market.max_confidence_bps = 0;
+        market.version = Market::CURRENT_VERSION;
+        market.trading_open_seconds = 0;
+        market.trading_close_seconds = 0;
+        market.holiday_calendar_hash = [0u8; 32];
+        market.session_open = true;
+        market.auto_pause_enabled = false;
+        market.max_oracle_deviation_bps = 0;
+        market.requires_oracle = params.market_type.requires_oracle();
+        market.fallback_oracle = None;
 
         emit!(MarketCreated {
             market: market.key(),
@@ -105,6 +275,292 @@ pub mod securities_engine {
         Ok(())
     }
 
+    /// Update market configuration (authority only; also the emergency fee override)
+    pub fn update_market_config(
+        ctx: Context<UpdateMarketConfig>,
+        params: UpdateMarketConfigParams,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        let market = &mut ctx.accounts.market;
+
+        if let Some(fee_bps) = params.trading_fee_bps {
+            require!(fee_bps <= 10000, SecuritiesError::FeeOutOfRange);
+            market.trading_fee_bps = fee_bps;
+        }
+        if let Some(min_liquidity) = params.min_liquidity_to_trade {
+            market.min_liquidity_to_trade = min_liquidity;
+        }
+        if let Some(enabled) = params.lp_governance_enabled {
+            market.lp_governance_enabled = enabled;
+        }
+        if let Some(min_fee) = params.min_governed_fee_bps {
+            market.min_governed_fee_bps = min_fee;
+        }
+        if let Some(max_fee) = params.max_governed_fee_bps {
+            market.max_governed_fee_bps = max_fee;
+        }
+        if let Some(enabled) = params.adl_enabled {
+            market.adl_enabled = enabled;
+        }
+        if let Some(bounty_bps) = params.liquidation_bounty_bps {
+            require!(bounty_bps <= 10000, SecuritiesError::InvalidAmount);
+            market.liquidation_bounty_bps = bounty_bps;
+        }
+        if let Some(max_confidence_bps) = params.max_confidence_bps {
+            market.max_confidence_bps = max_confidence_bps;
+        }
+        if let Some(status) = params.status {
+            transition_market_status(market, status, ctx.accounts.authority.key(), clock.unix_timestamp)?;
+        }
+        if let Some(open_seconds) = params.trading_open_seconds {
+            market.trading_open_seconds = open_seconds;
+        }
+        if let Some(close_seconds) = params.trading_close_seconds {
+            market.trading_close_seconds = close_seconds;
+        }
+        if let Some(calendar_hash) = params.holiday_calendar_hash {
+            market.holiday_calendar_hash = calendar_hash;
+        }
+        if let Some(enabled) = params.auto_pause_enabled {
+            market.auto_pause_enabled = enabled;
+        }
+        if let Some(max_deviation_bps) = params.max_oracle_deviation_bps {
+            market.max_oracle_deviation_bps = max_deviation_bps;
+        }
+        if let Some(staleness_seconds) = params.funding_staleness_seconds {
+            market.funding_staleness_seconds = staleness_seconds;
+        }
+        if let Some(close_fee_bps) = params.close_fee_bps {
+            require!(close_fee_bps <= 10000, SecuritiesError::FeeOutOfRange);
+            market.close_fee_bps = close_fee_bps;
+        }
+        if let Some(target) = params.target_insurance_balance {
+            market.target_insurance_balance = target;
+        }
+        if let Some(share_bps) = params.insurance_fee_share_bps {
+            require!(share_bps <= 10000, SecuritiesError::FeeOutOfRange);
+            market.insurance_fee_share_bps = share_bps;
+        }
+        if let Some(requires_oracle) = params.requires_oracle {
+            market.requires_oracle = requires_oracle;
+        }
+        if let Some(max_interval) = params.max_funding_interval_seconds {
+            market.max_funding_interval_seconds = max_interval;
+        }
+        if let Some(fallback_oracle) = params.fallback_oracle {
+            market.fallback_oracle = fallback_oracle;
+        }
+        require!(
+            market.fallback_oracle != Some(market.oracle),
+            SecuritiesError::OracleMismatch
+        );
+        require!(
+            !market.auto_pause_enabled || market.max_oracle_deviation_bps > 0,
+            SecuritiesError::InvalidDeviationThreshold
+        );
+        require!(
+            market.trading_open_seconds < Market::SECONDS_PER_DAY as u32
+                && market.trading_close_seconds < Market::SECONDS_PER_DAY as u32,
+            SecuritiesError::InvalidTradingSchedule
+        );
+        require!(
+            market.min_governed_fee_bps <= market.max_governed_fee_bps
+                && market.max_governed_fee_bps <= 10000,
+            SecuritiesError::FeeOutOfRange
+        );
+
+        emit!(MarketConfigUpdated {
+            market: market.key(),
+            authority: ctx.accounts.authority.key(),
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Move the market along its status graph (market authority only).
+    /// Paused blocks new trades, Settling still allows closing positions,
+    /// Closed blocks everything.
+    pub fn set_market_status(ctx: Context<SetMarketStatus>, new_status: MarketStatus) -> Result<()> {
+        let clock = Clock::get()?;
+        transition_market_status(
+            &mut ctx.accounts.market,
+            new_status,
+            ctx.accounts.authority.key(),
+            clock.unix_timestamp,
+        )
+    }
+
+    /// Propose a trading fee change (LP holders only)
+    pub fn propose_fee_change(ctx: Context<ProposeFeeChange>, new_fee_bps: u16) -> Result<()> {
+        let clock = Clock::get()?;
+        let market = &mut ctx.accounts.market;
+
+        require!(market.lp_governance_enabled, SecuritiesError::GovernanceDisabled);
+        require!(
+            new_fee_bps >= market.min_governed_fee_bps && new_fee_bps <= market.max_governed_fee_bps,
+            SecuritiesError::FeeOutOfRange
+        );
+        require!(ctx.accounts.proposer_lp.amount > 0, SecuritiesError::InsufficientLiquidity);
+
+        let proposal = &mut ctx.accounts.proposal;
+        proposal.market = market.key();
+        proposal.proposal_id = market.fee_proposal_count;
+        proposal.proposer = ctx.accounts.proposer.key();
+        proposal.new_fee_bps = new_fee_bps;
+        proposal.votes_for = 0;
+        proposal.votes_against = 0;
+        proposal.quorum_votes = FeeProposal::quorum_for(ctx.accounts.pool.lp_supply);
+        proposal.voting_ends_at = clock.unix_timestamp + FeeProposal::VOTING_PERIOD_SECONDS;
+        proposal.executable_at = proposal.voting_ends_at + FeeProposal::TIMELOCK_SECONDS;
+        proposal.executed = false;
+        proposal.created_at = clock.unix_timestamp;
+        proposal.bump = ctx.bumps.proposal;
+
+        market.fee_proposal_count = market.fee_proposal_count.saturating_add(1);
+
+        emit!(FeeChangeProposed {
+            market: market.key(),
+            proposal: proposal.key(),
+            proposer: proposal.proposer,
+            current_fee_bps: market.trading_fee_bps,
+            new_fee_bps,
+            voting_ends_at: proposal.voting_ends_at,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Vote on a fee proposal, weighted by the voter's LP balance. The LP
+    /// tokens are escrowed until voting ends (see `release_fee_vote`), so the
+    /// same tokens can't vote again from another wallet.
+    pub fn vote_fee_change(ctx: Context<VoteFeeChange>, approve: bool) -> Result<()> {
+        let clock = Clock::get()?;
+        let proposal = &mut ctx.accounts.proposal;
+
+        require!(proposal.is_voting_open(clock.unix_timestamp), SecuritiesError::VotingClosed);
+
+        let weight = ctx.accounts.voter_lp.amount;
+        require!(weight > 0, SecuritiesError::InvalidAmount);
+
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.voter_lp.to_account_info(),
+                    to: ctx.accounts.vote_escrow.to_account_info(),
+                    authority: ctx.accounts.voter.to_account_info(),
+                },
+            ),
+            weight,
+        )?;
+
+        if approve {
+            proposal.votes_for = proposal.votes_for.saturating_add(weight);
+        } else {
+            proposal.votes_against = proposal.votes_against.saturating_add(weight);
+        }
+
+        let vote = &mut ctx.accounts.vote_record;
+        vote.proposal = proposal.key();
+        vote.voter = ctx.accounts.voter.key();
+        vote.weight = weight;
+        vote.approve = approve;
+        vote.voted_at = clock.unix_timestamp;
+        vote.bump = ctx.bumps.vote_record;
+
+        emit!(FeeChangeVoted {
+            proposal: proposal.key(),
+            voter: vote.voter,
+            weight,
+            approve,
+            votes_for: proposal.votes_for,
+            votes_against: proposal.votes_against,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Return a voter's escrowed LP tokens once voting has ended, closing the
+    /// vote receipt and its escrow
+    pub fn release_fee_vote(ctx: Context<ReleaseFeeVote>) -> Result<()> {
+        let clock = Clock::get()?;
+        require!(
+            !ctx.accounts.proposal.is_voting_open(clock.unix_timestamp),
+            SecuritiesError::TimelockActive
+        );
+
+        let market_key = ctx.accounts.market.key();
+        let amount = ctx.accounts.vote_escrow.amount;
+        transfer_pool_funds(
+            &ctx.accounts.token_program,
+            ctx.accounts.vote_escrow.to_account_info(),
+            ctx.accounts.voter_lp.to_account_info(),
+            ctx.accounts.pool_authority.to_account_info(),
+            market_key,
+            ctx.bumps.pool_authority,
+            amount,
+        )?;
+        let authority_seeds = &[
+            b"pool_authority" as &[u8],
+            market_key.as_ref(),
+            &[ctx.bumps.pool_authority],
+        ];
+        token::close_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            CloseAccount {
+                account: ctx.accounts.vote_escrow.to_account_info(),
+                destination: ctx.accounts.voter.to_account_info(),
+                authority: ctx.accounts.pool_authority.to_account_info(),
+            },
+            &[&authority_seeds[..]],
+        ))?;
+
+        emit!(FeeVoteReleased {
+            proposal: ctx.accounts.proposal.key(),
+            voter: ctx.accounts.voter.key(),
+            amount,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Apply a passed fee proposal once voting and the timelock have elapsed
+    pub fn execute_fee_change(ctx: Context<ExecuteFeeChange>) -> Result<()> {
+        let clock = Clock::get()?;
+        let market = &mut ctx.accounts.market;
+        let proposal = &mut ctx.accounts.proposal;
+
+        require!(market.lp_governance_enabled, SecuritiesError::GovernanceDisabled);
+        require!(clock.unix_timestamp >= proposal.executable_at, SecuritiesError::TimelockActive);
+        require!(proposal.has_passed(), SecuritiesError::ProposalNotPassed);
+        // Bounds may have been tightened by the authority since the proposal was created
+        require!(
+            proposal.new_fee_bps >= market.min_governed_fee_bps
+                && proposal.new_fee_bps <= market.max_governed_fee_bps,
+            SecuritiesError::FeeOutOfRange
+        );
+
+        let old_fee_bps = market.trading_fee_bps;
+        market.trading_fee_bps = proposal.new_fee_bps;
+        proposal.executed = true;
+
+        emit!(FeeChangeExecuted {
+            market: market.key(),
+            proposal: proposal.key(),
+            old_fee_bps,
+            new_fee_bps: proposal.new_fee_bps,
+            votes_for: proposal.votes_for,
+            votes_against: proposal.votes_against,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
     /// Initialize AMM pool for a market
     pub fn initialize_pool(ctx: Context<InitializePool>) -> Result<()> {
         let clock = Clock::get()?;
@@ -122,17 +578,23 @@ pub mod securities_engine {
         pool.accumulated_fees_quote = 0;
         pool.twap = 0;
         pool.twap_last_update = clock.unix_timestamp;
+        pool.twap_last_price = 0;
+        pool.twap_window_cumulative = 0;
+        pool.twap_window_start = clock.unix_timestamp;
         pool.cumulative_price = 0;
         pool.k_last = 0;
         pool.is_active = true;
         pool.created_at = clock.unix_timestamp;
         pool.bump = ctx.bumps.pool;
         pool.authority_bump = ctx.bumps.pool_authority;
+        pool.version = Pool::CURRENT_VERSION;
 
         Ok(())
     }
 
-    /// Add liquidity to pool
+    /// Add liquidity to pool. `security_amount` and `quote_amount` are
+    /// maximums: only the part that pairs at the current reserve ratio is
+    /// pulled, and `LiquidityAdded` reports what was used.
     pub fn add_liquidity(
         ctx: Context<AddLiquidity>,
         security_amount: u64,
@@ -140,14 +602,28 @@ pub mod securities_engine {
         min_lp_tokens: u64,
     ) -> Result<()> {
         let clock = Clock::get()?;
+        require_not_shutdown(&ctx.accounts.protocol_state)?;
         let pool = &mut ctx.accounts.pool;
 
         require!(pool.is_active, SecuritiesError::MarketNotActive);
+        // New liquidity would be trapped in a halted, settling or closed market
+        require!(ctx.accounts.market.is_trading(), SecuritiesError::MarketNotActive);
         require!(security_amount > 0 && quote_amount > 0, SecuritiesError::InvalidAmount);
 
-        // Calculate LP tokens to mint
-        let lp_tokens = pool.calculate_lp_tokens(security_amount, quote_amount)
+        // Trim the over-supplied side instead of donating it to existing LPs
+        let max_security_amount = security_amount;
+        let max_quote_amount = quote_amount;
+        let (security_amount, quote_amount) = pool
+            .optimal_deposit(max_security_amount, max_quote_amount)
             .ok_or(SecuritiesError::MathOverflow)?;
+        require!(security_amount > 0 && quote_amount > 0, SecuritiesError::InvalidAmount);
+
+        // Calculate LP tokens to mint; the first deposit locks MINIMUM_LIQUIDITY
+        let lp_total = pool.calculate_lp_tokens(security_amount, quote_amount)
+            .ok_or(SecuritiesError::MathOverflow)?;
+        let locked = if pool.lp_supply == 0 { Pool::MINIMUM_LIQUIDITY } else { 0 };
+        require!(lp_total > locked, SecuritiesError::InitialLiquidityTooSmall);
+        let lp_tokens = lp_total - locked;
 
         require!(lp_tokens >= min_lp_tokens, SecuritiesError::SlippageExceeded);
 
@@ -197,15 +673,17 @@ pub mod securities_engine {
         // Update pool state
         pool.security_liquidity = pool.security_liquidity.saturating_add(security_amount);
         pool.quote_liquidity = pool.quote_liquidity.saturating_add(quote_amount);
-        pool.lp_supply = pool.lp_supply.saturating_add(lp_tokens);
+        pool.lp_supply = pool.lp_supply.saturating_add(lp_total);
         pool.k_last = pool.security_liquidity as u128 * pool.quote_liquidity as u128;
-        pool.update_twap(clock.unix_timestamp);
+        update_pool_twap(pool, clock.unix_timestamp);
 
         emit!(LiquidityAdded {
             pool: pool.key(),
             provider: ctx.accounts.user.key(),
             security_amount,
             quote_amount,
+            max_security_amount,
+            max_quote_amount,
             lp_tokens,
             timestamp: clock.unix_timestamp,
         });
@@ -213,6 +691,25 @@ pub mod securities_engine {
         Ok(())
     }
 
+    /// Add liquidity with a tolerance in basis points instead of an absolute
+    /// minimum. The quote is the LP amount the deposit earns at the current
+    /// reserves once trimmed to the pool ratio, as `add_liquidity` trims it.
+    pub fn add_liquidity_with_slippage(
+        ctx: Context<AddLiquidity>,
+        security_amount: u64,
+        quote_amount: u64,
+        slippage_bps: u16,
+    ) -> Result<()> {
+        require!(slippage_bps <= 10000, SecuritiesError::InvalidSlippage);
+
+        let expected = ctx.accounts.pool
+            .quote_lp_tokens(security_amount, quote_amount)
+            .ok_or(SecuritiesError::MathOverflow)?;
+        let min_lp_tokens = Pool::apply_slippage(expected, slippage_bps);
+
+        add_liquidity(ctx, security_amount, quote_amount, min_lp_tokens)
+    }
+
     /// Swap tokens in the AMM pool
     pub fn swap(
         ctx: Context<Swap>,
@@ -221,15 +718,47 @@ pub mod securities_engine {
         is_security_input: bool,
     ) -> Result<()> {
         let clock = Clock::get()?;
+        require_not_shutdown(&ctx.accounts.protocol_state)?;
+        require!(
+            ctx.accounts.market.is_within_trading_hours(clock.unix_timestamp),
+            SecuritiesError::MarketClosedHours
+        );
+        if ctx.accounts.market.sync_trading_session(clock.unix_timestamp) {
+            emit!(TradingSessionChanged {
+                market: ctx.accounts.market.key(),
+                is_open: true,
+                timestamp: clock.unix_timestamp,
+            });
+        }
+
+        // Oracle price for the required-oracle, auto-pause and deviation guards
+        let guard_price = swap_guard_price(
+            &ctx.accounts.market,
+            ctx.accounts.oracle_feed.as_deref(),
+            ctx.accounts.fallback_oracle_feed.as_deref(),
+            clock.unix_timestamp,
+        )?;
+
+        // Trip the breaker instead of trading against a pool pushed away from
+        // fair value. Returns Ok so the pause persists; the swap is not executed.
+        if trip_auto_pause(
+            &mut ctx.accounts.market,
+            &ctx.accounts.pool,
+            guard_price,
+            clock.unix_timestamp,
+        )? {
+            return Ok(());
+        }
+
         let market = &ctx.accounts.market;
-        let pool = &mut ctx.accounts.pool;
+        let pool = &ctx.accounts.pool;
 
         require!(market.is_trading(), SecuritiesError::MarketNotActive);
-        require!(amount_in > 0, SecuritiesError::InvalidAmount);
-        require!(amount_in >= market.min_trade_size, SecuritiesError::InvalidAmount);
-        if market.max_trade_size > 0 {
-            require!(amount_in <= market.max_trade_size, SecuritiesError::InvalidAmount);
-        }
+        require!(market.within_trade_size(amount_in), SecuritiesError::InvalidAmount);
+        require!(
+            pool.has_min_liquidity(market.min_liquidity_to_trade),
+            SecuritiesError::InsufficientLiquidity
+        );
 
         // Calculate output
         let (amount_out, fee) = pool
@@ -293,25 +822,22 @@ pub mod securities_engine {
             amount_out,
         )?;
 
-        // Update pool state
-        if is_security_input {
-            pool.security_liquidity = pool.security_liquidity.saturating_add(amount_in);
-            pool.quote_liquidity = pool.quote_liquidity.saturating_sub(amount_out);
-            pool.accumulated_fees_security = pool.accumulated_fees_security.saturating_add(fee);
-        } else {
-            pool.quote_liquidity = pool.quote_liquidity.saturating_add(amount_in);
-            pool.security_liquidity = pool.security_liquidity.saturating_sub(amount_out);
-            pool.accumulated_fees_quote = pool.accumulated_fees_quote.saturating_add(fee);
-        }
-
-        pool.update_twap(clock.unix_timestamp);
-
-        // Update market volume
-        let market = &mut ctx.accounts.market;
-        let volume = if is_security_input { amount_out } else { amount_in };
-        market.update_volume(volume, clock.unix_timestamp);
-        market.total_fees = market.total_fees.saturating_add(fee);
+        settle_pool_trade(
+            &mut ctx.accounts.market,
+            &mut ctx.accounts.pool,
+            PoolTrade { amount_in, amount_out, fee, is_security_input },
+            guard_price,
+            PoolFeeAccounts {
+                token_program: &ctx.accounts.token_program,
+                pool_authority: ctx.accounts.pool_authority.to_account_info(),
+                quote_vault: ctx.accounts.quote_vault.to_account_info(),
+                insurance_fund: ctx.accounts.insurance_fund.as_deref_mut(),
+                insurance_vault: ctx.accounts.insurance_vault.as_deref().map(|v| v.to_account_info()),
+            },
+            clock.unix_timestamp,
+        )?;
 
+        let pool = &ctx.accounts.pool;
         emit!(SwapExecuted {
             pool: pool.key(),
             user: ctx.accounts.user.key(),
@@ -319,241 +845,3726 @@ pub mod securities_engine {
             amount_out,
             fee,
             is_security_input,
-            price: pool.get_spot_price(),
+            price: pool.get_spot_price().ok_or(SecuritiesError::InsufficientLiquidity)?,
             timestamp: clock.unix_timestamp,
         });
 
         Ok(())
     }
 
-    /// Open a perpetual position
-    pub fn open_position(
-        ctx: Context<OpenPosition>,
-        params: OpenPositionParams,
-    ) -> Result<()> {
+    /// Multi-hop swap through up to `MAX_ROUTE_LEGS` pools. Each leg's output
+    /// moves vault-to-vault into the next pool, so intermediate tokens never
+    /// leave program control; only the final output reaches the user and it
+    /// must meet `min_amount_out`. Returns every leg's realized output.
+    ///
+    /// `remaining_accounts` holds, per leg: market, pool, pool authority,
+    /// security vault, quote vault, insurance fund, insurance vault. Legs on
+    /// markets without `target_insurance_balance` pass the program id in both
+    /// insurance slots. Markets with auto-pause enabled or `requires_oracle`
+    /// set need an oracle read and can't be routed through.
+    pub fn route_swap<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RouteSwap<'info>>,
+        amount_in: u64,
+        min_amount_out: u64,
+        security_input_legs: Vec<bool>,
+    ) -> Result<Vec<u64>> {
         let clock = Clock::get()?;
-        let market = &ctx.accounts.market;
-        let position = &mut ctx.accounts.position;
+        require_not_shutdown(&ctx.accounts.protocol_state)?;
+        let legs = security_input_legs.len();
 
-        require!(market.is_trading(), SecuritiesError::MarketNotActive);
-        require!(params.leverage >= 1 && params.leverage <= 100, SecuritiesError::InvalidLeverage);
+        require!(amount_in > 0, SecuritiesError::InvalidAmount);
+        require!(
+            legs > 0
+                && legs <= MAX_ROUTE_LEGS
+                && ctx.remaining_accounts.len() == legs * ROUTE_LEG_ACCOUNTS,
+            SecuritiesError::InvalidRoute
+        );
 
-        let required_collateral = params.size / params.leverage as u64;
-        require!(params.collateral >= required_collateral, SecuritiesError::InsufficientCollateral);
+        let mut outputs = Vec::with_capacity(legs);
+        let mut leg_in = amount_in;
+        let mut expected_mint = ctx.accounts.user_source.mint;
+
+        for (i, &is_security_input) in security_input_legs.iter().enumerate() {
+            let accounts = &ctx.remaining_accounts[i * ROUTE_LEG_ACCOUNTS..(i + 1) * ROUTE_LEG_ACCOUNTS];
+            let mut market: Account<Market> = Account::try_from(&accounts[0])?;
+            let mut pool: Account<Pool> = Account::try_from(&accounts[1])?;
+            let pool_authority = &accounts[2];
+            let (security_vault, quote_vault) = (&accounts[3], &accounts[4]);
+
+            // The leg's protocol fee is split with the insurance fund like a swap's
+            let mut insurance_fund: Option<Account<InsuranceFund>> = None;
+            let mut insurance_vault = None;
+            if market.target_insurance_balance > 0 {
+                let (expected_fund, _) = Pubkey::find_program_address(
+                    &[InsuranceFund::SEED_PREFIX, market.key().as_ref()],
+                    ctx.program_id,
+                );
+                let (expected_vault, _) = Pubkey::find_program_address(
+                    &[InsuranceFund::VAULT_SEED_PREFIX, market.key().as_ref()],
+                    ctx.program_id,
+                );
+                require!(
+                    accounts[5].key() == expected_fund && accounts[6].key() == expected_vault,
+                    SecuritiesError::InsuranceFundRequired
+                );
+                insurance_fund = Some(Account::try_from(&accounts[5])?);
+                insurance_vault = Some(accounts[6].clone());
+            }
+
+            let (expected_authority, _) = Pubkey::find_program_address(
+                &[b"pool_authority", market.key().as_ref()],
+                ctx.program_id,
+            );
+            require!(
+                pool.market == market.key()
+                    && pool_authority.key() == expected_authority
+                    && security_vault.key() == pool.security_vault
+                    && quote_vault.key() == pool.quote_vault
+                    && !market.auto_pause_enabled
+                    && !market.rejects_oracle_deviation()
+                    && !market.requires_oracle,
+                SecuritiesError::InvalidRoute
+            );
+
+            let (input_mint, output_mint) = if is_security_input {
+                (market.security_mint, market.quote_mint)
+            } else {
+                (market.quote_mint, market.security_mint)
+            };
+            require_keys_eq!(input_mint, expected_mint, SecuritiesError::InvalidRoute);
+            expected_mint = output_mint;
+
+            require!(market.is_trading(), SecuritiesError::MarketNotActive);
+            require!(
+                market.is_within_trading_hours(clock.unix_timestamp),
+                SecuritiesError::MarketClosedHours
+            );
+            require!(market.within_trade_size(leg_in), SecuritiesError::InvalidAmount);
+            require!(
+                pool.has_min_liquidity(market.min_liquidity_to_trade),
+                SecuritiesError::InsufficientLiquidity
+            );
+
+            let (leg_out, fee) = pool
+                .calculate_swap_output(leg_in, is_security_input, market.trading_fee_bps)
+                .ok_or(SecuritiesError::InsufficientLiquidity)?;
+            require!(leg_out > 0, SecuritiesError::InsufficientLiquidity);
 
-        // Transfer collateral
+            let (in_vault, out_vault) = if is_security_input {
+                (security_vault, quote_vault)
+            } else {
+                (quote_vault, security_vault)
+            };
+
+            // Only the first leg pulls from the user; later legs were funded by
+            // the previous pool's vault-to-vault transfer
+            if i == 0 {
+                token::transfer(
+                    CpiContext::new(
+                        ctx.accounts.token_program.to_account_info(),
+                        Transfer {
+                            from: ctx.accounts.user_source.to_account_info(),
+                            to: in_vault.clone(),
+                            authority: ctx.accounts.user.to_account_info(),
+                        },
+                    ),
+                    leg_in,
+                )?;
+            }
+
+            let destination = if i + 1 == legs {
+                ctx.accounts.user_destination.to_account_info()
+            } else {
+                let next = &ctx.remaining_accounts[(i + 1) * ROUTE_LEG_ACCOUNTS..];
+                // Next leg's input vault: its security vault when it sells security
+                if security_input_legs[i + 1] { next[3].clone() } else { next[4].clone() }
+            };
+
+            let market_key = market.key();
+            let authority_seeds = &[b"pool_authority" as &[u8], market_key.as_ref(), &[pool.authority_bump]];
+            token::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: out_vault.clone(),
+                        to: destination,
+                        authority: pool_authority.clone(),
+                    },
+                    &[&authority_seeds[..]],
+                ),
+                leg_out,
+            )?;
+
+            settle_pool_trade(
+                &mut market,
+                &mut pool,
+                PoolTrade { amount_in: leg_in, amount_out: leg_out, fee, is_security_input },
+                None,
+                PoolFeeAccounts {
+                    token_program: &ctx.accounts.token_program,
+                    pool_authority: pool_authority.clone(),
+                    quote_vault: quote_vault.clone(),
+                    insurance_fund: insurance_fund.as_mut(),
+                    insurance_vault,
+                },
+                clock.unix_timestamp,
+            )?;
+
+            emit!(SwapExecuted {
+                pool: pool.key(),
+                user: ctx.accounts.user.key(),
+                amount_in: leg_in,
+                amount_out: leg_out,
+                fee,
+                is_security_input,
+                price: pool.get_spot_price().ok_or(SecuritiesError::InsufficientLiquidity)?,
+                timestamp: clock.unix_timestamp,
+            });
+
+            pool.exit(ctx.program_id)?;
+            market.exit(ctx.program_id)?;
+            if let Some(fund) = &insurance_fund {
+                fund.exit(ctx.program_id)?;
+            }
+
+            outputs.push(leg_out);
+            leg_in = leg_out;
+        }
+
+        require_keys_eq!(ctx.accounts.user_destination.mint, expected_mint, SecuritiesError::InvalidRoute);
+        require!(leg_in >= min_amount_out, SecuritiesError::SlippageExceeded);
+
+        Ok(outputs)
+    }
+
+    /// Swap with a tolerance in basis points instead of an absolute minimum.
+    /// The quote is the output at the current spot price with no price impact,
+    /// so the tolerance bounds price impact.
+    pub fn swap_with_slippage(
+        ctx: Context<Swap>,
+        amount_in: u64,
+        slippage_bps: u16,
+        is_security_input: bool,
+    ) -> Result<()> {
+        require!(slippage_bps <= 10000, SecuritiesError::InvalidSlippage);
+
+        let expected = ctx.accounts.pool
+            .quote_swap_output(amount_in, is_security_input, ctx.accounts.market.trading_fee_bps)
+            .ok_or(SecuritiesError::InsufficientLiquidity)?;
+        let min_amount_out = Pool::apply_slippage(expected, slippage_bps);
+
+        swap(ctx, amount_in, min_amount_out, is_security_input)
+    }
+
+    /// Read the pool's spot price, windowed TWAP and their divergence
+    pub fn get_twap(ctx: Context<GetPoolInfo>) -> Result<TwapInfo> {
+        let pool = &ctx.accounts.pool;
+        Ok(TwapInfo {
+            spot_price: pool.get_spot_price().unwrap_or(0),
+            twap: pool.twap,
+            divergence_bps: pool.price_divergence_bps().unwrap_or(0),
+            window_start: pool.twap_window_start,
+        })
+    }
+
+    /// Read total accrued pool fees valued in quote at the current spot price
+    pub fn get_fees_in_quote(ctx: Context<GetPoolInfo>) -> Result<u64> {
+        let pool = &ctx.accounts.pool;
+        let price = match pool.get_spot_price() {
+            Some(price) => price,
+            // Nothing on the security side to convert
+            None if pool.accumulated_fees_security == 0 => 0,
+            None => return err!(SecuritiesError::InsufficientLiquidity),
+        };
+        pool.total_fees_in_quote(price)
+            .ok_or(SecuritiesError::MathOverflow.into())
+    }
+
+    /// Read the integer square root (floor) that sizes a pool's first LP mint
+    pub fn integer_sqrt(_ctx: Context<IntegerSqrt>, value: u128) -> Result<u128> {
+        Ok(isqrt(value))
+    }
+
+    /// Send accrued protocol fees from the quote vault to a treasury account
+    /// (market authority only)
+    pub fn withdraw_protocol_fees(ctx: Context<WithdrawProtocolFees>, amount: u64) -> Result<()> {
+        let clock = Clock::get()?;
+        let market = &mut ctx.accounts.market;
+
+        require!(amount > 0, SecuritiesError::InvalidAmount);
+        require!(
+            amount <= market.accumulated_protocol_fees,
+            SecuritiesError::InsufficientProtocolFees
+        );
+        market.accumulated_protocol_fees -= amount;
+
+        let market_key = market.key();
+        let authority_seeds = &[
+            b"pool_authority" as &[u8],
+            market_key.as_ref(),
+            &[ctx.accounts.pool.authority_bump],
+        ];
         token::transfer(
-            CpiContext::new(
+            CpiContext::new_with_signer(
                 ctx.accounts.token_program.to_account_info(),
                 Transfer {
-                    from: ctx.accounts.user_quote.to_account_info(),
-                    to: ctx.accounts.collateral_vault.to_account_info(),
-                    authority: ctx.accounts.user.to_account_info(),
+                    from: ctx.accounts.quote_vault.to_account_info(),
+                    to: ctx.accounts.treasury.to_account_info(),
+                    authority: ctx.accounts.pool_authority.to_account_info(),
                 },
+                &[authority_seeds],
             ),
-            params.collateral,
+            amount,
         )?;
 
-        // Initialize position
-        position.owner = ctx.accounts.user.key();
-        position.market = market.key();
-        position.position_type = params.position_type;
-        position.side = params.side;
-        position.size = params.size;
-        position.entry_price = params.entry_price;
-        position.leverage = params.leverage;
-        position.collateral = params.collateral;
-        position.unrealized_pnl = 0;
-        position.accumulated_funding = 0;
-        position.last_funding_update = clock.unix_timestamp;
-        position.liquidation_price = position.calculate_liquidation_price();
-        position.take_profit = params.take_profit;
-        position.stop_loss = params.stop_loss;
-        position.is_open = true;
-        position.created_at = clock.unix_timestamp;
-        position.updated_at = clock.unix_timestamp;
-        position.bump = ctx.bumps.position;
-
-        emit!(PositionOpened {
-            position: position.key(),
-            owner: ctx.accounts.user.key(),
-            market: market.key(),
-            side: params.side,
-            size: params.size,
-            entry_price: params.entry_price,
-            leverage: params.leverage,
+        emit!(ProtocolFeesWithdrawn {
+            market: market_key,
+            treasury: ctx.accounts.treasury.key(),
+            amount,
+            remaining: market.accumulated_protocol_fees,
             timestamp: clock.unix_timestamp,
         });
 
         Ok(())
     }
+
+    /// Create the market's order book (authority only)
+    pub fn initialize_order_book(ctx: Context<InitializeOrderBook>) -> Result<()> {
+        let book = &mut ctx.accounts.order_book;
+        book.market = ctx.accounts.market.key();
+        book.best_bid = 0;
+        book.best_ask = 0;
+        book.bid_volume = 0;
+        book.ask_volume = 0;
+        book.order_count = 0;
+        book.last_trade_price = 0;
+        book.last_trade_time = 0;
+        book.bump = ctx.bumps.order_book;
+
+        Ok(())
+    }
+
+    /// Place a resting limit order. A buy escrows `size × price` of quote, a
+    /// sell escrows `size` of the security, in a token account owned by the
+    /// pool authority until the order fills or is cancelled.
+    pub fn place_limit_order(ctx: Context<PlaceLimitOrder>, params: PlaceOrderParams) -> Result<()> {
+        let clock = Clock::get()?;
+        require_not_shutdown(&ctx.accounts.protocol_state)?;
+        let market = &ctx.accounts.market;
+
+        require!(market.is_trading(), SecuritiesError::MarketNotActive);
+        require!(params.price > 0 && params.size > 0, SecuritiesError::InvalidOrder);
+        let expires_at = match params.time_in_force {
+            TimeInForce::Gtc => 0,
+            TimeInForce::Gtd => {
+                require!(params.expires_at > clock.unix_timestamp, SecuritiesError::InvalidOrder);
+                params.expires_at
+            }
+            // These never rest: see `place_immediate_order`
+            TimeInForce::Ioc | TimeInForce::Fok => return err!(SecuritiesError::InvalidOrder),
+        };
+        // A reduce-only order reserves what it may reduce, so resting orders
+        // together never exceed the position
+        if params.reduce_only {
+            let reducible = Order::reducible_size(
+                params.side,
+                params.price,
+                ctx.accounts.position.as_deref(),
+                0,
+            );
+            require!(params.size <= reducible, SecuritiesError::ReduceOnlyViolation);
+            let notional =
+                Order::quote_for(params.size, params.price).ok_or(SecuritiesError::MathOverflow)?;
+            ctx.accounts
+                .position
+                .as_mut()
+                .ok_or(SecuritiesError::ReduceOnlyViolation)?
+                .reserve_reduce_only(notional);
+        }
+
+        let escrow_amount = match params.side {
+            OrderSide::Buy => Order::quote_for(params.size, params.price)
+                .ok_or(SecuritiesError::MathOverflow)?,
+            OrderSide::Sell => params.size,
+        };
+        require!(escrow_amount > 0, SecuritiesError::InvalidOrder);
+
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.owner_token.to_account_info(),
+                    to: ctx.accounts.escrow.to_account_info(),
+                    authority: ctx.accounts.owner.to_account_info(),
+                },
+            ),
+            escrow_amount,
+        )?;
+
+        let order = &mut ctx.accounts.order;
+        order.owner = ctx.accounts.owner.key();
+        order.market = market.key();
+        order.side = params.side;
+        order.order_type = OrderType::Limit;
+        order.price = params.price;
+        order.original_size = params.size;
+        order.remaining_size = params.size;
+        order.filled_size = 0;
+        order.avg_fill_price = 0;
+        order.time_in_force = params.time_in_force;
+        order.status = OrderStatus::Open;
+        order.reduce_only = params.reduce_only;
+        order.post_only = params.post_only;
+        order.created_at = clock.unix_timestamp;
+        order.expires_at = expires_at;
+        order.updated_at = clock.unix_timestamp;
+        order.bump = ctx.bumps.order;
+        order.nonce = params.nonce;
+        order.escrow_bump = ctx.bumps.escrow;
+
+        ctx.accounts
+            .order_book
+            .add_order(params.side, params.price, params.size);
+
+        emit!(OrderPlaced {
+            order: order.key(),
+            owner: order.owner,
+            market: market.key(),
+            side: params.side,
+            price: params.price,
+            size: params.size,
+            time_in_force: params.time_in_force,
+            expires_at,
+            escrow_amount,
+            reduce_only: params.reduce_only,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Cancel an open or partially filled order, returning what is left in
+    /// its escrow to the owner
+    pub fn cancel_order(ctx: Context<CancelOrder>) -> Result<()> {
+        let clock = Clock::get()?;
+        let market_key = ctx.accounts.market.key();
+        let authority_seeds = &[
+            b"pool_authority" as &[u8],
+            market_key.as_ref(),
+            &[ctx.bumps.pool_authority],
+        ];
+        let signer_seeds = &[&authority_seeds[..]];
+
+        let refunded = ctx.accounts.escrow.amount;
+        if refunded > 0 {
+            token::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: ctx.accounts.escrow.to_account_info(),
+                        to: ctx.accounts.owner_token.to_account_info(),
+                        authority: ctx.accounts.pool_authority.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                refunded,
+            )?;
+        }
+        token::close_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            CloseAccount {
+                account: ctx.accounts.escrow.to_account_info(),
+                destination: ctx.accounts.owner.to_account_info(),
+                authority: ctx.accounts.pool_authority.to_account_info(),
+            },
+            signer_seeds,
+        ))?;
+
+        let order = &mut ctx.accounts.order;
+        if order.reduce_only {
+            ctx.accounts
+                .position
+                .as_mut()
+                .ok_or(SecuritiesError::ReduceOnlyViolation)?
+                .release_reduce_only(order.reserved_notional());
+        }
+        order.status = OrderStatus::Cancelled;
+        order.updated_at = clock.unix_timestamp;
+        ctx.accounts
+            .order_book
+            .remove_order(order.side, order.remaining_size);
+
+        emit!(OrderCancelled {
+            order: order.key(),
+            owner: order.owner,
+            market: market_key,
+            remaining_size: order.remaining_size,
+            refunded,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Fill a resting limit order against the pool (permissionless crank).
+    /// Fills as much as the pool can take with an average price at or better
+    /// than the limit; the rest of the order keeps resting. `current_price` is
+    /// the pool spot price the crank observed: the match fails if the pool has
+    /// moved since, so a replayed crank can't fill the order twice. Settles
+    /// like `swap`, under the same oracle guards and trade size limits.
+    pub fn match_order(ctx: Context<MatchOrder>, current_price: u64) -> Result<()> {
+        let clock = Clock::get()?;
+        require_not_shutdown(&ctx.accounts.protocol_state)?;
+        let guard_price = swap_guard_price(
+            &ctx.accounts.market,
+            ctx.accounts.oracle_feed.as_deref(),
+            ctx.accounts.fallback_oracle_feed.as_deref(),
+            clock.unix_timestamp,
+        )?;
+        if trip_auto_pause(
+            &mut ctx.accounts.market,
+            &ctx.accounts.pool,
+            guard_price,
+            clock.unix_timestamp,
+        )? {
+            return Ok(());
+        }
+        let market = &ctx.accounts.market;
+        let pool = &ctx.accounts.pool;
+        let order = &ctx.accounts.order;
+
+        require!(market.is_trading(), SecuritiesError::MarketNotActive);
+        require!(
+            market.is_within_trading_hours(clock.unix_timestamp),
+            SecuritiesError::MarketClosedHours
+        );
+        require!(!order.is_expired(clock.unix_timestamp), SecuritiesError::OrderExpired);
+        require!(
+            pool.has_min_liquidity(market.min_liquidity_to_trade),
+            SecuritiesError::InsufficientLiquidity
+        );
+        let spot_price = pool
+            .get_spot_price()
+            .ok_or(SecuritiesError::InsufficientLiquidity)?;
+        require!(spot_price == current_price, SecuritiesError::MatchPriceMoved);
+        require!(order.can_match(spot_price), SecuritiesError::OrderNotMatchable);
+
+        // A reduce-only order fills no more than the position it reduces
+        // still holds beyond other orders' reservations; if the position has
+        // closed or flipped it can't fill
+        let max_size = if order.reduce_only {
+            let reducible = Order::reducible_size(
+                order.side,
+                order.price,
+                ctx.accounts.position.as_deref(),
+                order.reserved_notional(),
+            );
+            require!(reducible > 0, SecuritiesError::ReduceOnlyViolation);
+            order.remaining_size.min(reducible)
+        } else {
+            order.remaining_size
+        };
+
+        // A sell swaps escrowed security into the pool for quote; a buy swaps
+        // escrowed quote for security
+        let (fill_size, quote_amount, fee) = fill_at_limit(
+            pool,
+            order.side,
+            order.price,
+            max_size,
+            market.trading_fee_bps,
+        )?;
+        require!(fill_size > 0, SecuritiesError::OrderNotMatchable);
+        if order.side == OrderSide::Buy {
+            require!(
+                quote_amount <= ctx.accounts.escrow.amount,
+                SecuritiesError::OrderNotMatchable
+            );
+        }
+        let is_security_input = order.side == OrderSide::Sell;
+        let amount_in = if is_security_input { fill_size } else { quote_amount };
+        require!(market.within_trade_size(amount_in), SecuritiesError::InvalidAmount);
+        let (amount_in, amount_out, to_vault, from_vault) = if is_security_input {
+            (
+                fill_size,
+                quote_amount,
+                ctx.accounts.security_vault.to_account_info(),
+                ctx.accounts.quote_vault.to_account_info(),
+            )
+        } else {
+            (
+                quote_amount,
+                fill_size,
+                ctx.accounts.quote_vault.to_account_info(),
+                ctx.accounts.security_vault.to_account_info(),
+            )
+        };
+
+        let market_key = market.key();
+        let authority_seeds = &[
+            b"pool_authority" as &[u8],
+            market_key.as_ref(),
+            &[pool.authority_bump],
+        ];
+        let signer_seeds = &[&authority_seeds[..]];
+
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.escrow.to_account_info(),
+                    to: to_vault,
+                    authority: ctx.accounts.pool_authority.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            amount_in,
+        )?;
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: from_vault,
+                    to: ctx.accounts.owner_proceeds.to_account_info(),
+                    authority: ctx.accounts.pool_authority.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            amount_out,
+        )?;
+
+        settle_pool_trade(
+            &mut ctx.accounts.market,
+            &mut ctx.accounts.pool,
+            PoolTrade { amount_in, amount_out, fee, is_security_input },
+            guard_price,
+            PoolFeeAccounts {
+                token_program: &ctx.accounts.token_program,
+                pool_authority: ctx.accounts.pool_authority.to_account_info(),
+                quote_vault: ctx.accounts.quote_vault.to_account_info(),
+                insurance_fund: ctx.accounts.insurance_fund.as_deref_mut(),
+                insurance_vault: ctx.accounts.insurance_vault.as_deref().map(|v| v.to_account_info()),
+            },
+            clock.unix_timestamp,
+        )?;
+
+        let fill_price = (quote_amount as u128 * Order::PRICE_PRECISION as u128
+            / fill_size as u128) as u64;
+        let order = &mut ctx.accounts.order;
+        let reserved_before = order.reserved_notional();
+        order.fill(fill_size, fill_price);
+        order.updated_at = clock.unix_timestamp;
+        if let Some(position) = ctx.accounts.position.as_mut() {
+            position.release_reduce_only(reserved_before - order.reserved_notional());
+        }
+        let is_filled = order.status == OrderStatus::Filled;
+        ctx.accounts.order_book.record_fill(
+            order.side,
+            fill_size,
+            fill_price,
+            clock.unix_timestamp,
+            is_filled,
+        );
+
+        // A filled buy may leave price improvement in the escrow: return it
+        // to the owner along with the escrow's rent
+        let mut refunded = 0;
+        if is_filled {
+            refunded = ctx.accounts.escrow.amount.saturating_sub(amount_in);
+            if refunded > 0 {
+                token::transfer(
+                    CpiContext::new_with_signer(
+                        ctx.accounts.token_program.to_account_info(),
+                        Transfer {
+                            from: ctx.accounts.escrow.to_account_info(),
+                            to: ctx.accounts.owner_refund.to_account_info(),
+                            authority: ctx.accounts.pool_authority.to_account_info(),
+                        },
+                        signer_seeds,
+                    ),
+                    refunded,
+                )?;
+            }
+            token::close_account(CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                CloseAccount {
+                    account: ctx.accounts.escrow.to_account_info(),
+                    destination: ctx.accounts.owner.to_account_info(),
+                    authority: ctx.accounts.pool_authority.to_account_info(),
+                },
+                signer_seeds,
+            ))?;
+        }
+
+        emit!(OrderFilled {
+            order: order.key(),
+            owner: order.owner,
+            market: market_key,
+            side: order.side,
+            fill_size,
+            fill_price,
+            filled_size: order.filled_size,
+            avg_fill_price: order.avg_fill_price,
+            remaining_size: order.remaining_size,
+            refunded,
+            matched_by: ctx.accounts.keeper.key(),
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Execute an IOC or FOK order against the pool straight from the owner's
+    /// accounts, without an order account or escrow. IOC fills as much as the
+    /// pool can take at or better than the limit and drops the rest; FOK fails
+    /// with `FillOrKillFailed` unless the whole size fills. Settles like
+    /// `swap`, under the same oracle guards and trade size limits.
+    pub fn place_immediate_order(
+        ctx: Context<PlaceImmediateOrder>,
+        params: ImmediateOrderParams,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        require_not_shutdown(&ctx.accounts.protocol_state)?;
+        let guard_price = swap_guard_price(
+            &ctx.accounts.market,
+            ctx.accounts.oracle_feed.as_deref(),
+            ctx.accounts.fallback_oracle_feed.as_deref(),
+            clock.unix_timestamp,
+        )?;
+        if trip_auto_pause(
+            &mut ctx.accounts.market,
+            &ctx.accounts.pool,
+            guard_price,
+            clock.unix_timestamp,
+        )? {
+            return Ok(());
+        }
+        let market = &ctx.accounts.market;
+        let pool = &ctx.accounts.pool;
+
+        require!(market.is_trading(), SecuritiesError::MarketNotActive);
+        require!(
+            market.is_within_trading_hours(clock.unix_timestamp),
+            SecuritiesError::MarketClosedHours
+        );
+        require!(params.price > 0 && params.size > 0, SecuritiesError::InvalidOrder);
+        require!(
+            matches!(params.time_in_force, TimeInForce::Ioc | TimeInForce::Fok),
+            SecuritiesError::InvalidOrder
+        );
+        require!(
+            pool.has_min_liquidity(market.min_liquidity_to_trade),
+            SecuritiesError::InsufficientLiquidity
+        );
+
+        let (fill_size, quote_amount, fee) = fill_at_limit(
+            pool,
+            params.side,
+            params.price,
+            params.size,
+            market.trading_fee_bps,
+        )?;
+        if params.time_in_force == TimeInForce::Fok {
+            require!(fill_size == params.size, SecuritiesError::FillOrKillFailed);
+        }
+
+        let market_key = market.key();
+        let mut fill_price = 0;
+        if fill_size > 0 {
+            let is_security_input = params.side == OrderSide::Sell;
+            let amount_in = if is_security_input { fill_size } else { quote_amount };
+            require!(market.within_trade_size(amount_in), SecuritiesError::InvalidAmount);
+            let (amount_in, amount_out, from_account, to_vault, from_vault, to_account) =
+                if is_security_input {
+                    (
+                        fill_size,
+                        quote_amount,
+                        ctx.accounts.owner_security.to_account_info(),
+                        ctx.accounts.security_vault.to_account_info(),
+                        ctx.accounts.quote_vault.to_account_info(),
+                        ctx.accounts.owner_quote.to_account_info(),
+                    )
+                } else {
+                    (
+                        quote_amount,
+                        fill_size,
+                        ctx.accounts.owner_quote.to_account_info(),
+                        ctx.accounts.quote_vault.to_account_info(),
+                        ctx.accounts.security_vault.to_account_info(),
+                        ctx.accounts.owner_security.to_account_info(),
+                    )
+                };
+
+            token::transfer(
+                CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: from_account,
+                        to: to_vault,
+                        authority: ctx.accounts.owner.to_account_info(),
+                    },
+                ),
+                amount_in,
+            )?;
+
+            let authority_seeds = &[
+                b"pool_authority" as &[u8],
+                market_key.as_ref(),
+                &[pool.authority_bump],
+            ];
+            let signer_seeds = &[&authority_seeds[..]];
+            token::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: from_vault,
+                        to: to_account,
+                        authority: ctx.accounts.pool_authority.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                amount_out,
+            )?;
+
+            settle_pool_trade(
+                &mut ctx.accounts.market,
+                &mut ctx.accounts.pool,
+                PoolTrade { amount_in, amount_out, fee, is_security_input },
+                guard_price,
+                PoolFeeAccounts {
+                    token_program: &ctx.accounts.token_program,
+                    pool_authority: ctx.accounts.pool_authority.to_account_info(),
+                    quote_vault: ctx.accounts.quote_vault.to_account_info(),
+                    insurance_fund: ctx.accounts.insurance_fund.as_deref_mut(),
+                    insurance_vault: ctx.accounts.insurance_vault.as_deref().map(|v| v.to_account_info()),
+                },
+                clock.unix_timestamp,
+            )?;
+
+            fill_price = (quote_amount as u128 * Order::PRICE_PRECISION as u128
+                / fill_size as u128) as u64;
+            ctx.accounts
+                .order_book
+                .record_trade(fill_price, clock.unix_timestamp);
+        }
+
+        emit!(ImmediateOrderExecuted {
+            owner: ctx.accounts.owner.key(),
+            market: market_key,
+            side: params.side,
+            time_in_force: params.time_in_force,
+            price: params.price,
+            size: params.size,
+            fill_size,
+            fill_price,
+            cancelled_size: params.size - fill_size,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Open a perpetual position
+    pub fn open_position(
+        ctx: Context<OpenPosition>,
+        params: OpenPositionParams,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        require_not_shutdown(&ctx.accounts.protocol_state)?;
+        let market = &ctx.accounts.market;
+
+        // The PDA outlives a closed position and is reused for the next one
+        require!(!ctx.accounts.position.is_open, SecuritiesError::PositionAlreadyOpen);
+        require!(market.is_trading(), SecuritiesError::MarketNotActive);
+        require!(
+            market.is_within_trading_hours(clock.unix_timestamp),
+            SecuritiesError::MarketClosedHours
+        );
+        require!(params.leverage >= 1 && params.leverage <= 100, SecuritiesError::InvalidLeverage);
+
+        let required_collateral = params.size / params.leverage as u64;
+        require!(params.collateral >= required_collateral, SecuritiesError::InsufficientCollateral);
+
+        // Positions enter at the oracle mark, never a caller-chosen price
+        let entry_price = mark_price_or_failover(
+            market,
+            &ctx.accounts.oracle_feed,
+            ctx.accounts.fallback_oracle_feed.as_deref(),
+            clock.unix_timestamp,
+        )?;
+        // Don't open into a degraded feed
+        if market.max_confidence_bps > 0 {
+            market.check_confidence(&ctx.accounts.oracle_feed)?;
+        }
+
+        // Transfer collateral, crediting only what the vault actually received
+        // (a Token-2022 quote may withhold a transfer fee)
+        let vault_before = ctx.accounts.collateral_vault.amount;
+        token_interface::transfer_checked(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.user_quote.to_account_info(),
+                    mint: ctx.accounts.quote_mint.to_account_info(),
+                    to: ctx.accounts.collateral_vault.to_account_info(),
+                    authority: ctx.accounts.user.to_account_info(),
+                },
+            ),
+            params.collateral,
+            ctx.accounts.quote_mint.decimals,
+        )?;
+        ctx.accounts.collateral_vault.reload()?;
+        let received = ctx.accounts.collateral_vault.amount.saturating_sub(vault_before);
+        require!(received >= required_collateral, SecuritiesError::InsufficientCollateral);
+
+        // Initialize position, resetting everything a previous one left behind
+        let position = &mut ctx.accounts.position;
+        position.owner = ctx.accounts.user.key();
+        position.market = market.key();
+        position.position_type = params.position_type;
+        position.side = params.side;
+        position.size = params.size;
+        position.entry_price = entry_price;
+        position.leverage = params.leverage;
+        position.collateral = received;
+        position.unrealized_pnl = 0;
+        position.accumulated_funding = 0;
+        position.last_funding_update = clock.unix_timestamp;
+        position.liquidation_price = position.calculate_liquidation_price();
+        position.take_profit = params.take_profit;
+        position.stop_loss = params.stop_loss;
+        position.is_open = true;
+        position.created_at = clock.unix_timestamp;
+        position.updated_at = clock.unix_timestamp;
+        position.bump = ctx.bumps.position;
+        position.adl_count = 0;
+        position.last_adl_at = 0;
+        position.settled_at_shutdown = false;
+
+        emit!(PositionOpened {
+            position: position.key(),
+            owner: ctx.accounts.user.key(),
+            market: market.key(),
+            side: params.side,
+            size: params.size,
+            entry_price,
+            leverage: params.leverage,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Close `size` of a position at the oracle mark price. Realized PnL and the
+    /// pro-rata share of collateral are paid out of the collateral vault, less
+    /// `close_fee_bps` of the closed notional, which moves to the insurance
+    /// vault. A loss beyond the collateral is covered from the insurance vault
+    /// as far as the fund allows.
+    pub fn close_position(ctx: Context<ClosePosition>, size: u64) -> Result<()> {
+        let clock = Clock::get()?;
+        let market = &ctx.accounts.market;
+        let position = &mut ctx.accounts.position;
+
+        require!(market.accepts_closes(), SecuritiesError::MarketNotActive);
+        require!(size > 0 && size <= position.size, SecuritiesError::InvalidAmount);
+        let exit_price = mark_price_or_failover(
+            market,
+            &ctx.accounts.oracle_feed,
+            ctx.accounts.fallback_oracle_feed.as_deref(),
+            clock.unix_timestamp,
+        )?;
+
+        // Margin backing the part of the position that stays open
+        let retained = (position.collateral as u128 * (position.size - size) as u128
+            / position.size as u128) as u64;
+        let collateral_before = position.collateral;
+        let realized_pnl = position.realize(exit_price, size);
+        let payout = position.collateral.saturating_sub(retained);
+        // A loss beyond the closed slice's margin comes out of the retained
+        // margin; a loss beyond all of it is bad debt the insurance fund absorbs
+        let shortfall = (-(collateral_before as i128 + realized_pnl)).clamp(0, u64::MAX as i128) as u64;
+        position.collateral = retained.min(position.collateral);
+        if position.is_open {
+            position.liquidation_price = position.calculate_liquidation_price();
+        }
+        position.updated_at = clock.unix_timestamp;
+
+        let close_fee = market.close_fee(size, payout);
+        if close_fee > 0 || shortfall > 0 {
+            let fund = ctx
+                .accounts
+                .insurance_fund
+                .as_mut()
+                .ok_or(SecuritiesError::InsuranceFundRequired)?;
+            let insurance_vault = ctx
+                .accounts
+                .insurance_vault
+                .as_ref()
+                .ok_or(SecuritiesError::InsuranceFundRequired)?;
+            credit_insurance(fund, close_fee, InsuranceFlow::CloseFee, clock.unix_timestamp);
+            let covered = shortfall.min(fund.balance);
+            debit_insurance(fund, covered, InsuranceFlow::Shortfall, clock.unix_timestamp);
+
+            transfer_pool_funds(
+                &ctx.accounts.token_program,
+                ctx.accounts.collateral_vault.to_account_info(),
+                insurance_vault.to_account_info(),
+                ctx.accounts.pool_authority.to_account_info(),
+                market.key(),
+                ctx.bumps.pool_authority,
+                close_fee,
+            )?;
+            transfer_pool_funds(
+                &ctx.accounts.token_program,
+                insurance_vault.to_account_info(),
+                ctx.accounts.collateral_vault.to_account_info(),
+                ctx.accounts.pool_authority.to_account_info(),
+                market.key(),
+                ctx.bumps.pool_authority,
+                covered,
+            )?;
+        }
+
+        let net_payout = payout - close_fee;
+        if net_payout > 0 {
+            let market_key = market.key();
+            let authority_seeds = &[
+                b"pool_authority" as &[u8],
+                market_key.as_ref(),
+                &[ctx.bumps.pool_authority],
+            ];
+            token::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: ctx.accounts.collateral_vault.to_account_info(),
+                        to: ctx.accounts.user_quote.to_account_info(),
+                        authority: ctx.accounts.pool_authority.to_account_info(),
+                    },
+                    &[authority_seeds],
+                ),
+                net_payout,
+            )?;
+        }
+
+        emit!(PositionClosed {
+            position: position.key(),
+            owner: position.owner,
+            market: market.key(),
+            closed_size: size,
+            exit_price,
+            realized_pnl: realized_pnl.clamp(i64::MIN as i128, i64::MAX as i128) as i64,
+            close_fee,
+            payout: net_payout,
+            shortfall,
+            remaining_size: position.size,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Resize a position in place at the oracle mark price. `add_collateral`
+    /// is deposited first, then `reduce_size` is closed (its prorated PnL
+    /// credited to collateral, which stays in the position) and `add_size` is
+    /// opened, blending the entry price. The result must stay within 1-100x
+    /// leverage (rounded up, so an over-collateralized position reads 1x) and
+    /// clear maintenance margin; use `close_position` to take collateral out.
+    pub fn adjust_position(ctx: Context<AdjustPosition>, params: AdjustPositionParams) -> Result<()> {
+        let clock = Clock::get()?;
+        require_not_shutdown(&ctx.accounts.protocol_state)?;
+        let market = &ctx.accounts.market;
+
+        require!(market.accepts_closes(), SecuritiesError::MarketNotActive);
+        require!(
+            params.add_collateral > 0 || params.add_size > 0 || params.reduce_size > 0,
+            SecuritiesError::InvalidAmount
+        );
+        require!(
+            params.reduce_size < ctx.accounts.position.size,
+            SecuritiesError::InvalidAmount
+        );
+        let mark_price = mark_price_or_failover(
+            market,
+            &ctx.accounts.oracle_feed,
+            ctx.accounts.fallback_oracle_feed.as_deref(),
+            clock.unix_timestamp,
+        )?;
+        // Adding exposure is held to the same checks as opening it
+        if params.add_size > 0 {
+            require!(market.is_trading(), SecuritiesError::MarketNotActive);
+            require!(
+                market.is_within_trading_hours(clock.unix_timestamp),
+                SecuritiesError::MarketClosedHours
+            );
+            if market.max_confidence_bps > 0 {
+                market.check_confidence(&ctx.accounts.oracle_feed)?;
+            }
+        }
+
+        // Credit only what the vault actually received (a Token-2022 quote
+        // may withhold a transfer fee)
+        let mut added_collateral = 0;
+        if params.add_collateral > 0 {
+            let vault_before = ctx.accounts.collateral_vault.amount;
+            token_interface::transfer_checked(
+                CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    TransferChecked {
+                        from: ctx.accounts.user_quote.to_account_info(),
+                        mint: ctx.accounts.quote_mint.to_account_info(),
+                        to: ctx.accounts.collateral_vault.to_account_info(),
+                        authority: ctx.accounts.user.to_account_info(),
+                    },
+                ),
+                params.add_collateral,
+                ctx.accounts.quote_mint.decimals,
+            )?;
+            ctx.accounts.collateral_vault.reload()?;
+            added_collateral = ctx.accounts.collateral_vault.amount.saturating_sub(vault_before);
+        }
+
+        let position = &mut ctx.accounts.position;
+        position.collateral = position.collateral.saturating_add(added_collateral);
+        let realized_pnl = position.realize(mark_price, params.reduce_size);
+        if params.add_size > 0 {
+            position
+                .increase(mark_price, params.add_size)
+                .ok_or(SecuritiesError::MathOverflow)?;
+        }
+
+        let leverage = position.current_leverage().ok_or(SecuritiesError::InvalidLeverage)?;
+        require!((1..=100).contains(&leverage), SecuritiesError::InvalidLeverage);
+        require!(
+            !position.is_liquidatable(mark_price),
+            SecuritiesError::InsufficientCollateral
+        );
+        position.leverage = leverage as u8;
+        position.liquidation_price = position.calculate_liquidation_price();
+        position.updated_at = clock.unix_timestamp;
+
+        emit!(PositionAdjusted {
+            position: position.key(),
+            owner: position.owner,
+            market: market.key(),
+            added_collateral,
+            added_size: params.add_size,
+            reduced_size: params.reduce_size,
+            mark_price,
+            realized_pnl: realized_pnl.clamp(i64::MIN as i128, i64::MAX as i128) as i64,
+            size: position.size,
+            collateral: position.collateral,
+            entry_price: position.entry_price,
+            leverage: position.leverage,
+            liquidation_price: position.liquidation_price,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Close a whole position whose take-profit or stop-loss the oracle mark
+    /// has crossed (keeper crank). Settles like `close_position`: PnL is
+    /// realized at the mark and the collateral, less the close fee, is paid to
+    /// the owner.
+    pub fn execute_trigger(ctx: Context<ExecuteTrigger>) -> Result<()> {
+        let clock = Clock::get()?;
+        let market = &ctx.accounts.market;
+        let position = &mut ctx.accounts.position;
+
+        require!(market.accepts_closes(), SecuritiesError::MarketNotActive);
+        let mark_price = mark_price_or_failover(
+            market,
+            &ctx.accounts.oracle_feed,
+            ctx.accounts.fallback_oracle_feed.as_deref(),
+            clock.unix_timestamp,
+        )?;
+        require!(position.has_trigger(), SecuritiesError::NoTriggerSet);
+        let trigger = position
+            .crossed_trigger(mark_price)
+            .ok_or(SecuritiesError::TriggerNotReached)?;
+        let trigger_price = match trigger {
+            TriggerType::TakeProfit => position.take_profit,
+            TriggerType::StopLoss => position.stop_loss,
+        };
+
+        let closed_size = position.size;
+        let realized_pnl = position.realize(mark_price, closed_size);
+        let payout = position.collateral;
+        position.collateral = 0;
+        position.updated_at = clock.unix_timestamp;
+
+        let close_fee = market.close_fee(closed_size, payout);
+        if close_fee > 0 {
+            let fund = ctx
+                .accounts
+                .insurance_fund
+                .as_mut()
+                .ok_or(SecuritiesError::InsuranceFundRequired)?;
+            let insurance_vault = ctx
+                .accounts
+                .insurance_vault
+                .as_ref()
+                .ok_or(SecuritiesError::InsuranceFundRequired)?;
+            credit_insurance(fund, close_fee, InsuranceFlow::CloseFee, clock.unix_timestamp);
+            transfer_pool_funds(
+                &ctx.accounts.token_program,
+                ctx.accounts.collateral_vault.to_account_info(),
+                insurance_vault.to_account_info(),
+                ctx.accounts.pool_authority.to_account_info(),
+                market.key(),
+                ctx.bumps.pool_authority,
+                close_fee,
+            )?;
+        }
+
+        let net_payout = payout - close_fee;
+        if net_payout > 0 {
+            let market_key = market.key();
+            let authority_seeds = &[
+                b"pool_authority" as &[u8],
+                market_key.as_ref(),
+                &[ctx.bumps.pool_authority],
+            ];
+            token::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: ctx.accounts.collateral_vault.to_account_info(),
+                        to: ctx.accounts.owner_quote.to_account_info(),
+                        authority: ctx.accounts.pool_authority.to_account_info(),
+                    },
+                    &[authority_seeds],
+                ),
+                net_payout,
+            )?;
+        }
+
+        emit!(TriggerExecuted {
+            position: position.key(),
+            owner: position.owner,
+            market: market.key(),
+            keeper: ctx.accounts.keeper.key(),
+            trigger,
+            trigger_price,
+            exit_price: mark_price,
+            closed_size,
+            realized_pnl: realized_pnl.clamp(i64::MIN as i128, i64::MAX as i128) as i64,
+            close_fee,
+            payout: net_payout,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Read whether a position is liquidatable at the oracle mark price, with
+    /// its margin ratio and the estimated liquidator reward
+    pub fn check_liquidatable(ctx: Context<CheckLiquidatable>) -> Result<LiquidationStatus> {
+        let clock = Clock::get()?;
+        let market = &ctx.accounts.market;
+        let (mark_price, _) = market.mark_price_with_fallback(
+            &ctx.accounts.oracle_feed,
+            ctx.accounts.fallback_oracle_feed.as_deref(),
+            clock.unix_timestamp,
+        )?;
+
+        Ok(ctx
+            .accounts
+            .position
+            .liquidation_status(mark_price, market.liquidation_bounty_bps))
+    }
+
+    /// Liquidate an undercollateralized position at the oracle mark (keeper
+    /// crank). The keeper earns `liquidation_bounty_bps` of the remaining
+    /// equity; the rest moves to the insurance vault.
+    pub fn liquidate_position(ctx: Context<LiquidatePosition>) -> Result<()> {
+        let clock = Clock::get()?;
+        let market = &ctx.accounts.market;
+        let position = &mut ctx.accounts.position;
+
+        require!(market.accepts_closes(), SecuritiesError::MarketNotActive);
+        let mark_price = mark_price_or_failover(
+            market,
+            &ctx.accounts.oracle_feed,
+            ctx.accounts.fallback_oracle_feed.as_deref(),
+            clock.unix_timestamp,
+        )?;
+
+        let status = position.liquidation_status(mark_price, market.liquidation_bounty_bps);
+        require!(status.is_liquidatable, SecuritiesError::NotLiquidatable);
+
+        // Close the whole position; collateral becomes the remaining equity (floored at 0)
+        let closed_size = position.size;
+        position.realize(mark_price, closed_size);
+        let remaining = position.collateral;
+        let bounty = status.estimated_reward.min(remaining);
+        let insurance_amount = remaining - bounty;
+        position.collateral = 0;
+        position.updated_at = clock.unix_timestamp;
+
+        credit_insurance(
+            &mut ctx.accounts.insurance_fund,
+            insurance_amount,
+            InsuranceFlow::Liquidation,
+            clock.unix_timestamp,
+        );
+        transfer_pool_funds(
+            &ctx.accounts.token_program,
+            ctx.accounts.collateral_vault.to_account_info(),
+            ctx.accounts.insurance_vault.to_account_info(),
+            ctx.accounts.pool_authority.to_account_info(),
+            market.key(),
+            ctx.bumps.pool_authority,
+            insurance_amount,
+        )?;
+
+        if bounty > 0 {
+            let market_key = market.key();
+            let authority_seeds = &[
+                b"pool_authority" as &[u8],
+                market_key.as_ref(),
+                &[ctx.bumps.pool_authority],
+            ];
+            token::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: ctx.accounts.collateral_vault.to_account_info(),
+                        to: ctx.accounts.keeper_quote.to_account_info(),
+                        authority: ctx.accounts.pool_authority.to_account_info(),
+                    },
+                    &[authority_seeds],
+                ),
+                bounty,
+            )?;
+        }
+
+        emit!(PositionLiquidated {
+            position: position.key(),
+            owner: position.owner,
+            market: market.key(),
+            keeper: ctx.accounts.keeper.key(),
+            closed_size,
+            mark_price,
+            equity: status.equity,
+            bounty,
+            insurance_amount,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Settle accrued funding on a position at the oracle mark price
+    /// (permissionless crank for keepers). Both feeds must be fresh, and a
+    /// position is charged at most once per clock timestamp.
+    pub fn apply_funding(ctx: Context<ApplyFunding>) -> Result<()> {
+        let clock = Clock::get()?;
+        let market = &ctx.accounts.market;
+        let (mark_price, funding_rate) = market.mark_with_funding(
+            &ctx.accounts.oracle_feed,
+            &ctx.accounts.funding_feed,
+            clock.unix_timestamp,
+        )?;
+
+        let position = &mut ctx.accounts.position;
+        let elapsed = clock.unix_timestamp - position.last_funding_update;
+        require!(elapsed > 0, SecuritiesError::FundingAlreadyApplied);
+        let funding_before = position.accumulated_funding;
+        let charged = position.apply_funding(
+            funding_rate,
+            clock.unix_timestamp,
+            market.max_funding_interval_seconds as i64,
+        );
+        if charged < elapsed {
+            emit!(FundingClamped {
+                market: market.key(),
+                position: position.key(),
+                elapsed_seconds: elapsed,
+                charged_seconds: charged,
+                timestamp: clock.unix_timestamp,
+            });
+        }
+
+        emit!(FundingApplied {
+            market: market.key(),
+            position: position.key(),
+            funding_rate,
+            funding_delta: position.accumulated_funding - funding_before,
+            accumulated_funding: position.accumulated_funding,
+            mark_price,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Read a trader's aggregate notional, collateral and PnL at oracle mark
+    /// prices. Closed positions are skipped.
+    ///
+    /// `remaining_accounts` holds, per position: position, market, oracle
+    /// feed. At most `MAX_SUMMARY_POSITIONS` per call.
+    pub fn get_position_summary<'info>(
+        ctx: Context<'_, '_, 'info, 'info, GetPositionSummary<'info>>,
+    ) -> Result<PositionSummary> {
+        let clock = Clock::get()?;
+        let count = ctx.remaining_accounts.len() / SUMMARY_POSITION_ACCOUNTS;
+        require!(
+            count <= MAX_SUMMARY_POSITIONS
+                && ctx.remaining_accounts.len() == count * SUMMARY_POSITION_ACCOUNTS,
+            SecuritiesError::InvalidPosition
+        );
+
+        let mut summary = PositionSummary::default();
+        for accounts in ctx.remaining_accounts.chunks(SUMMARY_POSITION_ACCOUNTS) {
+            let position: Account<Position> = Account::try_from(&accounts[0])?;
+            let market: Account<Market> = Account::try_from(&accounts[1])?;
+            let feed: Account<PriceFeed> = Account::try_from(&accounts[2])?;
+            require!(
+                position.owner == ctx.accounts.trader.key() && position.market == market.key(),
+                SecuritiesError::InvalidPosition
+            );
+            if !position.is_open {
+                continue;
+            }
+
+            let mark_price = market.mark_price(&feed, clock.unix_timestamp)?;
+            summary.add(&position, mark_price);
+        }
+
+        Ok(summary)
+    }
+
+    /// Attach variance swap terms to an open variance-swap position. Strike
+    /// and notional are checked against `VarianceSwapData::VARIANCE_PRECISION`
+    /// so a mis-scaled strike is rejected rather than settled.
+    pub fn open_variance_swap(
+        ctx: Context<OpenVarianceSwap>,
+        params: VarianceSwapParams,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        require_not_shutdown(&ctx.accounts.protocol_state)?;
+
+        // Room for at least the two observations settlement needs
+        require!(
+            VarianceSwapData::valid_terms(params.strike_variance, params.variance_notional)
+                && params.settlement_date > clock.unix_timestamp
+                && params.observation_interval > 0
+                && params.observation_interval <= params.settlement_date - clock.unix_timestamp,
+            SecuritiesError::InvalidVarianceTerms
+        );
+
+        let variance_swap = &mut ctx.accounts.variance_swap;
+        variance_swap.position = ctx.accounts.position.key();
+        variance_swap.strike_variance = params.strike_variance;
+        variance_swap.realized_variance = 0;
+        variance_swap.observation_count = 0;
+        variance_swap.variance_notional = params.variance_notional;
+        variance_swap.settlement_date = params.settlement_date;
+        variance_swap.is_settled = false;
+        variance_swap.bump = ctx.bumps.variance_swap;
+        variance_swap.last_observed_price = 0;
+        variance_swap.first_observed_at = 0;
+        variance_swap.last_observed_at = 0;
+        variance_swap.sum_squared_returns = 0;
+        variance_swap.observation_interval = params.observation_interval;
+
+        emit!(VarianceSwapOpened {
+            position: variance_swap.position,
+            strike_variance: params.strike_variance,
+            variance_notional: params.variance_notional,
+            settlement_date: params.settlement_date,
+            observation_interval: params.observation_interval,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Quote a variance swap's settlement at a given realized variance
+    /// (scaled by `VarianceSwapData::VARIANCE_PRECISION`)
+    pub fn get_variance_settlement(
+        ctx: Context<GetVarianceSettlement>,
+        realized_variance: u64,
+    ) -> Result<i64> {
+        require!(
+            realized_variance <= VarianceSwapData::MAX_VARIANCE,
+            SecuritiesError::InvalidVarianceTerms
+        );
+        i64::try_from(ctx.accounts.variance_swap.settlement_at(realized_variance))
+            .map_err(|_| SecuritiesError::MathOverflow.into())
+    }
+
+    /// Record the oracle mark as a variance swap observation (keeper crank).
+    /// Observations follow the swap's fixed `observation_interval` cadence, up
+    /// to the settlement date.
+    pub fn add_variance_observation(ctx: Context<AddVarianceObservation>) -> Result<()> {
+        let clock = Clock::get()?;
+        let variance_swap = &mut ctx.accounts.variance_swap;
+
+        let price = mark_price_or_failover(
+            &ctx.accounts.market,
+            &ctx.accounts.oracle_feed,
+            ctx.accounts.fallback_oracle_feed.as_deref(),
+            clock.unix_timestamp,
+        )?;
+        require!(
+            clock.unix_timestamp <= variance_swap.settlement_date
+                && (variance_swap.observation_count == 0
+                    || clock.unix_timestamp >= variance_swap.next_observation_at()),
+            SecuritiesError::InvalidVarianceObservation
+        );
+
+        let log_return = variance_swap.record_observation(price, clock.unix_timestamp);
+
+        emit!(VarianceObservationAdded {
+            position: variance_swap.position,
+            price,
+            log_return: log_return as i64,
+            realized_variance: variance_swap.realized_variance,
+            observation_count: variance_swap.observation_count,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Settle a matured variance swap against the market's insurance fund
+    /// (permissionless). The payout moves between the position's collateral in
+    /// the collateral vault and the insurance vault, and is paid out when the
+    /// position is closed. A loss is capped at the collateral.
+    pub fn settle_variance_swap(ctx: Context<SettleVarianceSwap>) -> Result<()> {
+        let clock = Clock::get()?;
+        let variance_swap = &mut ctx.accounts.variance_swap;
+        let position = &mut ctx.accounts.position;
+        let fund = &mut ctx.accounts.insurance_fund;
+
+        require!(
+            clock.unix_timestamp >= variance_swap.settlement_date,
+            SecuritiesError::VarianceSwapNotMatured
+        );
+        require!(
+            variance_swap.observation_count >= 2,
+            SecuritiesError::InsufficientVarianceObservations
+        );
+
+        let settlement = variance_swap.calculate_settlement();
+        let owner_settlement = match position.side {
+            Side::Long => settlement,
+            Side::Short => -settlement,
+        };
+        let owner_pnl = if owner_settlement >= 0 {
+            let gain = u64::try_from(owner_settlement).map_err(|_| SecuritiesError::MathOverflow)?;
+            require!(fund.can_cover(gain), SecuritiesError::SettlementUnderfunded);
+            debit_insurance(fund, gain, InsuranceFlow::VarianceSwap, clock.unix_timestamp);
+            transfer_pool_funds(
+                &ctx.accounts.token_program,
+                ctx.accounts.insurance_vault.to_account_info(),
+                ctx.accounts.collateral_vault.to_account_info(),
+                ctx.accounts.pool_authority.to_account_info(),
+                ctx.accounts.market.key(),
+                ctx.bumps.pool_authority,
+                gain,
+            )?;
+            position.collateral = position.collateral.saturating_add(gain);
+            gain as i64
+        } else {
+            let loss = u64::try_from(-owner_settlement)
+                .unwrap_or(u64::MAX)
+                .min(position.collateral);
+            position.collateral -= loss;
+            credit_insurance(fund, loss, InsuranceFlow::VarianceSwap, clock.unix_timestamp);
+            transfer_pool_funds(
+                &ctx.accounts.token_program,
+                ctx.accounts.collateral_vault.to_account_info(),
+                ctx.accounts.insurance_vault.to_account_info(),
+                ctx.accounts.pool_authority.to_account_info(),
+                ctx.accounts.market.key(),
+                ctx.bumps.pool_authority,
+                loss,
+            )?;
+            -(loss as i64)
+        };
+        position.updated_at = clock.unix_timestamp;
+        variance_swap.is_settled = true;
+
+        emit!(VarianceSwapSettled {
+            position: position.key(),
+            owner: position.owner,
+            market: ctx.accounts.market.key(),
+            strike_variance: variance_swap.strike_variance,
+            realized_variance: variance_swap.realized_variance,
+            observation_count: variance_swap.observation_count,
+            owner_pnl,
+            collateral: position.collateral,
+            settled_by: ctx.accounts.keeper.key(),
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Record a scheduled session open/close (permissionless crank for keepers)
+    pub fn update_trading_session(ctx: Context<UpdateTradingSession>) -> Result<()> {
+        let clock = Clock::get()?;
+        let market = &mut ctx.accounts.market;
+
+        if market.sync_trading_session(clock.unix_timestamp) {
+            emit!(TradingSessionChanged {
+                market: market.key(),
+                is_open: market.session_open,
+                timestamp: clock.unix_timestamp,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Upgrade a market created under an older layout to `Market::CURRENT_VERSION`
+    /// (market authority only). Grows the account, zero-filling appended fields.
+    pub fn migrate_market(ctx: Context<MigrateMarket>) -> Result<()> {
+        let clock = Clock::get()?;
+        let info = ctx.accounts.market.to_account_info();
+
+        let authority = read_account_authority(&info, Market::DISCRIMINATOR)?;
+        require_keys_eq!(authority, ctx.accounts.authority.key(), SecuritiesError::Unauthorized);
+
+        grow_account(
+            &info,
+            8 + Market::INIT_SPACE,
+            &ctx.accounts.authority,
+            &ctx.accounts.system_program,
+        )?;
+
+        let mut market = Market::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+        let from_version = market.version;
+        require!(from_version < Market::CURRENT_VERSION, SecuritiesError::AlreadyMigrated);
+
+        // Zero is the default for every appended field except `requires_oracle`,
+        // which derivatives markets must not lose
+        if from_version < 7 {
+            market.requires_oracle = market.market_type.requires_oracle();
+        }
+        market.version = Market::CURRENT_VERSION;
+        market.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+
+        emit!(AccountMigrated {
+            account: info.key(),
+            from_version,
+            to_version: Market::CURRENT_VERSION,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Upgrade a pool created under an older layout to `Pool::CURRENT_VERSION`
+    /// (market authority only). Grows the account, zero-filling appended fields.
+    pub fn migrate_pool(ctx: Context<MigratePool>) -> Result<()> {
+        let clock = Clock::get()?;
+        let info = ctx.accounts.pool.to_account_info();
+
+        // A pool's first field is its market rather than an authority
+        let pool_market = read_account_authority(&info, Pool::DISCRIMINATOR)?;
+        require_keys_eq!(pool_market, ctx.accounts.market.key(), SecuritiesError::Unauthorized);
+
+        grow_account(
+            &info,
+            8 + Pool::INIT_SPACE,
+            &ctx.accounts.authority,
+            &ctx.accounts.system_program,
+        )?;
+
+        let mut pool = Pool::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+        let from_version = pool.version;
+        require!(from_version < Pool::CURRENT_VERSION, SecuritiesError::AlreadyMigrated);
+
+        // Open the TWAP window at the last update, so the cumulative price
+        // accrued after it is averaged over the time it actually covers
+        if from_version < 1 {
+            pool.twap_last_price = pool.get_spot_price().unwrap_or(0);
+            pool.twap_window_cumulative = pool.cumulative_price;
+            pool.twap_window_start = pool.twap_last_update;
+        }
+        pool.version = Pool::CURRENT_VERSION;
+        pool.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+
+        emit!(AccountMigrated {
+            account: info.key(),
+            from_version,
+            to_version: Pool::CURRENT_VERSION,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Create the market's insurance fund and its insurance vault (authority only)
+    pub fn initialize_insurance_fund(ctx: Context<InitializeInsuranceFund>) -> Result<()> {
+        let clock = Clock::get()?;
+        let fund = &mut ctx.accounts.insurance_fund;
+
+        fund.market = ctx.accounts.market.key();
+        fund.balance = 0;
+        fund.total_deposits = 0;
+        fund.total_payouts = 0;
+        fund.created_at = clock.unix_timestamp;
+        fund.bump = ctx.bumps.insurance_fund;
+        fund.pending_withdrawal = 0;
+        fund.withdrawal_unlocks_at = 0;
+
+        Ok(())
+    }
+
+    /// Read the quote available in the market's insurance fund
+    pub fn get_insurance_balance(ctx: Context<GetInsuranceBalance>) -> Result<u64> {
+        Ok(ctx.accounts.insurance_fund.balance)
+    }
+
+    /// Queue a withdrawal from the insurance fund (market authority only).
+    /// It can execute after `InsuranceFund::WITHDRAWAL_TIMELOCK_SECONDS`;
+    /// a new request replaces the pending one and restarts the timelock.
+    pub fn request_insurance_withdrawal(
+        ctx: Context<RequestInsuranceWithdrawal>,
+        amount: u64,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        let fund = &mut ctx.accounts.insurance_fund;
+
+        require!(amount > 0, SecuritiesError::InvalidAmount);
+        require!(fund.can_cover(amount), SecuritiesError::InsufficientInsuranceFund);
+        fund.pending_withdrawal = amount;
+        fund.withdrawal_unlocks_at = clock.unix_timestamp + InsuranceFund::WITHDRAWAL_TIMELOCK_SECONDS;
+
+        emit!(InsuranceWithdrawalRequested {
+            fund: fund.key(),
+            market: fund.market,
+            amount,
+            unlocks_at: fund.withdrawal_unlocks_at,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Pay the pending insurance withdrawal out of the insurance vault once
+    /// its timelock has elapsed (market authority only)
+    pub fn withdraw_insurance(ctx: Context<WithdrawInsurance>) -> Result<()> {
+        let clock = Clock::get()?;
+        let fund = &mut ctx.accounts.insurance_fund;
+
+        let amount = fund.pending_withdrawal;
+        require!(amount > 0, SecuritiesError::NoPendingWithdrawal);
+        require!(
+            clock.unix_timestamp >= fund.withdrawal_unlocks_at,
+            SecuritiesError::TimelockActive
+        );
+        // Bad debt may have drawn the fund down since the request
+        require!(fund.can_cover(amount), SecuritiesError::InsufficientInsuranceFund);
+        fund.pending_withdrawal = 0;
+        fund.withdrawal_unlocks_at = 0;
+        debit_insurance(fund, amount, InsuranceFlow::Withdrawal, clock.unix_timestamp);
+
+        transfer_pool_funds(
+            &ctx.accounts.token_program,
+            ctx.accounts.insurance_vault.to_account_info(),
+            ctx.accounts.destination.to_account_info(),
+            ctx.accounts.pool_authority.to_account_info(),
+            ctx.accounts.market.key(),
+            ctx.bumps.pool_authority,
+            amount,
+        )?;
+
+        Ok(())
+    }
+
+    /// Auto-deleverage a bankrupt position against profitable opposing positions
+    /// (keeper crank). Only runs when the insurance fund can't cover the bad debt.
+    /// Counterparty positions are passed via `remaining_accounts`, most profitable
+    /// first, and are reduced at the bankrupt position's bankruptcy price.
+    pub fn auto_deleverage<'info>(
+        ctx: Context<'_, '_, 'info, 'info, AutoDeleverage<'info>>,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        let market = &ctx.accounts.market;
+        let bankrupt = &mut ctx.accounts.bankrupt_position;
+
+        require!(market.adl_enabled, SecuritiesError::AdlDisabled);
+        require!(
+            !ctx.remaining_accounts.is_empty()
+                && ctx.remaining_accounts.len() <= MAX_ADL_COUNTERPARTIES,
+            SecuritiesError::InsufficientAdlCounterparties
+        );
+
+        let mark_price = market.mark_price(&ctx.accounts.oracle_feed, clock.unix_timestamp)?;
+        let equity = bankrupt.equity(mark_price);
+        require!(equity < 0, SecuritiesError::NoBadDebt);
+        let bad_debt = u64::try_from(-equity).map_err(|_| SecuritiesError::MathOverflow)?;
+        require!(
+            !ctx.accounts.insurance_fund.can_cover(bad_debt),
+            SecuritiesError::InsuranceFundSufficient
+        );
+
+        let bankruptcy_price = bankrupt.bankruptcy_price();
+        let mut remaining_size = bankrupt.size;
+        let mut previous_score = i128::MAX;
+
+        for info in ctx.remaining_accounts.iter() {
+            if remaining_size == 0 {
+                break;
+            }
+
+            let mut counterparty: Account<Position> = Account::try_from(info)?;
+            require!(
+                counterparty.market == market.key()
+                    && counterparty.is_open
+                    && counterparty.side != bankrupt.side
+                    && counterparty.calculate_pnl(bankruptcy_price) > 0,
+                SecuritiesError::InvalidAdlCounterparty
+            );
+
+            let score = counterparty.adl_score(mark_price);
+            require!(score <= previous_score, SecuritiesError::AdlOrderViolation);
+            previous_score = score;
+
+            let reduced_size = remaining_size.min(counterparty.size);
+            let realized_pnl = counterparty.realize(bankruptcy_price, reduced_size);
+            counterparty.adl_count = counterparty.adl_count.saturating_add(1);
+            counterparty.last_adl_at = clock.unix_timestamp;
+            counterparty.updated_at = clock.unix_timestamp;
+            counterparty.exit(ctx.program_id)?;
+
+            remaining_size -= reduced_size;
+
+            emit!(AutoDeleveraged {
+                market: market.key(),
+                position: info.key(),
+                owner: counterparty.owner,
+                bankrupt_position: bankrupt.key(),
+                reduced_size,
+                bankruptcy_price,
+                realized_pnl: realized_pnl as i64,
+                remaining_size: counterparty.size,
+                timestamp: clock.unix_timestamp,
+            });
+        }
+
+        require!(remaining_size == 0, SecuritiesError::InsufficientAdlCounterparties);
+
+        // Closed at the bankruptcy price: the collateral exactly covers the loss
+        let closed_size = bankrupt.size;
+        bankrupt.realize(bankruptcy_price, closed_size);
+        bankrupt.collateral = 0;
+        bankrupt.updated_at = clock.unix_timestamp;
+
+        Ok(())
+    }
+
+    /// Create the protocol state and name its guardian (program upgrade authority only)
+    pub fn initialize_protocol_state(
+        ctx: Context<InitializeProtocolState>,
+        guardian: Pubkey,
+    ) -> Result<()> {
+        let state = &mut ctx.accounts.protocol_state;
+        state.guardian = guardian;
+        state.is_shutdown = false;
+        state.shutdown_at = 0;
+        state.bump = ctx.bumps.protocol_state;
+
+        Ok(())
+    }
+
+    /// Freeze new trading, liquidity and positions across every market
+    /// (guardian only). Irreversible: open positions wind down through
+    /// `settle_at_shutdown`.
+    pub fn trigger_emergency_shutdown(ctx: Context<TriggerEmergencyShutdown>) -> Result<()> {
+        let clock = Clock::get()?;
+        let state = &mut ctx.accounts.protocol_state;
+
+        require!(!state.is_shutdown, SecuritiesError::ProtocolShutdown);
+        state.is_shutdown = true;
+        state.shutdown_at = clock.unix_timestamp;
+
+        emit!(EmergencyShutdownTriggered {
+            guardian: ctx.accounts.guardian.key(),
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Fix the price a market's open positions settle at after shutdown
+    /// (guardian only, once per market)
+    pub fn set_shutdown_settlement_price(
+        ctx: Context<SetShutdownSettlementPrice>,
+        settlement_price: u64,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        let market = &mut ctx.accounts.market;
+
+        require!(settlement_price > 0, SecuritiesError::InvalidAmount);
+        require!(
+            market.shutdown_settlement_price == 0,
+            SecuritiesError::SettlementPriceAlreadySet
+        );
+        market.shutdown_settlement_price = settlement_price;
+
+        emit!(ShutdownSettlementPriceSet {
+            market: market.key(),
+            settlement_price,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Close an open position at its market's shutdown settlement price and
+    /// return the remaining collateral to the owner. Permissionless, so no
+    /// position is left stranded. Variance swaps have no price exposure and
+    /// settle at entry, returning their collateral.
+    pub fn settle_at_shutdown(ctx: Context<SettleAtShutdown>) -> Result<()> {
+        let clock = Clock::get()?;
+        let market = &ctx.accounts.market;
+        let position = &mut ctx.accounts.position;
+
+        let settlement_price = market.shutdown_settlement_price;
+        require!(settlement_price > 0, SecuritiesError::SettlementPriceNotSet);
+        let exit_price = if position.position_type == PositionType::VarianceSwap {
+            position.entry_price
+        } else {
+            settlement_price
+        };
+
+        let closed_size = position.size;
+        let realized_pnl = position.realize(exit_price, closed_size);
+        let payout = position.collateral;
+        position.collateral = 0;
+        position.settled_at_shutdown = true;
+        position.updated_at = clock.unix_timestamp;
+
+        if payout > 0 {
+            let market_key = market.key();
+            let authority_seeds = &[
+                b"pool_authority" as &[u8],
+                market_key.as_ref(),
+                &[ctx.bumps.pool_authority],
+            ];
+            token::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: ctx.accounts.collateral_vault.to_account_info(),
+                        to: ctx.accounts.owner_quote.to_account_info(),
+                        authority: ctx.accounts.pool_authority.to_account_info(),
+                    },
+                    &[authority_seeds],
+                ),
+                payout,
+            )?;
+        }
+
+        emit!(SettledAtShutdown {
+            position: position.key(),
+            owner: position.owner,
+            market: market.key(),
+            settlement_price: exit_price,
+            closed_size,
+            realized_pnl: realized_pnl.clamp(i64::MIN as i128, i64::MAX as i128) as i64,
+            payout,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+/// Maximum counterparty positions per `auto_deleverage` call (account limits)
+pub const MAX_ADL_COUNTERPARTIES: usize = 5;
+
+/// Maximum pools per `route_swap` call (account limits)
+pub const MAX_ROUTE_LEGS: usize = 4;
+
+/// Remaining accounts per `route_swap` leg
+pub const ROUTE_LEG_ACCOUNTS: usize = 7;
+
+/// Maximum positions per `get_position_summary` call. Three accounts each
+/// keeps a full call within the legacy transaction size limit; page larger
+/// books across calls.
+pub const MAX_SUMMARY_POSITIONS: usize = 10;
+
+/// Remaining accounts per `get_position_summary` position
+pub const SUMMARY_POSITION_ACCOUNTS: usize = 3;
+
+/// Mark price for `market`, failing over to its fallback oracle when the
+/// primary is stale. Emits `OracleFailover` whenever the fallback is used.
+fn mark_price_or_failover(
+    market: &Account<Market>,
+    primary: &Account<PriceFeed>,
+    fallback: Option<&Account<PriceFeed>>,
+    current_time: i64,
+) -> Result<u64> {
+    let (price, failed_over) = market.mark_price_with_fallback(primary, fallback, current_time)?;
+    if failed_over {
+        emit!(OracleFailover {
+            market: market.key(),
+            primary_oracle: market.oracle,
+            primary_last_update: primary.last_update_time,
+            fallback_oracle: market.fallback_oracle.unwrap_or_default(),
+            price,
+            timestamp: current_time,
+        });
+    }
+    Ok(price)
+}
+
+/// Largest fill of up to `max_size` the pool can give a `side` order at
+/// `limit_price`, as `(size, quote, fee)`: a sell swaps `size` of security for
+/// `quote`, a buy pays `quote` for `size`. All zero when nothing fills.
+fn fill_at_limit(
+    pool: &Pool,
+    side: OrderSide,
+    limit_price: u64,
+    max_size: u64,
+    fee_bps: u16,
+) -> Result<(u64, u64, u64)> {
+    match side {
+        OrderSide::Sell => {
+            let size = pool.max_sell_at_limit(limit_price, fee_bps).min(max_size);
+            if size == 0 {
+                return Ok((0, 0, 0));
+            }
+            let (quote_out, fee) = pool
+                .calculate_swap_output(size, true, fee_bps)
+                .ok_or(SecuritiesError::InsufficientLiquidity)?;
+            let limit_quote =
+                (size as u128 * limit_price as u128 / Order::PRICE_PRECISION as u128) as u64;
+            require!(quote_out >= limit_quote, SecuritiesError::OrderNotMatchable);
+            Ok((size, quote_out, fee))
+        }
+        OrderSide::Buy => {
+            let size = pool.max_buy_at_limit(limit_price, fee_bps).min(max_size);
+            if size == 0 {
+                return Ok((0, 0, 0));
+            }
+            let quote_in = pool
+                .quote_input_for(size, fee_bps)
+                .ok_or(SecuritiesError::InsufficientLiquidity)?;
+            let limit_quote =
+                Order::quote_for(size, limit_price).ok_or(SecuritiesError::MathOverflow)?;
+            require!(quote_in <= limit_quote, SecuritiesError::OrderNotMatchable);
+            let fee = (quote_in as u128 * fee_bps as u128 / 10000) as u64;
+            Ok((size, quote_in, fee))
+        }
+    }
+}
+
+/// Amounts a trade moved through a pool's vaults, before fees are booked
+struct PoolTrade {
+    amount_in: u64,
+    amount_out: u64,
+    fee: u64,
+    is_security_input: bool,
+}
+
+/// Accounts a pool trade routes its protocol fee through. The insurance fund
+/// and vault are required when the market sets `target_insurance_balance`.
+struct PoolFeeAccounts<'a, 'info> {
+    token_program: &'a Program<'info, Token>,
+    pool_authority: AccountInfo<'info>,
+    quote_vault: AccountInfo<'info>,
+    insurance_fund: Option<&'a mut Account<'info, InsuranceFund>>,
+    insurance_vault: Option<AccountInfo<'info>>,
+}
+
+/// Oracle price a pool trade is guarded by: needed for `requires_oracle`,
+/// auto-pause and the deviation guard, `None` when the market uses none of
+/// them. A stale primary fails over to the market's fallback feed.
+fn swap_guard_price(
+    market: &Account<Market>,
+    oracle_feed: Option<&Account<PriceFeed>>,
+    fallback_oracle_feed: Option<&Account<PriceFeed>>,
+    timestamp: i64,
+) -> Result<Option<u64>> {
+    let auto_pause = market.auto_pause_enabled && market.is_trading();
+    if !(market.requires_oracle || auto_pause || market.rejects_oracle_deviation()) {
+        return Ok(None);
+    }
+    let feed = oracle_feed.ok_or(if market.requires_oracle {
+        SecuritiesError::StalePriceFeed
+    } else {
+        SecuritiesError::OracleMismatch
+    })?;
+    mark_price_or_failover(market, feed, fallback_oracle_feed, timestamp).map(Some)
+}
+
+/// Pause an auto-pause market whose pool has drifted beyond
+/// `max_oracle_deviation_bps` of `guard_price`, emitting `MarketAutoPaused`.
+/// Returns true when it paused; the caller then returns Ok without trading so
+/// the pause persists.
+fn trip_auto_pause(
+    market: &mut Account<Market>,
+    pool: &Pool,
+    guard_price: Option<u64>,
+    timestamp: i64,
+) -> Result<bool> {
+    let auto_pause = market.auto_pause_enabled && market.is_trading();
+    let (true, Some(oracle_price)) = (auto_pause, guard_price) else {
+        return Ok(false);
+    };
+    let spot_price = pool.get_spot_price().ok_or(SecuritiesError::InsufficientLiquidity)?;
+    let deviation_bps = Market::oracle_deviation_bps(spot_price, oracle_price);
+    if deviation_bps <= market.max_oracle_deviation_bps as u64 {
+        return Ok(false);
+    }
+
+    market.status = MarketStatus::Paused;
+    emit!(MarketAutoPaused {
+        market: market.key(),
+        spot_price,
+        oracle_price,
+        deviation_bps,
+        timestamp,
+    });
+    Ok(true)
+}
+
+/// Book a trade whose tokens already moved through the pool's vaults: apply
+/// it to the reserves less the protocol share of the fee, refresh the TWAP,
+/// reject a pool left beyond the oracle deviation limit, record volume, and
+/// split the protocol fee between the insurance vault and the treasury.
+/// Shared by every instruction that trades against a pool.
+fn settle_pool_trade<'info>(
+    market: &mut Account<'info, Market>,
+    pool: &mut Account<'info, Pool>,
+    trade: PoolTrade,
+    guard_price: Option<u64>,
+    fee_accounts: PoolFeeAccounts<'_, 'info>,
+    timestamp: i64,
+) -> Result<()> {
+    let PoolTrade { amount_in, amount_out, fee, is_security_input } = trade;
+
+    // The protocol share of the fee leaves the reserves
+    let protocol_fee = market.calculate_protocol_fee(fee);
+    pool.apply_swap(amount_in, amount_out, fee - protocol_fee, is_security_input);
+    let protocol_fee_quote = pool
+        .take_protocol_fee(protocol_fee, is_security_input)
+        .ok_or(SecuritiesError::InsufficientLiquidity)?;
+    update_pool_twap(pool, timestamp);
+
+    // Reject trades that leave the pool too far from fair value
+    if let (true, Some(oracle_price)) = (market.rejects_oracle_deviation(), guard_price) {
+        let spot_price = pool.get_spot_price().ok_or(SecuritiesError::InsufficientLiquidity)?;
+        require!(
+            Market::oracle_deviation_bps(spot_price, oracle_price)
+                <= market.max_oracle_deviation_bps as u64,
+            SecuritiesError::PriceDeviationExceeded
+        );
+    }
+
+    let volume = if is_security_input { amount_out } else { amount_in };
+    market.update_volume(volume, timestamp);
+    market.total_fees = market.total_fees.saturating_add(fee);
+
+    // Split the protocol share between the insurance fund and treasury
+    let mut treasury_fee = protocol_fee_quote;
+    if market.target_insurance_balance > 0 {
+        let (Some(fund), Some(insurance_vault)) =
+            (fee_accounts.insurance_fund, fee_accounts.insurance_vault)
+        else {
+            return err!(SecuritiesError::InsuranceFundRequired);
+        };
+        let routing_bps = market.insurance_routing_bps(fund.balance);
+        let insurance_amount =
+            (protocol_fee_quote as u128 * routing_bps as u128 / 10000) as u64;
+        let treasury_amount = protocol_fee_quote - insurance_amount;
+
+        // The insurance share leaves the pool's quote vault
+        transfer_pool_funds(
+            fee_accounts.token_program,
+            fee_accounts.quote_vault,
+            insurance_vault,
+            fee_accounts.pool_authority,
+            market.key(),
+            pool.authority_bump,
+            insurance_amount,
+        )?;
+        credit_insurance(fund, insurance_amount, InsuranceFlow::SwapFee, timestamp);
+        market.treasury_fees_accrued = market.treasury_fees_accrued.saturating_add(treasury_amount);
+        treasury_fee = treasury_amount;
+
+        emit!(ProtocolFeeRouted {
+            market: market.key(),
+            protocol_fee: protocol_fee_quote,
+            routing_bps,
+            insurance_amount,
+            treasury_amount,
+            insurance_balance: fund.balance,
+            timestamp,
+        });
+    }
+    market.accumulated_protocol_fees = market.accumulated_protocol_fees.saturating_add(treasury_fee);
+
+    Ok(())
+}
+
+/// Update the pool TWAP after its reserves changed, emitting `TwapUpdated`
+/// when time has elapsed since the last update
+fn update_pool_twap(pool: &mut Account<Pool>, timestamp: i64) {
+    if !pool.update_twap(timestamp) {
+        return;
+    }
+    emit!(TwapUpdated {
+        pool: pool.key(),
+        market: pool.market,
+        spot_price: pool.twap_last_price,
+        twap: pool.twap,
+        divergence_bps: pool.price_divergence_bps().unwrap_or(0),
+        timestamp,
+    });
+}
+
+/// Apply a status change if the transition graph allows it, emitting
+/// `MarketStatusChanged`
+fn transition_market_status(
+    market: &mut Account<Market>,
+    new_status: MarketStatus,
+    authority: Pubkey,
+    timestamp: i64,
+) -> Result<()> {
+    let old_status = market.status;
+    require!(
+        old_status.can_transition_to(new_status),
+        SecuritiesError::InvalidStatusTransition
+    );
+    market.status = new_status;
+
+    emit!(MarketStatusChanged {
+        market: market.key(),
+        old_status,
+        new_status,
+        authority,
+        timestamp,
+    });
+    Ok(())
+}
+
+/// Move `amount` between token accounts owned by the market's pool authority
+/// PDA (pool, collateral and insurance vaults), signing as that authority.
+/// Booking the move against an insurance fund is left to the caller.
+fn transfer_pool_funds<'info>(
+    token_program: &Program<'info, Token>,
+    from: AccountInfo<'info>,
+    to: AccountInfo<'info>,
+    pool_authority: AccountInfo<'info>,
+    market: Pubkey,
+    authority_bump: u8,
+    amount: u64,
+) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+    let authority_seeds = &[b"pool_authority" as &[u8], market.as_ref(), &[authority_bump]];
+    token::transfer(
+        CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            Transfer {
+                from,
+                to,
+                authority: pool_authority,
+            },
+            &[authority_seeds],
+        ),
+        amount,
+    )
+}
+
+/// Book `amount` into an insurance fund, emitting `InsuranceFundDeposit`
+fn credit_insurance(fund: &mut Account<InsuranceFund>, amount: u64, source: InsuranceFlow, timestamp: i64) {
+    if amount == 0 {
+        return;
+    }
+    fund.balance = fund.balance.saturating_add(amount);
+    fund.total_deposits = fund.total_deposits.saturating_add(amount);
+    emit!(InsuranceFundDeposit {
+        fund: fund.key(),
+        market: fund.market,
+        amount,
+        source,
+        balance: fund.balance,
+        timestamp,
+    });
+}
+
+/// Book `amount` out of an insurance fund, emitting `InsuranceFundPayout`.
+/// Callers check `can_cover` first.
+fn debit_insurance(fund: &mut Account<InsuranceFund>, amount: u64, reason: InsuranceFlow, timestamp: i64) {
+    if amount == 0 {
+        return;
+    }
+    fund.balance = fund.balance.saturating_sub(amount);
+    fund.total_payouts = fund.total_payouts.saturating_add(amount);
+    emit!(InsuranceFundPayout {
+        fund: fund.key(),
+        market: fund.market,
+        amount,
+        reason,
+        balance: fund.balance,
+        timestamp,
+    });
+}
+
+/// Fail once the guardian has triggered an emergency shutdown. The protocol
+/// state may not be initialized yet, which reads as running.
+fn require_not_shutdown(protocol_state: &UncheckedAccount) -> Result<()> {
+    if protocol_state.data_is_empty() {
+        return Ok(());
+    }
+    require_keys_eq!(*protocol_state.owner, crate::ID, ErrorCode::AccountOwnedByWrongProgram);
+    let state = ProtocolState::try_deserialize(&mut &protocol_state.try_borrow_data()?[..])?;
+    require!(!state.is_shutdown, SecuritiesError::ProtocolShutdown);
+    Ok(())
+}
+
+/// Read the authority (first field) of a raw account after checking its discriminator
+fn read_account_authority(info: &AccountInfo, discriminator: &[u8]) -> Result<Pubkey> {
+    let data = info.try_borrow_data()?;
+    require!(
+        data.len() >= 8 + 32 && &data[..8] == discriminator,
+        ErrorCode::AccountDiscriminatorMismatch
+    );
+    Ok(Pubkey::new_from_array(data[8..40].try_into().unwrap()))
+}
+
+/// Grow an account to `new_len`, topping up rent from `payer`
+fn grow_account<'info>(
+    info: &AccountInfo<'info>,
+    new_len: usize,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+) -> Result<()> {
+    if info.data_len() >= new_len {
+        return Ok(());
+    }
+
+    let shortfall = Rent::get()?.minimum_balance(new_len).saturating_sub(info.lamports());
+    if shortfall > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                system_program.to_account_info(),
+                system_program::Transfer {
+                    from: payer.to_account_info(),
+                    to: info.clone(),
+                },
+            ),
+            shortfall,
+        )?;
+    }
+    info.resize(new_len)?;
+    Ok(())
+}
+
+// Account structs
+#[derive(Accounts)]
+#[instruction(params: InitializeMarketParams)]
+pub struct InitializeMarket<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub security_mint: Account<'info, Mint>,
+    /// SPL Token or Token-2022
+    pub quote_mint: InterfaceAccount<'info, token_interface::Mint>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Market::INIT_SPACE,
+        seeds = [Market::SEED_PREFIX, security_mint.key().as_ref(), quote_mint.key().as_ref()],
+        bump
+    )]
+    pub market: Account<'info, Market>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializePool<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub market: Account<'info, Market>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Pool::INIT_SPACE,
+        seeds = [Pool::SEED_PREFIX, market.key().as_ref()],
+        bump
+    )]
+    pub pool: Account<'info, Pool>,
+
+    /// CHECK: PDA authority for the pool
+    #[account(seeds = [b"pool_authority", market.key().as_ref()], bump)]
+    pub pool_authority: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = authority,
+        mint::decimals = 6,
+        mint::authority = pool_authority,
+    )]
+    pub lp_mint: Account<'info, Mint>,
+
+    #[account(
+        init,
+        payer = authority,
+        token::mint = security_mint,
+        token::authority = pool_authority,
+    )]
+    pub security_vault: Account<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer = authority,
+        token::mint = quote_mint,
+        token::authority = pool_authority,
+    )]
+    pub quote_vault: Account<'info, TokenAccount>,
+
+    pub security_mint: Account<'info, Mint>,
+    pub quote_mint: Account<'info, Mint>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct AddLiquidity<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    pub market: Account<'info, Market>,
+
+    #[account(mut, seeds = [Pool::SEED_PREFIX, market.key().as_ref()], bump = pool.bump)]
+    pub pool: Account<'info, Pool>,
+
+    /// CHECK: Pool authority PDA
+    pub pool_authority: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub lp_mint: Account<'info, Mint>,
+
+    #[account(mut)]
+    pub security_vault: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub quote_vault: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub user_security: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub user_quote: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub user_lp: Account<'info, TokenAccount>,
+
+    /// Emergency shutdown switch (may be uninitialized, which reads as running)
+    /// CHECK: owner and discriminator checked by `require_not_shutdown`
+    #[account(seeds = [ProtocolState::SEED_PREFIX], bump)]
+    pub protocol_state: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct Swap<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(mut)]
+    pub market: Account<'info, Market>,
+
+    #[account(mut, seeds = [Pool::SEED_PREFIX, market.key().as_ref()], bump = pool.bump)]
+    pub pool: Account<'info, Pool>,
+
+    /// CHECK: Pool authority PDA
+    pub pool_authority: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub security_vault: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub quote_vault: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub user_security: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub user_quote: Account<'info, TokenAccount>,
+
+    /// Market oracle (required when `market.requires_oracle`,
+    /// `market.auto_pause_enabled` or `market.max_oracle_deviation_bps` is set)
+    pub oracle_feed: Option<Box<Account<'info, PriceFeed>>>,
+
+    /// `market.fallback_oracle`, consulted when `oracle_feed` is stale
+    pub fallback_oracle_feed: Option<Box<Account<'info, PriceFeed>>>,
+
+    /// Receives routed protocol fees (required when
+    /// `market.target_insurance_balance` is set)
+    #[account(
+        mut,
+        seeds = [InsuranceFund::SEED_PREFIX, market.key().as_ref()],
+        bump = insurance_fund.bump
+    )]
+    pub insurance_fund: Option<Box<Account<'info, InsuranceFund>>>,
+
+    /// Holds the insurance fund's quote (required with `insurance_fund`)
+    #[account(
+        mut,
+        seeds = [InsuranceFund::VAULT_SEED_PREFIX, market.key().as_ref()],
+        bump
+    )]
+    pub insurance_vault: Option<Box<Account<'info, TokenAccount>>>,
+
+    /// Emergency shutdown switch (may be uninitialized, which reads as running)
+    /// CHECK: owner and discriminator checked by `require_not_shutdown`
+    #[account(seeds = [ProtocolState::SEED_PREFIX], bump)]
+    pub protocol_state: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct RouteSwap<'info> {
+    pub user: Signer<'info>,
+
+    /// Input of the first leg
+    #[account(mut, token::authority = user)]
+    pub user_source: Account<'info, TokenAccount>,
+
+    /// Receives the last leg's output
+    #[account(mut)]
+    pub user_destination: Account<'info, TokenAccount>,
+
+    /// Emergency shutdown switch (may be uninitialized, which reads as running)
+    /// CHECK: owner and discriminator checked by `require_not_shutdown`
+    #[account(seeds = [ProtocolState::SEED_PREFIX], bump)]
+    pub protocol_state: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct GetPoolInfo<'info> {
+    pub market: Account<'info, Market>,
+
+    #[account(seeds = [Pool::SEED_PREFIX, market.key().as_ref()], bump = pool.bump)]
+    pub pool: Account<'info, Pool>,
+}
+
+#[derive(Accounts)]
+pub struct IntegerSqrt {}
+
+#[derive(Accounts)]
+pub struct WithdrawProtocolFees<'info> {
+    #[account(
+        constraint = authority.key() == market.authority @ SecuritiesError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    #[account(mut)]
+    pub market: Account<'info, Market>,
+
+    #[account(seeds = [Pool::SEED_PREFIX, market.key().as_ref()], bump = pool.bump)]
+    pub pool: Account<'info, Pool>,
+
+    /// CHECK: Pool authority PDA
+    #[account(seeds = [b"pool_authority", market.key().as_ref()], bump = pool.authority_bump)]
+    pub pool_authority: UncheckedAccount<'info>,
+
+    #[account(mut, address = pool.quote_vault)]
+    pub quote_vault: Account<'info, TokenAccount>,
+
+    #[account(mut, token::mint = market.quote_mint)]
+    pub treasury: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeOrderBook<'info> {
+    #[account(
+        mut,
+        constraint = authority.key() == market.authority @ SecuritiesError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    pub market: Account<'info, Market>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + OrderBook::INIT_SPACE,
+        seeds = [OrderBook::SEED_PREFIX, market.key().as_ref()],
+        bump
+    )]
+    pub order_book: Account<'info, OrderBook>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(params: PlaceOrderParams)]
+pub struct PlaceLimitOrder<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub market: Account<'info, Market>,
+
+    #[account(
+        mut,
+        seeds = [OrderBook::SEED_PREFIX, market.key().as_ref()],
+        bump = order_book.bump
+    )]
+    pub order_book: Account<'info, OrderBook>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + Order::INIT_SPACE,
+        seeds = [
+            Order::SEED_PREFIX,
+            owner.key().as_ref(),
+            market.key().as_ref(),
+            &params.nonce.to_le_bytes(),
+        ],
+        bump
+    )]
+    pub order: Account<'info, Order>,
+
+    /// CHECK: Pool authority PDA, owner of the order escrow
+    #[account(seeds = [b"pool_authority", market.key().as_ref()], bump)]
+    pub pool_authority: UncheckedAccount<'info>,
+
+    /// Quote mint for a buy, security mint for a sell
+    #[account(
+        constraint = escrow_mint.key() == match params.side {
+            OrderSide::Buy => market.quote_mint,
+            OrderSide::Sell => market.security_mint,
+        } @ SecuritiesError::InvalidOrder
+    )]
+    pub escrow_mint: Account<'info, Mint>,
+
+    #[account(
+        init,
+        payer = owner,
+        token::mint = escrow_mint,
+        token::authority = pool_authority,
+        seeds = [Order::ESCROW_SEED_PREFIX, order.key().as_ref()],
+        bump
+    )]
+    pub escrow: Account<'info, TokenAccount>,
+
+    #[account(mut, token::mint = escrow_mint)]
+    pub owner_token: Account<'info, TokenAccount>,
+
+    /// Owner's position on this market (required for reduce-only orders)
+    #[account(
+        mut,
+        seeds = [Position::SEED_PREFIX, owner.key().as_ref(), market.key().as_ref()],
+        bump = position.bump
+    )]
+    pub position: Option<Account<'info, Position>>,
+
+    /// Emergency shutdown switch (may be uninitialized, which reads as running)
+    /// CHECK: owner and discriminator checked by `require_not_shutdown`
+    #[account(seeds = [ProtocolState::SEED_PREFIX], bump)]
+    pub protocol_state: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelOrder<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub market: Account<'info, Market>,
+
+    #[account(
+        mut,
+        seeds = [OrderBook::SEED_PREFIX, market.key().as_ref()],
+        bump = order_book.bump
+    )]
+    pub order_book: Account<'info, OrderBook>,
+
+    #[account(
+        mut,
+        seeds = [
+            Order::SEED_PREFIX,
+            owner.key().as_ref(),
+            market.key().as_ref(),
+            &order.nonce.to_le_bytes(),
+        ],
+        bump = order.bump,
+        constraint = order.is_active() @ SecuritiesError::OrderNotActive
+    )]
+    pub order: Account<'info, Order>,
+
+    /// CHECK: Pool authority PDA, owner of the order escrow
+    #[account(seeds = [b"pool_authority", market.key().as_ref()], bump)]
+    pub pool_authority: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [Order::ESCROW_SEED_PREFIX, order.key().as_ref()],
+        bump = order.escrow_bump
+    )]
+    pub escrow: Account<'info, TokenAccount>,
+
+    #[account(mut, token::mint = escrow.mint)]
+    pub owner_token: Account<'info, TokenAccount>,
+
+    /// Owner's position on this market, releasing a reduce-only order's
+    /// reservation (required for reduce-only orders)
+    #[account(
+        mut,
+        seeds = [Position::SEED_PREFIX, owner.key().as_ref(), market.key().as_ref()],
+        bump = position.bump
+    )]
+    pub position: Option<Account<'info, Position>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct MatchOrder<'info> {
+    pub keeper: Signer<'info>,
+
+    #[account(mut)]
+    pub market: Account<'info, Market>,
+
+    #[account(mut, seeds = [Pool::SEED_PREFIX, market.key().as_ref()], bump = pool.bump)]
+    pub pool: Account<'info, Pool>,
+
+    /// CHECK: Pool authority PDA, owner of the vaults and the order escrow
+    #[account(seeds = [b"pool_authority", market.key().as_ref()], bump = pool.authority_bump)]
+    pub pool_authority: UncheckedAccount<'info>,
+
+    #[account(mut, address = pool.security_vault)]
+    pub security_vault: Account<'info, TokenAccount>,
+
+    #[account(mut, address = pool.quote_vault)]
+    pub quote_vault: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [OrderBook::SEED_PREFIX, market.key().as_ref()],
+        bump = order_book.bump
+    )]
+    pub order_book: Account<'info, OrderBook>,
+
+    #[account(
+        mut,
+        seeds = [
+            Order::SEED_PREFIX,
+            order.owner.as_ref(),
+            market.key().as_ref(),
+            &order.nonce.to_le_bytes(),
+        ],
+        bump = order.bump,
+        constraint = order.is_active() @ SecuritiesError::OrderNotActive
+    )]
+    pub order: Account<'info, Order>,
+
+    #[account(
+        mut,
+        seeds = [Order::ESCROW_SEED_PREFIX, order.key().as_ref()],
+        bump = order.escrow_bump
+    )]
+    pub escrow: Account<'info, TokenAccount>,
+
+    /// CHECK: order owner, receives the escrow rent once the order fills
+    #[account(mut, address = order.owner)]
+    pub owner: UncheckedAccount<'info>,
+
+    /// Owner's account for the fill: security for a buy, quote for a sell
+    #[account(
+        mut,
+        token::authority = order.owner,
+        constraint = owner_proceeds.mint == match order.side {
+            OrderSide::Buy => market.security_mint,
+            OrderSide::Sell => market.quote_mint,
+        } @ SecuritiesError::InvalidOrder
+    )]
+    pub owner_proceeds: Account<'info, TokenAccount>,
+
+    /// Owner's account in the escrow mint, refunded what is left in the
+    /// escrow once the order fills
+    #[account(mut, token::mint = escrow.mint, token::authority = order.owner)]
+    pub owner_refund: Account<'info, TokenAccount>,
+
+    /// Owner's position on this market (required for reduce-only orders)
+    #[account(
+        mut,
+        seeds = [Position::SEED_PREFIX, order.owner.as_ref(), market.key().as_ref()],
+        bump = position.bump
+    )]
+    pub position: Option<Account<'info, Position>>,
+
+    /// Market oracle (required when `market.requires_oracle`,
+    /// `market.auto_pause_enabled` or `market.max_oracle_deviation_bps` is set)
+    pub oracle_feed: Option<Box<Account<'info, PriceFeed>>>,
+
+    /// `market.fallback_oracle`, consulted when `oracle_feed` is stale
+    pub fallback_oracle_feed: Option<Box<Account<'info, PriceFeed>>>,
+
+    /// Receives routed protocol fees (required when
+    /// `market.target_insurance_balance` is set)
+    #[account(
+        mut,
+        seeds = [InsuranceFund::SEED_PREFIX, market.key().as_ref()],
+        bump = insurance_fund.bump
+    )]
+    pub insurance_fund: Option<Box<Account<'info, InsuranceFund>>>,
+
+    /// Holds the insurance fund's quote (required with `insurance_fund`)
+    #[account(
+        mut,
+        seeds = [InsuranceFund::VAULT_SEED_PREFIX, market.key().as_ref()],
+        bump
+    )]
+    pub insurance_vault: Option<Box<Account<'info, TokenAccount>>>,
+
+    /// Emergency shutdown switch (may be uninitialized, which reads as running)
+    /// CHECK: owner and discriminator checked by `require_not_shutdown`
+    #[account(seeds = [ProtocolState::SEED_PREFIX], bump)]
+    pub protocol_state: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct PlaceImmediateOrder<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(mut)]
+    pub market: Account<'info, Market>,
+
+    #[account(mut, seeds = [Pool::SEED_PREFIX, market.key().as_ref()], bump = pool.bump)]
+    pub pool: Account<'info, Pool>,
+
+    /// CHECK: Pool authority PDA, owner of the vaults
+    #[account(seeds = [b"pool_authority", market.key().as_ref()], bump = pool.authority_bump)]
+    pub pool_authority: UncheckedAccount<'info>,
+
+    #[account(mut, address = pool.security_vault)]
+    pub security_vault: Account<'info, TokenAccount>,
+
+    #[account(mut, address = pool.quote_vault)]
+    pub quote_vault: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [OrderBook::SEED_PREFIX, market.key().as_ref()],
+        bump = order_book.bump
+    )]
+    pub order_book: Account<'info, OrderBook>,
+
+    #[account(mut, token::mint = market.security_mint, token::authority = owner)]
+    pub owner_security: Account<'info, TokenAccount>,
+
+    #[account(mut, token::mint = market.quote_mint, token::authority = owner)]
+    pub owner_quote: Account<'info, TokenAccount>,
+
+    /// Market oracle (required when `market.requires_oracle`,
+    /// `market.auto_pause_enabled` or `market.max_oracle_deviation_bps` is set)
+    pub oracle_feed: Option<Box<Account<'info, PriceFeed>>>,
+
+    /// `market.fallback_oracle`, consulted when `oracle_feed` is stale
+    pub fallback_oracle_feed: Option<Box<Account<'info, PriceFeed>>>,
+
+    /// Receives routed protocol fees (required when
+    /// `market.target_insurance_balance` is set)
+    #[account(
+        mut,
+        seeds = [InsuranceFund::SEED_PREFIX, market.key().as_ref()],
+        bump = insurance_fund.bump
+    )]
+    pub insurance_fund: Option<Box<Account<'info, InsuranceFund>>>,
+
+    /// Holds the insurance fund's quote (required with `insurance_fund`)
+    #[account(
+        mut,
+        seeds = [InsuranceFund::VAULT_SEED_PREFIX, market.key().as_ref()],
+        bump
+    )]
+    pub insurance_vault: Option<Box<Account<'info, TokenAccount>>>,
+
+    /// Emergency shutdown switch (may be uninitialized, which reads as running)
+    /// CHECK: owner and discriminator checked by `require_not_shutdown`
+    #[account(seeds = [ProtocolState::SEED_PREFIX], bump)]
+    pub protocol_state: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(params: OpenPositionParams)]
+pub struct OpenPosition<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    pub market: Account<'info, Market>,
+
+    /// Created on the owner's first open and reused once the position closes
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + Position::INIT_SPACE,
+        seeds = [Position::SEED_PREFIX, user.key().as_ref(), market.key().as_ref()],
+        bump
+    )]
+    pub position: Account<'info, Position>,
+
+    /// CHECK: Pool authority PDA, owner of the collateral vault
+    #[account(seeds = [b"pool_authority", market.key().as_ref()], bump)]
+    pub pool_authority: UncheckedAccount<'info>,
+
+    #[account(address = market.quote_mint @ SecuritiesError::QuoteMintMismatch)]
+    pub quote_mint: InterfaceAccount<'info, token_interface::Mint>,
+
+    #[account(mut, token::mint = quote_mint)]
+    pub user_quote: InterfaceAccount<'info, token_interface::TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = quote_mint,
+        token::authority = pool_authority
+    )]
+    pub collateral_vault: InterfaceAccount<'info, token_interface::TokenAccount>,
+
+    /// Market oracle; sets the entry price
+    pub oracle_feed: Box<Account<'info, PriceFeed>>,
+
+    /// `market.fallback_oracle`, consulted when `oracle_feed` is stale
+    pub fallback_oracle_feed: Option<Box<Account<'info, PriceFeed>>>,
+
+    /// Emergency shutdown switch (may be uninitialized, which reads as running)
+    /// CHECK: owner and discriminator checked by `require_not_shutdown`
+    #[account(seeds = [ProtocolState::SEED_PREFIX], bump)]
+    pub protocol_state: UncheckedAccount<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClosePosition<'info> {
+    pub user: Signer<'info>,
+
+    pub market: Account<'info, Market>,
+
+    #[account(
+        mut,
+        seeds = [Position::SEED_PREFIX, user.key().as_ref(), market.key().as_ref()],
+        bump = position.bump,
+        constraint = position.is_open @ SecuritiesError::PositionNotFound
+    )]
+    pub position: Account<'info, Position>,
+
+    /// CHECK: Pool authority PDA, owner of the collateral vault
+    #[account(seeds = [b"pool_authority", market.key().as_ref()], bump)]
+    pub pool_authority: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        token::mint = market.quote_mint,
+        token::authority = pool_authority
+    )]
+    pub collateral_vault: Account<'info, TokenAccount>,
+
+    #[account(mut, token::mint = market.quote_mint)]
+    pub user_quote: Account<'info, TokenAccount>,
+
+    pub oracle_feed: Box<Account<'info, PriceFeed>>,
+
+    /// `market.fallback_oracle`, consulted when `oracle_feed` is stale
+    pub fallback_oracle_feed: Option<Box<Account<'info, PriceFeed>>>,
+
+    /// Receives the close fee (required when `market.close_fee_bps` is set)
+    #[account(
+        mut,
+        seeds = [InsuranceFund::SEED_PREFIX, market.key().as_ref()],
+        bump = insurance_fund.bump
+    )]
+    pub insurance_fund: Option<Account<'info, InsuranceFund>>,
+
+    /// Holds the insurance fund's quote (required with `insurance_fund`)
+    #[account(
+        mut,
+        seeds = [InsuranceFund::VAULT_SEED_PREFIX, market.key().as_ref()],
+        bump
+    )]
+    pub insurance_vault: Option<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct AdjustPosition<'info> {
+    pub user: Signer<'info>,
+
+    pub market: Account<'info, Market>,
+
+    #[account(
+        mut,
+        seeds = [Position::SEED_PREFIX, user.key().as_ref(), market.key().as_ref()],
+        bump = position.bump,
+        constraint = position.is_open @ SecuritiesError::PositionNotFound
+    )]
+    pub position: Account<'info, Position>,
+
+    /// CHECK: Pool authority PDA, owner of the collateral vault
+    #[account(seeds = [b"pool_authority", market.key().as_ref()], bump)]
+    pub pool_authority: UncheckedAccount<'info>,
+
+    #[account(address = market.quote_mint @ SecuritiesError::QuoteMintMismatch)]
+    pub quote_mint: InterfaceAccount<'info, token_interface::Mint>,
+
+    #[account(mut, token::mint = quote_mint)]
+    pub user_quote: InterfaceAccount<'info, token_interface::TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = quote_mint,
+        token::authority = pool_authority
+    )]
+    pub collateral_vault: InterfaceAccount<'info, token_interface::TokenAccount>,
+
+    pub oracle_feed: Box<Account<'info, PriceFeed>>,
+
+    /// `market.fallback_oracle`, consulted when `oracle_feed` is stale
+    pub fallback_oracle_feed: Option<Box<Account<'info, PriceFeed>>>,
+
+    /// Emergency shutdown switch (may be uninitialized, which reads as running)
+    /// CHECK: owner and discriminator checked by `require_not_shutdown`
+    #[account(seeds = [ProtocolState::SEED_PREFIX], bump)]
+    pub protocol_state: UncheckedAccount<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteTrigger<'info> {
+    pub keeper: Signer<'info>,
+
+    pub market: Account<'info, Market>,
+
+    #[account(
+        mut,
+        constraint = position.market == market.key() @ SecuritiesError::InvalidPosition,
+        constraint = position.is_open @ SecuritiesError::PositionNotFound
+    )]
+    pub position: Account<'info, Position>,
+
+    /// CHECK: Pool authority PDA, owner of the collateral vault
+    #[account(seeds = [b"pool_authority", market.key().as_ref()], bump)]
+    pub pool_authority: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        token::mint = market.quote_mint,
+        token::authority = pool_authority
+    )]
+    pub collateral_vault: Account<'info, TokenAccount>,
+
+    /// Receives the payout
+    #[account(
+        mut,
+        token::mint = market.quote_mint,
+        token::authority = position.owner
+    )]
+    pub owner_quote: Account<'info, TokenAccount>,
+
+    pub oracle_feed: Box<Account<'info, PriceFeed>>,
+
+    /// `market.fallback_oracle`, consulted when `oracle_feed` is stale
+    pub fallback_oracle_feed: Option<Box<Account<'info, PriceFeed>>>,
+
+    /// Receives the close fee (required when `market.close_fee_bps` is set)
+    #[account(
+        mut,
+        seeds = [InsuranceFund::SEED_PREFIX, market.key().as_ref()],
+        bump = insurance_fund.bump
+    )]
+    pub insurance_fund: Option<Account<'info, InsuranceFund>>,
+
+    /// Holds the insurance fund's quote (required with `insurance_fund`)
+    #[account(
+        mut,
+        seeds = [InsuranceFund::VAULT_SEED_PREFIX, market.key().as_ref()],
+        bump
+    )]
+    pub insurance_vault: Option<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateTradingSession<'info> {
+    #[account(mut)]
+    pub market: Account<'info, Market>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateMarket<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// CHECK: may not deserialize under the current layout; discriminator and
+    /// authority are verified in the handler
+    #[account(mut, owner = crate::ID)]
+    pub market: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigratePool<'info> {
+    #[account(
+        mut,
+        constraint = authority.key() == market.authority @ SecuritiesError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    pub market: Account<'info, Market>,
+
+    /// CHECK: may not deserialize under the current layout; discriminator and
+    /// market are verified in the handler
+    #[account(mut, owner = crate::ID)]
+    pub pool: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CheckLiquidatable<'info> {
+    pub market: Account<'info, Market>,
+
+    pub oracle_feed: Box<Account<'info, PriceFeed>>,
+
+    /// `market.fallback_oracle`, consulted when `oracle_feed` is stale
+    pub fallback_oracle_feed: Option<Box<Account<'info, PriceFeed>>>,
+
+    #[account(constraint = position.market == market.key() @ SecuritiesError::InvalidPosition)]
+    pub position: Account<'info, Position>,
+}
+
+#[derive(Accounts)]
+pub struct LiquidatePosition<'info> {
+    pub keeper: Signer<'info>,
+
+    pub market: Account<'info, Market>,
+
+    pub oracle_feed: Box<Account<'info, PriceFeed>>,
+
+    /// `market.fallback_oracle`, consulted when `oracle_feed` is stale
+    pub fallback_oracle_feed: Option<Box<Account<'info, PriceFeed>>>,
+
+    #[account(
+        mut,
+        constraint = position.market == market.key() @ SecuritiesError::InvalidPosition,
+        constraint = position.is_open @ SecuritiesError::PositionNotFound
+    )]
+    pub position: Account<'info, Position>,
+
+    /// CHECK: Pool authority PDA, owner of the collateral vault
+    #[account(seeds = [b"pool_authority", market.key().as_ref()], bump)]
+    pub pool_authority: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        token::mint = market.quote_mint,
+        token::authority = pool_authority
+    )]
+    pub collateral_vault: Account<'info, TokenAccount>,
+
+    /// Receives the liquidator bounty
+    #[account(mut, token::mint = market.quote_mint)]
+    pub keeper_quote: Account<'info, TokenAccount>,
+
+    /// Receives the equity left after the bounty
+    #[account(
+        mut,
+        seeds = [InsuranceFund::SEED_PREFIX, market.key().as_ref()],
+        bump = insurance_fund.bump
+    )]
+    pub insurance_fund: Account<'info, InsuranceFund>,
+
+    #[account(
+        mut,
+        seeds = [InsuranceFund::VAULT_SEED_PREFIX, market.key().as_ref()],
+        bump
+    )]
+    pub insurance_vault: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
 }
 
-// Account structs
 #[derive(Accounts)]
-#[instruction(params: InitializeMarketParams)]
-pub struct InitializeMarket<'info> {
+pub struct OpenVarianceSwap<'info> {
     #[account(mut)]
-    pub authority: Signer<'info>,
+    pub user: Signer<'info>,
 
-    pub security_mint: Account<'info, Mint>,
-    pub quote_mint: Account<'info, Mint>,
+    #[account(
+        constraint = market.market_type == MarketType::VarianceSwap @ SecuritiesError::InvalidPosition
+    )]
+    pub market: Account<'info, Market>,
+
+    #[account(
+        seeds = [Position::SEED_PREFIX, user.key().as_ref(), market.key().as_ref()],
+        bump = position.bump,
+        constraint = position.is_open @ SecuritiesError::PositionNotFound,
+        constraint = position.position_type == PositionType::VarianceSwap @ SecuritiesError::InvalidPosition
+    )]
+    pub position: Account<'info, Position>,
 
     #[account(
         init,
-        payer = authority,
-        space = 8 + Market::INIT_SPACE,
-        seeds = [Market::SEED_PREFIX, security_mint.key().as_ref(), quote_mint.key().as_ref()],
+        payer = user,
+        space = 8 + VarianceSwapData::INIT_SPACE,
+        seeds = [VarianceSwapData::SEED_PREFIX, position.key().as_ref()],
         bump
     )]
-    pub market: Account<'info, Market>,
+    pub variance_swap: Account<'info, VarianceSwapData>,
+
+    /// Emergency shutdown switch (may be uninitialized, which reads as running)
+    /// CHECK: owner and discriminator checked by `require_not_shutdown`
+    #[account(seeds = [ProtocolState::SEED_PREFIX], bump)]
+    pub protocol_state: UncheckedAccount<'info>,
 
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct InitializePool<'info> {
-    #[account(mut)]
-    pub authority: Signer<'info>,
+pub struct AddVarianceObservation<'info> {
+    pub keeper: Signer<'info>,
 
     pub market: Account<'info, Market>,
 
+    pub oracle_feed: Box<Account<'info, PriceFeed>>,
+
+    /// `market.fallback_oracle`, consulted when `oracle_feed` is stale
+    pub fallback_oracle_feed: Option<Box<Account<'info, PriceFeed>>>,
+
     #[account(
-        init,
-        payer = authority,
-        space = 8 + Pool::INIT_SPACE,
-        seeds = [Pool::SEED_PREFIX, market.key().as_ref()],
+        constraint = position.market == market.key() @ SecuritiesError::InvalidPosition,
+        constraint = position.position_type == PositionType::VarianceSwap @ SecuritiesError::InvalidPosition
+    )]
+    pub position: Account<'info, Position>,
+
+    #[account(
+        mut,
+        seeds = [VarianceSwapData::SEED_PREFIX, position.key().as_ref()],
+        bump = variance_swap.bump,
+        constraint = !variance_swap.is_settled @ SecuritiesError::VarianceSwapAlreadySettled
+    )]
+    pub variance_swap: Account<'info, VarianceSwapData>,
+}
+
+#[derive(Accounts)]
+pub struct SettleVarianceSwap<'info> {
+    pub keeper: Signer<'info>,
+
+    pub market: Account<'info, Market>,
+
+    #[account(
+        mut,
+        constraint = position.market == market.key() @ SecuritiesError::InvalidPosition,
+        constraint = position.is_open @ SecuritiesError::PositionNotFound,
+        constraint = position.position_type == PositionType::VarianceSwap @ SecuritiesError::InvalidPosition
+    )]
+    pub position: Account<'info, Position>,
+
+    #[account(
+        mut,
+        seeds = [VarianceSwapData::SEED_PREFIX, position.key().as_ref()],
+        bump = variance_swap.bump,
+        constraint = !variance_swap.is_settled @ SecuritiesError::VarianceSwapAlreadySettled
+    )]
+    pub variance_swap: Account<'info, VarianceSwapData>,
+
+    /// Counterparty to the swap
+    #[account(
+        mut,
+        seeds = [InsuranceFund::SEED_PREFIX, market.key().as_ref()],
+        bump = insurance_fund.bump
+    )]
+    pub insurance_fund: Account<'info, InsuranceFund>,
+
+    #[account(
+        mut,
+        seeds = [InsuranceFund::VAULT_SEED_PREFIX, market.key().as_ref()],
         bump
     )]
-    pub pool: Account<'info, Pool>,
+    pub insurance_vault: Account<'info, TokenAccount>,
 
-    /// CHECK: PDA authority for the pool
+    /// CHECK: Pool authority PDA, owner of the collateral and insurance vaults
+    #[account(seeds = [b"pool_authority", market.key().as_ref()], bump)]
+    pub pool_authority: UncheckedAccount<'info>,
+
+    /// Holds the position's collateral
+    #[account(
+        mut,
+        token::mint = market.quote_mint,
+        token::authority = pool_authority
+    )]
+    pub collateral_vault: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct GetVarianceSettlement<'info> {
+    pub variance_swap: Account<'info, VarianceSwapData>,
+}
+
+#[derive(Accounts)]
+pub struct GetPositionSummary<'info> {
+    /// CHECK: only compared against each position's owner
+    pub trader: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ApplyFunding<'info> {
+    pub market: Account<'info, Market>,
+
+    pub oracle_feed: Box<Account<'info, PriceFeed>>,
+
+    #[account(
+        seeds = [FundingFeed::SEED_PREFIX, market.symbol.as_bytes()],
+        bump = funding_feed.bump,
+        seeds::program = oracle::ID
+    )]
+    pub funding_feed: Box<Account<'info, FundingFeed>>,
+
+    #[account(
+        mut,
+        constraint = position.market == market.key() @ SecuritiesError::InvalidPosition
+    )]
+    pub position: Account<'info, Position>,
+}
+
+#[derive(Accounts)]
+pub struct GetInsuranceBalance<'info> {
+    pub market: Account<'info, Market>,
+
+    #[account(
+        seeds = [InsuranceFund::SEED_PREFIX, market.key().as_ref()],
+        bump = insurance_fund.bump
+    )]
+    pub insurance_fund: Account<'info, InsuranceFund>,
+}
+
+#[derive(Accounts)]
+pub struct RequestInsuranceWithdrawal<'info> {
+    #[account(
+        constraint = authority.key() == market.authority @ SecuritiesError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    pub market: Account<'info, Market>,
+
+    #[account(
+        mut,
+        seeds = [InsuranceFund::SEED_PREFIX, market.key().as_ref()],
+        bump = insurance_fund.bump
+    )]
+    pub insurance_fund: Account<'info, InsuranceFund>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawInsurance<'info> {
+    #[account(
+        constraint = authority.key() == market.authority @ SecuritiesError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    pub market: Account<'info, Market>,
+
+    #[account(
+        mut,
+        seeds = [InsuranceFund::SEED_PREFIX, market.key().as_ref()],
+        bump = insurance_fund.bump
+    )]
+    pub insurance_fund: Account<'info, InsuranceFund>,
+
+    /// CHECK: Pool authority PDA, owner of the insurance vault
     #[account(seeds = [b"pool_authority", market.key().as_ref()], bump)]
     pub pool_authority: UncheckedAccount<'info>,
 
+    #[account(
+        mut,
+        seeds = [InsuranceFund::VAULT_SEED_PREFIX, market.key().as_ref()],
+        bump
+    )]
+    pub insurance_vault: Account<'info, TokenAccount>,
+
+    #[account(mut, token::mint = market.quote_mint)]
+    pub destination: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeInsuranceFund<'info> {
+    #[account(
+        mut,
+        constraint = authority.key() == market.authority @ SecuritiesError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    pub market: Account<'info, Market>,
+
     #[account(
         init,
         payer = authority,
-        mint::decimals = 6,
-        mint::authority = pool_authority,
+        space = 8 + InsuranceFund::INIT_SPACE,
+        seeds = [InsuranceFund::SEED_PREFIX, market.key().as_ref()],
+        bump
     )]
-    pub lp_mint: Account<'info, Mint>,
+    pub insurance_fund: Account<'info, InsuranceFund>,
+
+    /// CHECK: Pool authority PDA, owner of the insurance vault
+    #[account(seeds = [b"pool_authority", market.key().as_ref()], bump)]
+    pub pool_authority: UncheckedAccount<'info>,
+
+    #[account(address = market.quote_mint @ SecuritiesError::QuoteMintMismatch)]
+    pub quote_mint: Account<'info, Mint>,
 
     #[account(
         init,
         payer = authority,
-        token::mint = security_mint,
+        seeds = [InsuranceFund::VAULT_SEED_PREFIX, market.key().as_ref()],
+        bump,
+        token::mint = quote_mint,
         token::authority = pool_authority,
     )]
-    pub security_vault: Account<'info, TokenAccount>,
+    pub insurance_vault: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeProtocolState<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
 
     #[account(
         init,
         payer = authority,
-        token::mint = quote_mint,
-        token::authority = pool_authority,
+        space = 8 + ProtocolState::INIT_SPACE,
+        seeds = [ProtocolState::SEED_PREFIX],
+        bump
     )]
-    pub quote_vault: Account<'info, TokenAccount>,
+    pub protocol_state: Account<'info, ProtocolState>,
 
-    pub security_mint: Account<'info, Mint>,
-    pub quote_mint: Account<'info, Mint>,
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, crate::program::SecuritiesEngine>,
+
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(authority.key()) @ SecuritiesError::Unauthorized
+    )]
+    pub program_data: Account<'info, ProgramData>,
 
-    pub token_program: Program<'info, Token>,
     pub system_program: Program<'info, System>,
-    pub rent: Sysvar<'info, Rent>,
 }
 
 #[derive(Accounts)]
-pub struct AddLiquidity<'info> {
+pub struct TriggerEmergencyShutdown<'info> {
+    pub guardian: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [ProtocolState::SEED_PREFIX],
+        bump = protocol_state.bump,
+        constraint = protocol_state.guardian == guardian.key() @ SecuritiesError::Unauthorized
+    )]
+    pub protocol_state: Account<'info, ProtocolState>,
+}
+
+#[derive(Accounts)]
+pub struct SetShutdownSettlementPrice<'info> {
+    pub guardian: Signer<'info>,
+
+    #[account(
+        seeds = [ProtocolState::SEED_PREFIX],
+        bump = protocol_state.bump,
+        constraint = protocol_state.guardian == guardian.key() @ SecuritiesError::Unauthorized,
+        constraint = protocol_state.is_shutdown @ SecuritiesError::ProtocolNotShutdown
+    )]
+    pub protocol_state: Account<'info, ProtocolState>,
+
     #[account(mut)]
-    pub user: Signer<'info>,
+    pub market: Account<'info, Market>,
+}
+
+#[derive(Accounts)]
+pub struct SettleAtShutdown<'info> {
+    pub cranker: Signer<'info>,
+
+    #[account(
+        seeds = [ProtocolState::SEED_PREFIX],
+        bump = protocol_state.bump,
+        constraint = protocol_state.is_shutdown @ SecuritiesError::ProtocolNotShutdown
+    )]
+    pub protocol_state: Account<'info, ProtocolState>,
 
     pub market: Account<'info, Market>,
 
-    #[account(mut, seeds = [Pool::SEED_PREFIX, market.key().as_ref()], bump = pool.bump)]
-    pub pool: Account<'info, Pool>,
+    #[account(
+        mut,
+        constraint = position.market == market.key() @ SecuritiesError::InvalidPosition,
+        constraint = position.is_open @ SecuritiesError::PositionNotFound
+    )]
+    pub position: Account<'info, Position>,
 
-    /// CHECK: Pool authority PDA
+    /// CHECK: Pool authority PDA, owner of the collateral vault
+    #[account(seeds = [b"pool_authority", market.key().as_ref()], bump)]
     pub pool_authority: UncheckedAccount<'info>,
 
-    #[account(mut)]
-    pub lp_mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        token::mint = market.quote_mint,
+        token::authority = pool_authority
+    )]
+    pub collateral_vault: Account<'info, TokenAccount>,
 
-    #[account(mut)]
-    pub security_vault: Account<'info, TokenAccount>,
+    /// Receives the returned collateral
+    #[account(
+        mut,
+        token::mint = market.quote_mint,
+        token::authority = position.owner
+    )]
+    pub owner_quote: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct AutoDeleverage<'info> {
+    pub keeper: Signer<'info>,
+
+    pub market: Account<'info, Market>,
+
+    #[account(
+        seeds = [InsuranceFund::SEED_PREFIX, market.key().as_ref()],
+        bump = insurance_fund.bump
+    )]
+    pub insurance_fund: Account<'info, InsuranceFund>,
+
+    pub oracle_feed: Box<Account<'info, PriceFeed>>,
+
+    #[account(
+        mut,
+        constraint = bankrupt_position.market == market.key() @ SecuritiesError::InvalidPosition,
+        constraint = bankrupt_position.is_open @ SecuritiesError::PositionNotFound
+    )]
+    pub bankrupt_position: Account<'info, Position>,
+}
+
+#[derive(Accounts)]
+pub struct SetMarketStatus<'info> {
+    #[account(
+        constraint = authority.key() == market.authority @ SecuritiesError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
 
     #[account(mut)]
-    pub quote_vault: Account<'info, TokenAccount>,
+    pub market: Account<'info, Market>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateMarketConfig<'info> {
+    #[account(
+        constraint = authority.key() == market.authority @ SecuritiesError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
 
     #[account(mut)]
-    pub user_security: Account<'info, TokenAccount>,
+    pub market: Account<'info, Market>,
+}
 
+#[derive(Accounts)]
+pub struct ProposeFeeChange<'info> {
     #[account(mut)]
-    pub user_quote: Account<'info, TokenAccount>,
+    pub proposer: Signer<'info>,
 
     #[account(mut)]
-    pub user_lp: Account<'info, TokenAccount>,
+    pub market: Account<'info, Market>,
 
-    pub token_program: Program<'info, Token>,
+    #[account(seeds = [Pool::SEED_PREFIX, market.key().as_ref()], bump = pool.bump)]
+    pub pool: Account<'info, Pool>,
+
+    #[account(
+        constraint = proposer_lp.mint == pool.lp_mint @ SecuritiesError::InvalidAmount,
+        constraint = proposer_lp.owner == proposer.key() @ SecuritiesError::Unauthorized
+    )]
+    pub proposer_lp: Account<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer = proposer,
+        space = 8 + FeeProposal::INIT_SPACE,
+        seeds = [FeeProposal::SEED_PREFIX, market.key().as_ref(), &market.fee_proposal_count.to_le_bytes()],
+        bump
+    )]
+    pub proposal: Account<'info, FeeProposal>,
+
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct Swap<'info> {
+pub struct VoteFeeChange<'info> {
     #[account(mut)]
-    pub user: Signer<'info>,
+    pub voter: Signer<'info>,
 
-    #[account(mut)]
     pub market: Account<'info, Market>,
 
-    #[account(mut, seeds = [Pool::SEED_PREFIX, market.key().as_ref()], bump = pool.bump)]
+    #[account(seeds = [Pool::SEED_PREFIX, market.key().as_ref()], bump = pool.bump)]
     pub pool: Account<'info, Pool>,
 
-    /// CHECK: Pool authority PDA
-    pub pool_authority: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [FeeProposal::SEED_PREFIX, market.key().as_ref(), &proposal.proposal_id.to_le_bytes()],
+        bump = proposal.bump
+    )]
+    pub proposal: Account<'info, FeeProposal>,
 
-    #[account(mut)]
-    pub security_vault: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = voter_lp.mint == pool.lp_mint @ SecuritiesError::InvalidAmount,
+        constraint = voter_lp.owner == voter.key() @ SecuritiesError::Unauthorized
+    )]
+    pub voter_lp: Account<'info, TokenAccount>,
 
-    #[account(mut)]
-    pub quote_vault: Account<'info, TokenAccount>,
+    #[account(address = pool.lp_mint)]
+    pub lp_mint: Account<'info, Mint>,
+
+    #[account(
+        init,
+        payer = voter,
+        space = 8 + FeeVote::INIT_SPACE,
+        seeds = [FeeVote::SEED_PREFIX, proposal.key().as_ref(), voter.key().as_ref()],
+        bump
+    )]
+    pub vote_record: Account<'info, FeeVote>,
 
-    #[account(mut)]
-    pub user_security: Account<'info, TokenAccount>,
+    /// CHECK: Pool authority PDA, owner of the vote escrow
+    #[account(seeds = [b"pool_authority", market.key().as_ref()], bump)]
+    pub pool_authority: UncheckedAccount<'info>,
 
-    #[account(mut)]
-    pub user_quote: Account<'info, TokenAccount>,
+    /// Holds the voter's LP tokens until voting ends
+    #[account(
+        init,
+        payer = voter,
+        seeds = [FeeVote::ESCROW_SEED_PREFIX, vote_record.key().as_ref()],
+        bump,
+        token::mint = lp_mint,
+        token::authority = pool_authority,
+    )]
+    pub vote_escrow: Account<'info, TokenAccount>,
 
     pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-#[instruction(params: OpenPositionParams)]
-pub struct OpenPosition<'info> {
+pub struct ReleaseFeeVote<'info> {
     #[account(mut)]
-    pub user: Signer<'info>,
+    pub voter: Signer<'info>,
 
     pub market: Account<'info, Market>,
 
     #[account(
-        init,
-        payer = user,
-        space = 8 + Position::INIT_SPACE,
-        seeds = [Position::SEED_PREFIX, user.key().as_ref(), market.key().as_ref()],
+        seeds = [FeeProposal::SEED_PREFIX, market.key().as_ref(), &proposal.proposal_id.to_le_bytes()],
+        bump = proposal.bump
+    )]
+    pub proposal: Account<'info, FeeProposal>,
+
+    #[account(
+        mut,
+        close = voter,
+        seeds = [FeeVote::SEED_PREFIX, proposal.key().as_ref(), voter.key().as_ref()],
+        bump = vote_record.bump
+    )]
+    pub vote_record: Account<'info, FeeVote>,
+
+    /// CHECK: Pool authority PDA, owner of the vote escrow
+    #[account(seeds = [b"pool_authority", market.key().as_ref()], bump)]
+    pub pool_authority: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [FeeVote::ESCROW_SEED_PREFIX, vote_record.key().as_ref()],
         bump
     )]
-    pub position: Account<'info, Position>,
+    pub vote_escrow: Account<'info, TokenAccount>,
 
-    #[account(mut)]
-    pub user_quote: Account<'info, TokenAccount>,
+    #[account(mut, token::mint = vote_escrow.mint, token::authority = voter)]
+    pub voter_lp: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteFeeChange<'info> {
+    pub executor: Signer<'info>,
 
     #[account(mut)]
-    pub collateral_vault: Account<'info, TokenAccount>,
+    pub market: Account<'info, Market>,
 
-    pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>,
+    #[account(
+        mut,
+        seeds = [FeeProposal::SEED_PREFIX, market.key().as_ref(), &proposal.proposal_id.to_le_bytes()],
+        bump = proposal.bump,
+        constraint = !proposal.executed @ SecuritiesError::VotingClosed
+    )]
+    pub proposal: Account<'info, FeeProposal>,
 }
 
 // Params
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct UpdateMarketConfigParams {
+    pub trading_fee_bps: Option<u16>,
+    pub min_liquidity_to_trade: Option<u64>,
+    pub lp_governance_enabled: Option<bool>,
+    pub min_governed_fee_bps: Option<u16>,
+    pub max_governed_fee_bps: Option<u16>,
+    pub adl_enabled: Option<bool>,
+    pub liquidation_bounty_bps: Option<u16>,
+    pub max_confidence_bps: Option<u16>,
+    pub status: Option<MarketStatus>,
+    pub trading_open_seconds: Option<u32>,
+    pub trading_close_seconds: Option<u32>,
+    pub holiday_calendar_hash: Option<[u8; 32]>,
+    pub auto_pause_enabled: Option<bool>,
+    pub max_oracle_deviation_bps: Option<u16>,
+    pub funding_staleness_seconds: Option<u32>,
+    pub close_fee_bps: Option<u16>,
+    pub target_insurance_balance: Option<u64>,
+    pub insurance_fee_share_bps: Option<u16>,
+    pub requires_oracle: Option<bool>,
+    pub max_funding_interval_seconds: Option<u32>,
+    /// `Some(None)` clears the fallback oracle
+    pub fallback_oracle: Option<Option<Pubkey>>,
+}
+
 #[derive(AnchorSerialize, AnchorDeserialize)]
 pub struct InitializeMarketParams {
     pub market_type: MarketType,
@@ -567,18 +4578,68 @@ pub struct InitializeMarketParams {
     pub isin: Option<[u8; 12]>,
 }
 
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct VarianceSwapParams {
+    /// Annualized strike variance, scaled by `VarianceSwapData::VARIANCE_PRECISION`
+    pub strike_variance: u64,
+    /// Quote units per 1.0 of variance difference
+    pub variance_notional: u64,
+    pub settlement_date: i64,
+    /// Seconds between observations
+    pub observation_interval: i64,
+}
+
 #[derive(AnchorSerialize, AnchorDeserialize)]
 pub struct OpenPositionParams {
     pub position_type: PositionType,
     pub side: Side,
     pub size: u64,
-    pub entry_price: u64,
     pub leverage: u8,
     pub collateral: u64,
     pub take_profit: u64,
     pub stop_loss: u64,
 }
 
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct AdjustPositionParams {
+    /// Quote to deposit as collateral
+    pub add_collateral: u64,
+    /// Size to add at the mark price
+    pub add_size: u64,
+    /// Size to close at the mark price (less than the position size)
+    pub reduce_size: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct PlaceOrderParams {
+    /// Distinguishes the owner's orders on this market (part of the order PDA)
+    pub nonce: u64,
+    pub side: OrderSide,
+    /// Limit price (scaled by 1e6)
+    pub price: u64,
+    /// Size in security units
+    pub size: u64,
+    /// GTC or GTD; IOC and FOK are rejected for resting orders
+    pub time_in_force: TimeInForce,
+    /// Expiry for GTD orders (ignored otherwise)
+    pub expires_at: i64,
+    pub post_only: bool,
+    /// Only reduce the owner's opposite-side position on this market; `size`
+    /// may not exceed it at `price`
+    pub reduce_only: bool,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct ImmediateOrderParams {
+    pub side: OrderSide,
+    /// Limit price (scaled by 1e6)
+    pub price: u64,
+    /// Size in security units
+    pub size: u64,
+    /// IOC or FOK
+    pub time_in_force: TimeInForce,
+}
+
 // Events
 #[event]
 pub struct MarketCreated {
@@ -588,12 +4649,99 @@ pub struct MarketCreated {
     pub timestamp: i64,
 }
 
+#[event]
+pub struct TradingSessionChanged {
+    pub market: Pubkey,
+    pub is_open: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MarketAutoPaused {
+    pub market: Pubkey,
+    pub spot_price: u64,
+    pub oracle_price: u64,
+    pub deviation_bps: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TwapUpdated {
+    pub pool: Pubkey,
+    pub market: Pubkey,
+    pub spot_price: u64,
+    pub twap: u64,
+    pub divergence_bps: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MarketStatusChanged {
+    pub market: Pubkey,
+    pub old_status: MarketStatus,
+    pub new_status: MarketStatus,
+    pub authority: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MarketConfigUpdated {
+    pub market: Pubkey,
+    pub authority: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FeeChangeProposed {
+    pub market: Pubkey,
+    pub proposal: Pubkey,
+    pub proposer: Pubkey,
+    pub current_fee_bps: u16,
+    pub new_fee_bps: u16,
+    pub voting_ends_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FeeChangeVoted {
+    pub proposal: Pubkey,
+    pub voter: Pubkey,
+    pub weight: u64,
+    pub approve: bool,
+    pub votes_for: u64,
+    pub votes_against: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FeeVoteReleased {
+    pub proposal: Pubkey,
+    pub voter: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FeeChangeExecuted {
+    pub market: Pubkey,
+    pub proposal: Pubkey,
+    pub old_fee_bps: u16,
+    pub new_fee_bps: u16,
+    pub votes_for: u64,
+    pub votes_against: u64,
+    pub timestamp: i64,
+}
+
 #[event]
 pub struct LiquidityAdded {
     pub pool: Pubkey,
     pub provider: Pubkey,
+    /// Amounts actually deposited
     pub security_amount: u64,
     pub quote_amount: u64,
+    /// Amounts the provider offered; the rest stayed in their accounts
+    pub max_security_amount: u64,
+    pub max_quote_amount: u64,
     pub lp_tokens: u64,
     pub timestamp: i64,
 }
@@ -610,6 +4758,67 @@ pub struct SwapExecuted {
     pub timestamp: i64,
 }
 
+#[event]
+pub struct ProtocolFeesWithdrawn {
+    pub market: Pubkey,
+    pub treasury: Pubkey,
+    pub amount: u64,
+    /// Protocol fees still accrued after the withdrawal
+    pub remaining: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ProtocolFeeRouted {
+    pub market: Pubkey,
+    /// Protocol share of the swap fee, in quote
+    pub protocol_fee: u64,
+    /// Share routed to the insurance fund, in basis points
+    pub routing_bps: u16,
+    pub insurance_amount: u64,
+    pub treasury_amount: u64,
+    pub insurance_balance: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct VarianceObservationAdded {
+    pub position: Pubkey,
+    pub price: u64,
+    /// Log return since the previous observation, scaled by
+    /// `VarianceSwapData::LOG_PRECISION` (0 for the first)
+    pub log_return: i64,
+    pub realized_variance: u64,
+    pub observation_count: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct VarianceSwapSettled {
+    pub position: Pubkey,
+    pub owner: Pubkey,
+    pub market: Pubkey,
+    pub strike_variance: u64,
+    pub realized_variance: u64,
+    pub observation_count: u32,
+    /// Booked to the position's collateral: positive paid by the insurance
+    /// fund, negative paid into it
+    pub owner_pnl: i64,
+    pub collateral: u64,
+    pub settled_by: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct VarianceSwapOpened {
+    pub position: Pubkey,
+    pub strike_variance: u64,
+    pub variance_notional: u64,
+    pub settlement_date: i64,
+    pub observation_interval: i64,
+    pub timestamp: i64,
+}
+
 #[event]
 pub struct PositionOpened {
     pub position: Pubkey,
@@ -621,3 +4830,244 @@ pub struct PositionOpened {
     pub leverage: u8,
     pub timestamp: i64,
 }
+
+#[event]
+pub struct FundingApplied {
+    pub market: Pubkey,
+    pub position: Pubkey,
+    pub funding_rate: i64,
+    /// Funding credited (+) or charged (-) by this call
+    pub funding_delta: i128,
+    pub accumulated_funding: i128,
+    pub mark_price: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PositionLiquidated {
+    pub position: Pubkey,
+    pub owner: Pubkey,
+    pub market: Pubkey,
+    pub keeper: Pubkey,
+    pub closed_size: u64,
+    /// Oracle mark the position closed at
+    pub mark_price: u64,
+    /// Equity at the mark (negative = bad debt)
+    pub equity: i64,
+    pub bounty: u64,
+    pub insurance_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct OrderPlaced {
+    pub order: Pubkey,
+    pub owner: Pubkey,
+    pub market: Pubkey,
+    pub side: OrderSide,
+    pub price: u64,
+    pub size: u64,
+    pub time_in_force: TimeInForce,
+    pub expires_at: i64,
+    pub escrow_amount: u64,
+    pub reduce_only: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct OrderCancelled {
+    pub order: Pubkey,
+    pub owner: Pubkey,
+    pub market: Pubkey,
+    pub remaining_size: u64,
+    pub refunded: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct OrderFilled {
+    pub order: Pubkey,
+    pub owner: Pubkey,
+    pub market: Pubkey,
+    pub side: OrderSide,
+    /// Size and average price of this fill
+    pub fill_size: u64,
+    pub fill_price: u64,
+    /// Order totals after this fill
+    pub filled_size: u64,
+    pub avg_fill_price: u64,
+    pub remaining_size: u64,
+    /// Escrow left over after a full fill, returned to the owner
+    pub refunded: u64,
+    pub matched_by: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ImmediateOrderExecuted {
+    pub owner: Pubkey,
+    pub market: Pubkey,
+    pub side: OrderSide,
+    pub time_in_force: TimeInForce,
+    /// Limit price and requested size
+    pub price: u64,
+    pub size: u64,
+    /// Size filled and its average price (0 when nothing filled)
+    pub fill_size: u64,
+    pub fill_price: u64,
+    /// Unfilled size dropped by an IOC
+    pub cancelled_size: u64,
+    pub timestamp: i64,
+}
+
+/// The primary oracle was stale and the fallback feed supplied the price
+#[event]
+pub struct OracleFailover {
+    pub market: Pubkey,
+    pub primary_oracle: Pubkey,
+    pub primary_last_update: i64,
+    pub fallback_oracle: Pubkey,
+    pub price: u64,
+    pub timestamp: i64,
+}
+
+/// The crank fell behind: only `charged_seconds` of `elapsed_seconds` were charged
+#[event]
+pub struct FundingClamped {
+    pub market: Pubkey,
+    pub position: Pubkey,
+    pub elapsed_seconds: i64,
+    pub charged_seconds: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PositionClosed {
+    pub position: Pubkey,
+    pub owner: Pubkey,
+    pub market: Pubkey,
+    pub closed_size: u64,
+    pub exit_price: u64,
+    pub realized_pnl: i64,
+    pub close_fee: u64,
+    pub payout: u64,
+    /// Loss beyond the collateral, absorbed by the insurance fund as far as
+    /// its balance allows
+    pub shortfall: u64,
+    pub remaining_size: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PositionAdjusted {
+    pub position: Pubkey,
+    pub owner: Pubkey,
+    pub market: Pubkey,
+    pub added_collateral: u64,
+    pub added_size: u64,
+    pub reduced_size: u64,
+    pub mark_price: u64,
+    /// PnL realized on `reduced_size`, credited to collateral
+    pub realized_pnl: i64,
+    /// Position after the adjustment
+    pub size: u64,
+    pub collateral: u64,
+    pub entry_price: u64,
+    pub leverage: u8,
+    pub liquidation_price: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct InsuranceFundDeposit {
+    pub fund: Pubkey,
+    pub market: Pubkey,
+    pub amount: u64,
+    pub source: InsuranceFlow,
+    pub balance: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct InsuranceFundPayout {
+    pub fund: Pubkey,
+    pub market: Pubkey,
+    pub amount: u64,
+    pub reason: InsuranceFlow,
+    pub balance: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct InsuranceWithdrawalRequested {
+    pub fund: Pubkey,
+    pub market: Pubkey,
+    pub amount: u64,
+    pub unlocks_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EmergencyShutdownTriggered {
+    pub guardian: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ShutdownSettlementPriceSet {
+    pub market: Pubkey,
+    pub settlement_price: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SettledAtShutdown {
+    pub position: Pubkey,
+    pub owner: Pubkey,
+    pub market: Pubkey,
+    /// Price the position closed at (entry price for variance swaps)
+    pub settlement_price: u64,
+    pub closed_size: u64,
+    pub realized_pnl: i64,
+    /// Collateral returned to the owner
+    pub payout: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TriggerExecuted {
+    pub position: Pubkey,
+    pub owner: Pubkey,
+    pub market: Pubkey,
+    pub keeper: Pubkey,
+    pub trigger: TriggerType,
+    /// The take-profit or stop-loss level that was crossed
+    pub trigger_price: u64,
+    pub exit_price: u64,
+    pub closed_size: u64,
+    pub realized_pnl: i64,
+    pub close_fee: u64,
+    pub payout: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AccountMigrated {
+    pub account: Pubkey,
+    pub from_version: u8,
+    pub to_version: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AutoDeleveraged {
+    pub market: Pubkey,
+    pub position: Pubkey,
+    pub owner: Pubkey,
+    pub bankrupt_position: Pubkey,
+    pub reduced_size: u64,
+    pub bankruptcy_price: u64,
+    pub realized_pnl: i64,
+    pub remaining_size: u64,
+    pub timestamp: i64,
+}
@@ -0,0 +1,94 @@
+use anchor_lang::prelude::*;
+
+/// LP-governed proposal to change a market's trading fee
+#[account]
+#[derive(InitSpace)]
+pub struct FeeProposal {
+    /// Associated market
+    pub market: Pubkey,
+
+    /// Proposal sequence number (from `Market.fee_proposal_count`)
+    pub proposal_id: u64,
+
+    /// LP holder that created the proposal
+    pub proposer: Pubkey,
+
+    /// Proposed trading fee in basis points
+    pub new_fee_bps: u16,
+
+    /// LP-weighted votes in favour
+    pub votes_for: u64,
+
+    /// LP-weighted votes against
+    pub votes_against: u64,
+
+    /// Minimum total votes required (snapshot of LP supply at creation)
+    pub quorum_votes: u64,
+
+    /// Voting closes at this timestamp
+    pub voting_ends_at: i64,
+
+    /// Earliest execution timestamp (voting end + timelock)
+    pub executable_at: i64,
+
+    /// Has the proposal been executed
+    pub executed: bool,
+
+    /// Creation timestamp
+    pub created_at: i64,
+
+    /// Bump seed
+    pub bump: u8,
+}
+
+impl FeeProposal {
+    pub const SEED_PREFIX: &'static [u8] = b"fee_proposal";
+    pub const VOTING_PERIOD_SECONDS: i64 = 3 * 86400;
+    pub const TIMELOCK_SECONDS: i64 = 86400;
+    pub const QUORUM_BPS: u64 = 2000; // 20% of LP supply
+
+    pub fn quorum_for(lp_supply: u64) -> u64 {
+        ((lp_supply as u128 * Self::QUORUM_BPS as u128) / 10000) as u64
+    }
+
+    pub fn is_voting_open(&self, current_time: i64) -> bool {
+        !self.executed && current_time < self.voting_ends_at
+    }
+
+    /// Quorum reached and a strict majority in favour
+    pub fn has_passed(&self) -> bool {
+        let total = self.votes_for.saturating_add(self.votes_against);
+        total >= self.quorum_votes && self.votes_for > self.votes_against
+    }
+}
+
+/// Vote receipt preventing a wallet from voting twice on one proposal.
+///
+/// Weight is the voter's LP balance at vote time. Those LP tokens sit in the
+/// vote escrow until voting ends, so they can't be moved and voted again.
+#[account]
+#[derive(InitSpace)]
+pub struct FeeVote {
+    /// Proposal voted on
+    pub proposal: Pubkey,
+
+    /// Voter
+    pub voter: Pubkey,
+
+    /// LP-weighted vote
+    pub weight: u64,
+
+    /// Vote direction
+    pub approve: bool,
+
+    /// Vote timestamp
+    pub voted_at: i64,
+
+    /// Bump seed
+    pub bump: u8,
+}
+
+impl FeeVote {
+    pub const SEED_PREFIX: &'static [u8] = b"fee_vote";
+    pub const ESCROW_SEED_PREFIX: &'static [u8] = b"fee_vote_escrow";
+}
@@ -0,0 +1,66 @@
+use anchor_lang::prelude::*;
+
+/// Per-market insurance fund backing bankrupt positions. Its quote is held
+/// in the market's insurance vault, a token account at
+/// `[VAULT_SEED_PREFIX, market]` owned by the pool authority PDA.
+#[account]
+#[derive(InitSpace)]
+pub struct InsuranceFund {
+    /// Associated market
+    pub market: Pubkey,
+
+    /// Quote available to cover bad debt
+    pub balance: u64,
+
+    /// Lifetime quote credited to the fund
+    pub total_deposits: u64,
+
+    /// Lifetime quote paid out to cover bad debt
+    pub total_payouts: u64,
+
+    /// Creation timestamp
+    pub created_at: i64,
+
+    /// Bump seed
+    pub bump: u8,
+
+    // =========================================================================
+    // Withdrawal timelock
+    // =========================================================================
+
+    /// Amount the market authority has requested to withdraw (0 = none)
+    pub pending_withdrawal: u64,
+
+    /// Earliest time `withdraw_insurance` may execute the pending withdrawal
+    pub withdrawal_unlocks_at: i64,
+}
+
+/// Why quote moved into or out of an insurance fund
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum InsuranceFlow {
+    /// Protocol share of a swap fee
+    SwapFee,
+    /// Fee on a closed position
+    CloseFee,
+    /// Equity left after a liquidation bounty
+    Liquidation,
+    /// Variance swap settlement
+    VarianceSwap,
+    /// Loss beyond a closed position's collateral
+    Shortfall,
+    /// Timelocked withdrawal by the market authority
+    Withdrawal,
+}
+
+impl InsuranceFund {
+    pub const SEED_PREFIX: &'static [u8] = b"insurance_fund";
+    pub const VAULT_SEED_PREFIX: &'static [u8] = b"insurance_vault";
+
+    /// Delay between requesting and executing a withdrawal
+    pub const WITHDRAWAL_TIMELOCK_SECONDS: i64 = 86400;
+
+    /// True when the fund alone can absorb `bad_debt`
+    pub fn can_cover(&self, bad_debt: u64) -> bool {
+        self.balance >= bad_debt
+    }
+}
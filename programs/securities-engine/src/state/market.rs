@@ -1,4 +1,8 @@
 use anchor_lang::prelude::*;
+use oracle::{FundingFeed, PriceFeed};
+
+use super::Position;
+use crate::SecuritiesError;
 
 /// Tokenized securities market configuration
 #[account]
@@ -65,6 +69,149 @@ pub struct Market {
 
     /// Bump seed
     pub bump: u8,
+
+    // =========================================================================
+    // LP governance
+    // =========================================================================
+
+    /// Allow LP holders to vote on `trading_fee_bps`
+    pub lp_governance_enabled: bool,
+
+    /// Lower bound for LP-governed fee changes
+    pub min_governed_fee_bps: u16,
+
+    /// Upper bound for LP-governed fee changes
+    pub max_governed_fee_bps: u16,
+
+    /// Number of fee proposals created (seeds the next proposal PDA)
+    pub fee_proposal_count: u64,
+
+    // =========================================================================
+    // Trading guards
+    // =========================================================================
+
+    /// Both pool reserves must exceed this before swaps are enabled
+    pub min_liquidity_to_trade: u64,
+
+    // =========================================================================
+    // Risk backstop
+    // =========================================================================
+
+    /// Allow auto-deleveraging of profitable positions when the insurance
+    /// fund cannot cover a bankrupt position
+    pub adl_enabled: bool,
+
+    /// Liquidator bounty in basis points of the remaining collateral
+    pub liquidation_bounty_bps: u16,
+
+    /// Maximum oracle confidence as a share of price for opening positions,
+    /// in basis points (0 = skip, for feeds that don't report confidence)
+    pub max_confidence_bps: u16,
+
+    // =========================================================================
+    // Versioning
+    // =========================================================================
+
+    /// Account layout version (see `Market::CURRENT_VERSION`)
+    pub version: u8,
+
+    // =========================================================================
+    // Trading schedule
+    // =========================================================================
+
+    /// Session open, seconds after 00:00 UTC
+    pub trading_open_seconds: u32,
+
+    /// Session close, seconds after 00:00 UTC. Equal to `trading_open_seconds`
+    /// means no schedule (24/7); a close before the open wraps past midnight.
+    pub trading_close_seconds: u32,
+
+    /// Hash of the off-chain holiday calendar the authority halts against
+    pub holiday_calendar_hash: [u8; 32],
+
+    /// Session state as of the last trade or `update_trading_session` crank
+    pub session_open: bool,
+
+    // =========================================================================
+    // Oracle deviation auto-pause
+    // =========================================================================
+
+    /// Pause the market when a trade finds the pool price too far from the oracle
+    pub auto_pause_enabled: bool,
+
+    /// Maximum pool/oracle price deviation, in basis points. With auto-pause
+    /// on, a trade finding the pool beyond it pauses the market; otherwise a
+    /// swap leaving the pool beyond it is rejected (0 = no check)
+    pub max_oracle_deviation_bps: u16,
+
+    // =========================================================================
+    // Funding
+    // =========================================================================
+
+    /// Maximum funding feed age before funding is refused, in seconds
+    /// (0 = `FundingFeed::MAX_STALENESS`)
+    pub funding_staleness_seconds: u32,
+
+    // =========================================================================
+    // Position close fee
+    // =========================================================================
+
+    /// Fee on closed position notional in basis points, credited to the
+    /// insurance fund (0 = no fee)
+    pub close_fee_bps: u16,
+
+    // =========================================================================
+    // Insurance fee routing
+    // =========================================================================
+
+    /// Insurance fund balance the fee routing aims for (0 = no routing)
+    pub target_insurance_balance: u64,
+
+    /// Share of the protocol fee routed to an empty insurance fund, in basis
+    /// points; tapers linearly to zero as the fund reaches its target
+    pub insurance_fee_share_bps: u16,
+
+    /// Protocol fees (in quote) routed to the treasury rather than the fund
+    pub treasury_fees_accrued: u64,
+
+    // =========================================================================
+    // Oracle dependence
+    // =========================================================================
+
+    /// Swaps and new positions fail closed without a fresh oracle price;
+    /// when false they run on pool mechanics alone
+    pub requires_oracle: bool,
+
+    // =========================================================================
+    // Funding cap
+    // =========================================================================
+
+    /// Most seconds of funding one `apply_funding` call charges; a longer
+    /// crank gap is not back-charged (0 = uncapped)
+    pub max_funding_interval_seconds: u32,
+
+    // =========================================================================
+    // Oracle failover
+    // =========================================================================
+
+    /// Price feed consulted when `oracle` is stale (None = fail closed on a stale primary)
+    pub fallback_oracle: Option<Pubkey>,
+
+    // =========================================================================
+    // Protocol fees
+    // =========================================================================
+
+    /// Protocol share of swap fees (in quote) held in the quote vault outside
+    /// the pool reserves, awaiting `withdraw_protocol_fees`
+    pub accumulated_protocol_fees: u64,
+
+    // =========================================================================
+    // Emergency shutdown
+    // =========================================================================
+
+    /// Price open positions settle at after an emergency shutdown, set once
+    /// by the guardian (0 = not set)
+    pub shutdown_settlement_price: u64,
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
@@ -81,6 +228,14 @@ pub enum MarketType {
     VarianceSwap,
 }
 
+impl MarketType {
+    /// Default for `Market::requires_oracle`: derivatives settle against the
+    /// oracle, spot markets can run on the pool alone
+    pub fn requires_oracle(&self) -> bool {
+        matches!(self, Self::Perpetual | Self::FundingSwap | Self::VarianceSwap)
+    }
+}
+
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
 pub enum MarketStatus {
     /// Market is active
@@ -93,20 +248,212 @@ pub enum MarketStatus {
     Closed,
 }
 
+impl MarketStatus {
+    /// Active and Paused toggle, either can enter settlement, and a settling
+    /// market can only close. Closed is final.
+    pub fn can_transition_to(self, next: MarketStatus) -> bool {
+        matches!(
+            (self, next),
+            (Self::Active, Self::Paused)
+                | (Self::Paused, Self::Active)
+                | (Self::Active | Self::Paused, Self::Settling)
+                | (Self::Settling, Self::Closed)
+        )
+    }
+}
+
 impl Market {
     pub const SEED_PREFIX: &'static [u8] = b"market";
+
+    /// Current account layout version. `migrate_market` upgrades older accounts:
+    /// - 0: created before versioning; fields appended after `bump` read as zero
+    /// - 1: adds `version`; all appended fields default to zero/false
+    /// - 2: adds the trading schedule (zero = always open)
+    /// - 3: adds oracle deviation auto-pause (disabled by default)
+    /// - 4: adds the funding feed staleness bound (zero = oracle default)
+    /// - 5: adds the position close fee (zero = no fee)
+    /// - 6: adds insurance fee routing (zero target = disabled)
+    /// - 7: adds `requires_oracle` (backfilled from the market type)
+    /// - 8: adds the funding interval cap (zero = uncapped)
+    /// - 9: adds `fallback_oracle` (None = no failover)
+    /// - 10: adds withdrawable protocol fees (zero = nothing accrued)
+    /// - 11: adds the shutdown settlement price (zero = not set)
+    pub const CURRENT_VERSION: u8 = 11;
     pub const SECONDS_PER_DAY: i64 = 86400;
 
+    /// Mark price from the market's oracle feed, in `Position::PRICE_PRECISION` units
+    pub fn mark_price(&self, feed: &Account<PriceFeed>, current_time: i64) -> Result<u64> {
+        require_keys_eq!(feed.key(), self.oracle, SecuritiesError::OracleMismatch);
+        Self::feed_price(feed, current_time)
+    }
+
+    /// Mark price from the primary oracle, or from `fallback_oracle` when the
+    /// primary is stale. Fails closed only when both are stale. Returns the
+    /// price and whether the fallback supplied it.
+    pub fn mark_price_with_fallback(
+        &self,
+        primary: &Account<PriceFeed>,
+        fallback: Option<&Account<PriceFeed>>,
+        current_time: i64,
+    ) -> Result<(u64, bool)> {
+        require_keys_eq!(primary.key(), self.oracle, SecuritiesError::OracleMismatch);
+        if !primary.is_stale(current_time) {
+            return Ok((Self::feed_price(primary, current_time)?, false));
+        }
+
+        let (Some(fallback_key), Some(fallback)) = (self.fallback_oracle, fallback) else {
+            return err!(SecuritiesError::StalePriceFeed);
+        };
+        require_keys_eq!(fallback.key(), fallback_key, SecuritiesError::OracleMismatch);
+        Ok((Self::feed_price(fallback, current_time)?, true))
+    }
+
+    /// Price from a fresh, active feed, in `Position::PRICE_PRECISION` units
+    fn feed_price(feed: &Account<PriceFeed>, current_time: i64) -> Result<u64> {
+        require!(feed.is_active && !feed.is_halted, SecuritiesError::InvalidOraclePrice);
+        require!(!feed.is_stale(current_time), SecuritiesError::StalePriceFeed);
+
+        let price = feed
+            .price_at_exponent(Position::PRICE_EXPONENT)
+            .ok_or(SecuritiesError::InvalidOraclePrice)?;
+        require!(price > 0, SecuritiesError::InvalidOraclePrice);
+        Ok(price)
+    }
+
+    /// Fail closed with `StalePriceFeed` on a missing or stale oracle when
+    /// the market requires one
+    pub fn check_required_oracle(
+        &self,
+        feed: Option<&Account<PriceFeed>>,
+        current_time: i64,
+    ) -> Result<()> {
+        if !self.requires_oracle {
+            return Ok(());
+        }
+        let feed = feed.ok_or(SecuritiesError::StalePriceFeed)?;
+        self.mark_price(feed, current_time).map(|_| ())
+    }
+
+    /// Mark price and funding rate, refusing both unless the price feed and the
+    /// funding feed are each within their staleness bound
+    pub fn mark_with_funding(
+        &self,
+        price_feed: &Account<PriceFeed>,
+        funding_feed: &FundingFeed,
+        current_time: i64,
+    ) -> Result<(u64, i64)> {
+        require!(!funding_feed.is_closed, SecuritiesError::InvalidOraclePrice);
+        let funding_staleness = match self.funding_staleness_seconds {
+            0 => FundingFeed::MAX_STALENESS,
+            seconds => seconds as i64,
+        };
+        require!(
+            !price_feed.is_stale(current_time)
+                && !funding_feed.is_stale_after(current_time, funding_staleness),
+            SecuritiesError::StalePriceFeed
+        );
+
+        let mark_price = self.mark_price(price_feed, current_time)?;
+        Ok((mark_price, funding_feed.aggregated_rate))
+    }
+
+    /// Close fee on `notional`, capped at what the position pays out
+    pub fn close_fee(&self, notional: u64, payout: u64) -> u64 {
+        let fee = (notional as u128 * self.close_fee_bps as u128 / 10000) as u64;
+        fee.min(payout)
+    }
+
+    /// Reject feeds whose confidence band is wider than `max_confidence_bps` of price
+    pub fn check_confidence(&self, feed: &PriceFeed) -> Result<()> {
+        if self.max_confidence_bps == 0 {
+            return Ok(());
+        }
+        require!(
+            feed.confidence as u128 * 10000
+                <= feed.current_price as u128 * self.max_confidence_bps as u128,
+            SecuritiesError::PriceTooUncertain
+        );
+        Ok(())
+    }
+
+    /// Whether swaps are rejected when they leave the pool beyond
+    /// `max_oracle_deviation_bps` of the oracle (auto-pause markets pause instead)
+    pub fn rejects_oracle_deviation(&self) -> bool {
+        !self.auto_pause_enabled && self.max_oracle_deviation_bps > 0
+    }
+
+    /// Distance between the pool spot price and the oracle price, in basis
+    /// points of the oracle price (both in `Pool::PRICE_PRECISION` units)
+    pub fn oracle_deviation_bps(spot_price: u64, oracle_price: u64) -> u64 {
+        if oracle_price == 0 {
+            return u64::MAX;
+        }
+        let diff = spot_price.abs_diff(oracle_price) as u128;
+        (diff * 10000 / oracle_price as u128).min(u64::MAX as u128) as u64
+    }
+
+    pub fn has_trading_schedule(&self) -> bool {
+        self.trading_open_seconds != self.trading_close_seconds
+    }
+
+    /// Whether `current_time` falls inside the daily session (always true without a schedule)
+    pub fn is_within_trading_hours(&self, current_time: i64) -> bool {
+        if !self.has_trading_schedule() {
+            return true;
+        }
+        let second_of_day = current_time.rem_euclid(Self::SECONDS_PER_DAY) as u32;
+        if self.trading_open_seconds < self.trading_close_seconds {
+            second_of_day >= self.trading_open_seconds && second_of_day < self.trading_close_seconds
+        } else {
+            second_of_day >= self.trading_open_seconds || second_of_day < self.trading_close_seconds
+        }
+    }
+
+    /// Record the session state at `current_time`, returning true if it changed
+    pub fn sync_trading_session(&mut self, current_time: i64) -> bool {
+        let open = self.is_within_trading_hours(current_time);
+        let changed = open != self.session_open;
+        self.session_open = open;
+        changed
+    }
+
+    /// Whether a trade of `amount` (in the input token) fits `min_trade_size`
+    /// and `max_trade_size` (0 = no maximum)
+    pub fn within_trade_size(&self, amount: u64) -> bool {
+        amount > 0
+            && amount >= self.min_trade_size
+            && (self.max_trade_size == 0 || amount <= self.max_trade_size)
+    }
+
     pub fn is_trading(&self) -> bool {
         self.is_active && matches!(self.status, MarketStatus::Active)
     }
 
+    /// Positions can still be reduced and closed while paused or settling
+    pub fn accepts_closes(&self) -> bool {
+        !matches!(self.status, MarketStatus::Closed)
+    }
+
     pub fn calculate_fee(&self, amount: u64) -> u64 {
         ((amount as u128 * self.trading_fee_bps as u128) / 10000) as u64
     }
 
     pub fn calculate_protocol_fee(&self, fee: u64) -> u64 {
-        ((fee as u128 * self.protocol_fee_bps as u128) / self.trading_fee_bps as u128) as u64
+        if self.trading_fee_bps == 0 {
+            return 0;
+        }
+        (((fee as u128 * self.protocol_fee_bps as u128) / self.trading_fee_bps as u128) as u64).min(fee)
+    }
+
+    /// Share of the protocol fee to route to an insurance fund holding
+    /// `fund_balance`, in basis points. Full share when empty, none at target.
+    pub fn insurance_routing_bps(&self, fund_balance: u64) -> u16 {
+        if fund_balance >= self.target_insurance_balance {
+            return 0;
+        }
+        let deficit = self.target_insurance_balance - fund_balance;
+        (self.insurance_fee_share_bps as u128 * deficit as u128
+            / self.target_insurance_balance as u128) as u16
     }
 
     pub fn update_volume(&mut self, amount: u64, current_time: i64) {
@@ -2,8 +2,14 @@ pub mod market;
 pub mod pool;
 pub mod position;
 pub mod order;
+pub mod governance;
+pub mod insurance;
+pub mod protocol;
 
 pub use market::*;
 pub use pool::*;
 pub use position::*;
 pub use order::*;
+pub use governance::*;
+pub use insurance::*;
+pub use protocol::*;
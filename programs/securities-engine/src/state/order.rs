@@ -1,5 +1,7 @@
 use anchor_lang::prelude::*;
 
+use super::{Position, Side};
+
 /// Limit order for order book trading
 #[account]
 #[derive(InitSpace)]
@@ -54,6 +56,16 @@ pub struct Order {
 
     /// Bump seed
     pub bump: u8,
+
+    // =========================================================================
+    // Placement
+    // =========================================================================
+
+    /// Owner-chosen nonce distinguishing the owner's orders on a market
+    pub nonce: u64,
+
+    /// Bump seed of the order's escrow token account
+    pub escrow_bump: u8,
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
@@ -104,11 +116,57 @@ pub enum OrderStatus {
 
 impl Order {
     pub const SEED_PREFIX: &'static [u8] = b"order";
+    pub const ESCROW_SEED_PREFIX: &'static [u8] = b"order_escrow";
+    pub const PRICE_PRECISION: u64 = 1_000_000; // 1e6
+
+    /// Quote needed to buy `size` at `price`, rounded up so the escrow
+    /// always covers the order
+    pub fn quote_for(size: u64, price: u64) -> Option<u64> {
+        let precision = Self::PRICE_PRECISION as u128;
+        let quote = (size as u128 * price as u128).div_ceil(precision);
+        u64::try_from(quote).ok()
+    }
 
     pub fn is_active(&self) -> bool {
         matches!(self.status, OrderStatus::Open | OrderStatus::PartiallyFilled)
     }
 
+    /// Most a reduce-only order on `side` may fill at `price`: the owner's
+    /// open position on the opposite side less what other resting reduce-only
+    /// orders have reserved, converted from quote notional to security units.
+    /// `own_reserved` is the order's own reservation (0 when placing it).
+    /// Zero when there is nothing to reduce.
+    pub fn reducible_size(
+        side: OrderSide,
+        price: u64,
+        position: Option<&Position>,
+        own_reserved: u64,
+    ) -> u64 {
+        let Some(position) = position else {
+            return 0;
+        };
+        let reduces = match side {
+            OrderSide::Buy => position.side == Side::Short,
+            OrderSide::Sell => position.side == Side::Long,
+        };
+        if !position.is_open || !reduces || price == 0 {
+            return 0;
+        }
+        let reserved_by_others = position.reduce_only_reserved.saturating_sub(own_reserved);
+        let unreserved = position.size.saturating_sub(reserved_by_others);
+        (unreserved as u128 * Self::PRICE_PRECISION as u128 / price as u128).min(u64::MAX as u128)
+            as u64
+    }
+
+    /// Notional a reduce-only order reserves on its position for what is
+    /// still open, at its limit price
+    pub fn reserved_notional(&self) -> u64 {
+        if !self.reduce_only {
+            return 0;
+        }
+        Self::quote_for(self.remaining_size, self.price).unwrap_or(u64::MAX)
+    }
+
     pub fn can_match(&self, current_price: u64) -> bool {
         if !self.is_active() {
             return false;
@@ -189,6 +247,70 @@ pub struct OrderBook {
 impl OrderBook {
     pub const SEED_PREFIX: &'static [u8] = b"orderbook";
 
+    /// Track a newly resting order. Best prices only tighten here.
+    pub fn add_order(&mut self, side: OrderSide, price: u64, size: u64) {
+        match side {
+            OrderSide::Buy => {
+                self.best_bid = self.best_bid.max(price);
+                self.bid_volume = self.bid_volume.saturating_add(size);
+            }
+            OrderSide::Sell => {
+                if self.best_ask == 0 || price < self.best_ask {
+                    self.best_ask = price;
+                }
+                self.ask_volume = self.ask_volume.saturating_add(size);
+            }
+        }
+        self.order_count = self.order_count.saturating_add(1);
+    }
+
+    /// Drop an order's unfilled size
+    pub fn remove_order(&mut self, side: OrderSide, remaining_size: u64) {
+        self.reduce_volume(side, remaining_size);
+        self.order_count = self.order_count.saturating_sub(1);
+    }
+
+    /// Book a fill against a resting order; `closes_order` once it is fully filled
+    pub fn record_fill(
+        &mut self,
+        side: OrderSide,
+        size: u64,
+        price: u64,
+        timestamp: i64,
+        closes_order: bool,
+    ) {
+        self.reduce_volume(side, size);
+        if closes_order {
+            self.order_count = self.order_count.saturating_sub(1);
+        }
+        self.record_trade(price, timestamp);
+    }
+
+    /// Book a trade that did not rest on the book (an IOC or FOK fill)
+    pub fn record_trade(&mut self, price: u64, timestamp: i64) {
+        self.last_trade_price = price;
+        self.last_trade_time = timestamp;
+    }
+
+    /// The next-best price can't be found without scanning every order, so a
+    /// side's best price only resets once that side has no volume left.
+    fn reduce_volume(&mut self, side: OrderSide, size: u64) {
+        match side {
+            OrderSide::Buy => {
+                self.bid_volume = self.bid_volume.saturating_sub(size);
+                if self.bid_volume == 0 {
+                    self.best_bid = 0;
+                }
+            }
+            OrderSide::Sell => {
+                self.ask_volume = self.ask_volume.saturating_sub(size);
+                if self.ask_volume == 0 {
+                    self.best_ask = 0;
+                }
+            }
+        }
+    }
+
     pub fn spread(&self) -> u64 {
         if self.best_ask > self.best_bid {
             self.best_ask - self.best_bid
@@ -34,7 +34,9 @@ pub struct Pool {
     /// Accumulated fees (quote)
     pub accumulated_fees_quote: u64,
 
-    /// TWAP (Time-Weighted Average Price) - scaled by 1e6
+    /// TWAP (Time-Weighted Average Price) - scaled by 1e6. Averages the
+    /// last completed `TWAP_WINDOW_SECONDS` window (the window so far until
+    /// the first one completes).
     pub twap: u64,
 
     /// Last TWAP update
@@ -57,19 +59,86 @@ pub struct Pool {
 
     /// Pool authority bump seed
     pub authority_bump: u8,
+
+    // =========================================================================
+    // Versioning
+    // =========================================================================
+
+    /// Account layout version (see `Pool::CURRENT_VERSION`)
+    pub version: u8,
+
+    // =========================================================================
+    // Windowed TWAP
+    // =========================================================================
+
+    /// Spot price at the last TWAP update: the price that held until now
+    pub twap_last_price: u64,
+
+    /// `cumulative_price` at the start of the current TWAP window
+    pub twap_window_cumulative: u128,
+
+    /// Start of the current TWAP window
+    pub twap_window_start: i64,
+}
+
+/// Result of `get_twap`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct TwapInfo {
+    /// Current spot price (1e6, 0 while the pool is empty)
+    pub spot_price: u64,
+    /// Windowed TWAP (1e6)
+    pub twap: u64,
+    /// Spot distance from the TWAP in basis points (0 without both prices)
+    pub divergence_bps: u64,
+    /// Start of the window the TWAP is accumulating
+    pub window_start: i64,
 }
 
 impl Pool {
     pub const SEED_PREFIX: &'static [u8] = b"pool";
     pub const PRICE_PRECISION: u64 = 1_000_000; // 1e6
 
-    /// Calculate current spot price (quote per security)
-    pub fn get_spot_price(&self) -> u64 {
-        if self.security_liquidity == 0 {
-            return 0;
+    /// Current account layout version. `migrate_pool` upgrades older accounts:
+    /// - 0: created before versioning; fields appended after `authority_bump`
+    ///   read as zero
+    /// - 1: adds `version` and the windowed TWAP (backfilled from the
+    ///   cumulative price so far)
+    pub const CURRENT_VERSION: u8 = 1;
+
+    /// Length of the window the TWAP averages over
+    pub const TWAP_WINDOW_SECONDS: i64 = 3600;
+
+    /// LP tokens locked out of the first deposit. They count toward
+    /// `lp_supply` but are never minted, so the pool can't be drained back to
+    /// a dust supply whose share price an attacker could inflate.
+    pub const MINIMUM_LIQUIDITY: u64 = 1_000;
+
+    /// Calculate current spot price (quote per security). `None` while either
+    /// reserve is empty: an empty pool has no price, not a price of zero.
+    pub fn get_spot_price(&self) -> Option<u64> {
+        if self.security_liquidity == 0 || self.quote_liquidity == 0 {
+            return None;
         }
-        ((self.quote_liquidity as u128 * Self::PRICE_PRECISION as u128)
-            / self.security_liquidity as u128) as u64
+        Some(
+            ((self.quote_liquidity as u128 * Self::PRICE_PRECISION as u128)
+                / self.security_liquidity as u128) as u64,
+        )
+    }
+
+    /// Both reserves strictly exceed `min_liquidity`
+    pub fn has_min_liquidity(&self, min_liquidity: u64) -> bool {
+        self.security_liquidity > min_liquidity && self.quote_liquidity > min_liquidity
+    }
+
+    /// Total accrued fees valued in quote: security-side fees converted at
+    /// `price` (quote per security, scaled by 1e6) plus quote-side fees.
+    /// The per-token accumulators remain the source of truth.
+    pub fn total_fees_in_quote(&self, price: u64) -> Option<u64> {
+        let security_fees_in_quote = (self.accumulated_fees_security as u128)
+            .checked_mul(price as u128)?
+            / Self::PRICE_PRECISION as u128;
+        let total = security_fees_in_quote.checked_add(self.accumulated_fees_quote as u128)?;
+        u64::try_from(total).ok()
     }
 
     /// Calculate output amount using constant product formula (x * y = k)
@@ -103,16 +172,18 @@ impl Pool {
         Some((output, fee_amount))
     }
 
-    /// Calculate LP tokens to mint for adding liquidity
+    /// LP tokens a deposit adds to `lp_supply`. On the first deposit this
+    /// includes the locked `MINIMUM_LIQUIDITY`.
     pub fn calculate_lp_tokens(
         &self,
         security_amount: u64,
         quote_amount: u64,
     ) -> Option<u64> {
         if self.lp_supply == 0 {
-            // Initial liquidity: sqrt(security * quote)
+            // Initial liquidity: sqrt(security * quote), in integer math so
+            // every validator computes the same amount
             let product = security_amount as u128 * quote_amount as u128;
-            Some((product as f64).sqrt() as u64)
+            u64::try_from(isqrt(product)).ok()
         } else {
             // Proportional to existing liquidity
             let security_ratio = (security_amount as u128 * self.lp_supply as u128)
@@ -125,6 +196,26 @@ impl Pool {
         }
     }
 
+    /// The part of a deposit of at most `max_security` and `max_quote` that
+    /// pairs at the current reserve ratio, as `(security, quote)`. The
+    /// over-supplied side is trimmed rather than donated to existing LPs; a
+    /// first deposit sets the ratio and is used in full. The paired side
+    /// rounds in the pool's favour.
+    pub fn optimal_deposit(&self, max_security: u64, max_quote: u64) -> Option<(u64, u64)> {
+        if self.lp_supply == 0 || self.security_liquidity == 0 || self.quote_liquidity == 0 {
+            return Some((max_security, max_quote));
+        }
+
+        let quote_needed = (max_security as u128 * self.quote_liquidity as u128)
+            .div_ceil(self.security_liquidity as u128);
+        if quote_needed <= max_quote as u128 {
+            return Some((max_security, u64::try_from(quote_needed).ok()?));
+        }
+        let security_needed =
+            max_quote as u128 * self.security_liquidity as u128 / self.quote_liquidity as u128;
+        Some((u64::try_from(security_needed).ok()?, max_quote))
+    }
+
     /// Calculate tokens to return for removing liquidity
     pub fn calculate_withdraw_amounts(&self, lp_amount: u64) -> Option<(u64, u64)> {
         if self.lp_supply == 0 || lp_amount > self.lp_supply {
@@ -139,41 +230,190 @@ impl Pool {
         Some((security_out, quote_out))
     }
 
-    /// Update TWAP with new price observation
-    pub fn update_twap(&mut self, current_time: i64) {
-        let time_elapsed = current_time - self.twap_last_update;
-        if time_elapsed <= 0 || self.security_liquidity == 0 {
-            return;
+    /// Output at the current spot price with no price impact, after fees.
+    /// The reference quote for bps-denominated slippage.
+    pub fn quote_swap_output(
+        &self,
+        input_amount: u64,
+        is_security_input: bool,
+        fee_bps: u16,
+    ) -> Option<u64> {
+        let (input_reserve, output_reserve) = if is_security_input {
+            (self.security_liquidity, self.quote_liquidity)
+        } else {
+            (self.quote_liquidity, self.security_liquidity)
+        };
+
+        if input_reserve == 0 || output_reserve == 0 {
+            return None;
         }
 
-        let current_price = self.get_spot_price();
-        self.cumulative_price += current_price as u128 * time_elapsed as u128;
-        self.twap = (self.cumulative_price / (current_time - self.created_at) as u128) as u64;
-        self.twap_last_update = current_time;
+        let fee = (input_amount as u128 * fee_bps as u128) / 10000;
+        let input_with_fee = input_amount as u128 - fee;
+        u64::try_from(input_with_fee * output_reserve as u128 / input_reserve as u128).ok()
+    }
+
+    /// LP tokens a deposit of at most `max_security` and `max_quote` earns
+    /// once trimmed to the pool ratio (see `optimal_deposit`), net of the
+    /// locked minimum on a first deposit. The reference quote for
+    /// bps-denominated slippage.
+    pub fn quote_lp_tokens(&self, max_security: u64, max_quote: u64) -> Option<u64> {
+        let (security_amount, quote_amount) = self.optimal_deposit(max_security, max_quote)?;
+        let lp_tokens = self.calculate_lp_tokens(security_amount, quote_amount)?;
+        if self.lp_supply == 0 {
+            return Some(lp_tokens.saturating_sub(Self::MINIMUM_LIQUIDITY));
+        }
+        Some(lp_tokens)
     }
 
-    /// Calculate price impact for a trade
-    pub fn calculate_price_impact(&self, input_amount: u64, is_security_input: bool) -> u64 {
-        let current_price = self.get_spot_price();
-        if current_price == 0 {
+    /// Largest security amount a sell limit at `limit_price` can swap into the
+    /// pool while its average fill, after fees, stays at or above the limit
+    pub fn max_sell_at_limit(&self, limit_price: u64, fee_bps: u16) -> u64 {
+        if limit_price == 0 || fee_bps >= 10000 {
             return 0;
         }
+        // Average fill y(1-f) / (x + s(1-f)) >= P  =>  s <= y/P - x/(1-f)
+        let precision = Self::PRICE_PRECISION as u128;
+        let max_reserve = self.quote_liquidity as u128 * precision / limit_price as u128;
+        let fee_adjusted = (self.security_liquidity as u128 * 10000)
+            .div_ceil(10000 - fee_bps as u128);
+        u64::try_from(max_reserve.saturating_sub(fee_adjusted)).unwrap_or(u64::MAX)
+    }
 
-        if let Some((output, _)) = self.calculate_swap_output(input_amount, is_security_input, 0) {
-            let effective_price = if is_security_input {
-                (output as u128 * Self::PRICE_PRECISION as u128 / input_amount as u128) as u64
-            } else {
-                (input_amount as u128 * Self::PRICE_PRECISION as u128 / output as u128) as u64
-            };
+    /// Largest security amount a buy limit at `limit_price` can take from the
+    /// pool while its average fill, after fees, stays at or below the limit
+    pub fn max_buy_at_limit(&self, limit_price: u64, fee_bps: u16) -> u64 {
+        if limit_price == 0 || fee_bps >= 10000 {
+            return 0;
+        }
+        // Average fill y / ((x - s)(1-f)) <= P  =>  s <= x - y/(P(1-f))
+        let precision = Self::PRICE_PRECISION as u128;
+        let min_reserve = (self.quote_liquidity as u128 * precision * 10000)
+            .div_ceil(limit_price as u128 * (10000 - fee_bps as u128));
+        (self.security_liquidity as u128).saturating_sub(min_reserve) as u64
+    }
+
+    /// Quote input, fee included, for the pool to return at least
+    /// `security_out`. `None` if the pool can't cover it.
+    pub fn quote_input_for(&self, security_out: u64, fee_bps: u16) -> Option<u64> {
+        if security_out >= self.security_liquidity || fee_bps >= 10000 {
+            return None;
+        }
+        let numerator = self.quote_liquidity as u128 * security_out as u128 * 10000;
+        let denominator =
+            (self.security_liquidity - security_out) as u128 * (10000 - fee_bps as u128);
+        u64::try_from(numerator.div_ceil(denominator)).ok()
+    }
+
+    /// Minimum acceptable amount given a quote and a tolerance in basis points
+    pub fn apply_slippage(expected: u64, slippage_bps: u16) -> u64 {
+        (expected as u128 * (10000 - slippage_bps.min(10000)) as u128 / 10000) as u64
+    }
 
-            // Price impact in basis points
-            if effective_price > current_price {
-                ((effective_price - current_price) as u128 * 10000 / current_price as u128) as u64
+    /// Book a swap into the reserves; the fee stays in the pool on the input side
+    pub fn apply_swap(&mut self, amount_in: u64, amount_out: u64, fee: u64, is_security_input: bool) {
+        if is_security_input {
+            self.security_liquidity = self.security_liquidity.saturating_add(amount_in);
+            self.quote_liquidity = self.quote_liquidity.saturating_sub(amount_out);
+            self.accumulated_fees_security = self.accumulated_fees_security.saturating_add(fee);
+        } else {
+            self.quote_liquidity = self.quote_liquidity.saturating_add(amount_in);
+            self.security_liquidity = self.security_liquidity.saturating_sub(amount_out);
+            self.accumulated_fees_quote = self.accumulated_fees_quote.saturating_add(fee);
+        }
+    }
+
+    /// Move the protocol's share of a swap fee, valued in quote at the
+    /// post-trade spot price, out of the LP reserves. The tokens stay in the
+    /// quote vault until withdrawn. Returns the amount carved out.
+    pub fn take_protocol_fee(&mut self, protocol_fee: u64, is_security_input: bool) -> Option<u64> {
+        let fee_quote = if is_security_input {
+            let price = self.get_spot_price()?;
+            (protocol_fee as u128 * price as u128 / Self::PRICE_PRECISION as u128) as u64
+        } else {
+            protocol_fee
+        };
+        let fee_quote = fee_quote.min(self.quote_liquidity);
+        self.quote_liquidity -= fee_quote;
+        Some(fee_quote)
+    }
+
+    /// Accrue the price that held since the last update into
+    /// `cumulative_price`, refresh the windowed `twap`, and record the
+    /// current spot price for the next interval. Call after the reserves
+    /// change. Returns whether time had elapsed since the last update.
+    pub fn update_twap(&mut self, current_time: i64) -> bool {
+        let time_elapsed = current_time - self.twap_last_update;
+        let accrued = time_elapsed > 0;
+        if accrued {
+            if self.twap_last_price == 0 {
+                // Nothing to average while the pool had no price: start afresh
+                self.twap_window_cumulative = self.cumulative_price;
+                self.twap_window_start = current_time;
             } else {
-                ((current_price - effective_price) as u128 * 10000 / current_price as u128) as u64
+                self.cumulative_price += self.twap_last_price as u128 * time_elapsed as u128;
             }
-        } else {
-            10000 // 100% impact if calculation fails
+            self.twap_last_update = current_time;
+
+            let window = current_time - self.twap_window_start;
+            if window > 0 && (window >= Self::TWAP_WINDOW_SECONDS || self.twap == 0) {
+                self.twap = ((self.cumulative_price - self.twap_window_cumulative)
+                    / window as u128) as u64;
+            }
+            if window >= Self::TWAP_WINDOW_SECONDS {
+                self.twap_window_cumulative = self.cumulative_price;
+                self.twap_window_start = current_time;
+            }
+        }
+        self.twap_last_price = self.get_spot_price().unwrap_or(0);
+        accrued
+    }
+
+    /// Distance of the spot price from the TWAP in basis points, or `None`
+    /// until the pool has both
+    pub fn price_divergence_bps(&self) -> Option<u64> {
+        let spot_price = self.get_spot_price()?;
+        if self.twap == 0 {
+            return None;
         }
+        let diff = spot_price.abs_diff(self.twap) as u128;
+        Some((diff * 10000 / self.twap as u128).min(u64::MAX as u128) as u64)
+    }
+
+    /// Calculate price impact for a trade, or `None` if the pool has no price
+    pub fn calculate_price_impact(&self, input_amount: u64, is_security_input: bool) -> Option<u64> {
+        let current_price = self.get_spot_price()?;
+
+        let output = match self.calculate_swap_output(input_amount, is_security_input, 0) {
+            Some((output, _)) if output > 0 && input_amount > 0 => output,
+            _ => return Some(10000), // 100% impact if calculation fails
+        };
+
+        let effective_price = if is_security_input {
+            (output as u128 * Self::PRICE_PRECISION as u128 / input_amount as u128) as u64
+        } else {
+            (input_amount as u128 * Self::PRICE_PRECISION as u128 / output as u128) as u64
+        };
+
+        // Price impact in basis points
+        Some(if effective_price > current_price {
+            ((effective_price - current_price) as u128 * 10000 / current_price as u128) as u64
+        } else {
+            ((current_price - effective_price) as u128 * 10000 / current_price as u128) as u64
+        })
+    }
+}
+
+/// Integer square root (floor)
+pub fn isqrt(value: u128) -> u128 {
+    if value < 2 {
+        return value;
+    }
+    let mut x = value;
+    let mut y = x / 2;
+    while y < x {
+        x = y;
+        y = (x + value / x) / 2;
     }
+    x
 }
@@ -57,6 +57,32 @@ pub struct Position {
 
     /// Bump seed
     pub bump: u8,
+
+    // =========================================================================
+    // Auto-deleveraging
+    // =========================================================================
+
+    /// Times this position was reduced by auto-deleveraging
+    pub adl_count: u32,
+
+    /// Last auto-deleveraging timestamp (0 = never)
+    pub last_adl_at: i64,
+
+    // =========================================================================
+    // Emergency shutdown
+    // =========================================================================
+
+    /// Closed by `settle_at_shutdown` at the market's shutdown settlement price
+    pub settled_at_shutdown: bool,
+
+    // =========================================================================
+    // Reduce-only orders
+    // =========================================================================
+
+    /// Notional (in quote) reserved by the owner's resting reduce-only orders,
+    /// released as they fill or are cancelled. Carries over when the PDA is
+    /// reused, since those orders outlive the position they were placed against.
+    pub reduce_only_reserved: u64,
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace)]
@@ -75,9 +101,17 @@ pub enum Side {
     Short,
 }
 
+/// Exit level that closed a position via `execute_trigger`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum TriggerType {
+    TakeProfit,
+    StopLoss,
+}
+
 impl Position {
     pub const SEED_PREFIX: &'static [u8] = b"position";
     pub const PRICE_PRECISION: u64 = 1_000_000; // 1e6
+    pub const PRICE_EXPONENT: i8 = -6;
     pub const MAINTENANCE_MARGIN_BPS: u64 = 500; // 5%
 
     /// Calculate unrealized PnL
@@ -98,10 +132,24 @@ impl Position {
         equity < maintenance_margin
     }
 
+    /// Liquidation eligibility at `current_price`. Shared by the read-only
+    /// scanner and the liquidation path so both agree on every input.
+    pub fn liquidation_status(&self, current_price: u64, bounty_bps: u16) -> LiquidationStatus {
+        let equity = self.equity(current_price);
+        let remaining = equity.clamp(0, u64::MAX as i128) as u128;
+        LiquidationStatus {
+            is_liquidatable: self.is_open && self.is_liquidatable(current_price),
+            margin_ratio_bps: self.margin_ratio(current_price),
+            equity: equity.clamp(i64::MIN as i128, i64::MAX as i128) as i64,
+            estimated_reward: (remaining * bounty_bps as u128 / 10000) as u64,
+            price: current_price,
+        }
+    }
+
     /// Calculate liquidation price
     pub fn calculate_liquidation_price(&self) -> u64 {
         let maintenance_margin = (self.size as u128 * Self::MAINTENANCE_MARGIN_BPS as u128) / 10000;
-        let max_loss = self.collateral as u128 - maintenance_margin;
+        let max_loss = (self.collateral as u128).saturating_sub(maintenance_margin);
         let price_move = (max_loss * Self::PRICE_PRECISION as u128) / self.size as u128;
 
         match self.side {
@@ -110,11 +158,123 @@ impl Position {
         }
     }
 
-    /// Apply funding payment
-    pub fn apply_funding(&mut self, funding_rate: i64, current_time: i64) {
-        let time_elapsed = current_time - self.last_funding_update;
+    /// Equity (collateral + PnL) at `current_price`
+    pub fn equity(&self, current_price: u64) -> i128 {
+        self.collateral as i128 + self.calculate_pnl(current_price)
+    }
+
+    /// Price at which equity reaches zero
+    pub fn bankruptcy_price(&self) -> u64 {
+        if self.size == 0 {
+            return self.entry_price;
+        }
+        let buffer = self.collateral as i128 + self.accumulated_funding;
+        let price_move = buffer * Self::PRICE_PRECISION as i128 / self.size as i128;
+        let price = match self.side {
+            Side::Long => self.entry_price as i128 - price_move,
+            Side::Short => self.entry_price as i128 + price_move,
+        };
+        price.clamp(0, u64::MAX as i128) as u64
+    }
+
+    /// Auto-deleveraging priority: return on collateral at `current_price`, in bps
+    pub fn adl_score(&self, current_price: u64) -> i128 {
+        if self.collateral == 0 {
+            return i128::MAX;
+        }
+        self.calculate_pnl(current_price) * 10000 / self.collateral as i128
+    }
+
+    /// Whether a take-profit or stop-loss level is set (0 = unset)
+    pub fn has_trigger(&self) -> bool {
+        self.take_profit > 0 || self.stop_loss > 0
+    }
+
+    /// The exit level `price` has crossed, if any. A long takes profit at or
+    /// above `take_profit` and stops out at or below `stop_loss`; a short the
+    /// reverse.
+    pub fn crossed_trigger(&self, price: u64) -> Option<TriggerType> {
+        let (take_profit_hit, stop_loss_hit) = match self.side {
+            Side::Long => (price >= self.take_profit, price <= self.stop_loss),
+            Side::Short => (price <= self.take_profit, price >= self.stop_loss),
+        };
+        if self.take_profit > 0 && take_profit_hit {
+            Some(TriggerType::TakeProfit)
+        } else if self.stop_loss > 0 && stop_loss_hit {
+            Some(TriggerType::StopLoss)
+        } else {
+            None
+        }
+    }
+
+    /// Reserve `notional` for a resting reduce-only order
+    pub fn reserve_reduce_only(&mut self, notional: u64) {
+        self.reduce_only_reserved = self.reduce_only_reserved.saturating_add(notional);
+    }
+
+    /// Release `notional` a reduce-only order no longer needs
+    pub fn release_reduce_only(&mut self, notional: u64) {
+        self.reduce_only_reserved = self.reduce_only_reserved.saturating_sub(notional);
+    }
+
+    /// Close `reduce_size` of the position at `price`, crediting the prorated
+    /// price and funding PnL to collateral. Returns the realized PnL.
+    pub fn realize(&mut self, price: u64, reduce_size: u64) -> i128 {
+        let reduce = reduce_size.min(self.size);
+        if reduce == 0 {
+            return 0;
+        }
+
+        let price_diff = price as i128 - self.entry_price as i128;
+        let price_pnl = match self.side {
+            Side::Long => (reduce as i128 * price_diff) / Self::PRICE_PRECISION as i128,
+            Side::Short => (reduce as i128 * -price_diff) / Self::PRICE_PRECISION as i128,
+        };
+        let funding = self.accumulated_funding * reduce as i128 / self.size as i128;
+        self.accumulated_funding -= funding;
+
+        let realized = price_pnl + funding;
+        self.collateral = (self.collateral as i128 + realized).clamp(0, u64::MAX as i128) as u64;
+        self.size -= reduce;
+
+        if self.size == 0 {
+            self.is_open = false;
+        } else {
+            self.liquidation_price = self.calculate_liquidation_price();
+        }
+
+        realized
+    }
+
+    /// Add `add_size` at `price`, blending the entry price by size so the
+    /// PnL at `price` is unchanged. `None` on overflow.
+    pub fn increase(&mut self, price: u64, add_size: u64) -> Option<()> {
+        let new_size = self.size.checked_add(add_size)?;
+        let blended = (self.size as u128 * self.entry_price as u128
+            + add_size as u128 * price as u128)
+            / new_size as u128;
+        self.entry_price = u64::try_from(blended).ok()?;
+        self.size = new_size;
+        Some(())
+    }
+
+    /// Size over collateral, rounded up. `None` without collateral.
+    pub fn current_leverage(&self) -> Option<u64> {
+        if self.collateral == 0 {
+            return None;
+        }
+        Some(self.size.div_ceil(self.collateral))
+    }
+
+    /// Apply funding payment for the time since the last update, charging at
+    /// most `max_elapsed` seconds (0 = uncapped). Returns the seconds charged.
+    pub fn apply_funding(&mut self, funding_rate: i64, current_time: i64, max_elapsed: i64) -> i64 {
+        let mut time_elapsed = current_time - self.last_funding_update;
         if time_elapsed <= 0 {
-            return;
+            return 0;
+        }
+        if max_elapsed > 0 {
+            time_elapsed = time_elapsed.min(max_elapsed);
         }
 
         // Funding = size * rate * time / (8 hours in seconds)
@@ -127,6 +287,7 @@ impl Position {
         };
 
         self.last_funding_update = current_time;
+        time_elapsed
     }
 
     /// Calculate margin ratio
@@ -140,23 +301,70 @@ impl Position {
     }
 }
 
+/// Result of `Position::liquidation_status`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct LiquidationStatus {
+    pub is_liquidatable: bool,
+    /// Equity / size in basis points
+    pub margin_ratio_bps: u64,
+    /// Collateral plus PnL (negative = bad debt)
+    pub equity: i64,
+    /// Liquidator bounty if liquidated at `price`
+    pub estimated_reward: u64,
+    /// Price the status was evaluated at (1e6)
+    pub price: u64,
+}
+
+/// Result of `get_position_summary`: a trader's aggregate book at oracle prices
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct PositionSummary {
+    /// Open positions included
+    pub position_count: u32,
+    /// Sum of position sizes (in quote)
+    pub total_notional: u64,
+    /// Sum of posted collateral (in quote)
+    pub total_collateral: u64,
+    /// Price plus funding PnL across all positions
+    pub net_pnl: i64,
+}
+
+impl PositionSummary {
+    /// Fold an open position marked at `current_price` into the summary
+    pub fn add(&mut self, position: &Position, current_price: u64) {
+        if !position.is_open {
+            return;
+        }
+        let pnl = self.net_pnl as i128 + position.calculate_pnl(current_price);
+
+        self.position_count += 1;
+        self.total_notional = self.total_notional.saturating_add(position.size);
+        self.total_collateral = self.total_collateral.saturating_add(position.collateral);
+        self.net_pnl = pnl.clamp(i64::MIN as i128, i64::MAX as i128) as i64;
+    }
+}
+
 /// Variance swap specific data
+///
+/// Variances are annualized (σ², with σ the annualized volatility) and scaled
+/// by `VARIANCE_PRECISION`: 40_000 is a variance of 0.04, i.e. 20% volatility.
+/// The long receives `variance_notional` quote units per 1.0 of variance that
+/// realized exceeds strike, and pays the same per 1.0 it falls short.
 #[account]
 #[derive(InitSpace)]
 pub struct VarianceSwapData {
     /// Associated position
     pub position: Pubkey,
 
-    /// Strike variance (annualized, scaled by 1e6)
+    /// Strike variance (annualized, scaled by `VARIANCE_PRECISION`)
     pub strike_variance: u64,
 
-    /// Realized variance accumulated
+    /// Realized variance accumulated (annualized, scaled by `VARIANCE_PRECISION`)
     pub realized_variance: u64,
 
     /// Number of observations
     pub observation_count: u32,
 
-    /// Variance notional
+    /// Quote units paid per 1.0 of variance difference
     pub variance_notional: u64,
 
     /// Settlement date
@@ -167,14 +375,127 @@ pub struct VarianceSwapData {
 
     /// Bump seed
     pub bump: u8,
+
+    // =========================================================================
+    // Observations
+    // =========================================================================
+
+    /// Most recently observed price
+    pub last_observed_price: u64,
+
+    /// Timestamp of the first observation
+    pub first_observed_at: i64,
+
+    /// Timestamp of the most recent observation
+    pub last_observed_at: i64,
+
+    /// Sum of squared log returns between observations, scaled by `LOG_PRECISION`²
+    pub sum_squared_returns: u128,
+
+    /// Seconds between observations, fixed when the swap is opened
+    pub observation_interval: i64,
 }
 
 impl VarianceSwapData {
     pub const SEED_PREFIX: &'static [u8] = b"variance_swap";
+    /// Fixed-point scale of strike and realized variance (1_000_000 = 1.0)
+    pub const VARIANCE_PRECISION: u64 = 1_000_000;
+    /// Largest accepted variance, 25.0 (500% volatility). Anything above is
+    /// taken as a scaling mistake rather than a real strike.
+    pub const MAX_VARIANCE: u64 = 25 * Self::VARIANCE_PRECISION;
+    /// Fixed-point scale of log returns (1e12 = 1.0)
+    pub const LOG_PRECISION: i128 = 1_000_000_000_000;
+    /// ln(2), scaled by `LOG_PRECISION`
+    const LN_2: i128 = 693_147_180_560;
+    pub const SECONDS_PER_YEAR: i64 = 365 * 86_400;
+
+    /// Strike within (0, `MAX_VARIANCE`] and a notional whose worst-case
+    /// settlement fits in an i64
+    pub fn valid_terms(strike_variance: u64, variance_notional: u64) -> bool {
+        let max_notional = i64::MAX as u64 / (Self::MAX_VARIANCE / Self::VARIANCE_PRECISION);
+        strike_variance > 0
+            && strike_variance <= Self::MAX_VARIANCE
+            && variance_notional > 0
+            && variance_notional <= max_notional
+    }
+
+    /// Earliest time the next observation may be recorded. Observations sit on
+    /// a grid of `observation_interval` from the first one, so a late crank
+    /// doesn't let the following one come early.
+    pub fn next_observation_at(&self) -> i64 {
+        self.first_observed_at
+            .saturating_add(self.observation_interval.saturating_mul(self.observation_count as i64))
+    }
 
-    /// Calculate settlement amount
+    /// Fold an observed price into the realized variance. Each observation
+    /// after the first adds its squared log return; the sum is annualized over
+    /// the time since the first observation and capped at `MAX_VARIANCE`.
+    /// Returns the log return, scaled by `LOG_PRECISION` (0 for the first).
+    pub fn record_observation(&mut self, price: u64, timestamp: i64) -> i128 {
+        let mut log_return = 0;
+        if self.observation_count == 0 {
+            self.first_observed_at = timestamp;
+        } else {
+            log_return = Self::ln_ratio(price, self.last_observed_price);
+            self.sum_squared_returns = self
+                .sum_squared_returns
+                .saturating_add((log_return * log_return) as u128);
+
+            let elapsed = (timestamp - self.first_observed_at).max(1) as u128;
+            // The sum is scaled by LOG_PRECISION²; rescale to VARIANCE_PRECISION
+            let rescale = (Self::LOG_PRECISION * Self::LOG_PRECISION) as u128
+                / Self::VARIANCE_PRECISION as u128;
+            let annualized = self
+                .sum_squared_returns
+                .saturating_mul(Self::SECONDS_PER_YEAR as u128)
+                / elapsed
+                / rescale;
+            self.realized_variance = annualized.min(Self::MAX_VARIANCE as u128) as u64;
+        }
+        self.last_observed_price = price;
+        self.last_observed_at = timestamp;
+        self.observation_count = self.observation_count.saturating_add(1);
+        log_return
+    }
+
+    /// ln(numerator / denominator), scaled by `LOG_PRECISION`. Both inputs
+    /// must be non-zero.
+    fn ln_ratio(numerator: u64, denominator: u64) -> i128 {
+        // Reduce to x = ratio / 2^k in [1, 2), then ln(ratio) = k·ln(2) + ln(x)
+        let (mut num, mut den) = (numerator as i128, denominator as i128);
+        let mut k = 0;
+        while num >= den * 2 {
+            den *= 2;
+            k += 1;
+        }
+        while num < den {
+            num *= 2;
+            k -= 1;
+        }
+
+        // ln(x) = 2·atanh(z) = 2(z + z³/3 + z⁵/5 + ...), z = (x − 1)/(x + 1) < 1/3
+        let z = (num - den) * Self::LOG_PRECISION / (num + den);
+        let z_squared = z * z / Self::LOG_PRECISION;
+        let mut term = z;
+        let mut sum = 0;
+        let mut n = 1;
+        while term != 0 {
+            sum += term / n;
+            term = term * z_squared / Self::LOG_PRECISION;
+            n += 2;
+        }
+        k * Self::LN_2 + 2 * sum
+    }
+
+    /// Settlement at the accumulated realized variance; positive pays the long
     pub fn calculate_settlement(&self) -> i128 {
-        let variance_diff = self.realized_variance as i128 - self.strike_variance as i128;
-        (self.variance_notional as i128 * variance_diff) / 1_000_000
+        self.settlement_at(self.realized_variance)
+    }
+
+    /// Settlement if realized variance were `realized_variance`:
+    /// `variance_notional × (realized − strike) / VARIANCE_PRECISION`
+    pub fn settlement_at(&self, realized_variance: u64) -> i128 {
+        let variance_diff = realized_variance as i128 - self.strike_variance as i128;
+        (self.variance_notional as i128 * variance_diff) / Self::VARIANCE_PRECISION as i128
     }
 }
@@ -0,0 +1,22 @@
+use anchor_lang::prelude::*;
+
+/// Program-wide guardian and emergency shutdown switch
+#[account]
+#[derive(InitSpace)]
+pub struct ProtocolState {
+    /// Guardian multisig allowed to trigger an emergency shutdown
+    pub guardian: Pubkey,
+
+    /// New activity is frozen and open positions settle via `settle_at_shutdown`
+    pub is_shutdown: bool,
+
+    /// Shutdown timestamp (0 = running)
+    pub shutdown_at: i64,
+
+    /// Bump seed
+    pub bump: u8,
+}
+
+impl ProtocolState {
+    pub const SEED_PREFIX: &'static [u8] = b"protocol_state";
+}
@@ -2,9 +2,14 @@ import * as anchor from '@coral-xyz/anchor';
 import { Program } from '@coral-xyz/anchor';
 import { PublicKey, Keypair, SystemProgram } from '@solana/web3.js';
 import {
+  TOKEN_PROGRAM_ID,
   TOKEN_2022_PROGRAM_ID,
   getAssociatedTokenAddressSync,
   createAssociatedTokenAccount,
+  createMint,
+  createAccount,
+  mintTo,
+  getAccount,
   ASSOCIATED_TOKEN_PROGRAM_ID,
 } from '@solana/spl-token';
 import { expect } from 'chai';
@@ -20,6 +25,7 @@ describe('meridian-stablecoin', () => {
   let mintConfigBump: number;
   let mintKeypair: Keypair;
   let collateralVaultPda: PublicKey;
+  let roleConfigPda: PublicKey;
   let issuerPda: PublicKey;
   let transferHookProgramId: PublicKey;
 
@@ -40,6 +46,12 @@ describe('meridian-stablecoin', () => {
       program.programId
     );
 
+    // Passed to mint even before initialize_roles creates it
+    [roleConfigPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from('role_config'), mintConfigPda.toBuffer()],
+      program.programId
+    );
+
     [issuerPda] = PublicKey.findProgramAddressSync(
       [Buffer.from('issuer'), authority.publicKey.toBuffer()],
       program.programId
@@ -116,6 +128,8 @@ describe('meridian-stablecoin', () => {
           dailyMintLimit: newDailyLimit,
           dailyBurnLimit: null,
           isActive: null,
+          attributedCollateral: null,
+          minMintInterval: null,
         })
         .accounts({
           authority: authority.publicKey,
@@ -164,6 +178,8 @@ describe('meridian-stablecoin', () => {
         .initializeVault({
           collateralType: { fiat: {} },
           auditor: authority.publicKey,
+          auditFloorBps: null,
+          priceFeed: null,
         })
         .accounts({
           authority: authority.publicKey,
@@ -191,6 +207,7 @@ describe('meridian-stablecoin', () => {
           authority: authority.publicKey,
           mintConfig: mintConfigPda,
           collateralVault: collateralVaultPda,
+          priceFeed: null,
         })
         .rpc();
 
@@ -228,9 +245,12 @@ describe('meridian-stablecoin', () => {
         .accounts({
           issuerAuthority: authority.publicKey,
           mintConfig: mintConfigPda,
+          roleConfig: roleConfigPda,
           issuer: issuerPda,
           mint: mintKeypair.publicKey,
           recipientTokenAccount: recipientAta,
+          collateralVault: collateralVaultPda,
+          priceFeed: null,
           tokenProgram: TOKEN_2022_PROGRAM_ID,
         })
         .rpc();
@@ -250,9 +270,14 @@ describe('meridian-stablecoin', () => {
         })
         .accounts({
           holder: recipient.publicKey,
+          burner: authority.publicKey,
           mintConfig: mintConfigPda,
+          roleConfig: null,
           mint: mintKeypair.publicKey,
           holderTokenAccount: recipientAta,
+          issuer: null,
+          collateralVault: null,
+          priceFeed: null,
           tokenProgram: TOKEN_2022_PROGRAM_ID,
         })
         .signers([recipient])
@@ -262,12 +287,120 @@ describe('meridian-stablecoin', () => {
       expect(mintConfig.totalSupply.toNumber()).to.equal(500_000_00);
     });
 
+    it('should release collateral 1:1 when burning against an unpriced vault', async () => {
+      const amount = new anchor.BN(1_000_00);
+      const configBefore = await program.account.mintConfig.fetch(mintConfigPda);
+      const vaultBefore = await program.account.collateralVault.fetch(collateralVaultPda);
+
+      await program.methods
+        .burn({ amount, redemptionInfo: Array.from(Buffer.alloc(64)) })
+        .accounts({
+          holder: recipient.publicKey,
+          burner: authority.publicKey,
+          mintConfig: mintConfigPda,
+          roleConfig: null,
+          mint: mintKeypair.publicKey,
+          holderTokenAccount: recipientAta,
+          issuer: null,
+          collateralVault: collateralVaultPda,
+          priceFeed: null,
+          tokenProgram: TOKEN_2022_PROGRAM_ID,
+        })
+        .signers([recipient])
+        .rpc();
+
+      const configAfter = await program.account.mintConfig.fetch(mintConfigPda);
+      const vaultAfter = await program.account.collateralVault.fetch(collateralVaultPda);
+      expect(configBefore.totalSupply.sub(configAfter.totalSupply).toString()).to.equal(amount.toString());
+      expect(configBefore.totalCollateral.sub(configAfter.totalCollateral).toString())
+        .to.equal(amount.toString());
+      expect(vaultBefore.totalCollateral.sub(vaultAfter.totalCollateral).toString())
+        .to.equal(amount.toString());
+    });
+
+    function burnFrom(burner: Keypair) {
+      return program.methods
+        .burn({ amount: new anchor.BN(1_00), redemptionInfo: Array.from(Buffer.alloc(64)) })
+        .accounts({
+          holder: recipient.publicKey,
+          burner: burner.publicKey,
+          mintConfig: mintConfigPda,
+          roleConfig: null,
+          mint: mintKeypair.publicKey,
+          holderTokenAccount: recipientAta,
+          issuer: null,
+          collateralVault: null,
+          priceFeed: null,
+          tokenProgram: TOKEN_2022_PROGRAM_ID,
+        })
+        .signers(burner === recipient ? [recipient] : [recipient, burner])
+        .rpc();
+    }
+
+    it('should let a holder redeem without the burner role', async () => {
+      const before = await program.account.mintConfig.fetch(mintConfigPda);
+      await burnFrom(recipient);
+      const after = await program.account.mintConfig.fetch(mintConfigPda);
+      expect(before.totalSupply.sub(after.totalSupply).toNumber()).to.equal(1_00);
+    });
+
+    it('should reject a third-party burner without the role', async () => {
+      try {
+        await burnFrom(Keypair.generate());
+        expect.fail('Should have thrown InvalidRole');
+      } catch (err: any) {
+        expect(err.toString()).to.include('InvalidRole');
+      }
+    });
+
+    it('should let a registered issuer mint while no minter is assigned', async () => {
+      const distributor = Keypair.generate();
+      const [distributorIssuerPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from('issuer'), distributor.publicKey.toBuffer()],
+        program.programId
+      );
+      await program.methods
+        .registerIssuer({
+          issuerAuthority: distributor.publicKey,
+          issuerType: { distributor: {} },
+          dailyMintLimit: new anchor.BN(1_000_00),
+          dailyBurnLimit: new anchor.BN(0),
+        })
+        .accounts({
+          authority: authority.publicKey,
+          mintConfig: mintConfigPda,
+          issuer: distributorIssuerPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      await program.methods
+        .mint({ amount: new anchor.BN(1_00), reference: Array.from(Buffer.alloc(32)) })
+        .accounts({
+          issuerAuthority: distributor.publicKey,
+          mintConfig: mintConfigPda,
+          roleConfig: roleConfigPda,
+          issuer: distributorIssuerPda,
+          mint: mintKeypair.publicKey,
+          recipientTokenAccount: recipientAta,
+          collateralVault: collateralVaultPda,
+          priceFeed: null,
+          tokenProgram: TOKEN_2022_PROGRAM_ID,
+        })
+        .signers([distributor])
+        .rpc();
+
+      const issuer = await program.account.issuer.fetch(distributorIssuerPda);
+      expect(issuer.totalMinted.toNumber()).to.equal(1_00);
+    });
+
     it('should reject minting when paused', async () => {
       await program.methods
         .pause()
         .accounts({
           authority: authority.publicKey,
           mintConfig: mintConfigPda,
+          roleConfig: null,
         })
         .rpc();
 
@@ -281,9 +414,12 @@ describe('meridian-stablecoin', () => {
           .accounts({
             issuerAuthority: authority.publicKey,
             mintConfig: mintConfigPda,
+            roleConfig: roleConfigPda,
             issuer: issuerPda,
             mint: mintKeypair.publicKey,
             recipientTokenAccount: recipientAta,
+            collateralVault: collateralVaultPda,
+            priceFeed: null,
             tokenProgram: TOKEN_2022_PROGRAM_ID,
           })
           .rpc();
@@ -297,11 +433,201 @@ describe('meridian-stablecoin', () => {
         .accounts({
           authority: authority.publicKey,
           mintConfig: mintConfigPda,
+          roleConfig: null,
         })
         .rpc();
 
       expect(mintFailed).to.be.true;
     });
+
+    it('should require the issuer attribution to cover its outstanding mint', async () => {
+      await program.methods
+        .updateMintConfig({ seizeEscrow: null, escrowBps: null, requireIssuerAttribution: true })
+        .accounts({ authority: authority.publicKey, mintConfig: mintConfigPda })
+        .rpc();
+
+      const mintAccounts = {
+        issuerAuthority: authority.publicKey,
+        mintConfig: mintConfigPda,
+        roleConfig: roleConfigPda,
+        issuer: issuerPda,
+        mint: mintKeypair.publicKey,
+        recipientTokenAccount: recipientAta,
+        collateralVault: collateralVaultPda,
+        priceFeed: null,
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+      };
+      const params = { amount: new anchor.BN(1_000_00), reference: Array.from(Buffer.alloc(32)) };
+
+      try {
+        await program.methods.mint(params).accounts(mintAccounts).rpc();
+        expect.fail('Should have thrown IssuerUndercollateralized');
+      } catch (err: any) {
+        expect(err.toString()).to.include('IssuerUndercollateralized');
+      }
+
+      // Attribute exactly enough for the existing mint plus this one
+      const before = await program.account.issuer.fetch(issuerPda);
+      await program.methods
+        .updateIssuer({
+          dailyMintLimit: null,
+          dailyBurnLimit: null,
+          isActive: null,
+          attributedCollateral: before.totalMinted.add(params.amount),
+          minMintInterval: null,
+        })
+        .accounts({ authority: authority.publicKey, mintConfig: mintConfigPda, issuer: issuerPda })
+        .rpc();
+      await program.methods.mint(params).accounts(mintAccounts).rpc();
+
+      let issuer = await program.account.issuer.fetch(issuerPda);
+      expect(issuer.totalMinted.toString()).to.equal(issuer.attributedCollateral.toString());
+
+      // A redemption attributed to the issuer nets its outstanding mint and
+      // releases collateral out of its attribution
+      await program.methods
+        .burn({ amount: new anchor.BN(1_000_00), redemptionInfo: Array.from(Buffer.alloc(64)) })
+        .accounts({
+          holder: recipient.publicKey,
+          burner: authority.publicKey,
+          mintConfig: mintConfigPda,
+          roleConfig: null,
+          mint: mintKeypair.publicKey,
+          holderTokenAccount: recipientAta,
+          issuer: issuerPda,
+          collateralVault: collateralVaultPda,
+          priceFeed: null,
+          tokenProgram: TOKEN_2022_PROGRAM_ID,
+        })
+        .signers([recipient])
+        .rpc();
+      issuer = await program.account.issuer.fetch(issuerPda);
+      expect(issuer.totalBurned.toNumber()).to.equal(1_000_00);
+      expect(issuer.attributedCollateral.toString())
+        .to.equal(issuer.totalMinted.subn(1_000_00).toString());
+      let mintConfig = await program.account.mintConfig.fetch(mintConfigPda);
+      expect(mintConfig.attributedCollateral.toString()).to.equal(issuer.attributedCollateral.toString());
+
+      // Attributions can't add up to more than the mint's collateral
+      const attribute = (amount: anchor.BN) =>
+        program.methods
+          .updateIssuer({
+            dailyMintLimit: null,
+            dailyBurnLimit: null,
+            isActive: null,
+            attributedCollateral: amount,
+            minMintInterval: null,
+          })
+          .accounts({ authority: authority.publicKey, mintConfig: mintConfigPda, issuer: issuerPda })
+          .rpc();
+      try {
+        await attribute(mintConfig.totalCollateral.addn(1));
+        expect.fail('Should have thrown AttributionExceedsCollateral');
+      } catch (err: any) {
+        expect(err.toString()).to.include('AttributionExceedsCollateral');
+      }
+      await attribute(new anchor.BN(0));
+      mintConfig = await program.account.mintConfig.fetch(mintConfigPda);
+      expect(mintConfig.attributedCollateral.toNumber()).to.equal(0);
+
+      await program.methods
+        .updateMintConfig({ seizeEscrow: null, escrowBps: null, requireIssuerAttribution: false })
+        .accounts({ authority: authority.publicKey, mintConfig: mintConfigPda })
+        .rpc();
+    });
+
+    it('should reject back-to-back mints inside the minimum interval', async () => {
+      const mintAccounts = {
+        issuerAuthority: authority.publicKey,
+        mintConfig: mintConfigPda,
+        roleConfig: roleConfigPda,
+        issuer: issuerPda,
+        mint: mintKeypair.publicKey,
+        recipientTokenAccount: recipientAta,
+        collateralVault: collateralVaultPda,
+        priceFeed: null,
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+      };
+      const params = { amount: new anchor.BN(1_00), reference: Array.from(Buffer.alloc(32)) };
+      const setInterval = (seconds: number) =>
+        program.methods
+          .updateIssuer({
+            dailyMintLimit: null,
+            dailyBurnLimit: null,
+            isActive: null,
+            attributedCollateral: null,
+            minMintInterval: new anchor.BN(seconds),
+          })
+          .accounts({ authority: authority.publicKey, mintConfig: mintConfigPda, issuer: issuerPda })
+          .rpc();
+
+      await program.methods.mint(params).accounts(mintAccounts).rpc();
+      await setInterval(3);
+      try {
+        try {
+          await program.methods.mint(params).accounts(mintAccounts).rpc();
+          expect.fail('Should have thrown MintTooFrequent');
+        } catch (err: any) {
+          expect(err.toString()).to.include('MintTooFrequent');
+        }
+
+        await new Promise((resolve) => setTimeout(resolve, 4000));
+        await program.methods.mint(params).accounts(mintAccounts).rpc();
+      } finally {
+        await setInterval(0);
+      }
+    });
+
+    it('should mint a single subunit (0.01) without rounding it away', async () => {
+      const before = await program.account.mintConfig.fetch(mintConfigPda);
+      const ataBefore = await getAccount(provider.connection, recipientAta, undefined, TOKEN_2022_PROGRAM_ID);
+
+      await program.methods
+        .mint({ amount: new anchor.BN(1), reference: Array.from(Buffer.alloc(32)) })
+        .accounts({
+          issuerAuthority: authority.publicKey,
+          mintConfig: mintConfigPda,
+          roleConfig: roleConfigPda,
+          issuer: issuerPda,
+          mint: mintKeypair.publicKey,
+          recipientTokenAccount: recipientAta,
+          collateralVault: collateralVaultPda,
+          priceFeed: null,
+          tokenProgram: TOKEN_2022_PROGRAM_ID,
+        })
+        .rpc();
+
+      const after = await program.account.mintConfig.fetch(mintConfigPda);
+      const ataAfter = await getAccount(provider.connection, recipientAta, undefined, TOKEN_2022_PROGRAM_ID);
+      expect(after.totalSupply.sub(before.totalSupply).toNumber()).to.equal(1);
+      expect(Number(ataAfter.amount - ataBefore.amount)).to.equal(1);
+    });
+
+    it('should move collateral in single-subunit steps', async () => {
+      const before = await program.account.collateralVault.fetch(collateralVaultPda);
+      const update = (isDeposit: boolean) =>
+        program.methods
+          .updateCollateral({
+            amount: new anchor.BN(1),
+            isDeposit,
+            proofHash: Array.from(Buffer.alloc(32)),
+          })
+          .accounts({
+            authority: authority.publicKey,
+            mintConfig: mintConfigPda,
+            collateralVault: collateralVaultPda,
+            priceFeed: null,
+          })
+          .rpc();
+
+      await update(true);
+      let vault = await program.account.collateralVault.fetch(collateralVaultPda);
+      expect(vault.totalCollateral.sub(before.totalCollateral).toNumber()).to.equal(1);
+
+      await update(false);
+      vault = await program.account.collateralVault.fetch(collateralVaultPda);
+      expect(vault.totalCollateral.toString()).to.equal(before.totalCollateral.toString());
+    });
   });
 
   describe('pause and unpause', () => {
@@ -311,6 +637,7 @@ describe('meridian-stablecoin', () => {
         .accounts({
           authority: authority.publicKey,
           mintConfig: mintConfigPda,
+          roleConfig: null,
         })
         .rpc();
 
@@ -324,6 +651,7 @@ describe('meridian-stablecoin', () => {
         .accounts({
           authority: authority.publicKey,
           mintConfig: mintConfigPda,
+          roleConfig: null,
         })
         .rpc();
 
@@ -340,6 +668,7 @@ describe('meridian-stablecoin', () => {
           .accounts({
             authority: fakeAuthority.publicKey,
             mintConfig: mintConfigPda,
+            roleConfig: null,
           })
           .signers([fakeAuthority])
           .rpc();
@@ -364,11 +693,88 @@ describe('meridian-stablecoin', () => {
           auditor: authority.publicKey,
           mintConfig: mintConfigPda,
           collateralVault: collateralVaultPda,
+          priceFeed: null,
         })
         .rpc();
 
       const vault = await program.account.collateralVault.fetch(collateralVaultPda);
       expect(vault.lastAuditAt.toNumber()).to.be.greaterThan(0);
+      expect(vault.lastAuditedAmount.toNumber()).to.equal(100_000_000_00);
+    });
+
+    it('should reject withdrawal below the audited floor', async () => {
+      await program.methods
+        .updateVaultConfig({ auditFloorBps: 9000 })
+        .accounts({
+          authority: authority.publicKey,
+          mintConfig: mintConfigPda,
+          collateralVault: collateralVaultPda,
+        })
+        .rpc();
+
+      try {
+        await program.methods
+          .updateCollateral({
+            amount: new anchor.BN(20_000_000_00),
+            isDeposit: false,
+            proofHash: Array.from(Buffer.alloc(32)),
+          })
+          .accounts({
+            authority: authority.publicKey,
+            mintConfig: mintConfigPda,
+            collateralVault: collateralVaultPda,
+            priceFeed: null,
+          })
+          .rpc();
+        expect.fail('Should have thrown AuditFloorViolation');
+      } catch (err: any) {
+        expect(err.toString()).to.include('AuditFloorViolation');
+      }
+
+      await program.methods
+        .updateVaultConfig({ auditFloorBps: 0 })
+        .accounts({
+          authority: authority.publicKey,
+          mintConfig: mintConfigPda,
+          collateralVault: collateralVaultPda,
+        })
+        .rpc();
+    });
+
+    it('should allow withdrawing down to exactly 100% and not one subunit further', async () => {
+      const update = (amount: anchor.BN, isDeposit: boolean) =>
+        program.methods
+          .updateCollateral({ amount, isDeposit, proofHash: Array.from(Buffer.alloc(32)) })
+          .accounts({
+            authority: authority.publicKey,
+            mintConfig: mintConfigPda,
+            collateralVault: collateralVaultPda,
+            priceFeed: null,
+          })
+          .rpc();
+
+      const before = await program.account.mintConfig.fetch(mintConfigPda);
+      const excess = before.totalCollateral.sub(before.totalSupply);
+
+      try {
+        await update(excess.addn(1), false);
+        expect.fail('Should have thrown CollateralRatioViolation');
+      } catch (err: any) {
+        expect(err.toString()).to.include('CollateralRatioViolation');
+      }
+
+      await update(excess, false);
+      const atPar = await program.account.mintConfig.fetch(mintConfigPda);
+      expect(atPar.totalCollateral.toString()).to.equal(atPar.totalSupply.toString());
+
+      try {
+        await update(new anchor.BN(1), false);
+        expect.fail('Should have thrown CollateralRatioViolation');
+      } catch (err: any) {
+        expect(err.toString()).to.include('CollateralRatioViolation');
+      }
+
+      await update(excess, true);
     });
   });
 
@@ -391,13 +797,16 @@ describe('meridian-stablecoin', () => {
           .seize({
             amount: new anchor.BN(100),
             reason: Array.from(Buffer.alloc(32)),
+            force: false,
           })
           .accounts({
             authority: authority.publicKey,
             mintConfig: mintConfigPda,
+            roleConfig: null,
             mint: mintKeypair.publicKey,
             source: fakeSource.publicKey,
             treasury: fakeTreasury.publicKey,
+            seizeEscrow: null,
             tokenProgram: TOKEN_2022_PROGRAM_ID,
           })
           .rpc();
@@ -415,6 +824,32 @@ describe('meridian-stablecoin', () => {
     });
   });
 
+  describe('seize escrow routing', () => {
+    it('should reject an escrow share without an escrow account', async () => {
+      try {
+        await program.methods
+          .updateMintConfig({ seizeEscrow: null, escrowBps: 2500, requireIssuerAttribution: null })
+          .accounts({ authority: authority.publicKey, mintConfig: mintConfigPda })
+          .rpc();
+        expect.fail('Should have thrown SeizeEscrowNotConfigured');
+      } catch (err: any) {
+        expect(err.toString()).to.include('SeizeEscrowNotConfigured');
+      }
+    });
+
+    it('should configure the seize escrow split', async () => {
+      const escrow = Keypair.generate().publicKey;
+      await program.methods
+        .updateMintConfig({ seizeEscrow: escrow, escrowBps: 2500, requireIssuerAttribution: null })
+        .accounts({ authority: authority.publicKey, mintConfig: mintConfigPda })
+        .rpc();
+
+      const mintConfig = await program.account.mintConfig.fetch(mintConfigPda);
+      expect(mintConfig.seizeEscrow.toBase58()).to.equal(escrow.toBase58());
+      expect(mintConfig.escrowBps).to.equal(2500);
+    });
+  });
+
   // ==========================================================================
   // Role Management
   // ==========================================================================
@@ -522,4 +957,880 @@ describe('meridian-stablecoin', () => {
       expect(roleConfig.minter).to.not.be.null;
     });
   });
+
+  describe('mint and thaw', () => {
+    const recipient = Keypair.generate();
+    let recipientAta: PublicKey;
+    let roleConfigPda: PublicKey;
+
+    before(async () => {
+      const payer = (provider.wallet as any).payer as Keypair;
+      recipientAta = await createAssociatedTokenAccount(
+        provider.connection,
+        payer,
+        mintKeypair.publicKey,
+        recipient.publicKey,
+        undefined,
+        TOKEN_2022_PROGRAM_ID,
+      );
+      [roleConfigPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from('role_config'), mintConfigPda.toBuffer()],
+        program.programId
+      );
+      await program.methods
+        .updateRoles({
+          minter: authority.publicKey,
+          burner: null,
+          blacklister: authority.publicKey,
+          pauser: null,
+          seizer: null,
+        })
+        .accounts({ authority: authority.publicKey, mintConfig: mintConfigPda, roleConfig: roleConfigPda })
+        .rpc();
+    });
+
+    function mintAndThaw(amount: number, refreeze: boolean) {
+      return program.methods
+        .mintAndThaw({ amount: new anchor.BN(amount), reference: Array.from(Buffer.alloc(32)), refreeze })
+        .accounts({
+          issuerAuthority: authority.publicKey,
+          blacklister: authority.publicKey,
+          mintConfig: mintConfigPda,
+          roleConfig: roleConfigPda,
+          issuer: issuerPda,
+          mint: mintKeypair.publicKey,
+          recipientTokenAccount: recipientAta,
+          collateralVault: collateralVaultPda,
+          priceFeed: null,
+          tokenProgram: TOKEN_2022_PROGRAM_ID,
+        })
+        .rpc();
+    }
+
+    it('should mint to a frozen recipient end to end', async () => {
+      // Mint and leave the account frozen, as a default-frozen deployment would
+      await mintAndThaw(1_000_00, true);
+      let account = await getAccount(provider.connection, recipientAta, undefined, TOKEN_2022_PROGRAM_ID);
+      expect(account.isFrozen).to.be.true;
+      expect(Number(account.amount)).to.equal(1_000_00);
+
+      // A plain mint can't reach the frozen account
+      try {
+        await program.methods
+          .mint({ amount: new anchor.BN(1_000_00), reference: Array.from(Buffer.alloc(32)) })
+          .accounts({
+            issuerAuthority: authority.publicKey,
+            mintConfig: mintConfigPda,
+            roleConfig: roleConfigPda,
+            issuer: issuerPda,
+            mint: mintKeypair.publicKey,
+            recipientTokenAccount: recipientAta,
+            collateralVault: collateralVaultPda,
+            priceFeed: null,
+            tokenProgram: TOKEN_2022_PROGRAM_ID,
+          })
+          .rpc();
+        expect.fail('Should have failed minting to a frozen account');
+      } catch (err: any) {
+        expect(err.toString()).to.not.include('Should have failed');
+      }
+
+      // Once KYC clears, thaw and mint in one step
+      await mintAndThaw(1_000_00, false);
+      account = await getAccount(provider.connection, recipientAta, undefined, TOKEN_2022_PROGRAM_ID);
+      expect(account.isFrozen).to.be.false;
+      expect(Number(account.amount)).to.equal(2_000_00);
+    });
+
+    it('should reject a signer without the blacklister role', async () => {
+      const outsider = Keypair.generate();
+      try {
+        await program.methods
+          .mintAndThaw({ amount: new anchor.BN(1), reference: Array.from(Buffer.alloc(32)), refreeze: false })
+          .accounts({
+            issuerAuthority: authority.publicKey,
+            blacklister: outsider.publicKey,
+            mintConfig: mintConfigPda,
+            roleConfig: roleConfigPda,
+            issuer: issuerPda,
+            mint: mintKeypair.publicKey,
+            recipientTokenAccount: recipientAta,
+            collateralVault: collateralVaultPda,
+            priceFeed: null,
+            tokenProgram: TOKEN_2022_PROGRAM_ID,
+          })
+          .signers([outsider])
+          .rpc();
+        expect.fail('Should have thrown InvalidRole');
+      } catch (err: any) {
+        expect(err.toString()).to.include('InvalidRole');
+      }
+    });
+  });
+
+  describe('role enforcement', () => {
+    const delegate = Keypair.generate();
+    let delegateIssuerPda: PublicKey;
+    let delegateAta: PublicKey;
+    let roleConfigPda: PublicKey;
+
+    const setMinter = (minter: PublicKey) =>
+      program.methods
+        .updateRoles({ minter, burner: null, blacklister: null, pauser: null, seizer: null })
+        .accounts({ authority: authority.publicKey, mintConfig: mintConfigPda, roleConfig: roleConfigPda })
+        .rpc();
+
+    before(async () => {
+      const payer = (provider.wallet as any).payer as Keypair;
+      [roleConfigPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from('role_config'), mintConfigPda.toBuffer()],
+        program.programId
+      );
+      [delegateIssuerPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from('issuer'), delegate.publicKey.toBuffer()],
+        program.programId
+      );
+      await program.methods
+        .registerIssuer({
+          issuerAuthority: delegate.publicKey,
+          issuerType: { distributor: {} },
+          dailyMintLimit: new anchor.BN(1_000_000_00),
+          dailyBurnLimit: new anchor.BN(0),
+        })
+        .accounts({
+          authority: authority.publicKey,
+          mintConfig: mintConfigPda,
+          issuer: delegateIssuerPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+      delegateAta = await createAssociatedTokenAccount(
+        provider.connection,
+        payer,
+        mintKeypair.publicKey,
+        delegate.publicKey,
+        undefined,
+        TOKEN_2022_PROGRAM_ID,
+      );
+    });
+
+    after(async () => {
+      await setMinter(authority.publicKey);
+    });
+
+    function mintAsDelegate() {
+      return program.methods
+        .mint({ amount: new anchor.BN(1_00), reference: Array.from(Buffer.alloc(32)) })
+        .accounts({
+          issuerAuthority: delegate.publicKey,
+          mintConfig: mintConfigPda,
+          roleConfig: roleConfigPda,
+          issuer: delegateIssuerPda,
+          mint: mintKeypair.publicKey,
+          recipientTokenAccount: delegateAta,
+          collateralVault: collateralVaultPda,
+          priceFeed: null,
+          tokenProgram: TOKEN_2022_PROGRAM_ID,
+        })
+        .signers([delegate])
+        .rpc();
+    }
+
+    it('should reject a registered issuer that is neither master nor minter', async () => {
+      try {
+        await mintAsDelegate();
+        expect.fail('Should have thrown InvalidRole');
+      } catch (err: any) {
+        expect(err.toString()).to.include('InvalidRole');
+      }
+    });
+
+    it('should let a delegated minter mint but not pause', async () => {
+      await setMinter(delegate.publicKey);
+
+      await mintAsDelegate();
+      const account = await getAccount(provider.connection, delegateAta, undefined, TOKEN_2022_PROGRAM_ID);
+      expect(Number(account.amount)).to.equal(1_00);
+
+      try {
+        await program.methods
+          .pause()
+          .accounts({ authority: delegate.publicKey, mintConfig: mintConfigPda, roleConfig: roleConfigPda })
+          .signers([delegate])
+          .rpc();
+        expect.fail('Should have thrown InvalidRole');
+      } catch (err: any) {
+        expect(err.toString()).to.include('InvalidRole');
+      }
+      const mintConfig = await program.account.mintConfig.fetch(mintConfigPda);
+      expect(mintConfig.isPaused).to.be.false;
+    });
+
+  });
+
+  describe('batch thaw', () => {
+    let roleConfigPda: PublicKey;
+
+    before(() => {
+      [roleConfigPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from('role_config'), mintConfigPda.toBuffer()],
+        program.programId
+      );
+    });
+
+    async function frozenHolder(frozen: boolean) {
+      const payer = (provider.wallet as any).payer as Keypair;
+      const ata = await createAssociatedTokenAccount(
+        provider.connection,
+        payer,
+        mintKeypair.publicKey,
+        Keypair.generate().publicKey,
+        undefined,
+        TOKEN_2022_PROGRAM_ID,
+      );
+      // Minter and blacklister roles were assigned under 'mint and thaw'
+      await program.methods
+        .mintAndThaw({ amount: new anchor.BN(1_00), reference: Array.from(Buffer.alloc(32)), refreeze: frozen })
+        .accounts({
+          issuerAuthority: authority.publicKey,
+          blacklister: authority.publicKey,
+          mintConfig: mintConfigPda,
+          roleConfig: roleConfigPda,
+          issuer: issuerPda,
+          mint: mintKeypair.publicKey,
+          recipientTokenAccount: ata,
+          collateralVault: collateralVaultPda,
+          priceFeed: null,
+          tokenProgram: TOKEN_2022_PROGRAM_ID,
+        })
+        .rpc();
+      return ata;
+    }
+
+    function thawBatch(blacklister: PublicKey, targets: PublicKey[]) {
+      return program.methods
+        .thawAccountsBatch()
+        .accounts({
+          blacklister,
+          mintConfig: mintConfigPda,
+          roleConfig: roleConfigPda,
+          mint: mintKeypair.publicKey,
+          tokenProgram: TOKEN_2022_PROGRAM_ID,
+        })
+        .remainingAccounts(targets.map((pubkey) => ({ pubkey, isSigner: false, isWritable: true })));
+    }
+
+    it('should thaw frozen accounts and skip one that is already thawed', async () => {
+      const first = await frozenHolder(true);
+      const alreadyThawed = await frozenHolder(false);
+      const second = await frozenHolder(true);
+
+      await thawBatch(authority.publicKey, [first, alreadyThawed, second]).rpc();
+
+      for (const ata of [first, alreadyThawed, second]) {
+        const account = await getAccount(provider.connection, ata, undefined, TOKEN_2022_PROGRAM_ID);
+        expect(account.isFrozen).to.be.false;
+      }
+    });
+
+    it('should reject a signer without the blacklister role', async () => {
+      const target = await frozenHolder(true);
+      const outsider = Keypair.generate();
+      try {
+        await thawBatch(outsider.publicKey, [target]).signers([outsider]).rpc();
+        expect.fail('Should have thrown InvalidRole');
+      } catch (err: any) {
+        expect(err.toString()).to.include('InvalidRole');
+      }
+    });
+  });
+
+  describe('single-account freeze and thaw', () => {
+    const holder = Keypair.generate();
+    let holderAta: PublicKey;
+    let roleConfigPda: PublicKey;
+
+    before(async () => {
+      const payer = (provider.wallet as any).payer as Keypair;
+      [roleConfigPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from('role_config'), mintConfigPda.toBuffer()],
+        program.programId
+      );
+      holderAta = await createAssociatedTokenAccount(
+        provider.connection,
+        payer,
+        mintKeypair.publicKey,
+        holder.publicKey,
+        undefined,
+        TOKEN_2022_PROGRAM_ID,
+      );
+    });
+
+    // Blacklister role was assigned to the authority under 'mint and thaw'
+    const roleAccounts = () => ({
+      blacklister: authority.publicKey,
+      mintConfig: mintConfigPda,
+      roleConfig: roleConfigPda,
+      mint: mintKeypair.publicKey,
+      target: holderAta,
+      tokenProgram: TOKEN_2022_PROGRAM_ID,
+    });
+
+    const isFrozen = async () =>
+      (await getAccount(provider.connection, holderAta, undefined, TOKEN_2022_PROGRAM_ID)).isFrozen;
+
+    it('should freeze and thaw a token account', async () => {
+      await program.methods.freezeAccount().accounts(roleAccounts()).rpc();
+      expect(await isFrozen()).to.be.true;
+
+      await program.methods
+        .thawAccount()
+        .accounts({ ...roleAccounts(), whitelistEntry: null })
+        .rpc();
+      expect(await isFrozen()).to.be.false;
+    });
+
+    it('should refuse to thaw an owner without an active whitelist entry', async () => {
+      await program.methods.freezeAccount().accounts(roleAccounts()).rpc();
+
+      const [whitelistEntry] = PublicKey.findProgramAddressSync(
+        [Buffer.from('whitelist'), holder.publicKey.toBuffer()],
+        transferHookProgramId
+      );
+      try {
+        await program.methods
+          .thawAccount()
+          .accounts({ ...roleAccounts(), whitelistEntry })
+          .rpc();
+        expect.fail('Should have thrown NotWhitelisted');
+      } catch (err: any) {
+        expect(err.toString()).to.include('NotWhitelisted');
+      }
+      expect(await isFrozen()).to.be.true;
+    });
+  });
+
+  // ==========================================================================
+  // Token-backed collateral
+  // ==========================================================================
+
+  describe('backed collateral', () => {
+    let backingMint: PublicKey;
+    let backingAccount: PublicKey;
+    let depositorAccount: PublicKey;
+
+    const moveAccounts = () => ({
+      authority: authority.publicKey,
+      mintConfig: mintConfigPda,
+      collateralVault: collateralVaultPda,
+      backingMint,
+      backingTokenAccount: backingAccount,
+      counterpartyTokenAccount: depositorAccount,
+      tokenProgram: TOKEN_PROGRAM_ID,
+      priceFeed: null,
+    });
+
+    const expectReconciled = async () => {
+      const vault = await program.account.collateralVault.fetch(collateralVaultPda);
+      const config = await program.account.mintConfig.fetch(mintConfigPda);
+      const balance = (await getAccount(provider.connection, backingAccount)).amount;
+      expect(vault.totalCollateral.toString()).to.equal(balance.toString());
+      expect(config.totalCollateral.toString()).to.equal(balance.toString());
+    };
+
+    before(async () => {
+      const payer = (provider.wallet as any).payer as Keypair;
+
+      // Tokenized JGB collateral, custodied by the vault PDA
+      backingMint = await createMint(provider.connection, payer, authority.publicKey, null, 6);
+      backingAccount = await createAccount(
+        provider.connection, payer, backingMint, collateralVaultPda, Keypair.generate(),
+      );
+      depositorAccount = await createAccount(
+        provider.connection, payer, backingMint, authority.publicKey, Keypair.generate(),
+      );
+      await mintTo(provider.connection, payer, backingMint, backingAccount, authority.publicKey, 100_000_000_00);
+      await mintTo(provider.connection, payer, backingMint, depositorAccount, authority.publicKey, 10_000_000_00);
+    });
+
+    const configAccounts = () => ({ authority: authority.publicKey, mintConfig: mintConfigPda });
+
+    it('should refuse a backing token missing from the settlement allowlist', async () => {
+      const otherToken = Keypair.generate().publicKey;
+      await program.methods.addSettlementToken(otherToken).accounts(configAccounts()).rpc();
+
+      try {
+        await program.methods
+          .enableBackedCollateral()
+          .accounts({
+            authority: authority.publicKey,
+            mintConfig: mintConfigPda,
+            collateralVault: collateralVaultPda,
+            backingMint,
+            backingTokenAccount: backingAccount,
+            priceFeed: null,
+          })
+          .rpc();
+        expect.fail('Should have thrown UnapprovedToken');
+      } catch (err: any) {
+        expect(err.toString()).to.include('UnapprovedToken');
+      }
+
+      try {
+        await program.methods.addSettlementToken(otherToken).accounts(configAccounts()).rpc();
+        expect.fail('Should have thrown SettlementTokenAlreadyApproved');
+      } catch (err: any) {
+        expect(err.toString()).to.include('SettlementTokenAlreadyApproved');
+      }
+
+      await program.methods.addSettlementToken(backingMint).accounts(configAccounts()).rpc();
+      await program.methods.removeSettlementToken(otherToken).accounts(configAccounts()).rpc();
+      const config = await program.account.mintConfig.fetch(mintConfigPda);
+      expect(config.settlementTokens.map((k: PublicKey) => k.toBase58())).to.deep.equal([
+        backingMint.toBase58(),
+      ]);
+    });
+
+    it('should replace the attested total with the backing balance', async () => {
+      await program.methods
+        .enableBackedCollateral()
+        .accounts({
+          authority: authority.publicKey,
+          mintConfig: mintConfigPda,
+          collateralVault: collateralVaultPda,
+          backingMint,
+          backingTokenAccount: backingAccount,
+          priceFeed: null,
+        })
+        .rpc();
+
+      const vault = await program.account.collateralVault.fetch(collateralVaultPda);
+      expect(vault.backingTokenAccount.toBase58()).to.equal(backingAccount.toBase58());
+      await expectReconciled();
+    });
+
+    it('should keep the recorded total equal to the vault balance across deposits and withdrawals', async () => {
+      await program.methods
+        .depositBackedCollateral(new anchor.BN(5_000_000_00))
+        .accounts(moveAccounts())
+        .rpc();
+      await expectReconciled();
+
+      await program.methods
+        .withdrawBackedCollateral(new anchor.BN(2_000_000_00))
+        .accounts(moveAccounts())
+        .rpc();
+      await expectReconciled();
+    });
+
+    it('should stop deposits of a delisted token but still allow withdrawals', async () => {
+      // Keep another entry so removing the backing mint doesn't switch the allowlist off
+      const otherToken = Keypair.generate().publicKey;
+      await program.methods.addSettlementToken(otherToken).accounts(configAccounts()).rpc();
+      await program.methods.removeSettlementToken(backingMint).accounts(configAccounts()).rpc();
+
+      try {
+        await program.methods
+          .depositBackedCollateral(new anchor.BN(1_000_000))
+          .accounts(moveAccounts())
+          .rpc();
+        expect.fail('Should have thrown UnapprovedToken');
+      } catch (err: any) {
+        expect(err.toString()).to.include('UnapprovedToken');
+      }
+
+      await program.methods
+        .withdrawBackedCollateral(new anchor.BN(1_000_000))
+        .accounts(moveAccounts())
+        .rpc();
+      await expectReconciled();
+
+      await program.methods.addSettlementToken(backingMint).accounts(configAccounts()).rpc();
+      await program.methods.removeSettlementToken(otherToken).accounts(configAccounts()).rpc();
+      await program.methods
+        .depositBackedCollateral(new anchor.BN(1_000_000))
+        .accounts(moveAccounts())
+        .rpc();
+      await expectReconciled();
+    });
+
+    it('should reject bookkeeping-only updates on a backed vault', async () => {
+      try {
+        await program.methods
+          .updateCollateral({
+            amount: new anchor.BN(1_000_000),
+            isDeposit: true,
+            proofHash: Array.from(Buffer.alloc(32)),
+          })
+          .accounts({
+            authority: authority.publicKey,
+            mintConfig: mintConfigPda,
+            collateralVault: collateralVaultPda,
+            priceFeed: null,
+          })
+          .rpc();
+        expect.fail('Should have thrown VaultIsBacked');
+      } catch (err: any) {
+        expect(err.toString()).to.include('VaultIsBacked');
+      }
+    });
+  });
+
+  describe('account versioning', () => {
+    it('should create issuers and the vault at the current version', async () => {
+      const issuer = await program.account.issuer.fetch(issuerPda);
+      expect(issuer.version).to.equal(1);
+
+      const vault = await program.account.collateralVault.fetch(collateralVaultPda);
+      expect(vault.version).to.equal(1);
+    });
+
+    it('should reject migrating an issuer that is already current', async () => {
+      try {
+        await program.methods
+          .migrateIssuer()
+          .accounts({
+            authority: authority.publicKey,
+            mintConfig: mintConfigPda,
+            issuer: issuerPda,
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+        expect.fail('Should have thrown AlreadyMigrated');
+      } catch (err: any) {
+        expect(err.toString()).to.include('AlreadyMigrated');
+      }
+    });
+
+    it('should reject migrating a vault that is already current', async () => {
+      try {
+        await program.methods
+          .migrateCollateralVault()
+          .accounts({
+            authority: authority.publicKey,
+            mintConfig: mintConfigPda,
+            collateralVault: collateralVaultPda,
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+        expect.fail('Should have thrown AlreadyMigrated');
+      } catch (err: any) {
+        expect(err.toString()).to.include('AlreadyMigrated');
+      }
+    });
+  });
+
+  describe('multisig authority', () => {
+    const signerA = Keypair.generate();
+    const signerB = Keypair.generate();
+    let multisigPda: PublicKey;
+    let rolesPda: PublicKey;
+
+    const proposalPda = (index: number) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from('multisig_proposal'), multisigPda.toBuffer(), new anchor.BN(index).toArrayLike(Buffer, 'le', 8)],
+        program.programId
+      )[0];
+
+    async function propose(action: object): Promise<PublicKey> {
+      const multisig = await program.account.multisigConfig.fetch(multisigPda);
+      const proposal = proposalPda(multisig.proposalCount.toNumber());
+      await program.methods
+        .proposeMultisigAction(action)
+        .accounts({
+          proposer: authority.publicKey,
+          mintConfig: mintConfigPda,
+          multisig: multisigPda,
+          proposal,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+      return proposal;
+    }
+
+    function approve(proposal: PublicKey, approver: Keypair) {
+      return program.methods
+        .approveMultisigAction()
+        .accounts({ approver: approver.publicKey, mintConfig: mintConfigPda, multisig: multisigPda, proposal })
+        .signers([approver])
+        .rpc();
+    }
+
+    function execution(proposal: PublicKey) {
+      return { executor: authority.publicKey, multisig: multisigPda, proposal, proposer: authority.publicKey };
+    }
+
+    function executeAction(proposal: PublicKey) {
+      return program.methods
+        .executeMultisigAction()
+        .accounts({ execution: execution(proposal), mintConfig: mintConfigPda })
+        .rpc();
+    }
+
+    before(() => {
+      [multisigPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from('multisig'), mintConfigPda.toBuffer()],
+        program.programId
+      );
+      [rolesPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from('role_config'), mintConfigPda.toBuffer()],
+        program.programId
+      );
+    });
+
+    it('should reject a threshold above the signer count', async () => {
+      try {
+        await program.methods
+          .configureMultisig([authority.publicKey, signerA.publicKey], 3)
+          .accounts({ authority: authority.publicKey, mintConfig: mintConfigPda, multisig: multisigPda })
+          .rpc();
+        expect.fail('Should have thrown InvalidMultisigConfig');
+      } catch (err: any) {
+        expect(err.toString()).to.include('InvalidMultisigConfig');
+      }
+    });
+
+    it('should attach a 2-of-3 multisig and block the direct paths', async () => {
+      await program.methods
+        .configureMultisig([authority.publicKey, signerA.publicKey, signerB.publicKey], 2)
+        .accounts({ authority: authority.publicKey, mintConfig: mintConfigPda, multisig: multisigPda })
+        .rpc();
+
+      const mintConfig = await program.account.mintConfig.fetch(mintConfigPda);
+      expect(mintConfig.multisig.toBase58()).to.equal(multisigPda.toBase58());
+
+      try {
+        await program.methods
+          .pause()
+          .accounts({ authority: authority.publicKey, mintConfig: mintConfigPda, roleConfig: null })
+          .rpc();
+        expect.fail('Should have thrown MultisigRequired');
+      } catch (err: any) {
+        expect(err.toString()).to.include('MultisigRequired');
+      }
+
+      try {
+        await program.methods
+          .updateRoles({ minter: null, burner: null, blacklister: null, pauser: null, seizer: null })
+          .accounts({ authority: authority.publicKey, mintConfig: mintConfigPda, roleConfig: rolesPda })
+          .rpc();
+        expect.fail('Should have thrown MultisigRequired');
+      } catch (err: any) {
+        expect(err.toString()).to.include('MultisigRequired');
+      }
+
+      try {
+        await program.methods
+          .updateMintConfig({ seizeEscrow: null, escrowBps: 0, requireIssuerAttribution: null })
+          .accounts({ authority: authority.publicKey, mintConfig: mintConfigPda })
+          .rpc();
+        expect.fail('Should have thrown MultisigRequired');
+      } catch (err: any) {
+        expect(err.toString()).to.include('MultisigRequired');
+      }
+
+      // Refreezing an account that was never frozen would be a direct freeze
+      const payer = (provider.wallet as any).payer as Keypair;
+      const unfrozen = await createAssociatedTokenAccount(
+        provider.connection,
+        payer,
+        mintKeypair.publicKey,
+        Keypair.generate().publicKey,
+        undefined,
+        TOKEN_2022_PROGRAM_ID,
+      );
+      try {
+        await program.methods
+          .mintAndThaw({ amount: new anchor.BN(1_00), reference: Array.from(Buffer.alloc(32)), refreeze: true })
+          .accounts({
+            issuerAuthority: authority.publicKey,
+            blacklister: authority.publicKey,
+            mintConfig: mintConfigPda,
+            roleConfig: rolesPda,
+            issuer: issuerPda,
+            mint: mintKeypair.publicKey,
+            recipientTokenAccount: unfrozen,
+            collateralVault: collateralVaultPda,
+            priceFeed: null,
+            tokenProgram: TOKEN_2022_PROGRAM_ID,
+          })
+          .rpc();
+        expect.fail('Should have thrown MultisigRequired');
+      } catch (err: any) {
+        expect(err.toString()).to.include('MultisigRequired');
+      }
+    });
+
+    it('should pause and unpause only once the threshold is met', async () => {
+      const pause = await propose({ pause: {} });
+      try {
+        await executeAction(pause);
+        expect.fail('Should have thrown ApprovalThresholdNotMet');
+      } catch (err: any) {
+        expect(err.toString()).to.include('ApprovalThresholdNotMet');
+      }
+
+      await approve(pause, signerA);
+      await executeAction(pause);
+      expect((await program.account.mintConfig.fetch(mintConfigPda)).isPaused).to.be.true;
+      expect(await provider.connection.getAccountInfo(pause)).to.be.null;
+
+      const unpause = await propose({ unpause: {} });
+      await approve(unpause, signerB);
+      await executeAction(unpause);
+      expect((await program.account.mintConfig.fetch(mintConfigPda)).isPaused).to.be.false;
+    });
+
+    it('should update roles through a proposal', async () => {
+      const proposal = await propose({
+        updateRoles: { minter: null, burner: null, blacklister: null, pauser: signerA.publicKey, seizer: null },
+      });
+      await approve(proposal, signerA);
+      await program.methods
+        .executeMultisigUpdateRoles()
+        .accounts({ execution: execution(proposal), mintConfig: mintConfigPda, roleConfig: rolesPda })
+        .rpc();
+
+      const roles = await program.account.roleConfig.fetch(rolesPda);
+      expect(roles.pauser.toBase58()).to.equal(signerA.publicKey.toBase58());
+    });
+
+    it('should reject executing a proposal through the wrong instruction', async () => {
+      const proposal = await propose({
+        updateRoles: { minter: null, burner: null, blacklister: null, pauser: authority.publicKey, seizer: null },
+      });
+      await approve(proposal, signerB);
+      try {
+        await executeAction(proposal);
+        expect.fail('Should have thrown ProposalMismatch');
+      } catch (err: any) {
+        expect(err.toString()).to.include('ProposalMismatch');
+      }
+      await program.methods
+        .executeMultisigUpdateRoles()
+        .accounts({ execution: execution(proposal), mintConfig: mintConfigPda, roleConfig: rolesPda })
+        .rpc();
+      const roles = await program.account.roleConfig.fetch(rolesPda);
+      expect(roles.pauser.toBase58()).to.equal(authority.publicKey.toBase58());
+    });
+
+    it('should hold a freeze until the threshold is met', async () => {
+      const holder = Keypair.generate();
+      const payer = (provider.wallet as any).payer as Keypair;
+      const holderAta = await createAssociatedTokenAccount(
+        provider.connection,
+        payer,
+        mintKeypair.publicKey,
+        holder.publicKey,
+        undefined,
+        TOKEN_2022_PROGRAM_ID,
+      );
+      const executeFreeze = (proposal: PublicKey) =>
+        program.methods
+          .executeMultisigFreeze()
+          .accounts({
+            execution: execution(proposal),
+            mintConfig: mintConfigPda,
+            mint: mintKeypair.publicKey,
+            target: holderAta,
+            tokenProgram: TOKEN_2022_PROGRAM_ID,
+          })
+          .rpc();
+
+      const proposal = await propose({ freeze: { target: holderAta } });
+      // The proposer's own approval is one of two
+      try {
+        await executeFreeze(proposal);
+        expect.fail('Should have thrown ApprovalThresholdNotMet');
+      } catch (err: any) {
+        expect(err.toString()).to.include('ApprovalThresholdNotMet');
+      }
+
+      // Approving twice doesn't count twice
+      try {
+        await program.methods
+          .approveMultisigAction()
+          .accounts({ approver: authority.publicKey, mintConfig: mintConfigPda, multisig: multisigPda, proposal })
+          .rpc();
+        expect.fail('Should have thrown AlreadyApproved');
+      } catch (err: any) {
+        expect(err.toString()).to.include('AlreadyApproved');
+      }
+
+      await approve(proposal, signerA);
+      await executeFreeze(proposal);
+
+      const account = await getAccount(provider.connection, holderAta, undefined, TOKEN_2022_PROGRAM_ID);
+      expect(account.isFrozen).to.be.true;
+      expect(await provider.connection.getAccountInfo(proposal)).to.be.null;
+    });
+
+    it('should let only the proposer cancel a pending proposal', async () => {
+      const proposal = await propose({ pause: {} });
+      try {
+        await program.methods
+          .cancelMultisigAction()
+          .accounts({ proposer: signerA.publicKey, multisig: multisigPda, proposal })
+          .signers([signerA])
+          .rpc();
+        expect.fail('Should have thrown Unauthorized');
+      } catch (err: any) {
+        expect(err.toString()).to.include('Unauthorized');
+      }
+
+      await program.methods
+        .cancelMultisigAction()
+        .accounts({ proposer: authority.publicKey, multisig: multisigPda, proposal })
+        .rpc();
+      expect(await provider.connection.getAccountInfo(proposal)).to.be.null;
+    });
+
+    it('should return to single-authority mode once disabled', async () => {
+      const proposal = await propose({ disable: {} });
+      await approve(proposal, signerA);
+      await executeAction(proposal);
+
+      const mintConfig = await program.account.mintConfig.fetch(mintConfigPda);
+      expect(mintConfig.multisig).to.be.null;
+
+      await program.methods
+        .pause()
+        .accounts({ authority: authority.publicKey, mintConfig: mintConfigPda, roleConfig: null })
+        .rpc();
+      await program.methods
+        .unpause()
+        .accounts({ authority: authority.publicKey, mintConfig: mintConfigPda, roleConfig: null })
+        .rpc();
+    });
+
+    it('should void proposals opened before the multisig was reconfigured', async () => {
+      const configure = () =>
+        program.methods
+          .configureMultisig([authority.publicKey, signerA.publicKey, signerB.publicKey], 2)
+          .accounts({ authority: authority.publicKey, mintConfig: mintConfigPda, multisig: multisigPda })
+          .rpc();
+      const disable = async () => {
+        const proposal = await propose({ disable: {} });
+        await approve(proposal, signerA);
+        await executeAction(proposal);
+      };
+
+      await configure();
+      const stale = await propose({ pause: {} });
+      await approve(stale, signerA);
+      await disable();
+
+      // Reconfigured back to back, likely within the same second
+      await configure();
+      try {
+        await executeAction(stale);
+        expect.fail('Should have thrown ApprovalThresholdNotMet');
+      } catch (err: any) {
+        expect(err.toString()).to.include('ApprovalThresholdNotMet');
+      }
+
+      await disable();
+      expect((await program.account.mintConfig.fetch(mintConfigPda)).multisig).to.be.null;
+    });
+  });
 });
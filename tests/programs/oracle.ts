@@ -38,6 +38,7 @@ describe('oracle', () => {
           assetSymbol: 'TESTUSD',
           assetType: { fiat: {} },
           sampleIntervalSeconds: 60,
+          priceExponent: null,
         })
         .accounts({
           authority: authority.publicKey,
@@ -50,6 +51,7 @@ describe('oracle', () => {
       expect(feed.authority.toString()).to.equal(authority.publicKey.toString());
       expect(feed.assetSymbol).to.equal('TESTUSD');
       expect(feed.isActive).to.be.true;
+      expect(feed.priceExponent).to.equal(-6);
     });
 
     it('should update price with new observation', async () => {
@@ -70,6 +72,16 @@ describe('oracle', () => {
       expect(feed.lastUpdateTime.toNumber()).to.be.greaterThan(0);
     });
 
+    it('should read price with its exponent', async () => {
+      const data = await program.methods
+        .getPrice()
+        .accounts({ priceFeed: testPriceFeedPda })
+        .view();
+
+      expect(data.price.toNumber()).to.equal(670000);
+      expect(data.exponent).to.equal(-6);
+    });
+
     it('should calculate TWAP after multiple updates', async () => {
       const prices = [670000, 671000, 669500, 670500, 670200];
 
@@ -89,6 +101,156 @@ describe('oracle', () => {
       expect(feed.twapValue.toNumber()).to.be.greaterThanOrEqual(0);
     });
 
+    it('should flag the TWAP as valid only once enough samples exist', async () => {
+      const [feed] = PublicKey.findProgramAddressSync(
+        [Buffer.from('price_feed'), Buffer.from('TWAPAGE')],
+        program.programId
+      );
+      await program.methods
+        .initializePriceFeed({
+          assetSymbol: 'TWAPAGE',
+          assetType: { fiat: {} },
+          sampleIntervalSeconds: 1,
+          priceExponent: null,
+        })
+        .accounts({
+          authority: authority.publicKey,
+          priceFeed: feed,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+      const update = (price: number) =>
+        program.methods
+          .updatePrice(new anchor.BN(price), new anchor.BN(1000))
+          .accounts({ authority: authority.publicKey, priceFeed: feed })
+          .rpc();
+      const twapValid = async () =>
+        (await program.methods.getPrice().accounts({ priceFeed: feed }).view()).twapValid;
+
+      // A single sample: the TWAP is just the current price
+      await update(1_000_000);
+      expect(await twapValid()).to.be.false;
+
+      await new Promise((r) => setTimeout(r, 1500));
+      await update(1_010_000);
+      expect(await twapValid()).to.be.true;
+
+      // Raising the minimum makes the same history immature again
+      await program.methods
+        .updatePriceFeedConfig({
+          minEventMoveBps: null,
+          eventHeartbeatSeconds: null,
+          minTwapSamples: 3,
+          maxStalenessSeconds: null,
+          maxMoveBps: null,
+        })
+        .accounts({ authority: authority.publicKey, priceFeed: feed })
+        .rpc();
+      expect(await twapValid()).to.be.false;
+    });
+
+    it('should refuse checked reads once the feed passes its staleness bound', async () => {
+      const [feed] = PublicKey.findProgramAddressSync(
+        [Buffer.from('price_feed'), Buffer.from('STALECHK')],
+        program.programId
+      );
+      await program.methods
+        .initializePriceFeed({
+          assetSymbol: 'STALECHK',
+          assetType: { rwa: {} },
+          sampleIntervalSeconds: 60,
+          priceExponent: null,
+        })
+        .accounts({ authority: authority.publicKey, priceFeed: feed, systemProgram: SystemProgram.programId })
+        .rpc();
+      await program.methods
+        .updatePriceFeedConfig({
+          minEventMoveBps: null,
+          eventHeartbeatSeconds: null,
+          minTwapSamples: null,
+          maxStalenessSeconds: new anchor.BN(1),
+          maxMoveBps: null,
+        })
+        .accounts({ authority: authority.publicKey, priceFeed: feed })
+        .rpc();
+      await program.methods
+        .updatePrice(new anchor.BN(1_000_000), new anchor.BN(1000))
+        .accounts({ authority: authority.publicKey, priceFeed: feed })
+        .rpc();
+
+      const data = await program.methods.readPrice().accounts({ priceFeed: feed }).view();
+      expect(data.price.toNumber()).to.equal(1_000_000);
+
+      await new Promise((r) => setTimeout(r, 3000));
+      try {
+        await program.methods.readPrice().accounts({ priceFeed: feed }).view();
+        expect.fail('Should have thrown an error');
+      } catch (err: any) {
+        expect(err.error?.errorCode?.code || err.message).to.include('StalePriceFeed');
+      }
+
+      // The unchecked read still returns the last price
+      const raw = await program.methods.getPrice().accounts({ priceFeed: feed }).view();
+      expect(raw.price.toNumber()).to.equal(1_000_000);
+    });
+
+    it('should halt the feed on a move past the circuit breaker', async () => {
+      const [feed] = PublicKey.findProgramAddressSync(
+        [Buffer.from('price_feed'), Buffer.from('BREAKER')],
+        program.programId
+      );
+      await program.methods
+        .initializePriceFeed({
+          assetSymbol: 'BREAKER',
+          assetType: { rwa: {} },
+          sampleIntervalSeconds: 60,
+          priceExponent: null,
+        })
+        .accounts({ authority: authority.publicKey, priceFeed: feed, systemProgram: SystemProgram.programId })
+        .rpc();
+      await program.methods
+        .updatePriceFeedConfig({
+          minEventMoveBps: null,
+          eventHeartbeatSeconds: null,
+          minTwapSamples: null,
+          maxStalenessSeconds: null,
+          maxMoveBps: 1000,
+        })
+        .accounts({ authority: authority.publicKey, priceFeed: feed })
+        .rpc();
+      const update = (price: number) =>
+        program.methods
+          .updatePrice(new anchor.BN(price), new anchor.BN(1000))
+          .accounts({ authority: authority.publicKey, priceFeed: feed })
+          .rpc();
+
+      // The first price is never checked
+      await update(1_000_000);
+      await update(1_050_000);
+
+      // ~43% move: halts instead of applying
+      await update(1_500_000);
+      let state = await program.account.priceFeed.fetch(feed);
+      expect(state.isHalted).to.be.true;
+      expect(state.currentPrice.toNumber()).to.equal(1_050_000);
+
+      try {
+        await update(1_060_000);
+        expect.fail('Should have thrown an error');
+      } catch (err: any) {
+        expect(err.error?.errorCode?.code || err.message).to.include('CircuitBreakerTripped');
+      }
+
+      await program.methods
+        .resumeFeed()
+        .accounts({ authority: authority.publicKey, priceFeed: feed })
+        .rpc();
+      await update(1_060_000);
+      state = await program.account.priceFeed.fetch(feed);
+      expect(state.isHalted).to.be.false;
+      expect(state.currentPrice.toNumber()).to.equal(1_060_000);
+    });
+
     it('should reject price update from non-authority', async () => {
       const fakeAuthority = Keypair.generate();
 
@@ -106,6 +268,85 @@ describe('oracle', () => {
         expect(err).to.exist;
       }
     });
+
+    it('should only emit price events on moves past the configured threshold', async () => {
+      const parser = new anchor.EventParser(program.programId, program.coder);
+      const update = async (price: number) => {
+        const sig = await program.methods
+          .updatePrice(new anchor.BN(price), new anchor.BN(1000))
+          .accounts({ authority: authority.publicKey, priceFeed: testPriceFeedPda })
+          .rpc({ commitment: 'confirmed' });
+        const tx = await provider.connection.getTransaction(sig, {
+          commitment: 'confirmed',
+          maxSupportedTransactionVersion: 0,
+        });
+        return Array.from(parser.parseLogs(tx!.meta!.logMessages!)).filter((e) => e.name === 'priceUpdated');
+      };
+      const configure = (minEventMoveBps: number) =>
+        program.methods
+          .updatePriceFeedConfig({
+            minEventMoveBps,
+            eventHeartbeatSeconds: null,
+            minTwapSamples: null,
+            maxStalenessSeconds: null,
+            maxMoveBps: null,
+          })
+          .accounts({ authority: authority.publicKey, priceFeed: testPriceFeedPda })
+          .rpc();
+
+      await configure(100);
+      try {
+        // ~4% from the last emitted price (670200)
+        expect(await update(700000)).to.have.length(1);
+
+        // 0.5% move: state updates, no event
+        expect(await update(703500)).to.have.length(0);
+        const feed = await program.account.priceFeed.fetch(testPriceFeedPda);
+        expect(feed.currentPrice.toNumber()).to.equal(703500);
+
+        // 1% from the last emitted price
+        expect(await update(707000)).to.have.length(1);
+      } finally {
+        await configure(0);
+      }
+    });
+
+    it('should aggregate observations from multiple sources and drop outliers', async () => {
+      const observation = (price: number, confidence: number, source: object) => ({
+        price: new anchor.BN(price),
+        confidence: new anchor.BN(confidence),
+        source,
+      });
+
+      await program.methods
+        .updatePriceMulti([
+          observation(1_000_000, 1000, { pyth: {} }),
+          observation(1_002_000, 1000, { switchboard: {} }),
+          observation(999_000, 2000, { exchange: {} }),
+          // One compromised source
+          observation(1_500_000, 1000, { appraiser: {} }),
+        ])
+        .accounts({ authority: authority.publicKey, priceFeed: testPriceFeedPda })
+        .rpc();
+
+      const feed = await program.account.priceFeed.fetch(testPriceFeedPda);
+      expect(feed.currentPrice.toNumber()).to.equal(1_000_000);
+      // Survivors disagree by more than their combined confidence
+      expect(feed.confidence.toNumber()).to.equal(1374);
+    });
+
+    it('should reject duplicate sources in one aggregated update', async () => {
+      const observation = { price: new anchor.BN(1_000_000), confidence: new anchor.BN(1000), source: { pyth: {} } };
+      try {
+        await program.methods
+          .updatePriceMulti([observation, observation])
+          .accounts({ authority: authority.publicKey, priceFeed: testPriceFeedPda })
+          .rpc();
+        expect.fail('Should have thrown an error');
+      } catch (err: any) {
+        expect(err.error?.errorCode?.code || err.message).to.include('DuplicateSource');
+      }
+    });
   });
 
   describe('volatility index', () => {
@@ -156,6 +397,69 @@ describe('oracle', () => {
       const vol = await program.account.volatilityIndex.fetch(testVolatilityPda);
       expect(vol.regime).to.exist;
     });
+
+    it('should require enough samples to compute realized volatility', async () => {
+      try {
+        await program.methods
+          .computeRealizedVolatility()
+          .accounts({ priceFeed: testPriceFeedPda, volatilityIndex: testVolatilityPda })
+          .rpc();
+        expect.fail('Should have thrown an error');
+      } catch (err: any) {
+        expect(err.error?.errorCode?.code || err.message).to.include('InsufficientSamples');
+      }
+    });
+
+    it('should compute realized volatility from the feed samples', async () => {
+      const [feed] = PublicKey.findProgramAddressSync(
+        [Buffer.from('price_feed'), Buffer.from('VOLCALC')],
+        program.programId
+      );
+      const [volIndex] = PublicKey.findProgramAddressSync(
+        [Buffer.from('volatility_index'), Buffer.from('VOLCALC')],
+        program.programId
+      );
+      await program.methods
+        .initializePriceFeed({
+          assetSymbol: 'VOLCALC',
+          assetType: { rwa: {} },
+          sampleIntervalSeconds: 0,
+          priceExponent: null,
+        })
+        .accounts({ authority: authority.publicKey, priceFeed: feed, systemProgram: SystemProgram.programId })
+        .rpc();
+      await program.methods
+        .initializeVolatilityIndex('VOLCALC')
+        .accounts({
+          authority: authority.publicKey,
+          volatilityIndex: volIndex,
+          priceFeed: feed,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      // 1% swings on every sample
+      for (let i = 0; i < 20; i++) {
+        await program.methods
+          .updatePrice(new anchor.BN(i % 2 === 0 ? 1_000_000 : 1_010_000), new anchor.BN(1000))
+          .accounts({ authority: authority.publicKey, priceFeed: feed })
+          .rpc();
+      }
+      await new Promise((r) => setTimeout(r, 1000));
+      await program.methods
+        .updatePrice(new anchor.BN(1_000_000), new anchor.BN(1000))
+        .accounts({ authority: authority.publicKey, priceFeed: feed })
+        .rpc();
+
+      await program.methods
+        .computeRealizedVolatility()
+        .accounts({ priceFeed: feed, volatilityIndex: volIndex })
+        .rpc();
+
+      const vol = await program.account.volatilityIndex.fetch(volIndex);
+      expect(vol.realizedVolatility.toNumber()).to.be.greaterThan(5000);
+      expect(vol.regime).to.deep.equal({ extreme: {} });
+    });
   });
 
   describe('funding feed', () => {
@@ -209,5 +513,112 @@ describe('oracle', () => {
       const feed = await program.account.fundingFeed.fetch(testFundingFeedPda);
       expect(feed.currentRate.toNumber()).to.be.within(45, 55);
     });
+
+    it('should leave a spiking source out of the aggregate and TWAP', async () => {
+      const [spikeFeed] = PublicKey.findProgramAddressSync(
+        [Buffer.from('funding_feed'), Buffer.from('SPIKE')],
+        program.programId
+      );
+      await program.methods
+        .initializeFundingFeed('SPIKE')
+        .accounts({ authority: authority.publicKey, fundingFeed: spikeFeed, systemProgram: SystemProgram.programId })
+        .rpc();
+
+      const sources = [
+        { rate: 50, source: { binance: {} } },
+        { rate: 48, source: { bybit: {} } },
+        { rate: 52, source: { drift: {} } },
+        // Transient anomaly on one exchange
+        { rate: 500, source: { okx: {} } },
+      ];
+      for (const { rate, source } of sources) {
+        await program.methods
+          .updateFundingRate(new anchor.BN(rate), source)
+          .accounts({ authority: authority.publicKey, fundingFeed: spikeFeed })
+          .rpc();
+      }
+
+      const feed = await program.account.fundingFeed.fetch(spikeFeed);
+      expect(feed.currentRate.toNumber()).to.equal(500);
+      expect(feed.aggregatedRate.toNumber()).to.equal(50);
+      expect(feed.twapRate.toNumber()).to.be.within(48, 52);
+    });
+  });
+
+  describe('closing feeds', () => {
+    const pda = (prefix: string, symbol: string) =>
+      PublicKey.findProgramAddressSync([Buffer.from(prefix), Buffer.from(symbol)], program.programId)[0];
+
+    it('should reject closing a feed from non-authority', async () => {
+      const fakeAuthority = Keypair.generate();
+      try {
+        await program.methods
+          .closeFundingFeed()
+          .accounts({ authority: fakeAuthority.publicKey, fundingFeed: pda('funding_feed', 'SPIKE') })
+          .signers([fakeAuthority])
+          .rpc();
+        expect.fail('Should have thrown an error');
+      } catch (err: any) {
+        expect(err).to.exist;
+      }
+    });
+
+    it('should close a volatility index before its price feed, leaving tombstones', async () => {
+      const volIndex = pda('volatility_index', 'VOLCALC');
+      const feed = pda('price_feed', 'VOLCALC');
+
+      await program.methods
+        .closeVolatilityIndex()
+        .accounts({ authority: authority.publicKey, volatilityIndex: volIndex })
+        .rpc();
+      await program.methods
+        .closePriceFeed()
+        .accounts({ authority: authority.publicKey, priceFeed: feed })
+        .rpc();
+
+      expect((await program.account.volatilityIndex.fetch(volIndex)).isClosed).to.be.true;
+      expect((await program.account.priceFeed.fetch(feed)).isActive).to.be.false;
+
+      try {
+        await program.methods
+          .updatePrice(new anchor.BN(100_000_000), new anchor.BN(100_000))
+          .accounts({ authority: authority.publicKey, priceFeed: feed })
+          .rpc();
+        expect.fail('Should have thrown an error');
+      } catch (err: any) {
+        expect(err.error?.errorCode?.code || err.message).to.include('FeedClosed');
+      }
+    });
+
+    it('should close a funding feed', async () => {
+      const feed = pda('funding_feed', 'SPIKE');
+      await program.methods
+        .closeFundingFeed()
+        .accounts({ authority: authority.publicKey, fundingFeed: feed })
+        .rpc();
+
+      expect((await program.account.fundingFeed.fetch(feed)).isClosed).to.be.true;
+
+      // The tombstone keeps the PDA occupied, so nobody can re-initialize the symbol
+      try {
+        await program.methods
+          .initializeFundingFeed('SPIKE')
+          .accounts({ authority: authority.publicKey, fundingFeed: feed, systemProgram: SystemProgram.programId })
+          .rpc();
+        expect.fail('Should have thrown an error');
+      } catch (err: any) {
+        expect(err).to.exist;
+      }
+
+      try {
+        await program.methods
+          .closeFundingFeed()
+          .accounts({ authority: authority.publicKey, fundingFeed: feed })
+          .rpc();
+        expect.fail('Should have thrown an error');
+      } catch (err: any) {
+        expect(err.error?.errorCode?.code || err.message).to.include('FeedClosed');
+      }
+    });
   });
 });
@@ -5,6 +5,10 @@ import {
   TOKEN_PROGRAM_ID,
   getAssociatedTokenAddressSync,
   createAssociatedTokenAccount,
+  createMint,
+  mintTo,
+  transfer,
+  getAccount,
   ASSOCIATED_TOKEN_PROGRAM_ID,
 } from '@solana/spl-token';
 import { expect } from 'chai';
@@ -14,14 +18,13 @@ describe('rwa-registry', () => {
   anchor.setProvider(provider);
 
   const program = anchor.workspace.RwaRegistry as Program;
+  const oracleProgram = anchor.workspace.Oracle as Program;
   const authority = provider.wallet;
   const custodian = Keypair.generate();
 
   let assetPda: PublicKey;
   let ownershipPda: PublicKey;
-  let dividendPda: PublicKey;
   let tokenMint: Keypair;
-  const recordDate = new anchor.BN(1);
 
   before(async () => {
     tokenMint = Keypair.generate();
@@ -39,17 +42,23 @@ describe('rwa-registry', () => {
       ],
       program.programId
     );
-
-    [dividendPda] = PublicKey.findProgramAddressSync(
-      [
-        Buffer.from('dividend'),
-        assetPda.toBuffer(),
-        recordDate.toArrayLike(Buffer, 'le', 8),
-      ],
-      program.programId
-    );
   });
 
+  // UpdateAssetConfigParams with every field left unchanged except `overrides`
+  function assetConfig(overrides: Record<string, any>) {
+    return {
+      requireDividendSnapshot: null,
+      minHoldingSeconds: null,
+      redemptionFeeBps: null,
+      feeTreasury: null,
+      maxDividendWindowSeconds: null,
+      clawbackWindowSeconds: null,
+      maxValuationConfidenceBps: null,
+      blockDelistedRedemptions: null,
+      ...overrides,
+    };
+  }
+
   describe('register_asset', () => {
     it('should register a new RWA asset', async () => {
       const legalDocHash = Buffer.alloc(32);
@@ -66,6 +75,7 @@ describe('rwa-registry', () => {
           isin: null,
           jurisdiction: { japan: {} },
           legalDocumentHash: Array.from(legalDocHash),
+          priceFeed: null,
         })
         .accounts({
           authority: authority.publicKey,
@@ -100,6 +110,7 @@ describe('rwa-registry', () => {
             isin: null,
             jurisdiction: { japan: {} },
             legalDocumentHash: Array.from(Buffer.alloc(32)),
+            priceFeed: null,
           })
           .accounts({
             authority: authority.publicKey,
@@ -204,19 +215,412 @@ describe('rwa-registry', () => {
       const asset = await program.account.rwaAsset.fetch(assetPda);
       expect(asset.valuation.toNumber()).to.equal(600_000_000);
     });
+
+    it('should accept updates from a delegated valuation authority', async () => {
+      const appraiser = Keypair.generate();
+      const update = (valuation: number) =>
+        program.methods
+          .updateValuation(new anchor.BN(valuation), Array.from(Buffer.alloc(32)))
+          .accounts({ authority: appraiser.publicKey, asset: assetPda })
+          .signers([appraiser])
+          .rpc();
+
+      try {
+        await update(610_000_000);
+        expect.fail('Should have thrown Unauthorized');
+      } catch (err: any) {
+        expect(err.toString()).to.include('Unauthorized');
+      }
+
+      await program.methods
+        .setValuationAuthority(appraiser.publicKey)
+        .accounts({ authority: authority.publicKey, asset: assetPda })
+        .rpc();
+      await update(610_000_000);
+
+      let asset = await program.account.rwaAsset.fetch(assetPda);
+      expect(asset.valuation.toNumber()).to.equal(610_000_000);
+      expect(asset.valuationAuthority.toBase58()).to.equal(appraiser.publicKey.toBase58());
+
+      // The asset authority keeps its own right to update
+      await program.methods
+        .updateValuation(new anchor.BN(600_000_000), Array.from(Buffer.alloc(32)))
+        .accounts({ authority: authority.publicKey, asset: assetPda })
+        .rpc();
+
+      await program.methods
+        .setValuationAuthority(null)
+        .accounts({ authority: authority.publicKey, asset: assetPda })
+        .rpc();
+      try {
+        await update(620_000_000);
+        expect.fail('Should have thrown Unauthorized');
+      } catch (err: any) {
+        expect(err.toString()).to.include('Unauthorized');
+      }
+      asset = await program.account.rwaAsset.fetch(assetPda);
+      expect(asset.valuation.toNumber()).to.equal(600_000_000);
+    });
+  });
+
+  describe('commodity units', () => {
+    const goldMint = Keypair.generate();
+    let goldAsset: PublicKey;
+    let goldFeed: PublicKey;
+
+    before(async () => {
+      [goldAsset] = PublicKey.findProgramAddressSync(
+        [Buffer.from('asset'), Buffer.from('MERI-AU-01')],
+        program.programId
+      );
+      await program.methods
+        .registerAsset({
+          custodian: custodian.publicKey,
+          assetType: { commodity: {} },
+          valuation: new anchor.BN(0),
+          valuationCurrency: { usd: {} },
+          name: 'Meridian Allocated Gold',
+          symbol: 'MERI-AU-01',
+          isin: null,
+          jurisdiction: { japan: {} },
+          legalDocumentHash: Array.from(Buffer.alloc(32)),
+          priceFeed: null,
+        })
+        .accounts({
+          authority: authority.publicKey,
+          asset: goldAsset,
+          tokenMint: goldMint.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: SYSVAR_RENT_PUBKEY,
+        })
+        .signers([goldMint])
+        .rpc();
+
+      // Mocked spot price: 2,350.50 USD per troy ounce at the default 1e6 scaling
+      [goldFeed] = PublicKey.findProgramAddressSync(
+        [Buffer.from('price_feed'), Buffer.from('XAU-RWA')],
+        oracleProgram.programId
+      );
+      await oracleProgram.methods
+        .initializePriceFeed({
+          assetSymbol: 'XAU-RWA',
+          assetType: { rwa: {} },
+          sampleIntervalSeconds: 60,
+          priceExponent: null,
+        })
+        .accounts({
+          authority: authority.publicKey,
+          priceFeed: goldFeed,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+      await oracleProgram.methods
+        .updatePrice(new anchor.BN(2_350_500_000), new anchor.BN(1_000_000))
+        .accounts({ authority: authority.publicKey, priceFeed: goldFeed })
+        .rpc();
+    });
+
+    function crank(priceFeed: PublicKey) {
+      return program.methods
+        .updateValuationFromOracle()
+        .accounts({ cranker: custodian.publicKey, asset: goldAsset, priceFeed })
+        .signers([custodian])
+        .rpc();
+    }
+
+    it('should reject commodity units on a non-commodity asset', async () => {
+      try {
+        await program.methods
+          .setCommodityUnits({
+            unitQuantity: new anchor.BN(1),
+            unitDecimals: 0,
+            unitSymbol: 'OZ',
+            priceFeed: goldFeed,
+          })
+          .accounts({ authority: authority.publicKey, asset: assetPda })
+          .rpc();
+        expect.fail('Should have thrown NotCommodity');
+      } catch (err: any) {
+        expect(err.toString()).to.include('NotCommodity');
+      }
+    });
+
+    it('should require a configured price feed', async () => {
+      try {
+        await crank(goldFeed);
+        expect.fail('Should have thrown PriceFeedMismatch');
+      } catch (err: any) {
+        expect(err.toString()).to.include('PriceFeedMismatch');
+      }
+    });
+
+    it('should value the holding at unit quantity times the spot price', async () => {
+      // 125.5 oz held, one decimal place
+      await program.methods
+        .setCommodityUnits({
+          unitQuantity: new anchor.BN(1255),
+          unitDecimals: 1,
+          unitSymbol: 'XAU-OZ',
+          priceFeed: goldFeed,
+        })
+        .accounts({ authority: authority.publicKey, asset: goldAsset })
+        .rpc();
+
+      await crank(goldFeed);
+
+      const asset = await program.account.rwaAsset.fetch(goldAsset);
+      expect(asset.unitSymbol).to.equal('XAU-OZ');
+      expect(asset.version).to.equal(8);
+      // 125.5 × 2,350.50 = 294,987.75 USD, in cents
+      expect(asset.valuation.toNumber()).to.equal(29_498_775);
+
+      await oracleProgram.methods
+        .updatePrice(new anchor.BN(2_400_000_000), new anchor.BN(1_000_000))
+        .accounts({ authority: authority.publicKey, priceFeed: goldFeed })
+        .rpc();
+      await crank(goldFeed);
+      const repriced = await program.account.rwaAsset.fetch(goldAsset);
+      expect(repriced.valuation.toNumber()).to.equal(30_120_000);
+    });
+
+    it('should skip oracle updates from a low-confidence feed', async () => {
+      // At most a 0.5% confidence band
+      await program.methods
+        .updateAssetConfig(assetConfig({ maxValuationConfidenceBps: 50 }))
+        .accounts({ authority: authority.publicKey, asset: goldAsset })
+        .rpc();
+
+      // 2% band: valuation stays at the last accepted price
+      await oracleProgram.methods
+        .updatePrice(new anchor.BN(2_500_000_000), new anchor.BN(50_000_000))
+        .accounts({ authority: authority.publicKey, priceFeed: goldFeed })
+        .rpc();
+      await crank(goldFeed);
+      let asset = await program.account.rwaAsset.fetch(goldAsset);
+      expect(asset.valuation.toNumber()).to.equal(30_120_000);
+
+      // 0.04% band: accepted
+      await oracleProgram.methods
+        .updatePrice(new anchor.BN(2_500_000_000), new anchor.BN(1_000_000))
+        .accounts({ authority: authority.publicKey, priceFeed: goldFeed })
+        .rpc();
+      await crank(goldFeed);
+      asset = await program.account.rwaAsset.fetch(goldAsset);
+      // 125.5 × 2,500 = 313,750 USD
+      expect(asset.valuation.toNumber()).to.equal(31_375_000);
+
+      await program.methods
+        .updateAssetConfig(assetConfig({ maxValuationConfidenceBps: 0 }))
+        .accounts({ authority: authority.publicKey, asset: goldAsset })
+        .rpc();
+    });
+
+    it('should reject a feed other than the configured one', async () => {
+      const [otherFeed] = PublicKey.findProgramAddressSync(
+        [Buffer.from('price_feed'), Buffer.from('XAG-RWA')],
+        oracleProgram.programId
+      );
+      await oracleProgram.methods
+        .initializePriceFeed({
+          assetSymbol: 'XAG-RWA',
+          assetType: { rwa: {} },
+          sampleIntervalSeconds: 60,
+          priceExponent: null,
+        })
+        .accounts({
+          authority: authority.publicKey,
+          priceFeed: otherFeed,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+      try {
+        await crank(otherFeed);
+        expect.fail('Should have thrown PriceFeedMismatch');
+      } catch (err: any) {
+        expect(err.toString()).to.include('PriceFeedMismatch');
+      }
+    });
+  });
+
+  describe('per-token oracle valuation', () => {
+    it('should value an equity asset at supply times the feed price', async () => {
+      const payer = (provider.wallet as any).payer as Keypair;
+      const equityMint = Keypair.generate();
+      const [equityAsset] = PublicKey.findProgramAddressSync(
+        [Buffer.from('asset'), Buffer.from('MERI-EQ-01')],
+        program.programId
+      );
+      const [equityFeed] = PublicKey.findProgramAddressSync(
+        [Buffer.from('price_feed'), Buffer.from('MERI-EQ-01')],
+        oracleProgram.programId
+      );
+      await oracleProgram.methods
+        .initializePriceFeed({
+          assetSymbol: 'MERI-EQ-01',
+          assetType: { rwa: {} },
+          sampleIntervalSeconds: 60,
+          priceExponent: null,
+        })
+        .accounts({
+          authority: authority.publicKey,
+          priceFeed: equityFeed,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+      // 10.25 USD per token
+      await oracleProgram.methods
+        .updatePrice(new anchor.BN(10_250_000), new anchor.BN(1_000))
+        .accounts({ authority: authority.publicKey, priceFeed: equityFeed })
+        .rpc();
+
+      await program.methods
+        .registerAsset({
+          custodian: custodian.publicKey,
+          assetType: { equity: {} },
+          valuation: new anchor.BN(0),
+          valuationCurrency: { usd: {} },
+          name: 'Meridian Listed Equity',
+          symbol: 'MERI-EQ-01',
+          isin: null,
+          jurisdiction: { japan: {} },
+          legalDocumentHash: Array.from(Buffer.alloc(32)),
+          priceFeed: equityFeed,
+        })
+        .accounts({
+          authority: authority.publicKey,
+          asset: equityAsset,
+          tokenMint: equityMint.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: SYSVAR_RENT_PUBKEY,
+        })
+        .signers([equityMint])
+        .rpc();
+      await program.methods
+        .verifyCustody(Array.from(Buffer.alloc(32, 0xef)))
+        .accounts({ custodian: custodian.publicKey, asset: equityAsset })
+        .signers([custodian])
+        .rpc();
+
+      // 2.5 tokens at 6 decimals
+      const recipientToken = await createAssociatedTokenAccount(
+        provider.connection,
+        payer,
+        equityMint.publicKey,
+        authority.publicKey,
+      );
+      await program.methods
+        .mintTokens(new anchor.BN(2_500_000), authority.publicKey)
+        .accounts({
+          authority: authority.publicKey,
+          asset: equityAsset,
+          tokenMint: equityMint.publicKey,
+          recipientToken,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      await program.methods
+        .updateValuationFromOracle()
+        .accounts({ cranker: custodian.publicKey, asset: equityAsset, priceFeed: equityFeed })
+        .signers([custodian])
+        .rpc();
+
+      const asset = await program.account.rwaAsset.fetch(equityAsset);
+      expect(asset.priceFeed.toBase58()).to.equal(equityFeed.toBase58());
+      // 2.5 × 10.25 = 25.625 USD, in cents rounded down
+      expect(asset.valuation.toNumber()).to.equal(2_562);
+    });
   });
 
   describe('dividends', () => {
+    let paymentMint: PublicKey;
+    // The authority both funds dividends and, as the holder, receives them
+    let authorityPayment: PublicKey;
+    // Main dividend, recorded once the holding has been issued
+    let recordDate: anchor.BN;
+    let dividendPda: PublicKey;
+
+    function escrowFor(dividend: PublicKey) {
+      return PublicKey.findProgramAddressSync(
+        [Buffer.from('dividend_escrow'), dividend.toBuffer()],
+        program.programId
+      )[0];
+    }
+
+    // Accounts distribute_dividend and fund_dividend use to fund the escrow
+    function funding(dividend: PublicKey) {
+      return {
+        paymentMint,
+        authorityPayment,
+        escrow: escrowFor(dividend),
+        tokenProgram: TOKEN_PROGRAM_ID,
+      };
+    }
+
+    // Accounts claim_dividend uses to pay the holder
+    function payout(dividend: PublicKey) {
+      return {
+        escrow: escrowFor(dividend),
+        ownerPayment: authorityPayment,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      };
+    }
+
+    // Snapshot the authority's holding for `dividend`, returning the snapshot PDA
+    async function snapshot(dividend: PublicKey) {
+      const [pda] = PublicKey.findProgramAddressSync(
+        [Buffer.from('dividend_snapshot'), dividend.toBuffer(), authority.publicKey.toBuffer()],
+        program.programId
+      );
+      await program.methods
+        .snapshotDividendHolder()
+        .accounts({
+          payer: authority.publicKey,
+          asset: assetPda,
+          dividend,
+          ownershipProof: ownershipPda,
+          snapshot: pda,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+      return pda;
+    }
+
+    async function paymentBalance() {
+      return Number((await getAccount(provider.connection, authorityPayment)).amount);
+    }
+
+    before(async () => {
+      const payer = (authority as anchor.Wallet).payer;
+      paymentMint = await createMint(provider.connection, payer, authority.publicKey, null, 6);
+      authorityPayment = await createAssociatedTokenAccount(
+        provider.connection,
+        payer,
+        paymentMint,
+        authority.publicKey
+      );
+      await mintTo(provider.connection, payer, paymentMint, authorityPayment, payer, 1_000_000);
+
+      // Live-holdings claims need the holding unchanged since the record date
+      recordDate = (await program.account.ownershipProof.fetch(ownershipPda)).amountChangedAt;
+      [dividendPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from('dividend'), assetPda.toBuffer(), recordDate.toArrayLike(Buffer, 'le', 8)],
+        program.programId
+      );
+    });
+
     it('should distribute a dividend', async () => {
-      const paymentToken = Keypair.generate().publicKey;
-      // Set payment date in the past so dividend is immediately claimable
-      const paymentDate = new anchor.BN(Math.floor(Date.now() / 1000) - 1);
+      // Pay on the record date, already past, so the dividend is immediately claimable
+      const paymentDate = recordDate;
 
       const tx = await program.methods
         .distributeDividend({
           amountPerToken: new anchor.BN(5_000),
-          totalAmount: new anchor.BN(5_000_000),
-          paymentToken,
+          totalAmount: new anchor.BN(5),
+          paymentToken: paymentMint,
           recordDate,
           paymentDate,
         })
@@ -224,6 +628,7 @@ describe('rwa-registry', () => {
           authority: authority.publicKey,
           asset: assetPda,
           dividend: dividendPda,
+          ...funding(dividendPda),
           systemProgram: SystemProgram.programId,
         })
         .rpc();
@@ -231,9 +636,13 @@ describe('rwa-registry', () => {
       const dividend = await program.account.dividend.fetch(dividendPda);
       expect(dividend.amountPerToken.toNumber()).to.equal(5_000);
       expect(dividend.status).to.deep.equal({ payable: {} });
+      const escrow = await getAccount(provider.connection, escrowFor(dividendPda));
+      expect(Number(escrow.amount)).to.equal(5);
     });
 
     it('should claim a dividend', async () => {
+      const before = await paymentBalance();
+
       const tx = await program.methods
         .claimDividend()
         .accounts({
@@ -241,65 +650,1190 @@ describe('rwa-registry', () => {
           asset: assetPda,
           ownershipProof: ownershipPda,
           dividend: dividendPda,
+          snapshot: null,
+          ...payout(dividendPda),
         })
         .rpc();
 
       const dividend = await program.account.dividend.fetch(dividendPda);
-      expect(dividend.claimedAmount.toNumber()).to.be.greaterThan(0);
+      expect(dividend.claimedAmount.toNumber()).to.equal(5);
+      expect(dividend.status).to.deep.equal({ completed: {} });
+      expect((await paymentBalance()) - before).to.equal(5);
+      const escrow = await getAccount(provider.connection, escrowFor(dividendPda));
+      expect(Number(escrow.amount)).to.equal(0);
     });
-  });
 
-  describe('freeze and unfreeze', () => {
-    it('should freeze an asset', async () => {
-      const tx = await program.methods
-        .freezeAsset()
+    it('should reject a second claim by the same holder', async () => {
+      try {
+        await program.methods
+          .claimDividend()
+          .accounts({
+            owner: authority.publicKey,
+            asset: assetPda,
+            ownershipProof: ownershipPda,
+            dividend: dividendPda,
+            snapshot: null,
+            ...payout(dividendPda),
+          })
+          .rpc();
+        expect.fail('Should have rejected the second claim');
+      } catch (err: any) {
+        expect(err.toString()).to.not.include('Should have rejected');
+      }
+    });
+
+    it('should reject a claim before the payment date', async () => {
+      const now = Math.floor(Date.now() / 1000);
+      const futureRecordDate = new anchor.BN(now - 60);
+      const [futureDividendPda] = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from('dividend'),
+          assetPda.toBuffer(),
+          futureRecordDate.toArrayLike(Buffer, 'le', 8),
+        ],
+        program.programId
+      );
+
+      await program.methods
+        .distributeDividend({
+          amountPerToken: new anchor.BN(1_000),
+          totalAmount: new anchor.BN(1),
+          paymentToken: paymentMint,
+          recordDate: futureRecordDate,
+          paymentDate: new anchor.BN(now + 3_600),
+        })
         .accounts({
           authority: authority.publicKey,
           asset: assetPda,
+          dividend: futureDividendPda,
+          ...funding(futureDividendPda),
+          systemProgram: SystemProgram.programId,
         })
         .rpc();
 
-      const asset = await program.account.rwaAsset.fetch(assetPda);
-      expect(asset.isFrozen).to.be.true;
+      try {
+        await program.methods
+          .claimDividend()
+          .accounts({
+            owner: authority.publicKey,
+            asset: assetPda,
+            ownershipProof: ownershipPda,
+            dividend: futureDividendPda,
+            snapshot: null,
+            ...payout(futureDividendPda),
+          })
+          .rpc();
+        expect.fail('Should have thrown DividendNotAvailable');
+      } catch (err: any) {
+        expect(err.toString()).to.include('DividendNotAvailable');
+      }
     });
 
-    it('should reject operations on frozen asset', async () => {
-      // Use the ATA that was created in the mint_tokens test
-      const recipientTokenAccount = getAssociatedTokenAddressSync(
-        tokenMint.publicKey,
-        authority.publicKey,
+    it('should require a record-date snapshot when configured', async () => {
+      const snapshotRecordDate = new anchor.BN(2);
+      const [snapshotDividendPda] = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from('dividend'),
+          assetPda.toBuffer(),
+          snapshotRecordDate.toArrayLike(Buffer, 'le', 8),
+        ],
+        program.programId
       );
 
+      await program.methods
+        .updateAssetConfig(assetConfig({ requireDividendSnapshot: true }))
+        .accounts({ authority: authority.publicKey, asset: assetPda })
+        .rpc();
+
+      await program.methods
+        .distributeDividend({
+          amountPerToken: new anchor.BN(5_000),
+          totalAmount: new anchor.BN(5),
+          paymentToken: paymentMint,
+          recordDate: snapshotRecordDate,
+          paymentDate: new anchor.BN(Math.floor(Date.now() / 1000) - 1),
+        })
+        .accounts({
+          authority: authority.publicKey,
+          asset: assetPda,
+          dividend: snapshotDividendPda,
+          ...funding(snapshotDividendPda),
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
       try {
         await program.methods
-          .mintTokens(new anchor.BN(100), authority.publicKey)
+          .claimDividend()
           .accounts({
-            authority: authority.publicKey,
+            owner: authority.publicKey,
             asset: assetPda,
-            tokenMint: tokenMint.publicKey,
-            recipientToken: recipientTokenAccount,
             ownershipProof: ownershipPda,
-            tokenProgram: TOKEN_PROGRAM_ID,
-            systemProgram: SystemProgram.programId,
+            dividend: snapshotDividendPda,
+            snapshot: null,
+            ...payout(snapshotDividendPda),
           })
           .rpc();
-        expect.fail('Should have thrown an error');
+        expect.fail('Should have thrown SnapshotRequired');
       } catch (err: any) {
-        expect(err).to.exist;
+        expect(err.error?.errorCode?.code || err.message).to.include('SnapshotRequired');
+      }
+
+      // The holding was issued long after this record date
+      try {
+        await snapshot(snapshotDividendPda);
+        expect.fail('Should have thrown HoldingChangedSinceRecord');
+      } catch (err: any) {
+        expect(err.error?.errorCode?.code || err.message).to.include('HoldingChangedSinceRecord');
       }
+
+      await program.methods
+        .updateAssetConfig(assetConfig({ requireDividendSnapshot: false }))
+        .accounts({ authority: authority.publicKey, asset: assetPda })
+        .rpc();
     });
 
-    it('should unfreeze an asset', async () => {
-      const tx = await program.methods
-        .unfreezeAsset()
+    it('should only snapshot from the record date and pay against the snapshot', async () => {
+      const recordDate = new anchor.BN(Math.floor(Date.now() / 1000) + 2);
+      const [dividend] = PublicKey.findProgramAddressSync(
+        [Buffer.from('dividend'), assetPda.toBuffer(), recordDate.toArrayLike(Buffer, 'le', 8)],
+        program.programId
+      );
+      const [claim] = PublicKey.findProgramAddressSync(
+        [Buffer.from('dividend_claim'), dividend.toBuffer(), authority.publicKey.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .updateAssetConfig(assetConfig({ requireDividendSnapshot: true }))
+        .accounts({ authority: authority.publicKey, asset: assetPda })
+        .rpc();
+      await program.methods
+        .distributeDividend({
+          amountPerToken: new anchor.BN(1_000),
+          totalAmount: new anchor.BN(1),
+          paymentToken: paymentMint,
+          recordDate,
+          paymentDate: recordDate,
+        })
         .accounts({
           authority: authority.publicKey,
           asset: assetPda,
+          dividend,
+          ...funding(dividend),
+          systemProgram: SystemProgram.programId,
         })
         .rpc();
 
-      const asset = await program.account.rwaAsset.fetch(assetPda);
-      expect(asset.isFrozen).to.be.false;
+      try {
+        await snapshot(dividend);
+        expect.fail('Should have thrown SnapshotNotOpen');
+      } catch (err: any) {
+        expect(err.error?.errorCode?.code || err.message).to.include('SnapshotNotOpen');
+      }
+
+      await new Promise((resolve) => setTimeout(resolve, 3000));
+      const snapshotPda = await snapshot(dividend);
+      await program.methods
+        .claimDividend()
+        .accounts({
+          owner: authority.publicKey,
+          asset: assetPda,
+          ownershipProof: ownershipPda,
+          dividend,
+          snapshot: snapshotPda,
+          claim,
+          ...payout(dividend),
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      const recorded = await program.account.dividendSnapshot.fetch(snapshotPda);
+      const paid = await program.account.dividendClaim.fetch(claim);
+      expect(paid.amount.toNumber()).to.equal(
+        Math.min(Math.floor(recorded.amount.toNumber() * 1_000 / 1_000_000), 1)
+      );
+
+      await program.methods
+        .updateAssetConfig(assetConfig({ requireDividendSnapshot: false }))
+        .accounts({ authority: authority.publicKey, asset: assetPda })
+        .rpc();
+    });
+
+    describe('minimum holding period', () => {
+      const nowSeconds = () => Math.floor(Date.now() / 1000);
+
+      // Pays on the record date; a past record date makes it payable immediately
+      async function announce(recordDate: anchor.BN) {
+        const [pda] = PublicKey.findProgramAddressSync(
+          [Buffer.from('dividend'), assetPda.toBuffer(), recordDate.toArrayLike(Buffer, 'le', 8)],
+          program.programId
+        );
+        await program.methods
+          .distributeDividend({
+            amountPerToken: new anchor.BN(1_000),
+            totalAmount: new anchor.BN(1),
+            paymentToken: paymentMint,
+            recordDate,
+            paymentDate: recordDate,
+          })
+          .accounts({
+            authority: authority.publicKey,
+            asset: assetPda,
+            dividend: pda,
+            ...funding(pda),
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+        return pda;
+      }
+
+      function claim(dividend: PublicKey) {
+        return program.methods
+          .claimDividend()
+          .accounts({
+            owner: authority.publicKey,
+            asset: assetPda,
+            ownershipProof: ownershipPda,
+            dividend,
+            snapshot: null,
+            ...payout(dividend),
+          })
+          .rpc();
+      }
+
+      before(async () => {
+        await program.methods
+          .updateAssetConfig(assetConfig({ minHoldingSeconds: new anchor.BN(86_400) }))
+          .accounts({ authority: authority.publicKey, asset: assetPda })
+          .rpc();
+      });
+
+      after(async () => {
+        await program.methods
+          .updateAssetConfig(assetConfig({ minHoldingSeconds: new anchor.BN(0) }))
+          .accounts({ authority: authority.publicKey, asset: assetPda })
+          .rpc();
+      });
+
+      it('should reject a holder who acquired just before the record date', async () => {
+        const dividend = await announce(new anchor.BN(nowSeconds() - 1));
+        try {
+          await claim(dividend);
+          expect.fail('Should have thrown HoldingPeriodNotMet');
+        } catch (err: any) {
+          expect(err.toString()).to.include('HoldingPeriodNotMet');
+        }
+      });
+
+      it('should pay a holder who held for the full period', async () => {
+        await program.methods
+          .updateAssetConfig(assetConfig({ minHoldingSeconds: new anchor.BN(1) }))
+          .accounts({ authority: authority.publicKey, asset: assetPda })
+          .rpc();
+        await new Promise((resolve) => setTimeout(resolve, 2000));
+
+        const dividend = await announce(new anchor.BN(nowSeconds() - 1));
+        await claim(dividend);
+
+        const account = await program.account.dividend.fetch(dividend);
+        expect(account.claimedAmount.toNumber()).to.be.greaterThan(0);
+      });
+    });
+
+    describe('claim clawback', () => {
+      const nowSeconds = () => Math.floor(Date.now() / 1000);
+
+      async function announceAndClaim(recordDate: anchor.BN) {
+        const [dividend] = PublicKey.findProgramAddressSync(
+          [Buffer.from('dividend'), assetPda.toBuffer(), recordDate.toArrayLike(Buffer, 'le', 8)],
+          program.programId
+        );
+        const [claim] = PublicKey.findProgramAddressSync(
+          [Buffer.from('dividend_claim'), dividend.toBuffer(), authority.publicKey.toBuffer()],
+          program.programId
+        );
+        await program.methods
+          .distributeDividend({
+            amountPerToken: new anchor.BN(1_000),
+            totalAmount: new anchor.BN(1),
+            paymentToken: paymentMint,
+            recordDate,
+            paymentDate: recordDate,
+          })
+          .accounts({
+            authority: authority.publicKey,
+            asset: assetPda,
+            dividend,
+            ...funding(dividend),
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+        await claimAgain(dividend, claim);
+        return { dividend, claim };
+      }
+
+      function claimAgain(dividend: PublicKey, claim: PublicKey) {
+        return program.methods
+          .claimDividend()
+          .accounts({
+            owner: authority.publicKey,
+            asset: assetPda,
+            ownershipProof: ownershipPda,
+            dividend,
+            snapshot: null,
+            claim,
+            ...payout(dividend),
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+      }
+
+      function clawback(dividend: PublicKey, claim: PublicKey) {
+        return program.methods
+          .clawbackDividendClaim(Array.from(Buffer.alloc(32, 0xcd)))
+          .accounts({ authority: authority.publicKey, asset: assetPda, dividend, claim })
+          .rpc();
+      }
+
+      after(async () => {
+        await program.methods
+          .updateAssetConfig(assetConfig({ clawbackWindowSeconds: new anchor.BN(0) }))
+          .accounts({ authority: authority.publicKey, asset: assetPda })
+          .rpc();
+      });
+
+      it('should reverse a claim inside the clawback window', async () => {
+        await program.methods
+          .updateAssetConfig(assetConfig({ clawbackWindowSeconds: new anchor.BN(3_600) }))
+          .accounts({ authority: authority.publicKey, asset: assetPda })
+          .rpc();
+
+        const { dividend, claim } = await announceAndClaim(new anchor.BN(nowSeconds() - 10));
+        expect((await program.account.dividend.fetch(dividend)).claimedAmount.toNumber())
+          .to.be.greaterThan(0);
+
+        const claimed = (await program.account.dividendClaim.fetch(claim)).amount.toNumber();
+        try {
+          await claimAgain(dividend, claim);
+          expect.fail('Should have thrown DividendAlreadyClaimed');
+        } catch (err: any) {
+          expect(err.toString()).to.include('DividendAlreadyClaimed');
+        }
+
+        await clawback(dividend, claim);
+
+        // The holder kept the payment, so the receipt records it as owed
+        let receipt = await program.account.dividendClaim.fetch(claim);
+        expect(receipt.clawedBack).to.be.true;
+        expect(receipt.owed.toNumber()).to.equal(claimed);
+        expect(receipt.amount.toNumber()).to.equal(0);
+        try {
+          await clawback(dividend, claim);
+          expect.fail('Should have thrown ClaimAlreadyClawedBack');
+        } catch (err: any) {
+          expect(err.toString()).to.include('ClaimAlreadyClawedBack');
+        }
+
+        // The corrected claim is netted against what the holder still has
+        const paymentBefore = await paymentBalance();
+        await claimAgain(dividend, claim);
+        expect(await paymentBalance()).to.equal(paymentBefore);
+
+        receipt = await program.account.dividendClaim.fetch(claim);
+        expect(receipt.clawedBack).to.be.false;
+        expect(receipt.owed.toNumber()).to.equal(0);
+        expect(receipt.amount.toNumber()).to.equal(claimed);
+        expect((await program.account.dividend.fetch(dividend)).claimedAmount.toNumber()).to.equal(claimed);
+      });
+
+      it('should treat claims as final once the window has passed', async () => {
+        await program.methods
+          .updateAssetConfig(assetConfig({ clawbackWindowSeconds: new anchor.BN(1) }))
+          .accounts({ authority: authority.publicKey, asset: assetPda })
+          .rpc();
+
+        const { dividend, claim } = await announceAndClaim(new anchor.BN(nowSeconds() - 20));
+        await new Promise((resolve) => setTimeout(resolve, 3000));
+
+        try {
+          await clawback(dividend, claim);
+          expect.fail('Should have thrown ClawbackWindowClosed');
+        } catch (err: any) {
+          expect(err.toString()).to.include('ClawbackWindowClosed');
+        }
+        expect((await program.account.dividend.fetch(dividend)).claimedAmount.toNumber())
+          .to.be.greaterThan(0);
+      });
+    });
+
+    describe('schedule validation', () => {
+      const nowSeconds = () => Math.floor(Date.now() / 1000);
+
+      async function expectRejected(params: Record<string, any>, code: string) {
+        const recordDate = params.recordDate as anchor.BN;
+        const [pda] = PublicKey.findProgramAddressSync(
+          [Buffer.from('dividend'), assetPda.toBuffer(), recordDate.toArrayLike(Buffer, 'le', 8)],
+          program.programId
+        );
+        try {
+          await program.methods
+            .distributeDividend({
+              amountPerToken: new anchor.BN(1_000),
+              totalAmount: new anchor.BN(1),
+              paymentToken: paymentMint,
+              ...params,
+            })
+            .accounts({
+              authority: authority.publicKey,
+              asset: assetPda,
+              dividend: pda,
+              ...funding(pda),
+              systemProgram: SystemProgram.programId,
+            })
+            .rpc();
+          expect.fail(`Should have thrown ${code}`);
+        } catch (err: any) {
+          expect(err.toString()).to.include(code);
+        }
+      }
+
+      it('should reject a payment date before the record date', async () => {
+        const now = nowSeconds();
+        await expectRejected(
+          { recordDate: new anchor.BN(now + 86_400), paymentDate: new anchor.BN(now + 3_600) },
+          'InvalidDividendSchedule'
+        );
+      });
+
+      it('should reject a payment date beyond the configured window', async () => {
+        await program.methods
+          .updateAssetConfig(assetConfig({ maxDividendWindowSeconds: new anchor.BN(7 * 86_400) }))
+          .accounts({ authority: authority.publicKey, asset: assetPda })
+          .rpc();
+
+        const now = nowSeconds();
+        try {
+          await expectRejected(
+            { recordDate: new anchor.BN(now + 86_400), paymentDate: new anchor.BN(now + 30 * 86_400) },
+            'InvalidDividendSchedule'
+          );
+        } finally {
+          await program.methods
+            .updateAssetConfig(assetConfig({ maxDividendWindowSeconds: new anchor.BN(0) }))
+            .accounts({ authority: authority.publicKey, asset: assetPda })
+            .rpc();
+        }
+      });
+
+      it('should reject a total that does not match amount per token times supply', async () => {
+        const now = nowSeconds();
+        // 1_000 tokens at 1_000 (1e-6 scaled) per token is 1, not 1_000_000
+        await expectRejected(
+          {
+            totalAmount: new anchor.BN(1_000_000),
+            recordDate: new anchor.BN(now + 86_400),
+            paymentDate: new anchor.BN(now + 2 * 86_400),
+          },
+          'DividendAmountMismatch'
+        );
+      });
+    });
+
+    it('should distribute a batch of dividends', async () => {
+      const recordDates = [new anchor.BN(3), new anchor.BN(4)];
+      const batchDividendPdas = recordDates.map(
+        (date) =>
+          PublicKey.findProgramAddressSync(
+            [Buffer.from('dividend'), assetPda.toBuffer(), date.toArrayLike(Buffer, 'le', 8)],
+            program.programId
+          )[0]
+      );
+      await program.methods
+        .distributeDividendsBatch(
+          recordDates.map((date) => ({
+            amountPerToken: new anchor.BN(1_000),
+            totalAmount: new anchor.BN(1),
+            paymentToken: paymentMint,
+            recordDate: date,
+            paymentDate: new anchor.BN(Math.floor(Date.now() / 1000) - 1),
+          }))
+        )
+        .accounts({
+          authority: authority.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .remainingAccounts(
+          batchDividendPdas.flatMap((pda) => [
+            { pubkey: assetPda, isSigner: false, isWritable: false },
+            { pubkey: pda, isSigner: false, isWritable: true },
+          ])
+        )
+        .rpc();
+
+      for (const pda of batchDividendPdas) {
+        const dividend = await program.account.dividend.fetch(pda);
+        expect(dividend.asset.toBase58()).to.equal(assetPda.toBase58());
+        expect(dividend.status).to.deep.equal({ payable: {} });
+      }
+
+      // Batch-announced dividends are funded separately before holders claim
+      const [funded] = batchDividendPdas;
+      await program.methods
+        .fundDividend()
+        .accounts({
+          authority: authority.publicKey,
+          asset: assetPda,
+          dividend: funded,
+          ...funding(funded),
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+      const escrow = await getAccount(provider.connection, escrowFor(funded));
+      expect(Number(escrow.amount)).to.equal(1);
+    });
+
+    describe('cancellation and dust', () => {
+      const nowSeconds = () => Math.floor(Date.now() / 1000);
+
+      function cancel(dividend: PublicKey) {
+        return program.methods
+          .cancelDividend()
+          .accounts({
+            authority: authority.publicKey,
+            asset: assetPda,
+            dividend,
+            escrow: escrowFor(dividend),
+            authorityPayment,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .rpc();
+      }
+
+      function reclaim(dividend: PublicKey) {
+        return program.methods
+          .reclaimDividendDust()
+          .accounts({
+            authority: authority.publicKey,
+            asset: assetPda,
+            dividend,
+            escrow: escrowFor(dividend),
+            authorityPayment,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .rpc();
+      }
+
+      // Announce and fund a dividend of 1 paid on `paymentDate`
+      async function announce(recordDate: anchor.BN, paymentDate: anchor.BN) {
+        const [dividend] = PublicKey.findProgramAddressSync(
+          [Buffer.from('dividend'), assetPda.toBuffer(), recordDate.toArrayLike(Buffer, 'le', 8)],
+          program.programId
+        );
+        await program.methods
+          .distributeDividend({
+            amountPerToken: new anchor.BN(1_000),
+            totalAmount: new anchor.BN(1),
+            paymentToken: paymentMint,
+            recordDate,
+            paymentDate,
+          })
+          .accounts({
+            authority: authority.publicKey,
+            asset: assetPda,
+            dividend,
+            ...funding(dividend),
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+        return dividend;
+      }
+
+      async function overfund(dividend: PublicKey, amount: number) {
+        await transfer(
+          provider.connection,
+          (authority as anchor.Wallet).payer,
+          authorityPayment,
+          escrowFor(dividend),
+          authority.publicKey,
+          amount
+        );
+      }
+
+      it('should cancel an announced dividend and refund its escrow', async () => {
+        const dividend = await announce(
+          new anchor.BN(nowSeconds() - 120),
+          new anchor.BN(nowSeconds() + 3_600)
+        );
+        const before = await paymentBalance();
+
+        await cancel(dividend);
+
+        expect((await program.account.dividend.fetch(dividend)).status).to.deep.equal({ cancelled: {} });
+        expect((await paymentBalance()) - before).to.equal(1);
+        const escrow = await getAccount(provider.connection, escrowFor(dividend));
+        expect(Number(escrow.amount)).to.equal(0);
+
+        try {
+          await cancel(dividend);
+          expect.fail('Should have thrown DividendNotCancellable');
+        } catch (err: any) {
+          expect(err.error?.errorCode?.code || err.message).to.include('DividendNotCancellable');
+        }
+
+        // Anything sent to a cancelled dividend's escrow goes back to the issuer
+        await overfund(dividend, 2);
+        const beforeSweep = await paymentBalance();
+        await reclaim(dividend);
+        expect((await paymentBalance()) - beforeSweep).to.equal(2);
+      });
+
+      it('should require the escrow to cancel a funded dividend', async () => {
+        const dividend = await announce(
+          new anchor.BN(nowSeconds() - 240),
+          new anchor.BN(nowSeconds() + 3_600)
+        );
+
+        try {
+          await program.methods
+            .cancelDividend()
+            .accounts({
+              authority: authority.publicKey,
+              asset: assetPda,
+              dividend,
+              escrow: null,
+              authorityPayment,
+              tokenProgram: TOKEN_PROGRAM_ID,
+            })
+            .rpc();
+          expect.fail('Should have thrown DividendEscrowRequired');
+        } catch (err: any) {
+          expect(err.error?.errorCode?.code || err.message).to.include('DividendEscrowRequired');
+        }
+        expect((await program.account.dividend.fetch(dividend)).status).to.deep.equal({ announced: {} });
+      });
+
+      it('should close claims and sweep the escrow once the claim window lapses', async () => {
+        const year = 366 * 86_400;
+        const dividend = await announce(
+          new anchor.BN(nowSeconds() - year - 86_400),
+          new anchor.BN(nowSeconds() - year)
+        );
+
+        try {
+          await program.methods
+            .claimDividend()
+            .accounts({
+              owner: authority.publicKey,
+              asset: assetPda,
+              ownershipProof: ownershipPda,
+              dividend,
+              snapshot: null,
+              ...payout(dividend),
+            })
+            .rpc();
+          expect.fail('Should have thrown DividendClaimWindowClosed');
+        } catch (err: any) {
+          expect(err.error?.errorCode?.code || err.message).to.include('DividendClaimWindowClosed');
+        }
+
+        // The unclaimed entitlement lapsed with the window, so all of it is swept
+        const before = await paymentBalance();
+        await reclaim(dividend);
+        expect((await paymentBalance()) - before).to.equal(1);
+      });
+
+      it('should refuse to cancel a dividend that is already payable', async () => {
+        try {
+          await cancel(dividendPda);
+          expect.fail('Should have thrown DividendNotCancellable');
+        } catch (err: any) {
+          expect(err.error?.errorCode?.code || err.message).to.include('DividendNotCancellable');
+        }
+      });
+
+      it('should sweep the remainder of a completed dividend to the issuer', async () => {
+        try {
+          await reclaim(dividendPda);
+          expect.fail('Should have thrown NoDividendDust');
+        } catch (err: any) {
+          expect(err.error?.errorCode?.code || err.message).to.include('NoDividendDust');
+        }
+
+        // Over-fund the fully claimed escrow
+        await overfund(dividendPda, 3);
+        const before = await paymentBalance();
+
+        await reclaim(dividendPda);
+
+        expect((await paymentBalance()) - before).to.equal(3);
+        const escrow = await getAccount(provider.connection, escrowFor(dividendPda));
+        expect(Number(escrow.amount)).to.equal(0);
+      });
+    });
+
+    describe('transfers after the record date', () => {
+      const second = Keypair.generate();
+      let secondProof: PublicKey;
+      let secondToken: PublicKey;
+      let secondPayment: PublicKey;
+      const authorityToken = () => getAssociatedTokenAddressSync(tokenMint.publicKey, authority.publicKey);
+
+      before(async () => {
+        const payer = (authority as anchor.Wallet).payer;
+        const sig = await provider.connection.requestAirdrop(second.publicKey, 1_000_000_000);
+        await provider.connection.confirmTransaction(sig);
+        [secondProof] = PublicKey.findProgramAddressSync(
+          [Buffer.from('ownership'), assetPda.toBuffer(), second.publicKey.toBuffer()],
+          program.programId
+        );
+        secondToken = await createAssociatedTokenAccount(
+          provider.connection, payer, tokenMint.publicKey, second.publicKey
+        );
+        secondPayment = await createAssociatedTokenAccount(
+          provider.connection, payer, paymentMint, second.publicKey
+        );
+      });
+
+      function move(from: Keypair | null, amount: number) {
+        const [sender, recipient] = from
+          ? [second.publicKey, authority.publicKey]
+          : [authority.publicKey, second.publicKey];
+        const builder = program.methods
+          .transferOwnership(new anchor.BN(amount))
+          .accounts({
+            sender,
+            recipient,
+            asset: assetPda,
+            senderProof: from ? secondProof : ownershipPda,
+            recipientProof: from ? ownershipPda : secondProof,
+            tokenMint: tokenMint.publicKey,
+            senderToken: from ? secondToken : authorityToken(),
+            recipientToken: from ? authorityToken() : secondToken,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+          });
+        return from ? builder.signers([from]).rpc() : builder.rpc();
+      }
+
+      it('should not pay tokens moved after a claim to their new holder', async () => {
+        const recordDate = new anchor.BN(Math.floor(Date.now() / 1000) - 7);
+        const [dividend] = PublicKey.findProgramAddressSync(
+          [Buffer.from('dividend'), assetPda.toBuffer(), recordDate.toArrayLike(Buffer, 'le', 8)],
+          program.programId
+        );
+        await program.methods
+          .distributeDividend({
+            amountPerToken: new anchor.BN(1_000),
+            totalAmount: new anchor.BN(1),
+            paymentToken: paymentMint,
+            recordDate,
+            paymentDate: recordDate,
+          })
+          .accounts({
+            authority: authority.publicKey,
+            asset: assetPda,
+            dividend,
+            ...funding(dividend),
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+        await program.methods
+          .claimDividend()
+          .accounts({
+            owner: authority.publicKey,
+            asset: assetPda,
+            ownershipProof: ownershipPda,
+            dividend,
+            snapshot: null,
+            ...payout(dividend),
+          })
+          .rpc();
+
+        await move(null, 500);
+        try {
+          try {
+            await program.methods
+              .claimDividend()
+              .accounts({
+                owner: second.publicKey,
+                asset: assetPda,
+                ownershipProof: secondProof,
+                dividend,
+                snapshot: null,
+                escrow: escrowFor(dividend),
+                ownerPayment: secondPayment,
+                tokenProgram: TOKEN_PROGRAM_ID,
+              })
+              .signers([second])
+              .rpc();
+            expect.fail('Should have thrown HoldingChangedSinceRecord');
+          } catch (err: any) {
+            expect(err.error?.errorCode?.code || err.message).to.include('HoldingChangedSinceRecord');
+          }
+          const paid = await program.account.dividend.fetch(dividend);
+          expect(paid.claimedAmount.toNumber()).to.equal(1);
+        } finally {
+          // Later suites expect the authority's full holding
+          await move(second, 500);
+        }
+      });
+    });
+  });
+
+  describe('property sub-units', () => {
+    const propertyPda = (id: number) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from('property'), assetPda.toBuffer(), new anchor.BN(id).toArrayLike(Buffer, 'le', 4)],
+        program.programId
+      )[0];
+
+    it('should aggregate asset valuation across properties', async () => {
+      for (const [id, valuation] of [[0, 300_000_000], [1, 200_000_000]]) {
+        await program.methods
+          .addProperty(new anchor.BN(valuation), Array.from(Buffer.alloc(32, id + 1)))
+          .accounts({
+            authority: authority.publicKey,
+            asset: assetPda,
+            property: propertyPda(id),
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+      }
+
+      let asset = await program.account.rwaAsset.fetch(assetPda);
+      expect(asset.valuation.toNumber()).to.equal(500_000_000);
+      expect(asset.activePropertyCount).to.equal(2);
+
+      await program.methods
+        .revalueProperty(new anchor.BN(250_000_000), Array.from(Buffer.alloc(32)))
+        .accounts({ authority: authority.publicKey, asset: assetPda, property: propertyPda(1) })
+        .rpc();
+
+      asset = await program.account.rwaAsset.fetch(assetPda);
+      expect(asset.valuation.toNumber()).to.equal(550_000_000);
+    });
+
+    it('should reduce asset valuation when a property is sold', async () => {
+      await program.methods
+        .markPropertySold(new anchor.BN(320_000_000))
+        .accounts({ authority: authority.publicKey, asset: assetPda, property: propertyPda(0) })
+        .rpc();
+
+      const asset = await program.account.rwaAsset.fetch(assetPda);
+      expect(asset.valuation.toNumber()).to.equal(250_000_000);
+      expect(asset.activePropertyCount).to.equal(1);
+
+      const property = await program.account.propertySubUnit.fetch(propertyPda(0));
+      expect(property.status).to.deep.equal({ sold: {} });
+    });
+
+    it('should reject lump valuation updates while properties are active', async () => {
+      try {
+        await program.methods
+          .updateValuation(new anchor.BN(1), Array.from(Buffer.alloc(32)))
+          .accounts({ authority: authority.publicKey, asset: assetPda })
+          .rpc();
+        expect.fail('Should have thrown ValuationAggregated');
+      } catch (err: any) {
+        expect(err.toString()).to.include('ValuationAggregated');
+      }
+
+      await program.methods
+        .markPropertySold(new anchor.BN(250_000_000))
+        .accounts({ authority: authority.publicKey, asset: assetPda, property: propertyPda(1) })
+        .rpc();
+    });
+  });
+
+  describe('freeze and unfreeze', () => {
+    it('should freeze an asset', async () => {
+      const tx = await program.methods
+        .freezeAsset()
+        .accounts({
+          authority: authority.publicKey,
+          asset: assetPda,
+        })
+        .rpc();
+
+      const asset = await program.account.rwaAsset.fetch(assetPda);
+      expect(asset.isFrozen).to.be.true;
+    });
+
+    it('should reject operations on frozen asset', async () => {
+      // Use the ATA that was created in the mint_tokens test
+      const recipientTokenAccount = getAssociatedTokenAddressSync(
+        tokenMint.publicKey,
+        authority.publicKey,
+      );
+
+      try {
+        await program.methods
+          .mintTokens(new anchor.BN(100), authority.publicKey)
+          .accounts({
+            authority: authority.publicKey,
+            asset: assetPda,
+            tokenMint: tokenMint.publicKey,
+            recipientToken: recipientTokenAccount,
+            ownershipProof: ownershipPda,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+        expect.fail('Should have thrown an error');
+      } catch (err: any) {
+        expect(err).to.exist;
+      }
+    });
+
+    it('should unfreeze an asset', async () => {
+      const tx = await program.methods
+        .unfreezeAsset()
+        .accounts({
+          authority: authority.publicKey,
+          asset: assetPda,
+        })
+        .rpc();
+
+      const asset = await program.account.rwaAsset.fetch(assetPda);
+      expect(asset.isFrozen).to.be.false;
+    });
+  });
+
+  describe('redemption', () => {
+    it('should route the redemption fee to the treasury and burn the rest', async () => {
+      const payer = (provider.wallet as any).payer as Keypair;
+      const treasuryOwner = Keypair.generate();
+      const treasury = await createAssociatedTokenAccount(
+        provider.connection,
+        payer,
+        tokenMint.publicKey,
+        treasuryOwner.publicKey,
+      );
+      const holderToken = getAssociatedTokenAddressSync(tokenMint.publicKey, authority.publicKey);
+
+      await program.methods
+        .updateAssetConfig(assetConfig({ redemptionFeeBps: 100, feeTreasury: treasury }))
+        .accounts({ authority: authority.publicKey, asset: assetPda })
+        .rpc();
+
+      const before = await program.account.rwaAsset.fetch(assetPda);
+      await program.methods
+        .redeemTokens(new anchor.BN(100))
+        .accounts({
+          holder: authority.publicKey,
+          asset: assetPda,
+          tokenMint: tokenMint.publicKey,
+          holderToken,
+          ownershipProof: ownershipPda,
+          feeTreasury: treasury,
+          kycAttestation: null,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc();
+
+      const after = await program.account.rwaAsset.fetch(assetPda);
+      expect(before.totalSupply.sub(after.totalSupply).toNumber()).to.equal(99);
+      const treasuryBalance = await provider.connection.getTokenAccountBalance(treasury);
+      expect(treasuryBalance.value.amount).to.equal('1');
+      const ownership = await program.account.ownershipProof.fetch(ownershipPda);
+      expect(ownership.amount.toNumber()).to.equal(900);
+    });
+
+    it('should reject a fee without a treasury', async () => {
+      try {
+        await program.methods
+          .redeemTokens(new anchor.BN(100))
+          .accounts({
+            holder: authority.publicKey,
+            asset: assetPda,
+            tokenMint: tokenMint.publicKey,
+            holderToken: getAssociatedTokenAddressSync(tokenMint.publicKey, authority.publicKey),
+            ownershipProof: ownershipPda,
+            feeTreasury: null,
+            kycAttestation: null,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .rpc();
+        expect.fail('Should have thrown FeeTreasuryNotConfigured');
+      } catch (err: any) {
+        expect(err.toString()).to.include('FeeTreasuryNotConfigured');
+      }
+    });
+
+    it('should reject redeeming more than the ownership proof records', async () => {
+      try {
+        await program.methods
+          .redeemTokens(new anchor.BN(901))
+          .accounts({
+            holder: authority.publicKey,
+            asset: assetPda,
+            tokenMint: tokenMint.publicKey,
+            holderToken: getAssociatedTokenAddressSync(tokenMint.publicKey, authority.publicKey),
+            ownershipProof: ownershipPda,
+            feeTreasury: null,
+            kycAttestation: null,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .rpc();
+        expect.fail('Should have thrown InvalidOwnershipProof');
+      } catch (err: any) {
+        expect(err.toString()).to.include('InvalidOwnershipProof');
+      }
+    });
+  });
+
+  describe('transfer_ownership', () => {
+    const buyer = Keypair.generate();
+    let buyerProofPda: PublicKey;
+    let buyerToken: PublicKey;
+    const senderToken = () => getAssociatedTokenAddressSync(tokenMint.publicKey, authority.publicKey);
+
+    before(async () => {
+      [buyerProofPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from('ownership'), assetPda.toBuffer(), buyer.publicKey.toBuffer()],
+        program.programId
+      );
+      buyerToken = await createAssociatedTokenAccount(
+        provider.connection,
+        (authority as anchor.Wallet).payer,
+        tokenMint.publicKey,
+        buyer.publicKey,
+      );
+    });
+
+    function transfer(amount: number) {
+      return program.methods
+        .transferOwnership(new anchor.BN(amount))
+        .accounts({
+          sender: authority.publicKey,
+          recipient: buyer.publicKey,
+          asset: assetPda,
+          senderProof: ownershipPda,
+          recipientProof: buyerProofPda,
+          tokenMint: tokenMint.publicKey,
+          senderToken: senderToken(),
+          recipientToken: buyerToken,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    }
+
+    it('should move recorded ownership, its cost basis and the tokens to the recipient', async () => {
+      const before = await program.account.ownershipProof.fetch(ownershipPda);
+      const senderBefore = Number((await getAccount(provider.connection, senderToken())).amount);
+
+      await transfer(300);
+
+      expect(Number((await getAccount(provider.connection, buyerToken)).amount)).to.equal(300);
+      expect(Number((await getAccount(provider.connection, senderToken())).amount))
+        .to.equal(senderBefore - 300);
+
+      const sender = await program.account.ownershipProof.fetch(ownershipPda);
+      const recipient = await program.account.ownershipProof.fetch(buyerProofPda);
+      const movedCost = before.acquisitionPrice.muln(300).div(before.amount);
+      expect(sender.amount.toNumber()).to.equal(before.amount.toNumber() - 300);
+      expect(sender.acquisitionPrice.toString())
+        .to.equal(before.acquisitionPrice.sub(movedCost).toString());
+      expect(recipient.owner.toBase58()).to.equal(buyer.publicKey.toBase58());
+      expect(recipient.amount.toNumber()).to.equal(300);
+      expect(recipient.acquisitionPrice.toString()).to.equal(movedCost.toString());
+      expect(recipient.isActive).to.be.true;
+    });
+
+    it('should reject moving more than the sender has recorded', async () => {
+      const { amount } = await program.account.ownershipProof.fetch(ownershipPda);
+      try {
+        await transfer(amount.toNumber() + 1);
+        expect.fail('Should have thrown InvalidOwnershipProof');
+      } catch (err: any) {
+        expect(err.toString()).to.include('InvalidOwnershipProof');
+      }
+    });
+
+    it('should reject transfers while the asset is suspended', async () => {
+      await program.methods
+        .setAssetStatus({ suspended: {} })
+        .accounts({ authority: authority.publicKey, asset: assetPda })
+        .rpc();
+      try {
+        await transfer(1);
+        expect.fail('Should have thrown AssetNotActive');
+      } catch (err: any) {
+        expect(err.toString()).to.include('AssetNotActive');
+      }
+      await program.methods
+        .setAssetStatus({ active: {} })
+        .accounts({ authority: authority.publicKey, asset: assetPda })
+        .rpc();
+    });
+  });
+
+  // Delisting is final, so this runs last
+  describe('asset status lifecycle', () => {
+    function setStatus(newStatus: Record<string, {}>) {
+      return program.methods
+        .setAssetStatus(newStatus)
+        .accounts({ authority: authority.publicKey, asset: assetPda })
+        .rpc();
+    }
+
+    // 10 tokens at 1% rounds the fee down to zero, so no treasury is needed
+    function redeem() {
+      return program.methods
+        .redeemTokens(new anchor.BN(10))
+        .accounts({
+          holder: authority.publicKey,
+          asset: assetPda,
+          tokenMint: tokenMint.publicKey,
+          holderToken: getAssociatedTokenAddressSync(tokenMint.publicKey, authority.publicKey),
+          ownershipProof: ownershipPda,
+          feeTreasury: null,
+          kycAttestation: null,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc();
+    }
+
+    it('should block redemptions while suspended and resume on reactivation', async () => {
+      await setStatus({ suspended: {} });
+      try {
+        await redeem();
+        expect.fail('Should have thrown AssetNotActive');
+      } catch (err: any) {
+        expect(err.toString()).to.include('AssetNotActive');
+      }
+
+      await setStatus({ active: {} });
+      await redeem();
+      const asset = await program.account.rwaAsset.fetch(assetPda);
+      expect(asset.status).to.deep.equal({ active: {} });
+    });
+
+    it('should keep a delisted asset redeemable unless configured otherwise', async () => {
+      await setStatus({ delisted: {} });
+      await redeem();
+
+      await program.methods
+        .updateAssetConfig(assetConfig({ blockDelistedRedemptions: true }))
+        .accounts({ authority: authority.publicKey, asset: assetPda })
+        .rpc();
+      try {
+        await redeem();
+        expect.fail('Should have thrown AssetNotActive');
+      } catch (err: any) {
+        expect(err.toString()).to.include('AssetNotActive');
+      }
+    });
+
+    it('should treat delisting as terminal', async () => {
+      try {
+        await setStatus({ active: {} });
+        expect.fail('Should have thrown InvalidStatusTransition');
+      } catch (err: any) {
+        expect(err.toString()).to.include('InvalidStatusTransition');
+      }
     });
   });
 });
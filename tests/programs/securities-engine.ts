@@ -1,12 +1,26 @@
 import * as anchor from '@coral-xyz/anchor';
 import { Program } from '@coral-xyz/anchor';
-import { PublicKey, Keypair, SystemProgram, SYSVAR_RENT_PUBKEY } from '@solana/web3.js';
+import {
+  PublicKey,
+  Keypair,
+  SystemProgram,
+  SYSVAR_RENT_PUBKEY,
+  Transaction,
+  sendAndConfirmTransaction,
+} from '@solana/web3.js';
 import {
   TOKEN_PROGRAM_ID,
+  TOKEN_2022_PROGRAM_ID,
+  ExtensionType,
+  getMintLen,
+  createInitializeMintInstruction,
+  createInitializeTransferFeeConfigInstruction,
   createMint,
   createAccount,
   mintTo,
+  getAccount,
   getAssociatedTokenAddressSync,
+  transfer,
 } from '@solana/spl-token';
 import { expect } from 'chai';
 
@@ -15,6 +29,7 @@ describe('securities-engine', () => {
   anchor.setProvider(provider);
 
   const program = anchor.workspace.SecuritiesEngine as Program;
+  const oracleProgram = anchor.workspace.Oracle as Program;
   const authority = provider.wallet;
 
   const securityMintKeypair = Keypair.generate();
@@ -80,8 +95,7 @@ describe('securities-engine', () => {
       program.programId
     );
 
-    // Use a mock oracle
-    oraclePda = Keypair.generate().publicKey;
+    oraclePda = await createPriceFeed('SHARED', 1_000_000, 0);
 
     // Compute user ATAs
     userSecurityAta = getAssociatedTokenAddressSync(securityMintPk, authority.publicKey);
@@ -89,6 +103,190 @@ describe('securities-engine', () => {
     userLpAta = getAssociatedTokenAddressSync(lpMintKeypair.publicKey, authority.publicKey);
   });
 
+  // Creates a market + empty pool on fresh mints with funded user accounts, so
+  // tests that change market state don't disturb the shared market above.
+  async function createIsolatedMarket(
+    symbol: string,
+    oracle: PublicKey = Keypair.generate().publicKey,
+    sharedQuoteMint?: PublicKey,
+    marketType: any = { equity: {} },
+  ) {
+    const payer = (provider.wallet as any).payer as Keypair;
+    const securityMint = await createMint(provider.connection, payer, authority.publicKey, null, 6);
+    const quoteMint = sharedQuoteMint
+      ?? await createMint(provider.connection, payer, authority.publicKey, null, 6);
+
+    const [market] = PublicKey.findProgramAddressSync(
+      [Buffer.from('market'), securityMint.toBuffer(), quoteMint.toBuffer()],
+      program.programId
+    );
+    const [pool] = PublicKey.findProgramAddressSync(
+      [Buffer.from('pool'), market.toBuffer()],
+      program.programId
+    );
+    const [poolAuth] = PublicKey.findProgramAddressSync(
+      [Buffer.from('pool_authority'), market.toBuffer()],
+      program.programId
+    );
+    const lpMint = Keypair.generate();
+    const securityVault = Keypair.generate();
+    const quoteVault = Keypair.generate();
+
+    await program.methods
+      .initializeMarket({
+        marketType,
+        oracle,
+        tradingFeeBps: 30,
+        protocolFeeBps: 5,
+        minTradeSize: new anchor.BN(1),
+        maxTradeSize: new anchor.BN(0),
+        symbol,
+        name: `${symbol} Isolated`,
+        isin: null,
+      })
+      .accounts({
+        authority: authority.publicKey,
+        securityMint,
+        quoteMint,
+        market,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+    await program.methods
+      .initializePool()
+      .accounts({
+        authority: authority.publicKey,
+        market,
+        pool,
+        poolAuthority: poolAuth,
+        lpMint: lpMint.publicKey,
+        securityVault: securityVault.publicKey,
+        quoteVault: quoteVault.publicKey,
+        securityMint,
+        quoteMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        rent: SYSVAR_RENT_PUBKEY,
+      })
+      .signers([lpMint, securityVault, quoteVault])
+      .rpc();
+
+    const userSecurity = await createAccount(
+      provider.connection, payer, securityMint, authority.publicKey, Keypair.generate(),
+    );
+    const userQuote = await createAccount(
+      provider.connection, payer, quoteMint, authority.publicKey, Keypair.generate(),
+    );
+    const userLp = await createAccount(
+      provider.connection, payer, lpMint.publicKey, authority.publicKey, Keypair.generate(),
+    );
+    await mintTo(provider.connection, payer, securityMint, userSecurity, authority.publicKey, 1_000_000_000_000);
+    await mintTo(provider.connection, payer, quoteMint, userQuote, authority.publicKey, 1_000_000_000_000);
+
+    return {
+      securityMint,
+      quoteMint,
+      market,
+      pool,
+      poolAuthority: poolAuth,
+      lpMint: lpMint.publicKey,
+      securityVault: securityVault.publicKey,
+      quoteVault: quoteVault.publicKey,
+      userSecurity,
+      userQuote,
+      userLp,
+    };
+  }
+
+  function swapAccounts(m: Awaited<ReturnType<typeof createIsolatedMarket>>) {
+    return {
+      user: authority.publicKey,
+      market: m.market,
+      pool: m.pool,
+      poolAuthority: m.poolAuthority,
+      securityVault: m.securityVault,
+      quoteVault: m.quoteVault,
+      userSecurity: m.userSecurity,
+      userQuote: m.userQuote,
+      oracleFeed: null,
+      insuranceFund: null,
+      insuranceVault: null,
+      fallbackOracleFeed: null,
+      tokenProgram: TOKEN_PROGRAM_ID,
+    };
+  }
+
+  function liquidityAccounts(m: Awaited<ReturnType<typeof createIsolatedMarket>>) {
+    return {
+      user: authority.publicKey,
+      market: m.market,
+      pool: m.pool,
+      poolAuthority: m.poolAuthority,
+      lpMint: m.lpMint,
+      securityVault: m.securityVault,
+      quoteVault: m.quoteVault,
+      userSecurity: m.userSecurity,
+      userQuote: m.userQuote,
+      userLp: m.userLp,
+      tokenProgram: TOKEN_PROGRAM_ID,
+    };
+  }
+
+  // Creates an oracle price feed (1e6 scaled) and publishes `price` with `confidence`
+  async function createPriceFeed(symbol: string, price: number, confidence: number) {
+    const [feed] = PublicKey.findProgramAddressSync(
+      [Buffer.from('price_feed'), Buffer.from(symbol)],
+      oracleProgram.programId
+    );
+    await oracleProgram.methods
+      .initializePriceFeed({
+        assetSymbol: symbol,
+        assetType: { equity: {} },
+        sampleIntervalSeconds: 60,
+        priceExponent: null,
+      })
+      .accounts({
+        authority: authority.publicKey,
+        priceFeed: feed,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+    await oracleProgram.methods
+      .updatePrice(new anchor.BN(price), new anchor.BN(confidence))
+      .accounts({ authority: authority.publicKey, priceFeed: feed })
+      .rpc();
+    return feed;
+  }
+
+  // UpdateMarketConfigParams with every field left unchanged except `overrides`
+  function marketConfig(overrides: Record<string, any>) {
+    return {
+      tradingFeeBps: null,
+      minLiquidityToTrade: null,
+      lpGovernanceEnabled: null,
+      minGovernedFeeBps: null,
+      maxGovernedFeeBps: null,
+      adlEnabled: null,
+      liquidationBountyBps: null,
+      maxConfidenceBps: null,
+      status: null,
+      tradingOpenSeconds: null,
+      tradingCloseSeconds: null,
+      holidayCalendarHash: null,
+      autoPauseEnabled: null,
+      maxOracleDeviationBps: null,
+      fundingStalenessSeconds: null,
+      closeFeeBps: null,
+      targetInsuranceBalance: null,
+      insuranceFeeShareBps: null,
+      requiresOracle: null,
+      maxFundingIntervalSeconds: null,
+      fallbackOracle: null,
+      ...overrides,
+    };
+  }
+
   describe('initialize_market', () => {
     it('should initialize a new equity market', async () => {
       const tx = await program.methods
@@ -275,6 +473,10 @@ describe('securities-engine', () => {
           quoteVault: quoteVaultKeypair.publicKey,
           userSecurity: userSecurityAccount,
           userQuote: userQuoteAccount,
+          oracleFeed: null,
+          insuranceFund: null,
+          insuranceVault: null,
+          fallbackOracleFeed: null,
           tokenProgram: TOKEN_PROGRAM_ID,
         })
         .rpc();
@@ -300,6 +502,10 @@ describe('securities-engine', () => {
             quoteVault: quoteVaultKeypair.publicKey,
             userSecurity: userSecurityAccount,
             userQuote: userQuoteAccount,
+            oracleFeed: null,
+            insuranceFund: null,
+            insuranceVault: null,
+            fallbackOracleFeed: null,
             tokenProgram: TOKEN_PROGRAM_ID,
           })
           .rpc();
@@ -324,6 +530,10 @@ describe('securities-engine', () => {
           quoteVault: quoteVaultKeypair.publicKey,
           userSecurity: userSecurityAccount,
           userQuote: userQuoteAccount,
+          oracleFeed: null,
+          insuranceFund: null,
+          insuranceVault: null,
+          fallbackOracleFeed: null,
           tokenProgram: TOKEN_PROGRAM_ID,
         })
         .rpc();
@@ -349,6 +559,10 @@ describe('securities-engine', () => {
           quoteVault: quoteVaultKeypair.publicKey,
           userSecurity: userSecurityAccount,
           userQuote: userQuoteAccount,
+          oracleFeed: null,
+          insuranceFund: null,
+          insuranceVault: null,
+          fallbackOracleFeed: null,
           tokenProgram: TOKEN_PROGRAM_ID,
         })
         .rpc();
@@ -356,6 +570,18 @@ describe('securities-engine', () => {
       const poolAfter = await program.account.pool.fetch(poolPda);
       expect(poolAfter.accumulatedFeesQuote.toNumber()).to.be.greaterThan(feesBefore);
     });
+
+    it('should report total fees normalized to quote', async () => {
+      const pool = await program.account.pool.fetch(poolPda);
+      const totalInQuote = await program.methods
+        .getFeesInQuote()
+        .accounts({ market: marketPda, pool: poolPda })
+        .view();
+
+      expect(totalInQuote.toNumber()).to.be.greaterThanOrEqual(
+        pool.accumulatedFeesQuote.toNumber()
+      );
+    });
   });
 
   describe('open_position (perpetuals)', () => {
@@ -408,12 +634,17 @@ describe('securities-engine', () => {
         collateralKp,
       );
 
+      // Positions enter at the oracle mark, so align it with the pool spot
+      await oracleProgram.methods
+        .updatePrice(new anchor.BN(Math.round(currentPrice * 1_000_000)), new anchor.BN(0))
+        .accounts({ authority: authority.publicKey, priceFeed: oraclePda })
+        .rpc();
+
       const tx = await program.methods
         .openPosition({
           positionType: { perpetual: {} },
           side: { long: {} },
           size: new anchor.BN(10_000_000),
-          entryPrice: new anchor.BN(Math.round(currentPrice * 1_000_000)),
           leverage: 5,
           collateral: new anchor.BN(3_000_000_00),
           takeProfit: new anchor.BN(0),
@@ -423,8 +654,11 @@ describe('securities-engine', () => {
           user: authority.publicKey,
           market: marketPda,
           position: positionPda,
+          quoteMint: quoteMintPk,
           userQuote: userQuoteAccount,
           collateralVault,
+          oracleFeed: oraclePda,
+          fallbackOracleFeed: null,
           tokenProgram: TOKEN_PROGRAM_ID,
           systemProgram: SystemProgram.programId,
         })
@@ -439,13 +673,3012 @@ describe('securities-engine', () => {
     });
   });
 
-  describe('constant product formula', () => {
-    it('should maintain x*y=k invariant', async () => {
-      const pool = await program.account.pool.fetch(poolPda);
-      const k = BigInt(pool.securityLiquidity.toString()) *
-                BigInt(pool.quoteLiquidity.toString());
+  describe('minimum liquidity guard', () => {
+    it('should reject swaps against a freshly created empty pool', async () => {
+      const m = await createIsolatedMarket('EMPTY');
 
-      expect(k > BigInt(0)).to.be.true;
+      try {
+        await program.methods
+          .swap(new anchor.BN(1_000), new anchor.BN(0), false)
+          .accounts(swapAccounts(m))
+          .rpc();
+        expect.fail('Should have thrown InsufficientLiquidity');
+      } catch (err: any) {
+        expect(err.error?.errorCode?.code || err.message).to.include('InsufficientLiquidity');
+      }
+    });
+
+    it('should reject swaps until both reserves exceed min_liquidity_to_trade', async () => {
+      const m = await createIsolatedMarket('THIN');
+
+      await program.methods
+        .updateMarketConfig(marketConfig({ minLiquidityToTrade: new anchor.BN(1_000_000) }))
+        .accounts({ authority: authority.publicKey, market: m.market })
+        .rpc();
+
+      await program.methods
+        .addLiquidity(new anchor.BN(500_000), new anchor.BN(500_000), new anchor.BN(0))
+        .accounts(liquidityAccounts(m))
+        .rpc();
+
+      try {
+        await program.methods
+          .swap(new anchor.BN(1_000), new anchor.BN(0), false)
+          .accounts(swapAccounts(m))
+          .rpc();
+        expect.fail('Should have thrown InsufficientLiquidity');
+      } catch (err: any) {
+        expect(err.error?.errorCode?.code || err.message).to.include('InsufficientLiquidity');
+      }
+
+      await program.methods
+        .addLiquidity(new anchor.BN(1_000_000), new anchor.BN(1_000_000), new anchor.BN(0))
+        .accounts(liquidityAccounts(m))
+        .rpc();
+
+      await program.methods
+        .swap(new anchor.BN(1_000), new anchor.BN(0), false)
+        .accounts(swapAccounts(m))
+        .rpc();
+    });
+  });
+
+  describe('oracle failover', () => {
+    it('should keep trading on the fallback feed while the primary is stale', async () => {
+      const primary = await createPriceFeed('FAILPRI', 1_000_000, 0);
+      const fallback = await createPriceFeed('FAILBAK', 1_000_000, 0);
+      const m = await createIsolatedMarket('FAILOVR', primary);
+
+      await program.methods
+        .addLiquidity(new anchor.BN(10_000_000), new anchor.BN(10_000_000), new anchor.BN(0))
+        .accounts(liquidityAccounts(m))
+        .rpc();
+      await program.methods
+        .updateMarketConfig(marketConfig({ requiresOracle: true, fallbackOracle: fallback }))
+        .accounts({ authority: authority.publicKey, market: m.market })
+        .rpc();
+
+      // Let the primary age past the oracle's 300s staleness bound; keep the fallback fresh
+      await new Promise((resolve) => setTimeout(resolve, 305_000));
+      await oracleProgram.methods
+        .updatePrice(new anchor.BN(1_000_000), new anchor.BN(0))
+        .accounts({ authority: authority.publicKey, priceFeed: fallback })
+        .rpc();
+
+      // Without the fallback account the market fails closed
+      try {
+        await program.methods
+          .swap(new anchor.BN(10_000), new anchor.BN(0), false)
+          .accounts({ ...swapAccounts(m), oracleFeed: primary })
+          .rpc();
+        expect.fail('Should have thrown StalePriceFeed');
+      } catch (err: any) {
+        expect(err.toString()).to.include('StalePriceFeed');
+      }
+
+      const sig = await program.methods
+        .swap(new anchor.BN(10_000), new anchor.BN(0), false)
+        .accounts({ ...swapAccounts(m), oracleFeed: primary, fallbackOracleFeed: fallback })
+        .rpc({ commitment: 'confirmed' });
+      const tx = await provider.connection.getTransaction(sig, {
+        commitment: 'confirmed',
+        maxSupportedTransactionVersion: 0,
+      });
+      const parser = new anchor.EventParser(program.programId, program.coder);
+      const failover = [...parser.parseLogs(tx!.meta!.logMessages!)].find(
+        (e) => e.name === 'oracleFailover'
+      )!.data as any;
+      expect(failover.primaryOracle.toBase58()).to.equal(primary.toBase58());
+      expect(failover.fallbackOracle.toBase58()).to.equal(fallback.toBase58());
+      expect(failover.price.toNumber()).to.equal(1_000_000);
+    });
+  });
+
+  describe('oracle requirement', () => {
+    it('should default by market type', async () => {
+      const spot = await createIsolatedMarket('ORQSPOT');
+      const perp = await createIsolatedMarket('ORQPERP', undefined, undefined, { perpetual: {} });
+
+      expect((await program.account.market.fetch(spot.market)).requiresOracle).to.be.false;
+      expect((await program.account.market.fetch(perp.market)).requiresOracle).to.be.true;
+    });
+
+    it('should trade on pool mechanics alone when the oracle is optional', async () => {
+      const m = await createIsolatedMarket('ORQOFF');
+
+      await program.methods
+        .addLiquidity(new anchor.BN(10_000_000), new anchor.BN(10_000_000), new anchor.BN(0))
+        .accounts(liquidityAccounts(m))
+        .rpc();
+      await program.methods
+        .swap(new anchor.BN(10_000), new anchor.BN(0), false)
+        .accounts(swapAccounts(m))
+        .rpc();
+    });
+
+    it('should fail closed without a fresh oracle when required', async () => {
+      const feed = await createPriceFeed('ORQON', 1_000_000, 0);
+      const m = await createIsolatedMarket('ORQON', feed);
+
+      await program.methods
+        .addLiquidity(new anchor.BN(10_000_000), new anchor.BN(10_000_000), new anchor.BN(0))
+        .accounts(liquidityAccounts(m))
+        .rpc();
+      await program.methods
+        .updateMarketConfig(marketConfig({ requiresOracle: true }))
+        .accounts({ authority: authority.publicKey, market: m.market })
+        .rpc();
+
+      try {
+        await program.methods
+          .swap(new anchor.BN(10_000), new anchor.BN(0), false)
+          .accounts(swapAccounts(m))
+          .rpc();
+        expect.fail('Should have thrown StalePriceFeed');
+      } catch (err: any) {
+        expect(err.error?.errorCode?.code || err.message).to.include('StalePriceFeed');
+      }
+
+      // A fresh feed lets the same swap through
+      await program.methods
+        .swap(new anchor.BN(10_000), new anchor.BN(0), false)
+        .accounts({ ...swapAccounts(m), oracleFeed: feed })
+        .rpc();
+    });
+  });
+
+  describe('empty pool pricing', () => {
+    it('should reject rather than auto-pause when the pool has no price', async () => {
+      const feed = await createPriceFeed('EMPTYPAUSE', 1_000_000, 0);
+      const m = await createIsolatedMarket('EMPTYPAUSE', feed);
+
+      await program.methods
+        .updateMarketConfig(marketConfig({ autoPauseEnabled: true, maxOracleDeviationBps: 500 }))
+        .accounts({ authority: authority.publicKey, market: m.market })
+        .rpc();
+
+      try {
+        await program.methods
+          .swap(new anchor.BN(1_000), new anchor.BN(0), false)
+          .accounts({ ...swapAccounts(m), oracleFeed: feed })
+          .rpc();
+        expect.fail('Should have thrown InsufficientLiquidity');
+      } catch (err: any) {
+        expect(err.error?.errorCode?.code || err.message).to.include('InsufficientLiquidity');
+      }
+
+      // A zero spot price must not read as a 100% deviation from the oracle
+      const market = await program.account.market.fetch(m.market);
+      expect(market.status).to.deep.equal({ active: {} });
+    });
+
+    it('should value fees of an empty pool at zero without a price', async () => {
+      const m = await createIsolatedMarket('EMPTYFEES');
+
+      const total = await program.methods
+        .getFeesInQuote()
+        .accounts({ market: m.market, pool: m.pool })
+        .view();
+      expect(total.toNumber()).to.equal(0);
+    });
+  });
+
+  describe('bps slippage', () => {
+    it('should accept a small swap and reject a large one under the same tolerance', async () => {
+      const m = await createIsolatedMarket('SLIPBPS');
+
+      await program.methods
+        .addLiquidity(new anchor.BN(100_000_000), new anchor.BN(100_000_000), new anchor.BN(0))
+        .accounts(liquidityAccounts(m))
+        .rpc();
+
+      // 0.01% of the pool moves the price ~1 bp
+      await program.methods
+        .swapWithSlippage(new anchor.BN(10_000), 50, false)
+        .accounts(swapAccounts(m))
+        .rpc();
+
+      // 10% of the pool moves the price ~900 bps
+      try {
+        await program.methods
+          .swapWithSlippage(new anchor.BN(10_000_000), 50, false)
+          .accounts(swapAccounts(m))
+          .rpc();
+        expect.fail('Should have thrown SlippageExceeded');
+      } catch (err: any) {
+        expect(err.error?.errorCode?.code || err.message).to.include('SlippageExceeded');
+      }
+    });
+
+    it('should quote an unbalanced deposit at its trimmed amounts', async () => {
+      const m = await createIsolatedMarket('SLIPLP');
+
+      await program.methods
+        .addLiquidityWithSlippage(new anchor.BN(1_000_000), new anchor.BN(1_000_000), 0)
+        .accounts(liquidityAccounts(m))
+        .rpc();
+
+      // Balanced deposits earn the full quote
+      await program.methods
+        .addLiquidityWithSlippage(new anchor.BN(500_000), new anchor.BN(500_000), 10)
+        .accounts(liquidityAccounts(m))
+        .rpc();
+
+      // The surplus security is trimmed, and the quote is what the rest earns
+      const lpBefore = (await getAccount(provider.connection, m.userLp)).amount;
+      const securityBefore = (await getAccount(provider.connection, m.userSecurity)).amount;
+      await program.methods
+        .addLiquidityWithSlippage(new anchor.BN(500_000), new anchor.BN(250_000), 0)
+        .accounts(liquidityAccounts(m))
+        .rpc();
+      const lpAfter = (await getAccount(provider.connection, m.userLp)).amount;
+      const securityAfter = (await getAccount(provider.connection, m.userSecurity)).amount;
+      expect(Number(securityBefore - securityAfter)).to.equal(250_000);
+      expect(Number(lpAfter - lpBefore)).to.equal(250_000);
+    });
+  });
+
+  describe('windowed TWAP', () => {
+    it('should average the price that held before a trade and report the divergence', async () => {
+      const m = await createIsolatedMarket('TWAPWIN');
+
+      await program.methods
+        .addLiquidity(new anchor.BN(100_000_000), new anchor.BN(100_000_000), new anchor.BN(0))
+        .accounts(liquidityAccounts(m))
+        .rpc();
+      await new Promise((resolve) => setTimeout(resolve, 2000));
+
+      // Buying security lifts spot; the TWAP reflects the 1.0 that held until now
+      await program.methods
+        .swap(new anchor.BN(10_000_000), new anchor.BN(0), false)
+        .accounts(swapAccounts(m))
+        .rpc();
+
+      const info = await program.methods
+        .getTwap()
+        .accounts({ market: m.market, pool: m.pool })
+        .view();
+      const pool = await program.account.pool.fetch(m.pool);
+      const spot = pool.quoteLiquidity.mul(new anchor.BN(1_000_000)).div(pool.securityLiquidity);
+
+      expect(info.twap.toNumber()).to.equal(1_000_000);
+      expect(info.spotPrice.toString()).to.equal(spot.toString());
+      expect(info.divergenceBps.toNumber()).to.equal(
+        Math.floor(((spot.toNumber() - 1_000_000) * 10_000) / 1_000_000)
+      );
+      expect(pool.twapLastPrice.toString()).to.equal(spot.toString());
+    });
+  });
+
+  describe('proportional deposits', () => {
+    it('should only pull the side that pairs at the pool ratio', async () => {
+      const m = await createIsolatedMarket('PAIRLP');
+
+      await program.methods
+        .addLiquidity(new anchor.BN(1_000_000), new anchor.BN(2_000_000), new anchor.BN(0))
+        .accounts(liquidityAccounts(m))
+        .rpc();
+
+      // Offer twice the quote that 500k security pairs with
+      const securityBefore = await getAccount(provider.connection, m.userSecurity);
+      const quoteBefore = await getAccount(provider.connection, m.userQuote);
+      await program.methods
+        .addLiquidity(new anchor.BN(500_000), new anchor.BN(2_000_000), new anchor.BN(0))
+        .accounts(liquidityAccounts(m))
+        .rpc();
+
+      const securityAfter = await getAccount(provider.connection, m.userSecurity);
+      const quoteAfter = await getAccount(provider.connection, m.userQuote);
+      expect(Number(securityBefore.amount - securityAfter.amount)).to.equal(500_000);
+      expect(Number(quoteBefore.amount - quoteAfter.amount)).to.equal(1_000_000);
+
+      const pool = await program.account.pool.fetch(m.pool);
+      expect(pool.securityLiquidity.toNumber()).to.equal(1_500_000);
+      expect(pool.quoteLiquidity.toNumber()).to.equal(3_000_000);
+    });
+  });
+
+  describe('initial LP tokens', () => {
+    it('should mint the integer square root of the first deposit less the locked minimum', async () => {
+      const m = await createIsolatedMarket('ISQRT');
+
+      // sqrt(1_000_000 * 2_000_000) = 1_414_213.56...
+      await program.methods
+        .addLiquidity(new anchor.BN(1_000_000), new anchor.BN(2_000_000), new anchor.BN(0))
+        .accounts(liquidityAccounts(m))
+        .rpc();
+
+      const pool = await program.account.pool.fetch(m.pool);
+      expect(pool.lpSupply.toNumber()).to.equal(1_414_213);
+      const lp = await getAccount(provider.connection, m.userLp);
+      expect(Number(lp.amount)).to.equal(1_414_213 - 1_000);
+    });
+
+    it('should reject a first deposit below the locked minimum', async () => {
+      const m = await createIsolatedMarket('DUSTLP');
+
+      try {
+        await program.methods
+          .addLiquidity(new anchor.BN(1), new anchor.BN(1), new anchor.BN(0))
+          .accounts(liquidityAccounts(m))
+          .rpc();
+        expect.fail('Should have thrown InitialLiquidityTooSmall');
+      } catch (err: any) {
+        expect(err.error?.errorCode?.code || err.message).to.include('InitialLiquidityTooSmall');
+      }
+    });
+
+    it('should give a later depositor their full share after a dust first deposit', async () => {
+      const m = await createIsolatedMarket('INFLATE');
+
+      // The attacker's 1_001 x 1_001 seed earns a single LP token
+      await program.methods
+        .addLiquidity(new anchor.BN(1_001), new anchor.BN(1_001), new anchor.BN(0))
+        .accounts(liquidityAccounts(m))
+        .rpc();
+      const attackerLp = Number((await getAccount(provider.connection, m.userLp)).amount);
+      expect(attackerLp).to.equal(1);
+
+      await program.methods
+        .addLiquidity(new anchor.BN(1_000_000), new anchor.BN(1_000_000), new anchor.BN(0))
+        .accounts(liquidityAccounts(m))
+        .rpc();
+      const victimLp = Number((await getAccount(provider.connection, m.userLp)).amount) - attackerLp;
+
+      // The victim's LP redeems for everything they put in; the attacker's
+      // token is still worth one unit of each side
+      const pool = await program.account.pool.fetch(m.pool);
+      const supply = pool.lpSupply.toNumber();
+      expect(Math.floor((victimLp * pool.securityLiquidity.toNumber()) / supply)).to.equal(1_000_000);
+      expect(Math.floor((attackerLp * pool.securityLiquidity.toNumber()) / supply)).to.equal(1);
+    });
+
+    it('should leave a donation to the vaults out of the reserves after a dust first deposit', async () => {
+      const m = await createIsolatedMarket('DONATE');
+
+      await program.methods
+        .addLiquidity(new anchor.BN(1_001), new anchor.BN(1_001), new anchor.BN(0))
+        .accounts(liquidityAccounts(m))
+        .rpc();
+      const attackerLp = Number((await getAccount(provider.connection, m.userLp)).amount);
+      expect(attackerLp).to.equal(1);
+
+      // Sending tokens straight to the vaults doesn't move the tracked reserves,
+      // so it can't inflate the price of an LP token
+      const payer = (provider.wallet as any).payer as Keypair;
+      await transfer(provider.connection, payer, m.userSecurity, m.securityVault, authority.publicKey, 1_000_000);
+      await transfer(provider.connection, payer, m.userQuote, m.quoteVault, authority.publicKey, 1_000_000);
+
+      await program.methods
+        .addLiquidity(new anchor.BN(1_000_000), new anchor.BN(1_000_000), new anchor.BN(0))
+        .accounts(liquidityAccounts(m))
+        .rpc();
+      const victimLp = Number((await getAccount(provider.connection, m.userLp)).amount) - attackerLp;
+      expect(victimLp).to.equal(1_000_000);
+
+      // The victim still redeems everything they put in, the attacker's token
+      // is still worth one unit of each side, and the donation is lost
+      const pool = await program.account.pool.fetch(m.pool);
+      const supply = pool.lpSupply.toNumber();
+      expect(pool.securityLiquidity.toNumber()).to.equal(1_001_001);
+      expect(Math.floor((victimLp * pool.securityLiquidity.toNumber()) / supply)).to.equal(1_000_000);
+      expect(Math.floor((attackerLp * pool.securityLiquidity.toNumber()) / supply)).to.equal(1);
+      const vault = await getAccount(provider.connection, m.securityVault);
+      expect(Number(vault.amount)).to.equal(2_001_001);
+    });
+
+    it('should take the floor of the square root across the u128 range', async () => {
+      const u64Max = (BigInt(1) << BigInt(64)) - BigInt(1);
+      const u128Max = (BigInt(1) << BigInt(128)) - BigInt(1);
+      const cases: [bigint, bigint][] = [
+        [BigInt(0), BigInt(0)],
+        [BigInt(1), BigInt(1)],
+        [BigInt(2), BigInt(1)],
+        [BigInt(3), BigInt(1)],
+        [BigInt(4), BigInt(2)],
+        [BigInt(5), BigInt(2)],
+        [BigInt(1_000_000 * 1_000_000 - 1), BigInt(999_999)],
+        [BigInt(1_000_000 * 1_000_000), BigInt(1_000_000)],
+        [BigInt(1_000_000 * 1_000_000 + 1), BigInt(1_000_000)],
+        [u64Max * u64Max - BigInt(1), u64Max - BigInt(1)],
+        [u64Max * u64Max, u64Max],
+        [u64Max * u64Max + BigInt(1), u64Max],
+        [u128Max, u64Max],
+      ];
+
+      for (const [value, root] of cases) {
+        const result = await program.methods
+          .integerSqrt(new anchor.BN(value.toString()))
+          .accounts({})
+          .view();
+        expect(result.toString(), `isqrt(${value})`).to.equal(root.toString());
+      }
+    });
+  });
+
+  describe('trading hours', () => {
+    const DAY = 86_400;
+    const secondOfDay = () => Math.floor(Date.now() / 1000) % DAY;
+
+    it('should reject swaps outside the trading window and accept them inside it', async () => {
+      const m = await createIsolatedMarket('HOURS');
+
+      await program.methods
+        .addLiquidity(new anchor.BN(10_000_000), new anchor.BN(10_000_000), new anchor.BN(0))
+        .accounts(liquidityAccounts(m))
+        .rpc();
+
+      // Window that opens two hours from now and lasts one hour
+      const now = secondOfDay();
+      await program.methods
+        .updateMarketConfig(marketConfig({
+          tradingOpenSeconds: (now + 7_200) % DAY,
+          tradingCloseSeconds: (now + 10_800) % DAY,
+        }))
+        .accounts({ authority: authority.publicKey, market: m.market })
+        .rpc();
+
+      try {
+        await program.methods
+          .swap(new anchor.BN(1_000), new anchor.BN(0), false)
+          .accounts(swapAccounts(m))
+          .rpc();
+        expect.fail('Should have thrown MarketClosedHours');
+      } catch (err: any) {
+        expect(err.error?.errorCode?.code || err.message).to.include('MarketClosedHours');
+      }
+
+      await program.methods.updateTradingSession().accounts({ market: m.market }).rpc();
+      expect((await program.account.market.fetch(m.market)).sessionOpen).to.equal(false);
+
+      // Window that opened an hour ago and closes an hour from now
+      await program.methods
+        .updateMarketConfig(marketConfig({
+          tradingOpenSeconds: (now + DAY - 3_600) % DAY,
+          tradingCloseSeconds: (now + 3_600) % DAY,
+        }))
+        .accounts({ authority: authority.publicKey, market: m.market })
+        .rpc();
+
+      await program.methods
+        .swap(new anchor.BN(1_000), new anchor.BN(0), false)
+        .accounts(swapAccounts(m))
+        .rpc();
+      expect((await program.account.market.fetch(m.market)).sessionOpen).to.equal(true);
+    });
+
+    it('should reject schedule offsets beyond one day', async () => {
+      const m = await createIsolatedMarket('HOURSBAD');
+
+      try {
+        await program.methods
+          .updateMarketConfig(marketConfig({ tradingOpenSeconds: DAY, tradingCloseSeconds: 0 }))
+          .accounts({ authority: authority.publicKey, market: m.market })
+          .rpc();
+        expect.fail('Should have thrown InvalidTradingSchedule');
+      } catch (err: any) {
+        expect(err.error?.errorCode?.code || err.message).to.include('InvalidTradingSchedule');
+      }
+    });
+  });
+
+  describe('oracle deviation auto-pause', () => {
+    it('should pause the market on the first trade after the pool is pushed off the oracle', async () => {
+      const feed = await createPriceFeed('AUTOPAUSE', 1_000_000, 0);
+      const m = await createIsolatedMarket('AUTOPAUSE', feed);
+
+      await program.methods
+        .addLiquidity(new anchor.BN(100_000_000), new anchor.BN(100_000_000), new anchor.BN(0))
+        .accounts(liquidityAccounts(m))
+        .rpc();
+      await program.methods
+        .updateMarketConfig(marketConfig({ autoPauseEnabled: true, maxOracleDeviationBps: 500 }))
+        .accounts({ authority: authority.publicKey, market: m.market })
+        .rpc();
+
+      // Pool starts at the oracle price: an honest trade goes through
+      await program.methods
+        .swap(new anchor.BN(10_000), new anchor.BN(0), false)
+        .accounts({ ...swapAccounts(m), oracleFeed: feed })
+        .rpc();
+
+      // Buying with an amount equal to the quote reserve roughly quadruples the pool price
+      await program.methods
+        .swap(new anchor.BN(100_000_000), new anchor.BN(0), false)
+        .accounts({ ...swapAccounts(m), oracleFeed: feed })
+        .rpc();
+
+      const poolBefore = await program.account.pool.fetch(m.pool);
+      await program.methods
+        .swap(new anchor.BN(10_000), new anchor.BN(0), false)
+        .accounts({ ...swapAccounts(m), oracleFeed: feed })
+        .rpc();
+
+      const market = await program.account.market.fetch(m.market);
+      expect(market.status).to.deep.equal({ paused: {} });
+      // The tripping trade itself was not executed
+      const poolAfter = await program.account.pool.fetch(m.pool);
+      expect(poolAfter.quoteLiquidity.toString()).to.equal(poolBefore.quoteLiquidity.toString());
+
+      try {
+        await program.methods
+          .swap(new anchor.BN(10_000), new anchor.BN(0), false)
+          .accounts({ ...swapAccounts(m), oracleFeed: feed })
+          .rpc();
+        expect.fail('Should have thrown MarketNotActive');
+      } catch (err: any) {
+        expect(err.error?.errorCode?.code || err.message).to.include('MarketNotActive');
+      }
+    });
+  });
+
+  describe('oracle deviation guard', () => {
+    it('should reject a swap that pushes the pool too far from the oracle', async () => {
+      const feed = await createPriceFeed('DEVGUARD', 1_000_000, 0);
+      const m = await createIsolatedMarket('DEVGUARD', feed);
+
+      await program.methods
+        .addLiquidity(new anchor.BN(100_000_000), new anchor.BN(100_000_000), new anchor.BN(0))
+        .accounts(liquidityAccounts(m))
+        .rpc();
+      await program.methods
+        .updateMarketConfig(marketConfig({ maxOracleDeviationBps: 500 }))
+        .accounts({ authority: authority.publicKey, market: m.market })
+        .rpc();
+
+      // The guard needs the market oracle
+      try {
+        await program.methods
+          .swap(new anchor.BN(10_000), new anchor.BN(0), false)
+          .accounts(swapAccounts(m))
+          .rpc();
+        expect.fail('Should have thrown OracleMismatch');
+      } catch (err: any) {
+        expect(err.error?.errorCode?.code || err.message).to.include('OracleMismatch');
+      }
+
+      // A small trade stays near the oracle price
+      await program.methods
+        .swap(new anchor.BN(10_000), new anchor.BN(0), false)
+        .accounts({ ...swapAccounts(m), oracleFeed: feed })
+        .rpc();
+
+      // 10% of the quote reserve moves the pool price ~21%
+      try {
+        await program.methods
+          .swap(new anchor.BN(10_000_000), new anchor.BN(0), false)
+          .accounts({ ...swapAccounts(m), oracleFeed: feed })
+          .rpc();
+        expect.fail('Should have thrown PriceDeviationExceeded');
+      } catch (err: any) {
+        expect(err.error?.errorCode?.code || err.message).to.include('PriceDeviationExceeded');
+      }
+
+      // Rejected, not paused
+      const market = await program.account.market.fetch(m.market);
+      expect(market.status).to.deep.equal({ active: {} });
+    });
+  });
+
+  describe('route_swap', () => {
+    const insurancePdas = (market: PublicKey) =>
+      ['insurance_fund', 'insurance_vault'].map(
+        (seed) => PublicKey.findProgramAddressSync([Buffer.from(seed), market.toBuffer()], program.programId)[0]
+      );
+
+    // Markets without an insurance target take the program id in both insurance slots
+    const routeLeg = (m: Awaited<ReturnType<typeof createIsolatedMarket>>, insured = false) => [
+      ...[m.market, m.pool, m.poolAuthority, m.securityVault, m.quoteVault].map((pubkey) => ({
+        pubkey,
+        isWritable: pubkey !== m.poolAuthority,
+        isSigner: false,
+      })),
+      ...(insured ? insurancePdas(m.market) : [program.programId, program.programId]).map((pubkey) => ({
+        pubkey,
+        isWritable: insured,
+        isSigner: false,
+      })),
+    ];
+
+    // Borsh Vec<u64> from the "Program return:" log line
+    const legOutputs = (logs: string[]) => {
+      const line = logs.find((l) => l.startsWith(`Program return: ${program.programId.toBase58()}`))!;
+      const data = Buffer.from(line.split(' ').pop()!, 'base64');
+      const count = data.readUInt32LE(0);
+      return Array.from({ length: count }, (_, i) => data.readBigUInt64LE(4 + 8 * i));
+    };
+
+    it('should route A -> quote -> B atomically and return each leg output', async () => {
+      const a = await createIsolatedMarket('ROUTEA');
+      const b = await createIsolatedMarket('ROUTEB', Keypair.generate().publicKey, a.quoteMint);
+      for (const m of [a, b]) {
+        await program.methods
+          .addLiquidity(new anchor.BN(100_000_000), new anchor.BN(100_000_000), new anchor.BN(0))
+          .accounts(liquidityAccounts(m))
+          .rpc();
+      }
+
+      const route = () =>
+        program.methods
+          .routeSwap(new anchor.BN(1_000_000), new anchor.BN(0), [true, false])
+          .accounts({
+            user: authority.publicKey,
+            userSource: a.userSecurity,
+            userDestination: b.userSecurity,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .remainingAccounts([...routeLeg(a), ...routeLeg(b)]);
+
+      const simulated = await route().simulate();
+      const [quoteOut, securityOut] = legOutputs(simulated.raw as string[]);
+      expect(Number(quoteOut)).to.be.greaterThan(0);
+      expect(Number(securityOut)).to.be.greaterThan(0);
+
+      const destBefore = await provider.connection.getTokenAccountBalance(b.userSecurity);
+      const quoteBefore = await provider.connection.getTokenAccountBalance(b.userQuote);
+      await route().rpc();
+      const destAfter = await provider.connection.getTokenAccountBalance(b.userSecurity);
+      const quoteAfter = await provider.connection.getTokenAccountBalance(b.userQuote);
+
+      expect(BigInt(destAfter.value.amount) - BigInt(destBefore.value.amount)).to.equal(securityOut);
+      // The intermediate quote went vault-to-vault, never through the user
+      expect(quoteAfter.value.amount).to.equal(quoteBefore.value.amount);
+
+      // A final-output miss reverts every leg
+      const poolBefore = await program.account.pool.fetch(a.pool);
+      try {
+        await program.methods
+          .routeSwap(new anchor.BN(1_000_000), new anchor.BN(1_000_000_000), [true, false])
+          .accounts({
+            user: authority.publicKey,
+            userSource: a.userSecurity,
+            userDestination: b.userSecurity,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .remainingAccounts([...routeLeg(a), ...routeLeg(b)])
+          .rpc();
+        expect.fail('Should have thrown SlippageExceeded');
+      } catch (err: any) {
+        expect(err.error?.errorCode?.code || err.message).to.include('SlippageExceeded');
+      }
+      const poolAfter = await program.account.pool.fetch(a.pool);
+      expect(poolAfter.securityLiquidity.toString()).to.equal(poolBefore.securityLiquidity.toString());
+    });
+
+    it('should split each leg protocol fee with the insurance fund like a swap', async () => {
+      const m = await createIsolatedMarket('ROUTEINS');
+      const [insuranceFund, insuranceVault] = insurancePdas(m.market);
+      await program.methods
+        .initializeInsuranceFund()
+        .accounts({
+          authority: authority.publicKey,
+          market: m.market,
+          insuranceFund,
+          poolAuthority: m.poolAuthority,
+          quoteMint: m.quoteMint,
+          insuranceVault,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+      await program.methods
+        .addLiquidity(new anchor.BN(100_000_000), new anchor.BN(100_000_000), new anchor.BN(0))
+        .accounts(liquidityAccounts(m))
+        .rpc();
+      await program.methods
+        .updateMarketConfig(
+          marketConfig({
+            targetInsuranceBalance: new anchor.BN(1_000_000),
+            insuranceFeeShareBps: 5000,
+          })
+        )
+        .accounts({ authority: authority.publicKey, market: m.market })
+        .rpc();
+
+      const route = (insured: boolean) =>
+        program.methods
+          .routeSwap(new anchor.BN(10_000_000), new anchor.BN(0), [false])
+          .accounts({
+            user: authority.publicKey,
+            userSource: m.userQuote,
+            userDestination: m.userSecurity,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .remainingAccounts(routeLeg(m, insured))
+          .rpc();
+
+      try {
+        await route(false);
+        expect.fail('Should have thrown InsuranceFundRequired');
+      } catch (err: any) {
+        expect(err.error?.errorCode?.code || err.message).to.include('InsuranceFundRequired');
+      }
+
+      // 30 bps fee = 30_000, protocol share (5 of 30 bps) = 5_000; empty fund gets 50%
+      await route(true);
+      const fund = await program.account.insuranceFund.fetch(insuranceFund);
+      expect(fund.balance.toNumber()).to.equal(2_500);
+      const vault = await getAccount(provider.connection, insuranceVault);
+      expect(Number(vault.amount)).to.equal(2_500);
+      const market = await program.account.market.fetch(m.market);
+      expect(market.accumulatedProtocolFees.toNumber()).to.equal(2_500);
+    });
+  });
+
+  describe('add_liquidity market status', () => {
+    it('should reject adding liquidity to a closed market', async () => {
+      const m = await createIsolatedMarket('CLOSED');
+
+      // A market closes through settlement
+      await program.methods
+        .updateMarketConfig(marketConfig({ status: { settling: {} } }))
+        .accounts({ authority: authority.publicKey, market: m.market })
+        .rpc();
+      await program.methods
+        .updateMarketConfig(marketConfig({ status: { closed: {} } }))
+        .accounts({ authority: authority.publicKey, market: m.market })
+        .rpc();
+
+      try {
+        await program.methods
+          .addLiquidity(new anchor.BN(1_000_000), new anchor.BN(1_000_000), new anchor.BN(0))
+          .accounts(liquidityAccounts(m))
+          .rpc();
+        expect.fail('Should have thrown MarketNotActive');
+      } catch (err: any) {
+        expect(err.toString()).to.include('MarketNotActive');
+      }
+    });
+  });
+
+  describe('set_market_status', () => {
+    it('should follow the status graph and gate trading on it', async () => {
+      const feed = await createPriceFeed('STATUS', 100_000_000, 0);
+      const m = await createIsolatedMarket('STATUS', feed);
+      const [position] = PublicKey.findProgramAddressSync(
+        [Buffer.from('position'), authority.publicKey.toBuffer(), m.market.toBuffer()],
+        program.programId
+      );
+
+      const setStatus = (status: any) =>
+        program.methods
+          .setMarketStatus(status)
+          .accounts({ authority: authority.publicKey, market: m.market })
+          .rpc();
+      const expectRejected = async (action: Promise<unknown>, code: string) => {
+        try {
+          await action;
+          expect.fail(`Should have thrown ${code}`);
+        } catch (err: any) {
+          expect(err.error?.errorCode?.code || err.message).to.include(code);
+        }
+      };
+      const open = () =>
+        program.methods
+          .openPosition({
+            positionType: { perpetual: {} },
+            side: { long: {} },
+            size: new anchor.BN(10_000_000),
+            leverage: 5,
+            collateral: new anchor.BN(2_000_000),
+            takeProfit: new anchor.BN(0),
+            stopLoss: new anchor.BN(0),
+          })
+          .accounts({
+            user: authority.publicKey,
+            market: m.market,
+            position,
+            quoteMint: m.quoteMint,
+            userQuote: m.userQuote,
+            collateralVault: m.quoteVault,
+            oracleFeed: feed,
+            fallbackOracleFeed: null,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+      const close = (size: number) =>
+        program.methods
+          .closePosition(new anchor.BN(size))
+          .accounts({
+            user: authority.publicKey,
+            market: m.market,
+            position,
+            poolAuthority: m.poolAuthority,
+            collateralVault: m.quoteVault,
+            userQuote: m.userQuote,
+            oracleFeed: feed,
+            fallbackOracleFeed: null,
+            insuranceFund: null,
+            insuranceVault: null,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .rpc();
+
+      // Paused blocks new exposure until resumed
+      await setStatus({ paused: {} });
+      await expectRejected(open(), 'MarketNotActive');
+      await setStatus({ active: {} });
+      await open();
+
+      // Settling still lets holders close, but never reopens trading
+      await setStatus({ settling: {} });
+      await expectRejected(setStatus({ active: {} }), 'InvalidStatusTransition');
+      await close(5_000_000);
+
+      // Closed is final and blocks everything
+      await setStatus({ closed: {} });
+      await expectRejected(close(5_000_000), 'MarketNotActive');
+      await expectRejected(setStatus({ active: {} }), 'InvalidStatusTransition');
+      const market = await program.account.market.fetch(m.market);
+      expect(market.status).to.deep.equal({ closed: {} });
+    });
+  });
+
+  describe('oracle confidence guard', () => {
+    it('should reject opening a position against a feed with a wide confidence band', async () => {
+      // Confidence of 5% of price against a 1% limit
+      const feed = await createPriceFeed('CONFBAD', 100_000_000, 5_000_000);
+      const m = await createIsolatedMarket('CONFBAD', feed);
+
+      await program.methods
+        .updateMarketConfig(marketConfig({ maxConfidenceBps: 100 }))
+        .accounts({ authority: authority.publicKey, market: m.market })
+        .rpc();
+
+      const [position] = PublicKey.findProgramAddressSync(
+        [Buffer.from('position'), authority.publicKey.toBuffer(), m.market.toBuffer()],
+        program.programId
+      );
+
+      try {
+        await program.methods
+          .openPosition({
+            positionType: { perpetual: {} },
+            side: { long: {} },
+            size: new anchor.BN(10_000_000),
+            leverage: 5,
+            collateral: new anchor.BN(2_000_000),
+            takeProfit: new anchor.BN(0),
+            stopLoss: new anchor.BN(0),
+          })
+          .accounts({
+            user: authority.publicKey,
+            market: m.market,
+            position,
+            quoteMint: m.quoteMint,
+            userQuote: m.userQuote,
+            collateralVault: m.quoteVault,
+            oracleFeed: feed,
+            fallbackOracleFeed: null,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+        expect.fail('Should have thrown PriceTooUncertain');
+      } catch (err: any) {
+        expect(err.toString()).to.include('PriceTooUncertain');
+      }
+    });
+  });
+
+  describe('apply_funding', () => {
+    it('should reject funding when the funding feed is stale and the price feed is fresh', async () => {
+      const feed = await createPriceFeed('FUNDSTL', 100_000_000, 0);
+      const m = await createIsolatedMarket('FUNDSTL', feed);
+
+      const [fundingFeed] = PublicKey.findProgramAddressSync(
+        [Buffer.from('funding_feed'), Buffer.from('FUNDSTL')],
+        oracleProgram.programId
+      );
+      await oracleProgram.methods
+        .initializeFundingFeed('FUNDSTL')
+        .accounts({
+          authority: authority.publicKey,
+          fundingFeed,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      await program.methods
+        .updateMarketConfig(marketConfig({ fundingStalenessSeconds: 1 }))
+        .accounts({ authority: authority.publicKey, market: m.market })
+        .rpc();
+
+      const [position] = PublicKey.findProgramAddressSync(
+        [Buffer.from('position'), authority.publicKey.toBuffer(), m.market.toBuffer()],
+        program.programId
+      );
+      await program.methods
+        .openPosition({
+          positionType: { perpetual: {} },
+          side: { long: {} },
+          size: new anchor.BN(10_000_000),
+          leverage: 5,
+          collateral: new anchor.BN(2_000_000),
+          takeProfit: new anchor.BN(0),
+          stopLoss: new anchor.BN(0),
+        })
+        .accounts({
+          user: authority.publicKey,
+          market: m.market,
+          position,
+          quoteMint: m.quoteMint,
+          userQuote: m.userQuote,
+          collateralVault: m.quoteVault,
+          oracleFeed: feed,
+          fallbackOracleFeed: null,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      // Let the funding feed age past its 1s bound, then refresh the price feed
+      await new Promise((resolve) => setTimeout(resolve, 3000));
+      await oracleProgram.methods
+        .updatePrice(new anchor.BN(100_000_000), new anchor.BN(0))
+        .accounts({ authority: authority.publicKey, priceFeed: feed })
+        .rpc();
+
+      try {
+        await program.methods
+          .applyFunding()
+          .accounts({
+            market: m.market,
+            oracleFeed: feed,
+            fundingFeed,
+            position,
+          })
+          .rpc();
+        expect.fail('Should have thrown StalePriceFeed');
+      } catch (err: any) {
+        expect(err.toString()).to.include('StalePriceFeed');
+      }
+    });
+
+    it('should charge at most the capped interval after a long crank gap', async () => {
+      const feed = await createPriceFeed('FUNDCAP', 100_000_000, 0);
+      const m = await createIsolatedMarket('FUNDCAP', feed);
+
+      const [fundingFeed] = PublicKey.findProgramAddressSync(
+        [Buffer.from('funding_feed'), Buffer.from('FUNDCAP')],
+        oracleProgram.programId
+      );
+      await oracleProgram.methods
+        .initializeFundingFeed('FUNDCAP')
+        .accounts({
+          authority: authority.publicKey,
+          fundingFeed,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      await program.methods
+        .updateMarketConfig(marketConfig({ maxFundingIntervalSeconds: 1 }))
+        .accounts({ authority: authority.publicKey, market: m.market })
+        .rpc();
+
+      const [position] = PublicKey.findProgramAddressSync(
+        [Buffer.from('position'), authority.publicKey.toBuffer(), m.market.toBuffer()],
+        program.programId
+      );
+      await program.methods
+        .openPosition({
+          positionType: { perpetual: {} },
+          side: { long: {} },
+          size: new anchor.BN(10_000_000),
+          leverage: 5,
+          collateral: new anchor.BN(2_000_000),
+          takeProfit: new anchor.BN(0),
+          stopLoss: new anchor.BN(0),
+        })
+        .accounts({
+          user: authority.publicKey,
+          market: m.market,
+          position,
+          quoteMint: m.quoteMint,
+          userQuote: m.userQuote,
+          collateralVault: m.quoteVault,
+          oracleFeed: feed,
+          fallbackOracleFeed: null,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      // Several seconds pass without a crank
+      await new Promise((resolve) => setTimeout(resolve, 3000));
+      await oracleProgram.methods
+        .updateFundingRate(new anchor.BN(2_880), { internal: {} })
+        .accounts({ authority: authority.publicKey, fundingFeed })
+        .rpc();
+      await oracleProgram.methods
+        .updatePrice(new anchor.BN(100_000_000), new anchor.BN(0))
+        .accounts({ authority: authority.publicKey, priceFeed: feed })
+        .rpc();
+
+      await program.methods
+        .applyFunding()
+        .accounts({ market: m.market, oracleFeed: feed, fundingFeed, position })
+        .rpc();
+
+      // One second charged: 10_000_000 × 2_880 × 1 / 28_800, paid by the long
+      const charged = await program.account.position.fetch(position);
+      expect(charged.accumulatedFunding.toString()).to.equal('-1000000');
+    });
+
+    it('should not charge funding twice at the same timestamp', async () => {
+      const feed = await createPriceFeed('FUNDDUP', 100_000_000, 0);
+      const m = await createIsolatedMarket('FUNDDUP', feed);
+
+      const [fundingFeed] = PublicKey.findProgramAddressSync(
+        [Buffer.from('funding_feed'), Buffer.from('FUNDDUP')],
+        oracleProgram.programId
+      );
+      await oracleProgram.methods
+        .initializeFundingFeed('FUNDDUP')
+        .accounts({
+          authority: authority.publicKey,
+          fundingFeed,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      const [position] = PublicKey.findProgramAddressSync(
+        [Buffer.from('position'), authority.publicKey.toBuffer(), m.market.toBuffer()],
+        program.programId
+      );
+      await program.methods
+        .openPosition({
+          positionType: { perpetual: {} },
+          side: { long: {} },
+          size: new anchor.BN(10_000_000),
+          leverage: 5,
+          collateral: new anchor.BN(2_000_000),
+          takeProfit: new anchor.BN(0),
+          stopLoss: new anchor.BN(0),
+        })
+        .accounts({
+          user: authority.publicKey,
+          market: m.market,
+          position,
+          quoteMint: m.quoteMint,
+          userQuote: m.userQuote,
+          collateralVault: m.quoteVault,
+          oracleFeed: feed,
+          fallbackOracleFeed: null,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      await new Promise((resolve) => setTimeout(resolve, 1500));
+      await oracleProgram.methods
+        .updateFundingRate(new anchor.BN(2_880), { internal: {} })
+        .accounts({ authority: authority.publicKey, fundingFeed })
+        .rpc();
+
+      // Two cranks in one transaction share a timestamp: the second must fail
+      const applyIx = await program.methods
+        .applyFunding()
+        .accounts({ market: m.market, oracleFeed: feed, fundingFeed, position })
+        .instruction();
+      try {
+        await provider.sendAndConfirm(new Transaction().add(applyIx, applyIx));
+        expect.fail('Should have thrown FundingAlreadyApplied');
+      } catch (err: any) {
+        const logs = (err.logs ?? []).join('\n');
+        expect(err.toString() + logs).to.include('FundingAlreadyApplied');
+      }
+
+      // The failed transaction rolled back, so a single crank still charges once
+      await program.methods
+        .applyFunding()
+        .accounts({ market: m.market, oracleFeed: feed, fundingFeed, position })
+        .rpc();
+      const charged = await program.account.position.fetch(position);
+      expect(charged.accumulatedFunding.isNeg()).to.be.true;
+    });
+  });
+
+  describe('get_position_summary', () => {
+    it('should aggregate a trader\'s positions at the oracle mark price', async () => {
+      const feed = await createPriceFeed('SUMMARY', 100_000_000, 0);
+      const m = await createIsolatedMarket('SUMMARY', feed);
+
+      const [position] = PublicKey.findProgramAddressSync(
+        [Buffer.from('position'), authority.publicKey.toBuffer(), m.market.toBuffer()],
+        program.programId
+      );
+      await program.methods
+        .openPosition({
+          positionType: { perpetual: {} },
+          side: { long: {} },
+          size: new anchor.BN(10_000_000),
+          leverage: 5,
+          collateral: new anchor.BN(2_000_000),
+          takeProfit: new anchor.BN(0),
+          stopLoss: new anchor.BN(0),
+        })
+        .accounts({
+          user: authority.publicKey,
+          market: m.market,
+          position,
+          quoteMint: m.quoteMint,
+          userQuote: m.userQuote,
+          collateralVault: m.quoteVault,
+          oracleFeed: feed,
+          fallbackOracleFeed: null,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      // Mark 10% above entry
+      await oracleProgram.methods
+        .updatePrice(new anchor.BN(110_000_000), new anchor.BN(0))
+        .accounts({ authority: authority.publicKey, priceFeed: feed })
+        .rpc();
+
+      const summary = await program.methods
+        .getPositionSummary()
+        .accounts({ trader: authority.publicKey })
+        .remainingAccounts(
+          [position, m.market, feed].map((pubkey) => ({ pubkey, isWritable: false, isSigner: false }))
+        )
+        .view();
+
+      expect(summary.positionCount).to.equal(1);
+      expect(summary.totalNotional.toNumber()).to.equal(10_000_000);
+      expect(summary.totalCollateral.toNumber()).to.equal(2_000_000);
+      expect(summary.netPnl.toNumber()).to.equal(100_000_000);
+    });
+  });
+
+  describe('close_position', () => {
+    it('should pay out collateral net of the close fee and credit the insurance fund', async () => {
+      const feed = await createPriceFeed('CLOSEFEE', 100_000_000, 0);
+      const m = await createIsolatedMarket('CLOSEFEE', feed);
+
+      const [position] = PublicKey.findProgramAddressSync(
+        [Buffer.from('position'), authority.publicKey.toBuffer(), m.market.toBuffer()],
+        program.programId
+      );
+      const [insuranceFund] = PublicKey.findProgramAddressSync(
+        [Buffer.from('insurance_fund'), m.market.toBuffer()],
+        program.programId
+      );
+      const [insuranceVault] = PublicKey.findProgramAddressSync(
+        [Buffer.from('insurance_vault'), m.market.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .initializeInsuranceFund()
+        .accounts({
+          authority: authority.publicKey,
+          market: m.market,
+          insuranceFund,
+          poolAuthority: m.poolAuthority,
+          quoteMint: m.quoteMint,
+          insuranceVault,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+      // 1% of closed notional
+      await program.methods
+        .updateMarketConfig(marketConfig({ closeFeeBps: 100 }))
+        .accounts({ authority: authority.publicKey, market: m.market })
+        .rpc();
+
+      await program.methods
+        .openPosition({
+          positionType: { perpetual: {} },
+          side: { long: {} },
+          size: new anchor.BN(10_000_000),
+          leverage: 5,
+          collateral: new anchor.BN(2_000_000),
+          takeProfit: new anchor.BN(0),
+          stopLoss: new anchor.BN(0),
+        })
+        .accounts({
+          user: authority.publicKey,
+          market: m.market,
+          position,
+          quoteMint: m.quoteMint,
+          userQuote: m.userQuote,
+          collateralVault: m.quoteVault,
+          oracleFeed: feed,
+          fallbackOracleFeed: null,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      const before = await getAccount(provider.connection, m.userQuote);
+      await program.methods
+        .closePosition(new anchor.BN(10_000_000))
+        .accounts({
+          user: authority.publicKey,
+          market: m.market,
+          position,
+          poolAuthority: m.poolAuthority,
+          collateralVault: m.quoteVault,
+          userQuote: m.userQuote,
+          oracleFeed: feed,
+          fallbackOracleFeed: null,
+          insuranceFund,
+          insuranceVault,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc();
+
+      // Closed flat at entry: collateral back less 1% of 10_000_000
+      const after = await getAccount(provider.connection, m.userQuote);
+      expect(Number(after.amount - before.amount)).to.equal(1_900_000);
+
+      const fund = await program.account.insuranceFund.fetch(insuranceFund);
+      expect(fund.balance.toNumber()).to.equal(100_000);
+
+      const closed = await program.account.position.fetch(position);
+      expect(closed.isOpen).to.be.false;
+      expect(closed.collateral.toNumber()).to.equal(0);
+
+      // The closed PDA is reused, entering at the current mark
+      await oracleProgram.methods
+        .updatePrice(new anchor.BN(110_000_000), new anchor.BN(0))
+        .accounts({ authority: authority.publicKey, priceFeed: feed })
+        .rpc();
+      const reopen = () =>
+        program.methods
+          .openPosition({
+            positionType: { perpetual: {} },
+            side: { short: {} },
+            size: new anchor.BN(10_000_000),
+            leverage: 5,
+            collateral: new anchor.BN(2_000_000),
+            takeProfit: new anchor.BN(0),
+            stopLoss: new anchor.BN(0),
+          })
+          .accounts({
+            user: authority.publicKey,
+            market: m.market,
+            position,
+            quoteMint: m.quoteMint,
+            userQuote: m.userQuote,
+            collateralVault: m.quoteVault,
+            oracleFeed: feed,
+            fallbackOracleFeed: null,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+      await reopen();
+      const reopened = await program.account.position.fetch(position);
+      expect(reopened.isOpen).to.be.true;
+      expect('short' in reopened.side).to.be.true;
+      expect(reopened.entryPrice.toNumber()).to.equal(110_000_000);
+
+      try {
+        await reopen();
+        expect.fail('Should have thrown PositionAlreadyOpen');
+      } catch (err: any) {
+        expect(err.error?.errorCode?.code || err.message).to.include('PositionAlreadyOpen');
+      }
+    });
+  });
+
+    it('should charge a partial close loss beyond its slice to the retained margin', async () => {
+      const feed = await createPriceFeed('CLOSELOSS', 100_000_000, 0);
+      const m = await createIsolatedMarket('CLOSELOSS', feed);
+      const [position] = PublicKey.findProgramAddressSync(
+        [Buffer.from('position'), authority.publicKey.toBuffer(), m.market.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .openPosition({
+          positionType: { perpetual: {} },
+          side: { long: {} },
+          size: new anchor.BN(10_000_000),
+          leverage: 5,
+          collateral: new anchor.BN(2_000_000),
+          takeProfit: new anchor.BN(0),
+          stopLoss: new anchor.BN(0),
+        })
+        .accounts({
+          user: authority.publicKey,
+          market: m.market,
+          position,
+          quoteMint: m.quoteMint,
+          userQuote: m.userQuote,
+          collateralVault: m.quoteVault,
+          oracleFeed: feed,
+          fallbackOracleFeed: null,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      // Down 0.3: closing half realizes -1_500_000 against a 1_000_000 slice
+      await oracleProgram.methods
+        .updatePrice(new anchor.BN(99_700_000), new anchor.BN(0))
+        .accounts({ authority: authority.publicKey, priceFeed: feed })
+        .rpc();
+
+      const before = await getAccount(provider.connection, m.userQuote);
+      await program.methods
+        .closePosition(new anchor.BN(5_000_000))
+        .accounts({
+          user: authority.publicKey,
+          market: m.market,
+          position,
+          poolAuthority: m.poolAuthority,
+          collateralVault: m.quoteVault,
+          userQuote: m.userQuote,
+          oracleFeed: feed,
+          fallbackOracleFeed: null,
+          insuranceFund: null,
+          insuranceVault: null,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc();
+
+      const after = await getAccount(provider.connection, m.userQuote);
+      expect(Number(after.amount - before.amount)).to.equal(0);
+
+      // The 500_000 beyond the slice is taken from the retained margin
+      const pos = await program.account.position.fetch(position);
+      expect(pos.isOpen).to.be.true;
+      expect(pos.size.toNumber()).to.equal(5_000_000);
+      expect(pos.collateral.toNumber()).to.equal(500_000);
+    });
+  });
+
+  describe('adjust_position', () => {
+    it('should add collateral, blend the entry price and realize PnL on a reduction', async () => {
+      const feed = await createPriceFeed('ADJUST', 100_000_000, 0);
+      const m = await createIsolatedMarket('ADJUST', feed);
+      const [position] = PublicKey.findProgramAddressSync(
+        [Buffer.from('position'), authority.publicKey.toBuffer(), m.market.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .openPosition({
+          positionType: { perpetual: {} },
+          side: { long: {} },
+          size: new anchor.BN(10_000_000),
+          leverage: 5,
+          collateral: new anchor.BN(2_000_000),
+          takeProfit: new anchor.BN(0),
+          stopLoss: new anchor.BN(0),
+        })
+        .accounts({
+          user: authority.publicKey,
+          market: m.market,
+          position,
+          quoteMint: m.quoteMint,
+          userQuote: m.userQuote,
+          collateralVault: m.quoteVault,
+          oracleFeed: feed,
+          fallbackOracleFeed: null,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      const adjust = (addCollateral: number, addSize: number, reduceSize: number) =>
+        program.methods
+          .adjustPosition({
+            addCollateral: new anchor.BN(addCollateral),
+            addSize: new anchor.BN(addSize),
+            reduceSize: new anchor.BN(reduceSize),
+          })
+          .accounts({
+            user: authority.publicKey,
+            market: m.market,
+            position,
+            poolAuthority: m.poolAuthority,
+            quoteMint: m.quoteMint,
+            userQuote: m.userQuote,
+            collateralVault: m.quoteVault,
+            oracleFeed: feed,
+            fallbackOracleFeed: null,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .rpc();
+
+      const quoteBefore = await getAccount(provider.connection, m.userQuote);
+      await adjust(1_000_000, 0, 0);
+      const quoteAfter = await getAccount(provider.connection, m.userQuote);
+      expect(Number(quoteBefore.amount - quoteAfter.amount)).to.equal(1_000_000);
+      let pos = await program.account.position.fetch(position);
+      expect(pos.collateral.toNumber()).to.equal(3_000_000);
+
+      // Double the size at 102: entry blends to 101
+      await oracleProgram.methods
+        .updatePrice(new anchor.BN(102_000_000), new anchor.BN(0))
+        .accounts({ authority: authority.publicKey, priceFeed: feed })
+        .rpc();
+      await adjust(0, 10_000_000, 0);
+      pos = await program.account.position.fetch(position);
+      expect(pos.size.toNumber()).to.equal(20_000_000);
+      expect(pos.entryPrice.toNumber()).to.equal(101_000_000);
+      expect(pos.leverage).to.equal(7);
+
+      // Closing a quarter at 102 realizes its 1.0 gain per unit into collateral
+      await adjust(0, 0, 5_000_000);
+      pos = await program.account.position.fetch(position);
+      expect(pos.size.toNumber()).to.equal(15_000_000);
+      expect(pos.collateral.toNumber()).to.equal(8_000_000);
+      expect(pos.entryPrice.toNumber()).to.equal(101_000_000);
+      expect(pos.liquidationPrice.toNumber()).to.be.lessThan(101_000_000);
+    });
+
+    it('should reject an adjustment beyond 100x leverage', async () => {
+      const feed = await createPriceFeed('ADJLEV', 100_000_000, 0);
+      const m = await createIsolatedMarket('ADJLEV', feed);
+      const [position] = PublicKey.findProgramAddressSync(
+        [Buffer.from('position'), authority.publicKey.toBuffer(), m.market.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .openPosition({
+          positionType: { perpetual: {} },
+          side: { short: {} },
+          size: new anchor.BN(10_000_000),
+          leverage: 10,
+          collateral: new anchor.BN(1_000_000),
+          takeProfit: new anchor.BN(0),
+          stopLoss: new anchor.BN(0),
+        })
+        .accounts({
+          user: authority.publicKey,
+          market: m.market,
+          position,
+          quoteMint: m.quoteMint,
+          userQuote: m.userQuote,
+          collateralVault: m.quoteVault,
+          oracleFeed: feed,
+          fallbackOracleFeed: null,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      try {
+        await program.methods
+          .adjustPosition({
+            addCollateral: new anchor.BN(0),
+            addSize: new anchor.BN(100_000_000),
+            reduceSize: new anchor.BN(0),
+          })
+          .accounts({
+            user: authority.publicKey,
+            market: m.market,
+            position,
+            poolAuthority: m.poolAuthority,
+            quoteMint: m.quoteMint,
+            userQuote: m.userQuote,
+            collateralVault: m.quoteVault,
+            oracleFeed: feed,
+            fallbackOracleFeed: null,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .rpc();
+        expect.fail('Should have thrown InvalidLeverage');
+      } catch (err: any) {
+        expect(err.error?.errorCode?.code || err.message).to.include('InvalidLeverage');
+      }
+    });
+  });
+
+  describe('liquidate_position', () => {
+    it('should liquidate an undercollateralized position, paying the keeper bounty', async () => {
+      const feed = await createPriceFeed('LIQUIDATE', 100_000_000, 0);
+      const m = await createIsolatedMarket('LIQUIDATE', feed);
+
+      const [position] = PublicKey.findProgramAddressSync(
+        [Buffer.from('position'), authority.publicKey.toBuffer(), m.market.toBuffer()],
+        program.programId
+      );
+      const [insuranceFund] = PublicKey.findProgramAddressSync(
+        [Buffer.from('insurance_fund'), m.market.toBuffer()],
+        program.programId
+      );
+      const [insuranceVault] = PublicKey.findProgramAddressSync(
+        [Buffer.from('insurance_vault'), m.market.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .initializeInsuranceFund()
+        .accounts({
+          authority: authority.publicKey,
+          market: m.market,
+          insuranceFund,
+          poolAuthority: m.poolAuthority,
+          quoteMint: m.quoteMint,
+          insuranceVault,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+      // 10% of the remaining equity goes to the keeper
+      await program.methods
+        .updateMarketConfig(marketConfig({ liquidationBountyBps: 1000 }))
+        .accounts({ authority: authority.publicKey, market: m.market })
+        .rpc();
+
+      await program.methods
+        .openPosition({
+          positionType: { perpetual: {} },
+          side: { long: {} },
+          size: new anchor.BN(10_000_000),
+          leverage: 5,
+          collateral: new anchor.BN(2_000_000),
+          takeProfit: new anchor.BN(0),
+          stopLoss: new anchor.BN(0),
+        })
+        .accounts({
+          user: authority.publicKey,
+          market: m.market,
+          position,
+          quoteMint: m.quoteMint,
+          userQuote: m.userQuote,
+          collateralVault: m.quoteVault,
+          oracleFeed: feed,
+          fallbackOracleFeed: null,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      const liquidate = () =>
+        program.methods
+          .liquidatePosition()
+          .accounts({
+            keeper: authority.publicKey,
+            market: m.market,
+            oracleFeed: feed,
+            fallbackOracleFeed: null,
+            position,
+            poolAuthority: m.poolAuthority,
+            collateralVault: m.quoteVault,
+            keeperQuote: m.userQuote,
+            insuranceFund,
+            insuranceVault,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .rpc();
+
+      // Healthy at entry
+      try {
+        await liquidate();
+        expect.fail('Should have thrown NotLiquidatable');
+      } catch (err: any) {
+        expect(err.toString()).to.include('NotLiquidatable');
+      }
+
+      await oracleProgram.methods
+        .updatePrice(new anchor.BN(84_000_000), new anchor.BN(0))
+        .accounts({ authority: authority.publicKey, priceFeed: feed })
+        .rpc();
+
+      // At 84: equity 2_000_000 - 1_600_000 = 400_000, below the 500_000 maintenance margin
+      const before = await getAccount(provider.connection, m.userQuote);
+      await liquidate();
+
+      const after = await getAccount(provider.connection, m.userQuote);
+      expect(Number(after.amount - before.amount)).to.equal(40_000);
+
+      const fund = await program.account.insuranceFund.fetch(insuranceFund);
+      expect(fund.balance.toNumber()).to.equal(360_000);
+      const vault = await getAccount(provider.connection, insuranceVault);
+      expect(Number(vault.amount)).to.equal(360_000);
+
+      const liquidated = await program.account.position.fetch(position);
+      expect(liquidated.isOpen).to.be.false;
+      expect(liquidated.collateral.toNumber()).to.equal(0);
+    });
+  });
+
+  describe('take-profit and stop-loss triggers', () => {
+    it('should close a position once its stop-loss level is crossed', async () => {
+      const feed = await createPriceFeed('TRIG', 1_000_000, 0);
+      const m = await createIsolatedMarket('TRIG', feed);
+
+      const [position] = PublicKey.findProgramAddressSync(
+        [Buffer.from('position'), authority.publicKey.toBuffer(), m.market.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .openPosition({
+          positionType: { perpetual: {} },
+          side: { long: {} },
+          size: new anchor.BN(10_000_000),
+          leverage: 5,
+          collateral: new anchor.BN(2_000_000),
+          takeProfit: new anchor.BN(0),
+          stopLoss: new anchor.BN(900_000),
+        })
+        .accounts({
+          user: authority.publicKey,
+          market: m.market,
+          position,
+          quoteMint: m.quoteMint,
+          userQuote: m.userQuote,
+          collateralVault: m.quoteVault,
+          oracleFeed: feed,
+          fallbackOracleFeed: null,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      const executeTrigger = () =>
+        program.methods
+          .executeTrigger()
+          .accounts({
+            keeper: authority.publicKey,
+            market: m.market,
+            position,
+            poolAuthority: m.poolAuthority,
+            collateralVault: m.quoteVault,
+            ownerQuote: m.userQuote,
+            oracleFeed: feed,
+            fallbackOracleFeed: null,
+            insuranceFund: null,
+            insuranceVault: null,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .rpc();
+
+      try {
+        await executeTrigger();
+        expect.fail('Should have thrown TriggerNotReached');
+      } catch (err: any) {
+        expect(err.toString()).to.include('TriggerNotReached');
+      }
+
+      await oracleProgram.methods
+        .updatePrice(new anchor.BN(900_000), new anchor.BN(0))
+        .accounts({ authority: authority.publicKey, priceFeed: feed })
+        .rpc();
+
+      // At 0.9: 2_000_000 collateral less the 1_000_000 loss
+      const before = await getAccount(provider.connection, m.userQuote);
+      await executeTrigger();
+      const after = await getAccount(provider.connection, m.userQuote);
+      expect(Number(after.amount - before.amount)).to.equal(1_000_000);
+
+      const closed = await program.account.position.fetch(position);
+      expect(closed.isOpen).to.be.false;
+    });
+  });
+
+  describe('order book', () => {
+    let m: Awaited<ReturnType<typeof createIsolatedMarket>>;
+    let orderBook: PublicKey;
+    let ordersFeed: PublicKey;
+
+    const orderPda = (nonce: number) =>
+      PublicKey.findProgramAddressSync(
+        [
+          Buffer.from('order'),
+          authority.publicKey.toBuffer(),
+          m.market.toBuffer(),
+          new anchor.BN(nonce).toArrayLike(Buffer, 'le', 8),
+        ],
+        program.programId
+      )[0];
+    const escrowPda = (order: PublicKey) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from('order_escrow'), order.toBuffer()],
+        program.programId
+      )[0];
+
+    function placeOrder(
+      nonce: number,
+      side: any,
+      price: number,
+      size: number,
+      timeInForce: any,
+      expiresAt = 0,
+      position: PublicKey | null = null
+    ) {
+      const order = orderPda(nonce);
+      const isBuy = 'buy' in side;
+      return program.methods
+        .placeLimitOrder({
+          nonce: new anchor.BN(nonce),
+          side,
+          price: new anchor.BN(price),
+          size: new anchor.BN(size),
+          timeInForce,
+          expiresAt: new anchor.BN(expiresAt),
+          postOnly: false,
+          reduceOnly: position !== null,
+        })
+        .accounts({
+          owner: authority.publicKey,
+          market: m.market,
+          orderBook,
+          order,
+          poolAuthority: m.poolAuthority,
+          escrowMint: isBuy ? m.quoteMint : m.securityMint,
+          escrow: escrowPda(order),
+          ownerToken: isBuy ? m.userQuote : m.userSecurity,
+          position,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    }
+
+    function cancelOrder(nonce: number, ownerToken: PublicKey, position: PublicKey | null = null) {
+      const order = orderPda(nonce);
+      return program.methods
+        .cancelOrder()
+        .accounts({
+          owner: authority.publicKey,
+          market: m.market,
+          orderBook,
+          order,
+          poolAuthority: m.poolAuthority,
+          escrow: escrowPda(order),
+          ownerToken,
+          position,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc();
+    }
+
+    before(async () => {
+      ordersFeed = await createPriceFeed('ORDERS', 2_000_000, 0);
+      m = await createIsolatedMarket('ORDERS', ordersFeed);
+      [orderBook] = PublicKey.findProgramAddressSync(
+        [Buffer.from('orderbook'), m.market.toBuffer()],
+        program.programId
+      );
+      await program.methods
+        .initializeOrderBook()
+        .accounts({
+          authority: authority.publicKey,
+          market: m.market,
+          orderBook,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    });
+
+    it('should escrow quote for a buy and security for a sell', async () => {
+      // 1.0 security at 2.5 quote
+      await placeOrder(1, { buy: {} }, 2_500_000, 1_000_000, { gtc: {} });
+      const buyEscrow = await getAccount(provider.connection, escrowPda(orderPda(1)));
+      expect(Number(buyEscrow.amount)).to.equal(2_500_000);
+
+      const expiresAt = Math.floor(Date.now() / 1000) + 3600;
+      await placeOrder(2, { sell: {} }, 3_000_000, 500_000, { gtd: {} }, expiresAt);
+      const sellEscrow = await getAccount(provider.connection, escrowPda(orderPda(2)));
+      expect(Number(sellEscrow.amount)).to.equal(500_000);
+
+      const sell = await program.account.order.fetch(orderPda(2));
+      expect(sell.expiresAt.toNumber()).to.equal(expiresAt);
+      expect(sell.status).to.deep.equal({ open: {} });
+
+      const book = await program.account.orderBook.fetch(orderBook);
+      expect(book.bestBid.toNumber()).to.equal(2_500_000);
+      expect(book.bestAsk.toNumber()).to.equal(3_000_000);
+      expect(book.orderCount).to.equal(2);
+    });
+
+    it('should reject immediate-or-cancel resting orders', async () => {
+      try {
+        await placeOrder(3, { buy: {} }, 2_500_000, 1_000_000, { ioc: {} });
+        expect.fail('Should have thrown InvalidOrder');
+      } catch (err: any) {
+        expect(err.toString()).to.include('InvalidOrder');
+      }
+    });
+
+    it('should return the escrow on cancel and refuse a second cancel', async () => {
+      const before = await getAccount(provider.connection, m.userQuote);
+      await cancelOrder(1, m.userQuote);
+      const after = await getAccount(provider.connection, m.userQuote);
+      expect(Number(after.amount - before.amount)).to.equal(2_500_000);
+
+      const order = await program.account.order.fetch(orderPda(1));
+      expect(order.status).to.deep.equal({ cancelled: {} });
+      expect(await provider.connection.getAccountInfo(escrowPda(orderPda(1)))).to.be.null;
+
+      const book = await program.account.orderBook.fetch(orderBook);
+      expect(book.orderCount).to.equal(1);
+      expect(book.bestBid.toNumber()).to.equal(0);
+
+      try {
+        await cancelOrder(1, m.userQuote);
+        expect.fail('Should have rejected a second cancel');
+      } catch (err: any) {
+        expect(err.toString()).to.not.include('Should have rejected');
+      }
+    });
+
+    async function spotPrice() {
+      const pool = await program.account.pool.fetch(m.pool);
+      return pool.quoteLiquidity.mul(new anchor.BN(1_000_000)).div(pool.securityLiquidity);
+    }
+
+    function matchOrder(nonce: number, isBuy: boolean, currentPrice: anchor.BN) {
+      const order = orderPda(nonce);
+      return program.methods
+        .matchOrder(currentPrice)
+        .accounts({
+          keeper: authority.publicKey,
+          market: m.market,
+          pool: m.pool,
+          poolAuthority: m.poolAuthority,
+          securityVault: m.securityVault,
+          quoteVault: m.quoteVault,
+          orderBook,
+          order,
+          escrow: escrowPda(order),
+          owner: authority.publicKey,
+          ownerProceeds: isBuy ? m.userSecurity : m.userQuote,
+          ownerRefund: isBuy ? m.userQuote : m.userSecurity,
+          position: null,
+          oracleFeed: null,
+          fallbackOracleFeed: null,
+          insuranceFund: null,
+          insuranceVault: null,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc();
+    }
+
+    it('should not match an order the pool price has not crossed', async () => {
+      // 100 security against 200 quote: spot 2.0, below the resting 3.0 ask
+      await program.methods
+        .addLiquidity(new anchor.BN(100_000_000), new anchor.BN(200_000_000), new anchor.BN(0))
+        .accounts(liquidityAccounts(m))
+        .rpc();
+
+      try {
+        await matchOrder(2, false, await spotPrice());
+        expect.fail('Should have thrown OrderNotMatchable');
+      } catch (err: any) {
+        expect(err.toString()).to.include('OrderNotMatchable');
+      }
+    });
+
+    it('should partially fill a buy up to its limit and refuse a replay', async () => {
+      // 10.0 security bid at 2.1; the pool can only supply part of it at or below the limit
+      await placeOrder(4, { buy: {} }, 2_100_000, 10_000_000, { gtc: {} });
+      const securityBefore = await getAccount(provider.connection, m.userSecurity);
+      const price = await spotPrice();
+      await matchOrder(4, true, price);
+
+      const order = await program.account.order.fetch(orderPda(4));
+      expect(order.status).to.deep.equal({ partiallyFilled: {} });
+      expect(order.filledSize.toNumber()).to.be.greaterThan(0);
+      expect(order.remainingSize.toNumber()).to.equal(10_000_000 - order.filledSize.toNumber());
+      expect(order.avgFillPrice.toNumber()).to.be.at.most(2_100_000);
+
+      const securityAfter = await getAccount(provider.connection, m.userSecurity);
+      expect(Number(securityAfter.amount - securityBefore.amount)).to.equal(order.filledSize.toNumber());
+
+      const book = await program.account.orderBook.fetch(orderBook);
+      expect(book.lastTradePrice.toNumber()).to.equal(order.avgFillPrice.toNumber());
+      expect(book.bidVolume.toNumber()).to.equal(order.remainingSize.toNumber());
+
+      // The fill moved the pool, so replaying the same crank is rejected
+      try {
+        await matchOrder(4, true, price);
+        expect.fail('Should have thrown MatchPriceMoved');
+      } catch (err: any) {
+        expect(err.toString()).to.include('MatchPriceMoved');
+      }
+      // At the new price the bid no longer crosses
+      try {
+        await matchOrder(4, true, await spotPrice());
+        expect.fail('Should have thrown OrderNotMatchable');
+      } catch (err: any) {
+        expect(err.toString()).to.include('OrderNotMatchable');
+      }
+      const replayed = await program.account.order.fetch(orderPda(4));
+      expect(replayed.filledSize.toNumber()).to.equal(order.filledSize.toNumber());
+    });
+
+    it('should fully fill a sell and close its escrow', async () => {
+      await placeOrder(5, { sell: {} }, 1_500_000, 1_000_000, { gtc: {} });
+      const quoteBefore = await getAccount(provider.connection, m.userQuote);
+      const countBefore = (await program.account.orderBook.fetch(orderBook)).orderCount;
+
+      await matchOrder(5, false, await spotPrice());
+
+      const order = await program.account.order.fetch(orderPda(5));
+      expect(order.status).to.deep.equal({ filled: {} });
+      expect(order.remainingSize.toNumber()).to.equal(0);
+      expect(order.avgFillPrice.toNumber()).to.be.at.least(1_500_000);
+      expect(await provider.connection.getAccountInfo(escrowPda(orderPda(5)))).to.be.null;
+
+      const quoteAfter = await getAccount(provider.connection, m.userQuote);
+      expect(Number(quoteAfter.amount - quoteBefore.amount)).to.be.at.least(1_500_000);
+      const book = await program.account.orderBook.fetch(orderBook);
+      expect(book.orderCount).to.equal(countBefore - 1);
+    });
+
+    function placeImmediate(side: any, price: anchor.BN, size: number, timeInForce: any) {
+      return program.methods
+        .placeImmediateOrder({ side, price, size: new anchor.BN(size), timeInForce })
+        .accounts({
+          owner: authority.publicKey,
+          market: m.market,
+          pool: m.pool,
+          poolAuthority: m.poolAuthority,
+          securityVault: m.securityVault,
+          quoteVault: m.quoteVault,
+          orderBook,
+          ownerSecurity: m.userSecurity,
+          ownerQuote: m.userQuote,
+          oracleFeed: null,
+          fallbackOracleFeed: null,
+          insuranceFund: null,
+          insuranceVault: null,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc();
+    }
+
+    it('should execute a fill-or-kill order the pool can fill in full', async () => {
+      const securityBefore = await getAccount(provider.connection, m.userSecurity);
+      const quoteBefore = await getAccount(provider.connection, m.userQuote);
+      const countBefore = (await program.account.orderBook.fetch(orderBook)).orderCount;
+
+      // A small sell well below spot fills entirely
+      const limit = (await spotPrice()).divn(2);
+      await placeImmediate({ sell: {} }, limit, 1_000_000, { fok: {} });
+
+      const securityAfter = await getAccount(provider.connection, m.userSecurity);
+      const quoteAfter = await getAccount(provider.connection, m.userQuote);
+      expect(Number(securityBefore.amount - securityAfter.amount)).to.equal(1_000_000);
+      expect(Number(quoteAfter.amount - quoteBefore.amount)).to.be.at.least(limit.toNumber());
+
+      const book = await program.account.orderBook.fetch(orderBook);
+      expect(book.orderCount).to.equal(countBefore);
+      expect(book.lastTradePrice.toNumber()).to.be.at.least(limit.toNumber());
+    });
+
+    it('should revert a fill-or-kill order the pool can only partially fill', async () => {
+      const securityBefore = await getAccount(provider.connection, m.userSecurity);
+      const quoteBefore = await getAccount(provider.connection, m.userQuote);
+
+      // 50.0 security within 5% of spot is more than the pool can supply
+      const limit = (await spotPrice()).muln(105).divn(100);
+      try {
+        await placeImmediate({ buy: {} }, limit, 50_000_000, { fok: {} });
+        expect.fail('Should have thrown FillOrKillFailed');
+      } catch (err: any) {
+        expect(err.toString()).to.include('FillOrKillFailed');
+      }
+
+      const securityAfter = await getAccount(provider.connection, m.userSecurity);
+      const quoteAfter = await getAccount(provider.connection, m.userQuote);
+      expect(securityAfter.amount).to.equal(securityBefore.amount);
+      expect(quoteAfter.amount).to.equal(quoteBefore.amount);
+    });
+
+    it('should fill what it can of an immediate-or-cancel order and drop the rest', async () => {
+      const securityBefore = await getAccount(provider.connection, m.userSecurity);
+      const countBefore = (await program.account.orderBook.fetch(orderBook)).orderCount;
+      const feesBefore = (await program.account.market.fetch(m.market)).accumulatedProtocolFees;
+
+      const limit = (await spotPrice()).muln(105).divn(100);
+      await placeImmediate({ buy: {} }, limit, 50_000_000, { ioc: {} });
+
+      // Settles like a swap: the protocol share of the fee leaves the pool
+      const market = await program.account.market.fetch(m.market);
+      expect(market.accumulatedProtocolFees.gt(feesBefore)).to.be.true;
+
+      const securityAfter = await getAccount(provider.connection, m.userSecurity);
+      const filled = Number(securityAfter.amount - securityBefore.amount);
+      expect(filled).to.be.greaterThan(0);
+      expect(filled).to.be.lessThan(50_000_000);
+
+      // Nothing rests on the book
+      const book = await program.account.orderBook.fetch(orderBook);
+      expect(book.orderCount).to.equal(countBefore);
+      expect(book.lastTradePrice.toNumber()).to.be.at.most(limit.toNumber());
+    });
+
+    it('should reject good-till-cancelled immediate orders', async () => {
+      try {
+        await placeImmediate({ buy: {} }, await spotPrice(), 1_000_000, { gtc: {} });
+        expect.fail('Should have thrown InvalidOrder');
+      } catch (err: any) {
+        expect(err.toString()).to.include('InvalidOrder');
+      }
+    });
+
+    it('should only accept reduce-only orders against an opposite position', async () => {
+      const [position] = PublicKey.findProgramAddressSync(
+        [Buffer.from('position'), authority.publicKey.toBuffer(), m.market.toBuffer()],
+        program.programId
+      );
+
+      // No position yet: nothing to reduce
+      try {
+        await program.methods
+          .placeLimitOrder({
+            nonce: new anchor.BN(20),
+            side: { buy: {} },
+            price: new anchor.BN(2_000_000),
+            size: new anchor.BN(1_000_000),
+            timeInForce: { gtc: {} },
+            expiresAt: new anchor.BN(0),
+            postOnly: false,
+            reduceOnly: true,
+          })
+          .accounts({
+            owner: authority.publicKey,
+            market: m.market,
+            orderBook,
+            order: orderPda(20),
+            poolAuthority: m.poolAuthority,
+            escrowMint: m.quoteMint,
+            escrow: escrowPda(orderPda(20)),
+            ownerToken: m.userQuote,
+            position: null,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+        expect.fail('Should have thrown ReduceOnlyViolation');
+      } catch (err: any) {
+        expect(err.toString()).to.include('ReduceOnlyViolation');
+      }
+
+      // Short 10.0 quote of notional at a fresh 2.0 mark
+      await oracleProgram.methods
+        .updatePrice(new anchor.BN(2_000_000), new anchor.BN(0))
+        .accounts({ authority: authority.publicKey, priceFeed: ordersFeed })
+        .rpc();
+      await program.methods
+        .openPosition({
+          positionType: { perpetual: {} },
+          side: { short: {} },
+          size: new anchor.BN(10_000_000),
+          leverage: 5,
+          collateral: new anchor.BN(2_000_000),
+          takeProfit: new anchor.BN(0),
+          stopLoss: new anchor.BN(0),
+        })
+        .accounts({
+          user: authority.publicKey,
+          market: m.market,
+          position,
+          quoteMint: m.quoteMint,
+          userQuote: m.userQuote,
+          collateralVault: m.quoteVault,
+          oracleFeed: ordersFeed,
+          fallbackOracleFeed: null,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      // A sell would add to the short; a buy over 5.0 security at 2.0 exceeds it
+      for (const [nonce, side, size] of [
+        [21, { sell: {} }, 1_000_000],
+        [22, { buy: {} }, 6_000_000],
+      ] as const) {
+        try {
+          await placeOrder(nonce, side, 2_000_000, size, { gtc: {} }, 0, position);
+          expect.fail('Should have thrown ReduceOnlyViolation');
+        } catch (err: any) {
+          expect(err.toString()).to.include('ReduceOnlyViolation');
+        }
+      }
+
+      await placeOrder(23, { buy: {} }, 2_000_000, 5_000_000, { gtc: {} }, 0, position);
+      const order = await program.account.order.fetch(orderPda(23));
+      expect(order.reduceOnly).to.be.true;
+      expect(order.remainingSize.toNumber()).to.equal(5_000_000);
+      let reserved = (await program.account.position.fetch(position)).reduceOnlyReserved;
+      expect(reserved.toNumber()).to.equal(10_000_000);
+
+      // The resting order reserved the whole short: a second one can't stack on it
+      try {
+        await placeOrder(24, { buy: {} }, 2_000_000, 1_000_000, { gtc: {} }, 0, position);
+        expect.fail('Should have thrown ReduceOnlyViolation');
+      } catch (err: any) {
+        expect(err.toString()).to.include('ReduceOnlyViolation');
+      }
+
+      // Cancelling releases the reservation
+      await cancelOrder(23, m.userQuote, position);
+      reserved = (await program.account.position.fetch(position)).reduceOnlyReserved;
+      expect(reserved.toNumber()).to.equal(0);
+      await placeOrder(24, { buy: {} }, 2_000_000, 1_000_000, { gtc: {} }, 0, position);
+    });
+  });
+
+  describe('fee-bearing collateral', () => {
+    it('should record the collateral the vault received, net of the transfer fee', async () => {
+      const payer = (provider.wallet as any).payer as Keypair;
+
+      // Token-2022 quote mint withholding 1% on every transfer
+      const quoteMintKp = Keypair.generate();
+      const mintLen = getMintLen([ExtensionType.TransferFeeConfig]);
+      await sendAndConfirmTransaction(
+        provider.connection,
+        new Transaction().add(
+          SystemProgram.createAccount({
+            fromPubkey: payer.publicKey,
+            newAccountPubkey: quoteMintKp.publicKey,
+            space: mintLen,
+            lamports: await provider.connection.getMinimumBalanceForRentExemption(mintLen),
+            programId: TOKEN_2022_PROGRAM_ID,
+          }),
+          createInitializeTransferFeeConfigInstruction(
+            quoteMintKp.publicKey,
+            authority.publicKey,
+            authority.publicKey,
+            100,
+            BigInt(1_000_000_000),
+            TOKEN_2022_PROGRAM_ID,
+          ),
+          createInitializeMintInstruction(
+            quoteMintKp.publicKey,
+            6,
+            authority.publicKey,
+            null,
+            TOKEN_2022_PROGRAM_ID,
+          ),
+        ),
+        [payer, quoteMintKp],
+      );
+      const quoteMint = quoteMintKp.publicKey;
+      const securityMint = await createMint(provider.connection, payer, authority.publicKey, null, 6);
+
+      const [market] = PublicKey.findProgramAddressSync(
+        [Buffer.from('market'), securityMint.toBuffer(), quoteMint.toBuffer()],
+        program.programId
+      );
+      const [poolAuth] = PublicKey.findProgramAddressSync(
+        [Buffer.from('pool_authority'), market.toBuffer()],
+        program.programId
+      );
+      const [position] = PublicKey.findProgramAddressSync(
+        [Buffer.from('position'), authority.publicKey.toBuffer(), market.toBuffer()],
+        program.programId
+      );
+
+      const feed = await createPriceFeed('FEEQUOTE', 100_000_000, 0);
+      await program.methods
+        .initializeMarket({
+          marketType: { equity: {} },
+          oracle: feed,
+          tradingFeeBps: 30,
+          protocolFeeBps: 5,
+          minTradeSize: new anchor.BN(1),
+          maxTradeSize: new anchor.BN(0),
+          symbol: 'FEEQUOTE',
+          name: 'FEEQUOTE Isolated',
+          isin: null,
+        })
+        .accounts({
+          authority: authority.publicKey,
+          securityMint,
+          quoteMint,
+          market,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      const userQuote = await createAccount(
+        provider.connection, payer, quoteMint, authority.publicKey, Keypair.generate(),
+        undefined, TOKEN_2022_PROGRAM_ID,
+      );
+      const collateralVault = await createAccount(
+        provider.connection, payer, quoteMint, poolAuth, Keypair.generate(),
+        undefined, TOKEN_2022_PROGRAM_ID,
+      );
+      await mintTo(
+        provider.connection, payer, quoteMint, userQuote, authority.publicKey, 100_000_000,
+        [], undefined, TOKEN_2022_PROGRAM_ID,
+      );
+
+      await program.methods
+        .openPosition({
+          positionType: { perpetual: {} },
+          side: { long: {} },
+          size: new anchor.BN(10_000_000),
+          leverage: 5,
+          collateral: new anchor.BN(3_000_000),
+          takeProfit: new anchor.BN(0),
+          stopLoss: new anchor.BN(0),
+        })
+        .accounts({
+          user: authority.publicKey,
+          market,
+          position,
+          quoteMint,
+          userQuote,
+          collateralVault,
+          oracleFeed: feed,
+          fallbackOracleFeed: null,
+          tokenProgram: TOKEN_2022_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      // 3_000_000 sent, 1% (30_000) withheld by the mint
+      const vault = await getAccount(provider.connection, collateralVault, undefined, TOKEN_2022_PROGRAM_ID);
+      const opened = await program.account.position.fetch(position);
+      expect(opened.collateral.toNumber()).to.equal(2_970_000);
+      expect(opened.collateral.toNumber()).to.equal(Number(vault.amount));
+    });
+  });
+
+  describe('insurance fee routing', () => {
+    it('should route more protocol fee to the fund below target than at target', async () => {
+      const m = await createIsolatedMarket('FEEROUTE');
+      const [insuranceFund] = PublicKey.findProgramAddressSync(
+        [Buffer.from('insurance_fund'), m.market.toBuffer()],
+        program.programId
+      );
+      const [insuranceVault] = PublicKey.findProgramAddressSync(
+        [Buffer.from('insurance_vault'), m.market.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .initializeInsuranceFund()
+        .accounts({
+          authority: authority.publicKey,
+          market: m.market,
+          insuranceFund,
+          poolAuthority: m.poolAuthority,
+          quoteMint: m.quoteMint,
+          insuranceVault,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+      await program.methods
+        .addLiquidity(new anchor.BN(100_000_000), new anchor.BN(100_000_000), new anchor.BN(0))
+        .accounts(liquidityAccounts(m))
+        .rpc();
+      // Up to half the protocol fee while the fund is below 1_000_000
+      await program.methods
+        .updateMarketConfig(
+          marketConfig({
+            targetInsuranceBalance: new anchor.BN(1_000_000),
+            insuranceFeeShareBps: 5000,
+          })
+        )
+        .accounts({ authority: authority.publicKey, market: m.market })
+        .rpc();
+
+      const parser = new anchor.EventParser(program.programId, program.coder);
+      const swap = async () => {
+        const sig = await program.methods
+          .swap(new anchor.BN(10_000_000), new anchor.BN(0), false)
+          .accounts({ ...swapAccounts(m), insuranceFund, insuranceVault })
+          .rpc({ commitment: 'confirmed' });
+        const tx = await provider.connection.getTransaction(sig, {
+          commitment: 'confirmed',
+          maxSupportedTransactionVersion: 0,
+        });
+        return [...parser.parseLogs(tx!.meta!.logMessages!)].find(
+          (e) => e.name === 'protocolFeeRouted'
+        )!.data as any;
+      };
+
+      // 30 bps fee = 30_000, protocol share (5 of 30 bps) = 5_000; empty fund gets 50%
+      const below = await swap();
+      expect(below.routingBps).to.equal(5000);
+      expect(below.insuranceAmount.toNumber()).to.equal(2_500);
+      expect(below.treasuryAmount.toNumber()).to.equal(2_500);
+
+      // Lower the target under the current balance: everything goes to the treasury
+      await program.methods
+        .updateMarketConfig(marketConfig({ targetInsuranceBalance: new anchor.BN(2_000) }))
+        .accounts({ authority: authority.publicKey, market: m.market })
+        .rpc();
+
+      const above = await swap();
+      expect(above.routingBps).to.equal(0);
+      expect(above.insuranceAmount.toNumber()).to.equal(0);
+
+      const fund = await program.account.insuranceFund.fetch(insuranceFund);
+      expect(fund.balance.toNumber()).to.equal(2_500);
+      const vault = await getAccount(provider.connection, insuranceVault);
+      expect(Number(vault.amount)).to.equal(2_500);
+      const market = await program.account.market.fetch(m.market);
+      expect(market.treasuryFeesAccrued.toNumber()).to.equal(2_500 + above.treasuryAmount.toNumber());
+    });
+
+    it('should hold insurance withdrawals behind the timelock', async () => {
+      const payer = (provider.wallet as any).payer as Keypair;
+      const m = await createIsolatedMarket('INSWDRAW');
+      const [insuranceFund] = PublicKey.findProgramAddressSync(
+        [Buffer.from('insurance_fund'), m.market.toBuffer()],
+        program.programId
+      );
+      const [insuranceVault] = PublicKey.findProgramAddressSync(
+        [Buffer.from('insurance_vault'), m.market.toBuffer()],
+        program.programId
+      );
+      const destination = await createAccount(
+        provider.connection, payer, m.quoteMint, Keypair.generate().publicKey, Keypair.generate(),
+      );
+
+      await program.methods
+        .initializeInsuranceFund()
+        .accounts({
+          authority: authority.publicKey,
+          market: m.market,
+          insuranceFund,
+          poolAuthority: m.poolAuthority,
+          quoteMint: m.quoteMint,
+          insuranceVault,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+      await program.methods
+        .addLiquidity(new anchor.BN(100_000_000), new anchor.BN(100_000_000), new anchor.BN(0))
+        .accounts(liquidityAccounts(m))
+        .rpc();
+      await program.methods
+        .updateMarketConfig(
+          marketConfig({
+            targetInsuranceBalance: new anchor.BN(1_000_000),
+            insuranceFeeShareBps: 5000,
+          })
+        )
+        .accounts({ authority: authority.publicKey, market: m.market })
+        .rpc();
+      await program.methods
+        .swap(new anchor.BN(10_000_000), new anchor.BN(0), false)
+        .accounts({ ...swapAccounts(m), insuranceFund, insuranceVault })
+        .rpc();
+
+      const balance = await program.methods
+        .getInsuranceBalance()
+        .accounts({ market: m.market, insuranceFund })
+        .view();
+      expect(balance.toNumber()).to.equal(2_500);
+
+      const request = (amount: number) =>
+        program.methods
+          .requestInsuranceWithdrawal(new anchor.BN(amount))
+          .accounts({ authority: authority.publicKey, market: m.market, insuranceFund })
+          .rpc();
+
+      try {
+        await request(2_501);
+        expect.fail('Should have thrown InsufficientInsuranceFund');
+      } catch (err: any) {
+        expect(err.error?.errorCode?.code || err.message).to.include('InsufficientInsuranceFund');
+      }
+
+      await request(1_000);
+      const fund = await program.account.insuranceFund.fetch(insuranceFund);
+      expect(fund.pendingWithdrawal.toNumber()).to.equal(1_000);
+      expect(fund.withdrawalUnlocksAt.toNumber()).to.be.greaterThan(Math.floor(Date.now() / 1000));
+
+      try {
+        await program.methods
+          .withdrawInsurance()
+          .accounts({
+            authority: authority.publicKey,
+            market: m.market,
+            insuranceFund,
+            poolAuthority: m.poolAuthority,
+            insuranceVault,
+            destination,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .rpc();
+        expect.fail('Should have thrown TimelockActive');
+      } catch (err: any) {
+        expect(err.error?.errorCode?.code || err.message).to.include('TimelockActive');
+      }
+    });
+  });
+
+  describe('protocol fees', () => {
+    it('should accrue the protocol share of swap fees and let the authority withdraw it', async () => {
+      const payer = (provider.wallet as any).payer as Keypair;
+      const m = await createIsolatedMarket('PROTOFEE');
+      const treasury = await createAccount(
+        provider.connection, payer, m.quoteMint, Keypair.generate().publicKey, Keypair.generate(),
+      );
+
+      await program.methods
+        .addLiquidity(new anchor.BN(100_000_000), new anchor.BN(100_000_000), new anchor.BN(0))
+        .accounts(liquidityAccounts(m))
+        .rpc();
+      await program.methods
+        .swap(new anchor.BN(10_000_000), new anchor.BN(0), false)
+        .accounts(swapAccounts(m))
+        .rpc();
+
+      // 30 bps fee = 30_000, protocol share (5 of 30 bps) = 5_000, kept out of the reserves
+      let market = await program.account.market.fetch(m.market);
+      expect(market.accumulatedProtocolFees.toNumber()).to.equal(5_000);
+      const pool = await program.account.pool.fetch(m.pool);
+      expect(pool.quoteLiquidity.toNumber()).to.equal(110_000_000 - 5_000);
+
+      const withdraw = (amount: number, signer = authority.publicKey) =>
+        program.methods
+          .withdrawProtocolFees(new anchor.BN(amount))
+          .accounts({
+            authority: signer,
+            market: m.market,
+            pool: m.pool,
+            poolAuthority: m.poolAuthority,
+            quoteVault: m.quoteVault,
+            treasury,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          });
+
+      try {
+        await withdraw(5_001).rpc();
+        expect.fail('Should have thrown InsufficientProtocolFees');
+      } catch (err: any) {
+        expect(err.error?.errorCode?.code || err.message).to.include('InsufficientProtocolFees');
+      }
+
+      const outsider = Keypair.generate();
+      try {
+        await withdraw(1_000, outsider.publicKey).signers([outsider]).rpc();
+        expect.fail('Should have thrown Unauthorized');
+      } catch (err: any) {
+        expect(err.toString()).to.include('Unauthorized');
+      }
+
+      await withdraw(5_000).rpc();
+      const received = await getAccount(provider.connection, treasury);
+      expect(Number(received.amount)).to.equal(5_000);
+      market = await program.account.market.fetch(m.market);
+      expect(market.accumulatedProtocolFees.toNumber()).to.equal(0);
+    });
+  });
+
+  describe('variance swaps', () => {
+    it('should pin settlement arithmetic and reject mis-scaled strikes', async () => {
+      const feed = await createPriceFeed('VARSWAP', 1_000_000, 0);
+      const m = await createIsolatedMarket('VARSWAP', feed, undefined, { varianceSwap: {} });
+
+      const [position] = PublicKey.findProgramAddressSync(
+        [Buffer.from('position'), authority.publicKey.toBuffer(), m.market.toBuffer()],
+        program.programId
+      );
+      const [varianceSwap] = PublicKey.findProgramAddressSync(
+        [Buffer.from('variance_swap'), position.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .openPosition({
+          positionType: { varianceSwap: {} },
+          side: { long: {} },
+          size: new anchor.BN(1_000_000),
+          leverage: 5,
+          collateral: new anchor.BN(200_000),
+          takeProfit: new anchor.BN(0),
+          stopLoss: new anchor.BN(0),
+        })
+        .accounts({
+          user: authority.publicKey,
+          market: m.market,
+          position,
+          quoteMint: m.quoteMint,
+          userQuote: m.userQuote,
+          collateralVault: m.quoteVault,
+          oracleFeed: feed,
+          fallbackOracleFeed: null,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      const settlementDate = new anchor.BN(Math.floor(Date.now() / 1000) + 30 * 86_400);
+      const open = (strikeVariance: number) =>
+        program.methods
+          .openVarianceSwap({
+            strikeVariance: new anchor.BN(strikeVariance),
+            varianceNotional: new anchor.BN(1_000_000),
+            settlementDate,
+            observationInterval: new anchor.BN(86_400),
+          })
+          .accounts({
+            user: authority.publicKey,
+            market: m.market,
+            position,
+            varianceSwap,
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+
+      // 20% vol expressed at 1e12 instead of 1e6
+      try {
+        await open(40_000_000_000);
+        expect.fail('Should have thrown InvalidVarianceTerms');
+      } catch (err: any) {
+        expect(err.error?.errorCode?.code || err.message).to.include('InvalidVarianceTerms');
+      }
+
+      // Strike 0.04 (20% vol)
+      await open(40_000);
+
+      const settle = async (realizedVariance: number) =>
+        (
+          await program.methods
+            .getVarianceSettlement(new anchor.BN(realizedVariance))
+            .accounts({ varianceSwap })
+            .view()
+        ).toNumber();
+
+      // 1_000_000 notional × (realized − 0.04)
+      expect(await settle(90_000)).to.equal(50_000); // 30% vol realized
+      expect(await settle(40_000)).to.equal(0);
+      expect(await settle(10_000)).to.equal(-30_000); // 10% vol realized
+    });
+
+    it('should accumulate observed log returns and settle once against the insurance fund', async () => {
+      const feed = await createPriceFeed('VARSETL', 1_000_000, 0);
+      const m = await createIsolatedMarket('VARSETL', feed, undefined, { varianceSwap: {} });
+      const [position] = PublicKey.findProgramAddressSync(
+        [Buffer.from('position'), authority.publicKey.toBuffer(), m.market.toBuffer()],
+        program.programId
+      );
+      const [varianceSwap] = PublicKey.findProgramAddressSync(
+        [Buffer.from('variance_swap'), position.toBuffer()],
+        program.programId
+      );
+      const [insuranceFund] = PublicKey.findProgramAddressSync(
+        [Buffer.from('insurance_fund'), m.market.toBuffer()],
+        program.programId
+      );
+      const [insuranceVault] = PublicKey.findProgramAddressSync(
+        [Buffer.from('insurance_vault'), m.market.toBuffer()],
+        program.programId
+      );
+      await program.methods
+        .initializeInsuranceFund()
+        .accounts({
+          authority: authority.publicKey,
+          market: m.market,
+          insuranceFund,
+          poolAuthority: m.poolAuthority,
+          quoteMint: m.quoteMint,
+          insuranceVault,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      await program.methods
+        .openPosition({
+          positionType: { varianceSwap: {} },
+          side: { short: {} },
+          size: new anchor.BN(1_000_000),
+          leverage: 5,
+          collateral: new anchor.BN(200_000),
+          takeProfit: new anchor.BN(0),
+          stopLoss: new anchor.BN(0),
+        })
+        .accounts({
+          user: authority.publicKey,
+          market: m.market,
+          position,
+          quoteMint: m.quoteMint,
+          userQuote: m.userQuote,
+          collateralVault: m.quoteVault,
+          oracleFeed: feed,
+          fallbackOracleFeed: null,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+      const settlementDate = Math.floor(Date.now() / 1000) + 8;
+      await program.methods
+        .openVarianceSwap({
+          strikeVariance: new anchor.BN(40_000),
+          varianceNotional: new anchor.BN(1_000_000),
+          settlementDate: new anchor.BN(settlementDate),
+          observationInterval: new anchor.BN(2),
+        })
+        .accounts({
+          user: authority.publicKey,
+          market: m.market,
+          position,
+          varianceSwap,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      const observe = () =>
+        program.methods
+          .addVarianceObservation()
+          .accounts({
+            keeper: authority.publicKey,
+            market: m.market,
+            oracleFeed: feed,
+            fallbackOracleFeed: null,
+            position,
+            varianceSwap,
+          })
+          .rpc();
+      const settleSwap = () =>
+        program.methods
+          .settleVarianceSwap()
+          .accounts({
+            keeper: authority.publicKey,
+            market: m.market,
+            position,
+            varianceSwap,
+            insuranceFund,
+            insuranceVault,
+            poolAuthority: m.poolAuthority,
+            collateralVault: m.quoteVault,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .rpc();
+
+      // Observations record the mark, on a 2s cadence
+      await observe();
+      await oracleProgram.methods
+        .updatePrice(new anchor.BN(1_000_100), new anchor.BN(0))
+        .accounts({ authority: authority.publicKey, priceFeed: feed })
+        .rpc();
+      try {
+        await observe();
+        expect.fail('Should have thrown InvalidVarianceObservation');
+      } catch (err: any) {
+        expect(err.toString()).to.include('InvalidVarianceObservation');
+      }
+      await new Promise((resolve) => setTimeout(resolve, 2500));
+      await observe();
+
+      // One 0.01% log return, annualized over the observed window
+      const swap = await program.account.varianceSwapData.fetch(varianceSwap);
+      expect(swap.observationCount).to.equal(2);
+      const elapsed = swap.lastObservedAt.toNumber() - swap.firstObservedAt.toNumber();
+      const expected = (Math.log(1_000_100 / 1_000_000) ** 2 * 365 * 86_400 / elapsed) * 1_000_000;
+      expect(swap.realizedVariance.toNumber()).to.be.closeTo(expected, 2);
+
+      try {
+        await settleSwap();
+        expect.fail('Should have thrown VarianceSwapNotMatured');
+      } catch (err: any) {
+        expect(err.toString()).to.include('VarianceSwapNotMatured');
+      }
+
+      while (Math.floor(Date.now() / 1000) <= settlementDate + 1) {
+        await new Promise((resolve) => setTimeout(resolve, 1000));
+      }
+      await settleSwap();
+
+      // Realized above strike: the short pays notional × (realized − strike)
+      const settled = await program.account.varianceSwapData.fetch(varianceSwap);
+      const owed = Math.floor(
+        (1_000_000 * (settled.realizedVariance.toNumber() - 40_000)) / 1_000_000
+      );
+      expect(settled.isSettled).to.be.true;
+      const pos = await program.account.position.fetch(position);
+      expect(pos.collateral.toNumber()).to.equal(200_000 - owed);
+      const fund = await program.account.insuranceFund.fetch(insuranceFund);
+      expect(fund.balance.toNumber()).to.equal(owed);
+
+      try {
+        await settleSwap();
+        expect.fail('Should have thrown VarianceSwapAlreadySettled');
+      } catch (err: any) {
+        expect(err.toString()).to.include('VarianceSwapAlreadySettled');
+      }
+    });
+  });
+
+  describe('fee governance', () => {
+    it('should escrow voting LP tokens until the vote ends', async () => {
+      const m = await createIsolatedMarket('GOVN');
+      await program.methods
+        .addLiquidity(new anchor.BN(1_000_000), new anchor.BN(1_000_000), new anchor.BN(0))
+        .accounts(liquidityAccounts(m))
+        .rpc();
+      await program.methods
+        .updateMarketConfig(
+          marketConfig({ lpGovernanceEnabled: true, minGovernedFeeBps: 10, maxGovernedFeeBps: 100 })
+        )
+        .accounts({ authority: authority.publicKey, market: m.market })
+        .rpc();
+
+      const [proposal] = PublicKey.findProgramAddressSync(
+        [Buffer.from('fee_proposal'), m.market.toBuffer(), new anchor.BN(0).toArrayLike(Buffer, 'le', 8)],
+        program.programId
+      );
+      await program.methods
+        .proposeFeeChange(50)
+        .accounts({
+          proposer: authority.publicKey,
+          market: m.market,
+          pool: m.pool,
+          proposerLp: m.userLp,
+          proposal,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      const [voteRecord] = PublicKey.findProgramAddressSync(
+        [Buffer.from('fee_vote'), proposal.toBuffer(), authority.publicKey.toBuffer()],
+        program.programId
+      );
+      const [voteEscrow] = PublicKey.findProgramAddressSync(
+        [Buffer.from('fee_vote_escrow'), voteRecord.toBuffer()],
+        program.programId
+      );
+      const weight = (await getAccount(provider.connection, m.userLp)).amount;
+
+      await program.methods
+        .voteFeeChange(true)
+        .accounts({
+          voter: authority.publicKey,
+          market: m.market,
+          pool: m.pool,
+          proposal,
+          voterLp: m.userLp,
+          lpMint: m.lpMint,
+          voteRecord,
+          poolAuthority: m.poolAuthority,
+          voteEscrow,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      // The voted LP can't be moved to another wallet to vote again
+      expect((await getAccount(provider.connection, m.userLp)).amount).to.equal(BigInt(0));
+      expect((await getAccount(provider.connection, voteEscrow)).amount).to.equal(weight);
+      const p = await program.account.feeProposal.fetch(proposal);
+      expect(p.votesFor.toString()).to.equal(weight.toString());
+
+      try {
+        await program.methods
+          .releaseFeeVote()
+          .accounts({
+            voter: authority.publicKey,
+            market: m.market,
+            proposal,
+            voteRecord,
+            poolAuthority: m.poolAuthority,
+            voteEscrow,
+            voterLp: m.userLp,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .rpc();
+        expect.fail('Should have thrown TimelockActive');
+      } catch (err: any) {
+        expect(err.error?.errorCode?.code || err.message).to.include('TimelockActive');
+      }
+    });
+  });
+
+  describe('account versioning', () => {
+    it('should create markets at the current version', async () => {
+      const m = await createIsolatedMarket('VERS');
+      const market = await program.account.market.fetch(m.market);
+      expect(market.version).to.equal(11);
+    });
+
+    it('should reject migrating a market that is already current', async () => {
+      const m = await createIsolatedMarket('VERSMIG');
+
+      try {
+        await program.methods
+          .migrateMarket()
+          .accounts({
+            authority: authority.publicKey,
+            market: m.market,
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+        expect.fail('Should have thrown AlreadyMigrated');
+      } catch (err: any) {
+        expect(err.toString()).to.include('AlreadyMigrated');
+      }
+    });
+
+    it('should create pools at the current version', async () => {
+      const pool = await program.account.pool.fetch(poolPda);
+      expect(pool.version).to.equal(1);
+    });
+
+    it('should reject migrating a pool that is already current', async () => {
+      try {
+        await program.methods
+          .migratePool()
+          .accounts({
+            authority: authority.publicKey,
+            market: marketPda,
+            pool: poolPda,
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+        expect.fail('Should have thrown AlreadyMigrated');
+      } catch (err: any) {
+        expect(err.toString()).to.include('AlreadyMigrated');
+      }
+    });
+  });
+
+  describe('constant product formula', () => {
+    it('should maintain x*y=k invariant', async () => {
+      const pool = await program.account.pool.fetch(poolPda);
+      const k = BigInt(pool.securityLiquidity.toString()) *
+                BigInt(pool.quoteLiquidity.toString());
+
+      expect(k > BigInt(0)).to.be.true;
+    });
+  });
+
+  // Runs last: the shutdown is program-wide and can't be undone
+  describe('emergency shutdown', () => {
+    const guardian = Keypair.generate();
+    const [protocolState] = PublicKey.findProgramAddressSync(
+      [Buffer.from('protocol_state')],
+      program.programId
+    );
+    let feed: PublicKey;
+    let m: Awaited<ReturnType<typeof createIsolatedMarket>>;
+    let position: PublicKey;
+
+    before(async () => {
+      const [programData] = PublicKey.findProgramAddressSync(
+        [program.programId.toBuffer()],
+        new PublicKey('BPFLoaderUpgradeab1e11111111111111111111111')
+      );
+      await program.methods
+        .initializeProtocolState(guardian.publicKey)
+        .accounts({
+          authority: authority.publicKey,
+          protocolState,
+          program: program.programId,
+          programData,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      feed = await createPriceFeed('SHUTDOWN', 1_000_000, 0);
+      m = await createIsolatedMarket('SHUTDOWN', feed);
+      [position] = PublicKey.findProgramAddressSync(
+        [Buffer.from('position'), authority.publicKey.toBuffer(), m.market.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .addLiquidity(new anchor.BN(100_000_000), new anchor.BN(100_000_000), new anchor.BN(0))
+        .accounts(liquidityAccounts(m))
+        .rpc();
+      await program.methods
+        .openPosition({
+          positionType: { perpetual: {} },
+          side: { long: {} },
+          size: new anchor.BN(10_000_000),
+          leverage: 5,
+          collateral: new anchor.BN(2_000_000),
+          takeProfit: new anchor.BN(0),
+          stopLoss: new anchor.BN(0),
+        })
+        .accounts({
+          user: authority.publicKey,
+          market: m.market,
+          position,
+          quoteMint: m.quoteMint,
+          userQuote: m.userQuote,
+          collateralVault: m.quoteVault,
+          oracleFeed: feed,
+          fallbackOracleFeed: null,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    });
+
+    const settle = () =>
+      program.methods
+        .settleAtShutdown()
+        .accounts({
+          cranker: authority.publicKey,
+          protocolState,
+          market: m.market,
+          position,
+          poolAuthority: m.poolAuthority,
+          collateralVault: m.quoteVault,
+          ownerQuote: m.userQuote,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc();
+
+    it('should only let the guardian trigger the shutdown', async () => {
+      try {
+        await settle();
+        expect.fail('Should have thrown ProtocolNotShutdown');
+      } catch (err: any) {
+        expect(err.toString()).to.include('ProtocolNotShutdown');
+      }
+
+      try {
+        await program.methods
+          .triggerEmergencyShutdown()
+          .accounts({ guardian: authority.publicKey, protocolState })
+          .rpc();
+        expect.fail('Should have thrown Unauthorized');
+      } catch (err: any) {
+        expect(err.toString()).to.include('Unauthorized');
+      }
+
+      await program.methods
+        .triggerEmergencyShutdown()
+        .accounts({ guardian: guardian.publicKey, protocolState })
+        .signers([guardian])
+        .rpc();
+
+      const state = await program.account.protocolState.fetch(protocolState);
+      expect(state.isShutdown).to.be.true;
+    });
+
+    it('should reject new activity during the shutdown', async () => {
+      try {
+        await program.methods
+          .swap(new anchor.BN(10_000), new anchor.BN(0), false)
+          .accounts({ ...swapAccounts(m), protocolState })
+          .rpc();
+        expect.fail('Should have thrown ProtocolShutdown');
+      } catch (err: any) {
+        expect(err.error?.errorCode?.code || err.message).to.include('ProtocolShutdown');
+      }
+    });
+
+    it('should settle an open position at the guardian-set price', async () => {
+      try {
+        await settle();
+        expect.fail('Should have thrown SettlementPriceNotSet');
+      } catch (err: any) {
+        expect(err.toString()).to.include('SettlementPriceNotSet');
+      }
+
+      await program.methods
+        .setShutdownSettlementPrice(new anchor.BN(1_050_000))
+        .accounts({ guardian: guardian.publicKey, protocolState, market: m.market })
+        .signers([guardian])
+        .rpc();
+
+      // 10_000_000 notional up 5%: 2_000_000 collateral + 500_000 profit
+      const before = await getAccount(provider.connection, m.userQuote);
+      await settle();
+      const after = await getAccount(provider.connection, m.userQuote);
+      expect(Number(after.amount - before.amount)).to.equal(2_500_000);
+
+      const settled = await program.account.position.fetch(position);
+      expect(settled.isOpen).to.be.false;
+      expect(settled.settledAtShutdown).to.be.true;
+
+      try {
+        await settle();
+        expect.fail('Should have thrown PositionNotFound');
+      } catch (err: any) {
+        expect(err.toString()).to.include('PositionNotFound');
+      }
     });
   });
 });